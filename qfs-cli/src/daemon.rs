@@ -0,0 +1,340 @@
+//! Warm query daemon
+//!
+//! `qfs daemon` keeps the store open and the embedding model loaded, and
+//! answers searches over a Unix domain socket next to the database
+//! (`index.sqlite.sock`). Each connection carries one JSON request line and
+//! gets one JSON reply line. `qfs search` tries the socket first and searches
+//! in-process when no daemon is listening, so the daemon is purely a cache.
+
+use anyhow::Result;
+use qfs::search::SearchResponse;
+use qfs::SearchOptions;
+use std::path::{Path, PathBuf};
+
+/// A search sent to the daemon
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SearchRequest {
+    pub query: String,
+    pub options: SearchOptions,
+}
+
+/// The daemon's answer to a `SearchRequest`
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchReply {
+    Results(SearchResponse),
+    Error(String),
+}
+
+/// Socket the daemon for `db_path` listens on
+pub fn socket_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".sock");
+    PathBuf::from(path)
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use crate::{execute_search, QueryEmbedder};
+    use qfs::Store;
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::unix::OwnedWriteHalf;
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::sync::mpsc;
+
+    /// How long a client may take to send its request
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// How long to wait for the daemon's reply before searching in-process
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Run `query` through the daemon serving `db_path`. Returns `None` when
+    /// no daemon is listening or answering, or `QFS_NO_DAEMON` is set, so the
+    /// caller can search in-process.
+    pub async fn search(
+        db_path: &Path,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Option<SearchResponse>> {
+        if std::env::var_os("QFS_NO_DAEMON").is_some() {
+            return Ok(None);
+        }
+        search_within(db_path, query, options, REPLY_TIMEOUT).await
+    }
+
+    async fn search_within(
+        db_path: &Path,
+        query: &str,
+        options: &SearchOptions,
+        timeout: Duration,
+    ) -> Result<Option<SearchResponse>> {
+        let Ok(stream) = UnixStream::connect(socket_path(db_path)).await else {
+            return Ok(None);
+        };
+
+        let (reader, mut writer) = stream.into_split();
+        let request = SearchRequest {
+            query: query.to_string(),
+            options: options.clone(),
+        };
+        let exchange = async {
+            writer
+                .write_all(format!("{}\n", serde_json::to_string(&request)?).as_bytes())
+                .await?;
+            let mut line = String::new();
+            BufReader::new(reader).read_line(&mut line).await?;
+            anyhow::Ok(line)
+        };
+        let Ok(line) = tokio::time::timeout(timeout, exchange).await else {
+            tracing::warn!(
+                "qfs daemon didn't answer within {}s; searching without it",
+                timeout.as_secs_f64()
+            );
+            return Ok(None);
+        };
+        let line = line?;
+        if line.trim().is_empty() {
+            tracing::warn!("qfs daemon closed the connection; searching without it");
+            return Ok(None);
+        }
+
+        match serde_json::from_str(&line)? {
+            SearchReply::Results(response) => Ok(Some(response)),
+            SearchReply::Error(message) => anyhow::bail!(message),
+        }
+    }
+
+    /// Serve searches for `db_path` until interrupted
+    pub async fn run(db_path: &Path) -> Result<()> {
+        let socket = socket_path(db_path);
+        if UnixStream::connect(&socket).await.is_ok() {
+            anyhow::bail!(
+                "A qfs daemon is already serving {} at {}",
+                db_path.display(),
+                socket.display()
+            );
+        }
+        // A socket nobody listens on was left behind by a daemon that died
+        if socket.exists() {
+            std::fs::remove_file(&socket)?;
+        }
+
+        let store = Store::open(db_path).await?;
+        let embedder = QueryEmbedder::load()?;
+
+        let listener = bind_private(&socket)?;
+        println!(
+            "qfs daemon serving {} at {} (Ctrl-C to stop)",
+            db_path.display(),
+            socket.display()
+        );
+
+        // Requests are read concurrently, so a client that is slow to send
+        // holds up nobody, and answered one at a time on the shared store
+        let (requests, mut pending) = mpsc::channel(16);
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let requests = requests.clone();
+                        tokio::spawn(async move {
+                            match read_request(stream).await {
+                                Ok(Some(request)) => {
+                                    let _ = requests.send(request).await;
+                                }
+                                Ok(None) => {}
+                                Err(e) => tracing::warn!("Daemon request failed: {}", e),
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!("Daemon accept failed: {}", e),
+                },
+                Some((line, writer)) = pending.recv() => {
+                    if let Err(e) = answer(&store, &embedder, &line, writer).await {
+                        tracing::warn!("Daemon request failed: {}", e);
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+
+        std::fs::remove_file(&socket)?;
+        println!("qfs daemon stopped");
+        Ok(())
+    }
+
+    /// Bind `socket` so that only its owner can ever connect: it is created
+    /// in a private directory, restricted, and only then moved into place
+    fn bind_private(socket: &Path) -> Result<UnixListener> {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        let mut staging = socket.as_os_str().to_owned();
+        staging.push(".d");
+        let staging = PathBuf::from(staging);
+        // Left behind by a daemon that died while starting
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+
+        let bind = || -> Result<UnixListener> {
+            let path = staging.join("sock");
+            let listener = UnixListener::bind(&path)?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            std::fs::rename(&path, socket)?;
+            Ok(listener)
+        };
+        let listener = bind();
+        std::fs::remove_dir_all(&staging)?;
+        listener
+    }
+
+    /// Read a client's request line, `None` for an empty one
+    async fn read_request(stream: UnixStream) -> Result<Option<(String, OwnedWriteHalf)>> {
+        let (reader, writer) = stream.into_split();
+        let mut line = String::new();
+        tokio::time::timeout(REQUEST_TIMEOUT, BufReader::new(reader).read_line(&mut line))
+            .await??;
+        // A liveness probe, such as a second `qfs daemon` checking the socket
+        Ok((!line.trim().is_empty()).then_some((line, writer)))
+    }
+
+    /// Answer one request; searches run one at a time on the shared store
+    async fn answer(
+        store: &Store,
+        embedder: &QueryEmbedder,
+        line: &str,
+        mut writer: OwnedWriteHalf,
+    ) -> Result<()> {
+        let reply = match serde_json::from_str::<SearchRequest>(line) {
+            Ok(request) => {
+                match execute_search(store, embedder, &request.query, request.options).await {
+                    Ok(response) => SearchReply::Results(response),
+                    Err(e) => SearchReply::Error(e.to_string()),
+                }
+            }
+            Err(e) => SearchReply::Error(format!("Invalid daemon request: {}", e)),
+        };
+
+        writer
+            .write_all(format!("{}\n", serde_json::to_string(&reply)?).as_bytes())
+            .await?;
+        Ok(())
+    }
+
+    /// Resolves on Ctrl-C or SIGTERM
+    async fn shutdown_signal() {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_wedged_daemon_falls_back() {
+            let dir = tempfile::tempdir().unwrap();
+            let db_path = dir.path().join("index.sqlite");
+            // Accepts connections but never answers
+            let _listener = UnixListener::bind(socket_path(&db_path)).unwrap();
+
+            let reply = search_within(
+                &db_path,
+                "rust",
+                &SearchOptions::default(),
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap();
+            assert!(reply.is_none());
+        }
+
+        #[tokio::test]
+        async fn test_socket_is_private_from_the_start() {
+            use std::os::unix::fs::PermissionsExt;
+
+            let dir = tempfile::tempdir().unwrap();
+            let socket = socket_path(&dir.path().join("index.sqlite"));
+            let _listener = bind_private(&socket).unwrap();
+
+            let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            assert!(UnixStream::connect(&socket).await.is_ok());
+            // Only the socket is left next to the database
+            assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{run, search};
+
+/// Without Unix sockets there is never a daemon to ask
+#[cfg(not(unix))]
+pub async fn search(
+    _db_path: &Path,
+    _query: &str,
+    _options: &SearchOptions,
+) -> Result<Option<SearchResponse>> {
+    Ok(None)
+}
+
+#[cfg(not(unix))]
+pub async fn run(_db_path: &Path) -> Result<()> {
+    anyhow::bail!("qfs daemon needs Unix domain sockets, which this platform lacks")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_socket_path_sits_next_to_database() {
+        assert_eq!(
+            socket_path(Path::new("/home/me/.cache/qfs/index.sqlite")),
+            PathBuf::from("/home/me/.cache/qfs/index.sqlite.sock")
+        );
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let request = SearchRequest {
+            query: "rust async".to_string(),
+            options: SearchOptions {
+                mode: qfs::SearchMode::Hybrid,
+                limit: 5,
+                collections: vec!["notes".to_string()],
+                ..Default::default()
+            },
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.contains(r#""mode":"hybrid""#));
+
+        let parsed: SearchRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.options.mode, qfs::SearchMode::Hybrid);
+        assert_eq!(parsed.options.limit, 5);
+        assert_eq!(parsed.options.collections, vec!["notes".to_string()]);
+
+        // Fields a newer client adds or an older one omits fall back to defaults
+        let minimal: SearchRequest =
+            serde_json::from_str(r#"{"query": "x", "options": {"limit": 3}}"#).unwrap();
+        assert_eq!(minimal.options.mode, qfs::SearchMode::Bm25);
+        assert!(minimal.options.fuzzy);
+    }
+}
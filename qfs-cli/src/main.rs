@@ -1,10 +1,15 @@
 //! QFS CLI - Quick File Search command-line interface
 
+mod daemon;
 mod mcp;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use qfs::parser::{page_label, ParseOptions};
+use qfs::search::RankingOptions;
+use qfs::store::DocumentFilter;
 use qfs::{Indexer, SearchMode, SearchOptions, Store};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
@@ -30,6 +35,7 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)] // Parsed once per run; `Search` carries many flags
 enum Commands {
     /// Initialize a new database
     Init,
@@ -76,6 +82,14 @@ enum Commands {
     Index {
         /// Collection name (index all if not specified)
         name: Option<String>,
+
+        /// Include text outputs of notebook code cells
+        #[arg(long)]
+        notebook_outputs: bool,
+
+        /// Index the files inside .zip, .tar, .tar.gz and .tgz archives
+        #[arg(long)]
+        archives: bool,
     },
 
     /// Generate embeddings for documents
@@ -117,15 +131,35 @@ enum Commands {
         #[arg(long, default_value = "0.0")]
         min_score: f64,
 
-        /// Filter by collection
-        #[arg(long, short = 'c')]
-        collection: Option<String>,
+        /// Filter by collection (repeatable or comma-separated)
+        #[arg(long, short = 'c', value_delimiter = ',')]
+        collection: Vec<String>,
+
+        /// Leave out a collection (repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',', value_name = "COLLECTION")]
+        exclude_collection: Vec<String>,
 
-        /// Filter documents modified on or after this date (ISO 8601, e.g., 2025-01-01)
+        /// Only these file extensions (e.g. -t md,rs)
+        #[arg(long = "type", short = 't', value_delimiter = ',', value_name = "EXT")]
+        file_types: Vec<String>,
+
+        /// Only these MIME types or classes (e.g. text/markdown, image/*, text)
+        #[arg(long = "mime", value_delimiter = ',', value_name = "MIME")]
+        mime_types: Vec<String>,
+
+        /// Only paths starting with this prefix, relative to the collection root
+        #[arg(long)]
+        path_prefix: Option<String>,
+
+        /// Only paths matching this glob, relative to the collection root (`*` also matches `/`)
+        #[arg(long)]
+        path_glob: Option<String>,
+
+        /// Filter documents modified on or after this date (2025-01-01, RFC 3339, 2025-Q1, 7d, yesterday, last week)
         #[arg(long)]
         from_date: Option<String>,
 
-        /// Filter documents modified on or before this date (ISO 8601, e.g., 2025-12-31)
+        /// Filter documents modified on or before this date (same formats; 2025-12 includes Dec 31)
         #[arg(long)]
         to_date: Option<String>,
 
@@ -133,6 +167,54 @@ enum Commands {
         #[arg(long)]
         include_binary: bool,
 
+        /// Show facet counts (collection, file type, path prefix, modified month)
+        #[arg(long)]
+        facets: bool,
+
+        /// Don't retry with spelling-corrected terms when nothing matches
+        #[arg(long)]
+        no_fuzzy: bool,
+
+        /// Return matching passages with line ranges (requires `qfs embed`)
+        #[arg(long)]
+        passages: bool,
+
+        /// Number of line-numbered snippets to show per keyword result
+        #[arg(long, default_value = "1")]
+        snippets: usize,
+
+        /// Maximum characters per snippet
+        #[arg(long, default_value = "160")]
+        snippet_size: usize,
+
+        /// Match highlighting in text output (auto, ansi, plain, mark)
+        #[arg(long, default_value = "auto")]
+        highlight: String,
+
+        /// Boost recent documents; a document this many days old keeps half its boost
+        #[arg(long, value_name = "DAYS")]
+        half_life: Option<f64>,
+
+        /// Share of the score subject to recency decay (0.0-1.0, default 0.5)
+        #[arg(long)]
+        recency_weight: Option<f64>,
+
+        /// Demote documents shorter than this many bytes
+        #[arg(long, value_name = "BYTES")]
+        min_length: Option<u64>,
+
+        /// Demote deeply nested files by this much per directory level
+        #[arg(long)]
+        depth_penalty: Option<f64>,
+
+        /// Score multiplier for a collection (repeatable, e.g. --weight notes=2)
+        #[arg(long = "weight", value_name = "COLLECTION=WEIGHT")]
+        weights: Vec<String>,
+
+        /// Only documents whose metadata field contains this value (repeatable, e.g. --meta from=alice)
+        #[arg(long = "meta", value_name = "KEY=VALUE")]
+        meta: Vec<String>,
+
         /// Output format (text, json)
         #[arg(long, short = 'o', default_value = "text")]
         format: String,
@@ -161,6 +243,115 @@ enum Commands {
         format: String,
     },
 
+    /// Find files by approximate path or title (fuzzy)
+    Find {
+        /// Fuzzy path or title (e.g., "apiref", "meeting jan")
+        query: String,
+
+        /// Maximum number of results
+        #[arg(long, short = 'n', default_value = "20")]
+        limit: usize,
+
+        /// Filter by collection
+        #[arg(long, short = 'c')]
+        collection: Option<String>,
+
+        /// Output format (text, json)
+        #[arg(long, short = 'o', default_value = "text")]
+        format: String,
+    },
+
+    /// Find exact strings or regex matches in indexed content
+    Grep {
+        /// Literal string to find (or regex with --regex)
+        pattern: String,
+
+        /// Treat the pattern as a regular expression
+        #[arg(long, short = 'E')]
+        regex: bool,
+
+        /// Case-insensitive matching
+        #[arg(long, short = 'i')]
+        ignore_case: bool,
+
+        /// Lines of context before and after each match
+        #[arg(long, short = 'C', default_value = "0")]
+        context: usize,
+
+        /// Maximum number of matching lines
+        #[arg(long, short = 'n', default_value = "100")]
+        limit: usize,
+
+        /// Filter by collection
+        #[arg(long, short = 'c')]
+        collection: Option<String>,
+
+        /// Filter documents modified on or after this date (2025-01-01, RFC 3339, 2025-Q1, 7d, yesterday, last week)
+        #[arg(long)]
+        from_date: Option<String>,
+
+        /// Filter documents modified on or before this date (same formats; 2025-12 includes Dec 31)
+        #[arg(long)]
+        to_date: Option<String>,
+
+        /// Output format (text, json)
+        #[arg(long, short = 'o', default_value = "text")]
+        format: String,
+    },
+
+    /// Find documents similar to an existing one
+    Similar {
+        /// Document path (collection/relative_path or docid)
+        path: String,
+
+        /// Maximum number of results
+        #[arg(long, short = 'n', default_value = "10")]
+        limit: usize,
+
+        /// Filter by collection
+        #[arg(long, short = 'c')]
+        collection: Option<String>,
+
+        /// Output format (text, json)
+        #[arg(long, short = 'o', default_value = "text")]
+        format: String,
+    },
+
+    /// Measure search relevance against judged queries
+    Eval {
+        /// Query set (JSONL: {"query": ..., "relevant": {"collection/path": grade}})
+        queries: PathBuf,
+
+        /// Metric cutoff and results fetched per query
+        #[arg(short = 'k', long, default_value = "10")]
+        k: usize,
+
+        /// Modes to evaluate (comma-separated; default: bm25, plus vector
+        /// and hybrid when embeddings exist)
+        #[arg(long, short = 'm', value_delimiter = ',')]
+        mode: Vec<String>,
+
+        /// Filter by collection (repeatable or comma-separated)
+        #[arg(long, short = 'c', value_delimiter = ',')]
+        collection: Vec<String>,
+
+        /// Earlier JSON report to compare against
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Write the JSON report to a file
+        #[arg(long, value_name = "FILE")]
+        save: Option<PathBuf>,
+
+        /// Show metrics for every query
+        #[arg(long)]
+        per_query: bool,
+
+        /// Output format (text, json)
+        #[arg(long, short = 'o', default_value = "text")]
+        format: String,
+    },
+
     /// Get multiple documents by pattern
     MultiGet {
         /// Glob pattern or comma-separated list of paths
@@ -186,11 +377,33 @@ enum Commands {
     /// Start MCP server (stdio transport)
     Serve,
 
+    /// Keep the index and embedding model loaded and answer searches over
+    /// a Unix socket next to the database
+    Daemon,
+
     /// Manage context descriptions for collections and paths
     Context {
         #[command(subcommand)]
         action: ContextAction,
     },
+
+    /// Inspect and rebuild the native vector index
+    VectorIndex {
+        #[command(subcommand)]
+        action: VectorIndexAction,
+    },
+
+    /// Manage per-collection ranking profiles
+    Ranking {
+        #[command(subcommand)]
+        action: RankingAction,
+    },
+
+    /// Manage per-collection external parser commands
+    Parsers {
+        #[command(subcommand)]
+        action: ParsersAction,
+    },
 }
 
 #[derive(Subcommand)]
@@ -218,6 +431,126 @@ enum ContextAction {
     },
 }
 
+#[derive(Subcommand)]
+enum RankingAction {
+    /// Set the ranking profile for a collection (replaces any existing one)
+    Set {
+        /// Collection name
+        collection: String,
+
+        /// Score multiplier for the collection's documents
+        #[arg(long, default_value = "1.0")]
+        weight: f64,
+
+        /// Default recency half-life in days
+        #[arg(long, value_name = "DAYS")]
+        half_life: Option<f64>,
+
+        /// Default share of the score subject to recency decay (0.0-1.0)
+        #[arg(long)]
+        recency_weight: Option<f64>,
+
+        /// Default minimum length in bytes before documents are demoted
+        #[arg(long, value_name = "BYTES")]
+        min_length: Option<u64>,
+
+        /// Default penalty per directory level
+        #[arg(long)]
+        depth_penalty: Option<f64>,
+    },
+
+    /// Show ranking profiles
+    Show {
+        /// Output format (text, json)
+        #[arg(long, short = 'o', default_value = "text")]
+        format: String,
+    },
+
+    /// Remove a collection's ranking profile
+    Clear {
+        /// Collection name
+        collection: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ParsersAction {
+    /// Parse an extension with an external command (replaces any existing one)
+    Set {
+        /// Collection name
+        collection: String,
+
+        /// File extension (rtf, .rtf or *.rtf)
+        extension: String,
+
+        /// Command whose stdout is indexed, e.g. "pandoc -t plain {file}";
+        /// without {file} the content is piped to stdin
+        command: String,
+
+        /// Seconds the command may run before it is killed
+        #[arg(long, default_value = "30")]
+        timeout: u64,
+    },
+
+    /// Show external parsers
+    Show {
+        /// Only this collection
+        collection: Option<String>,
+
+        /// Output format (text, json)
+        #[arg(long, short = 'o', default_value = "text")]
+        format: String,
+    },
+
+    /// Remove the external parser for an extension
+    Clear {
+        /// Collection name
+        collection: String,
+
+        /// File extension
+        extension: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum VectorIndexAction {
+    /// Show index parameters, size, legacy embeddings and the active search path
+    Status {
+        /// Output format (text, json)
+        #[arg(long, short = 'o', default_value = "text")]
+        format: String,
+    },
+
+    /// Drop and recreate the index (parameters are kept for later lazy creation)
+    Rebuild {
+        /// Distance metric (cosine, l2)
+        #[arg(long, default_value = "cosine")]
+        metric: String,
+
+        /// Neighbour compression (float1bit, float8, float16, floatb16, float32, none)
+        #[arg(long, default_value = "float8")]
+        compress_neighbors: String,
+
+        /// Maximum graph neighbours per node
+        #[arg(long, default_value = "32")]
+        max_neighbors: u32,
+
+        /// Candidate list size while searching (libsql default if unset)
+        #[arg(long)]
+        search_l: Option<u32>,
+
+        /// Candidate list size while inserting (libsql default if unset)
+        #[arg(long)]
+        insert_l: Option<u32>,
+    },
+
+    /// Convert legacy raw-BLOB embeddings to the native vector format
+    Convert,
+
+    /// Drop the index (vector search falls back to the legacy scan)
+    Drop,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -253,7 +586,18 @@ async fn main() -> Result<()> {
         Commands::Remove { name } => cmd_remove(&db_path, &name).await,
         Commands::List => cmd_list(&db_path).await,
         Commands::Ls { path, format } => cmd_ls(&db_path, path.as_deref(), &format).await,
-        Commands::Index { name } => cmd_index(&db_path, name.as_deref()).await,
+        Commands::Index {
+            name,
+            notebook_outputs,
+            archives,
+        } => {
+            let options = ParseOptions {
+                notebook_outputs,
+                archives,
+                ..Default::default()
+            };
+            cmd_index(&db_path, name.as_deref(), options).await
+        }
         Commands::Embed {
             name,
             force,
@@ -267,21 +611,61 @@ async fn main() -> Result<()> {
             limit,
             min_score,
             collection,
+            exclude_collection,
+            file_types,
+            mime_types,
+            path_prefix,
+            path_glob,
             from_date,
             to_date,
             include_binary,
+            facets,
+            no_fuzzy,
+            passages,
+            snippets,
+            snippet_size,
+            highlight,
+            half_life,
+            recency_weight,
+            min_length,
+            depth_penalty,
+            weights,
+            meta,
             format,
         } => {
+            let ranking = RankingOptions {
+                half_life_days: half_life,
+                recency_weight,
+                min_length,
+                depth_penalty,
+                collection_weights: parse_weights(&weights)?,
+            };
+            let filter = DocumentFilter {
+                collections: collection,
+                exclude_collections: exclude_collection,
+                file_types,
+                mime_types,
+                path_prefix,
+                path_glob,
+                from_date,
+                to_date,
+                metadata: parse_meta(&meta)?,
+            };
             cmd_search(
                 &db_path,
                 &query,
                 &mode,
                 limit,
                 min_score,
-                collection.as_deref(),
-                from_date.as_deref(),
-                to_date.as_deref(),
+                filter,
                 include_binary,
+                facets,
+                !no_fuzzy,
+                passages,
+                snippets,
+                snippet_size,
+                &highlight,
+                ranking,
                 &format,
             )
             .await
@@ -293,6 +677,60 @@ async fn main() -> Result<()> {
             line_numbers,
             format,
         } => cmd_get(&db_path, &path, from, max_lines, line_numbers, &format).await,
+        Commands::Find {
+            query,
+            limit,
+            collection,
+            format,
+        } => cmd_find(&db_path, &query, limit, collection.as_deref(), &format).await,
+        Commands::Grep {
+            pattern,
+            regex,
+            ignore_case,
+            context,
+            limit,
+            collection,
+            from_date,
+            to_date,
+            format,
+        } => {
+            let options = qfs::search::GrepOptions {
+                regex,
+                ignore_case,
+                context,
+                limit,
+                collection,
+                from_date,
+                to_date,
+            };
+            cmd_grep(&db_path, &pattern, &options, &format).await
+        }
+        Commands::Similar {
+            path,
+            limit,
+            collection,
+            format,
+        } => cmd_similar(&db_path, &path, limit, collection.as_deref(), &format).await,
+        Commands::Eval {
+            queries,
+            k,
+            mode,
+            collection,
+            baseline,
+            save,
+            per_query,
+            format,
+        } => {
+            let options = EvalArgs {
+                k,
+                modes: mode,
+                collections: collection,
+                baseline,
+                save,
+                per_query,
+            };
+            cmd_eval(&db_path, &queries, options, &format).await
+        }
         Commands::MultiGet {
             pattern,
             max_bytes,
@@ -301,7 +739,11 @@ async fn main() -> Result<()> {
         } => cmd_multi_get(&db_path, &pattern, max_bytes, max_lines, &format).await,
         Commands::Status => cmd_status(&db_path).await,
         Commands::Serve => cmd_serve(&db_path).await,
+        Commands::Daemon => daemon::run(&db_path).await,
         Commands::Context { action } => cmd_context(&db_path, action).await,
+        Commands::VectorIndex { action } => cmd_vector_index(&db_path, action).await,
+        Commands::Ranking { action } => cmd_ranking(&db_path, action).await,
+        Commands::Parsers { action } => cmd_parsers(&db_path, action).await,
     }
 }
 
@@ -513,16 +955,32 @@ async fn cmd_ls(db_path: &Path, path: Option<&str>, format: &str) -> Result<()>
     Ok(())
 }
 
-async fn cmd_index(db_path: &Path, name: Option<&str>) -> Result<()> {
+/// Reports files that failed to index, such as a parser command exiting
+/// with an error or timing out
+struct ErrorReporter;
+
+impl qfs::indexer::IndexProgress for ErrorReporter {
+    fn on_file(&mut self, path: &Path, status: qfs::indexer::FileStatus) {
+        if let qfs::indexer::FileStatus::Error(e) = status {
+            eprintln!("  {}: {}", path.display(), e);
+        }
+    }
+
+    fn on_complete(&mut self, _stats: &qfs::indexer::IndexStats) {}
+}
+
+async fn cmd_index(db_path: &Path, name: Option<&str>, options: ParseOptions) -> Result<()> {
     let store = Store::open(db_path).await?;
-    let indexer = Indexer::new(&store);
+    let indexer = Indexer::with_options(&store, options);
 
     let stats = if let Some(collection_name) = name {
         println!("Indexing collection '{}'...", collection_name);
-        indexer.index_collection(collection_name).await?
+        indexer
+            .index_collection_with_progress(collection_name, &mut ErrorReporter)
+            .await?
     } else {
         println!("Indexing all collections...");
-        indexer.index_all().await?
+        indexer.index_all_with_progress(&mut ErrorReporter).await?
     };
 
     println!(
@@ -592,14 +1050,19 @@ async fn cmd_embed(
             continue;
         }
 
-        // Delete existing embeddings if force
+        // Delete existing embeddings and passages if force
         if force {
             store.delete_embeddings(&doc.hash).await?;
+            store.delete_chunks(&doc.hash).await?;
         }
 
-        // Get document content
-        let content = match store.get_content(&doc.hash).await {
-            Ok(c) => c,
+        // Get document text (extracted text for PDFs)
+        let text = match store.get_text(&doc.hash).await {
+            Ok(Some(t)) => t,
+            Ok(None) => {
+                skipped += 1; // Binary file
+                continue;
+            }
             Err(e) => {
                 errors += 1;
                 tracing::warn!("Failed to get content for {}: {}", doc.path, e);
@@ -607,14 +1070,6 @@ async fn cmd_embed(
             }
         };
 
-        let text = match String::from_utf8(content.data) {
-            Ok(t) => t,
-            Err(_) => {
-                skipped += 1; // Binary file
-                continue;
-            }
-        };
-
         if text.trim().is_empty() {
             skipped += 1;
             continue;
@@ -638,7 +1093,7 @@ async fn cmd_embed(
             }
         };
 
-        // Store embeddings
+        // Store embeddings, plus the same chunks for passage-level BM25
         for (chunk, embedding) in chunks.iter().zip(embeddings.iter()) {
             let bytes = embedding_to_bytes(embedding);
             store
@@ -650,6 +1105,18 @@ async fn cmd_embed(
                     &bytes,
                 )
                 .await?;
+
+            let (line_start, line_end) =
+                qfs::line_range(&text, chunk.char_offset, chunk.char_length);
+            let chunk_row = qfs::store::ChunkRow {
+                hash: doc.hash.clone(),
+                chunk_index: chunk.index as i32,
+                char_offset: chunk.char_offset as i32,
+                char_length: chunk.char_length as i32,
+                line_start: line_start as i32,
+                line_end: line_end as i32,
+            };
+            store.insert_chunk(&chunk_row, &chunk.text).await?;
         }
 
         embedded += 1;
@@ -694,102 +1161,312 @@ async fn cmd_search(
     mode: &str,
     limit: usize,
     min_score: f64,
-    collection: Option<&str>,
-    from_date: Option<&str>,
-    to_date: Option<&str>,
+    filter: DocumentFilter,
     include_binary: bool,
+    facets: bool,
+    fuzzy: bool,
+    passages: bool,
+    snippets: usize,
+    snippet_size: usize,
+    highlight: &str,
+    ranking: RankingOptions,
     format: &str,
 ) -> Result<()> {
-    let store = Store::open(db_path).await?;
-
+    let highlight: Highlight = highlight.parse()?;
     let search_mode: SearchMode = mode.parse()?;
     let options = SearchOptions {
-        mode: search_mode.clone(),
+        mode: search_mode,
         limit,
         min_score,
-        collection: collection.map(String::from),
+        collection: None,
+        collections: filter.collections,
+        exclude_collections: filter.exclude_collections,
+        file_types: filter.file_types,
+        mime_types: filter.mime_types,
+        path_prefix: filter.path_prefix,
+        path_glob: filter.path_glob,
         include_binary,
-        from_date: from_date.map(String::from),
-        to_date: to_date.map(String::from),
+        from_date: filter.from_date,
+        to_date: filter.to_date,
+        metadata: filter.metadata,
+        facets,
+        fuzzy,
+        passages,
+        snippets,
+        snippet_size,
+        ranking,
     };
 
-    let searcher = qfs::search::Searcher::new(&store);
+    // A running `qfs daemon` already has the model loaded
+    if let Some(response) = daemon::search(db_path, query, &options).await? {
+        return print_search_response(query, &response, highlight, format);
+    }
+
+    let store = Store::open(db_path).await?;
+    let response = execute_search(&store, &QueryEmbedder::new(), query, options).await?;
+    print_search_response(query, &response, highlight, format)
+}
 
-    // For vector/hybrid modes, we need to embed the query first
-    let results = match search_mode {
-        SearchMode::Bm25 => searcher.search(query, options).await?,
-        SearchMode::Vector => {
-            // Check if embeddings exist
-            let embed_count = store.count_embeddings(collection).await?;
-            if embed_count == 0 {
+/// Run a search, embedding the query for vector and hybrid modes
+async fn execute_search(
+    store: &Store,
+    embedder: &QueryEmbedder,
+    query: &str,
+    options: SearchOptions,
+) -> Result<qfs::search::SearchResponse> {
+    let searcher = qfs::search::Searcher::new(store);
+
+    let results = match options.mode {
+        SearchMode::Bm25 => {
+            // BM25 computes facets and spelling suggestions itself
+            return Ok(searcher.search_detailed(query, options).await?);
+        }
+        SearchMode::Vector | SearchMode::Hybrid => {
+            let collection = options.filter().single_collection().map(String::from);
+            if store.count_embeddings(collection.as_deref()).await? == 0 {
                 anyhow::bail!(
-                    "No embeddings found. Run 'qfs embed' first to enable vector search."
+                    "No embeddings found. Run 'qfs embed' first to enable {} search.",
+                    options.mode
                 );
             }
 
-            // Initialize embedder and embed query
-            let embedder =
-                qfs_embed::Embedder::new().map_err(|e| anyhow::anyhow!("Embedder error: {}", e))?;
-            let query_embedding = embedder
-                .embed_one(query)
-                .map_err(|e| anyhow::anyhow!("Embedding error: {}", e))?;
+            let query_embedding = embedder.embed(store, query).await?;
+            if options.mode == SearchMode::Vector {
+                searcher
+                    .search_vector_with_embedding(&query_embedding, &options)
+                    .await?
+            } else {
+                searcher
+                    .search_hybrid_with_embedding(query, &query_embedding, &options)
+                    .await?
+            }
+        }
+    };
+
+    let mut response = qfs::search::SearchResponse {
+        results,
+        corrected_query: searcher.last_corrected_query(),
+        vector_search: searcher.last_vector_path(),
+        ..Default::default()
+    };
+    if options.facets {
+        let facet_query = response.corrected_query.as_deref().unwrap_or(query);
+        let facets = searcher
+            .facets(facet_query, &response.results, &options)
+            .await?;
+        response.facets = Some(facets);
+    }
+    if response.results.is_empty() && options.mode == SearchMode::Hybrid {
+        response.suggestions = searcher.suggest(query).await?;
+    }
 
-            searcher
-                .search_vector_with_embedding(&query_embedding, &options)
-                .await?
+    Ok(response)
+}
+
+/// Embeds search queries through the store's query-embedding cache, loading
+/// the model only on the first cache miss
+struct QueryEmbedder {
+    model: qfs_embed::Model,
+    embedder: tokio::sync::OnceCell<qfs_embed::Embedder>,
+}
+
+impl QueryEmbedder {
+    /// Default model, loaded lazily
+    fn new() -> Self {
+        QueryEmbedder {
+            model: qfs_embed::Model::default(),
+            embedder: tokio::sync::OnceCell::new(),
         }
-        SearchMode::Hybrid => {
-            // Check if embeddings exist
-            let embed_count = store.count_embeddings(collection).await?;
-            if embed_count == 0 {
-                anyhow::bail!(
-                    "No embeddings found. Run 'qfs embed' first to enable hybrid search."
-                );
-            }
+    }
 
-            // Initialize embedder and embed query
-            let embedder =
-                qfs_embed::Embedder::new().map_err(|e| anyhow::anyhow!("Embedder error: {}", e))?;
-            let query_embedding = embedder
-                .embed_one(query)
-                .map_err(|e| anyhow::anyhow!("Embedding error: {}", e))?;
+    /// Default model, loaded now
+    fn load() -> Result<Self> {
+        let query_embedder = QueryEmbedder::new();
+        let embedder =
+            qfs_embed::Embedder::new().map_err(|e| anyhow::anyhow!("Embedder error: {}", e))?;
+        let _ = query_embedder.embedder.set(embedder);
+        Ok(query_embedder)
+    }
 
-            searcher
-                .search_hybrid_with_embedding(query, &query_embedding, &options)
-                .await?
+    async fn embed(&self, store: &Store, query: &str) -> Result<Vec<f32>> {
+        let model = self.model.name();
+        if let Some(embedding) = store.get_query_embedding(model, query).await? {
+            return Ok(embedding);
         }
-    };
 
-    if format == "json" {
-        println!("{}", serde_json::to_string_pretty(&results)?);
-    } else {
-        if results.is_empty() {
-            println!("No results found for '{}'", query);
-            return Ok(());
+        let embedder = self
+            .embedder
+            .get_or_try_init(|| async {
+                qfs_embed::Embedder::with_config(qfs_embed::EmbedConfig {
+                    model: self.model,
+                    ..Default::default()
+                })
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Embedder error: {}", e))?;
+        let embedding = embedder
+            .embed_one(&qfs::store::normalize_query_key(query))
+            .map_err(|e| anyhow::anyhow!("Embedding error: {}", e))?;
+
+        // The cache is an optimization; a read-only database still searches
+        if let Err(e) = store.put_query_embedding(model, query, &embedding).await {
+            tracing::warn!("Could not cache query embedding: {}", e);
         }
+        Ok(embedding)
+    }
+}
 
-        println!("Found {} results for '{}':\n", results.len(), query);
-        for (i, result) in results.iter().enumerate() {
-            println!("{}. {} (score: {:.3})", i + 1, result.path, result.score);
-            if let Some(ref snippet) = result.snippet {
-                println!("   {}", snippet.replace('\n', "\n   "));
-            }
-            println!();
+/// How `<mark>` tags in snippets are rendered in text output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    /// ANSI bold red
+    Ansi,
+    /// Tags removed
+    Plain,
+    /// Tags left as-is
+    Mark,
+}
+
+impl std::str::FromStr for Highlight {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        use std::io::IsTerminal;
+
+        match s.to_lowercase().as_str() {
+            "auto" if std::io::stdout().is_terminal() => Ok(Highlight::Ansi),
+            "auto" | "plain" => Ok(Highlight::Plain),
+            "ansi" => Ok(Highlight::Ansi),
+            "mark" => Ok(Highlight::Mark),
+            _ => anyhow::bail!(
+                "Invalid highlight mode: {} (expected auto, ansi, plain or mark)",
+                s
+            ),
         }
     }
+}
 
-    Ok(())
+impl Highlight {
+    fn render(self, snippet: &str) -> String {
+        match self {
+            Highlight::Ansi => snippet
+                .replace("<mark>", "\x1b[1;31m")
+                .replace("</mark>", "\x1b[0m"),
+            Highlight::Plain => snippet.replace("<mark>", "").replace("</mark>", ""),
+            Highlight::Mark => snippet.to_string(),
+        }
+    }
 }
 
-async fn cmd_get(
-    db_path: &Path,
-    path: &str,
-    from_line: Option<usize>,
-    max_lines: Option<usize>,
-    line_numbers: bool,
+/// Print search results with any facets and spelling suggestions
+fn print_search_response(
+    query: &str,
+    response: &qfs::search::SearchResponse,
+    highlight: Highlight,
     format: &str,
 ) -> Result<()> {
-    let store = Store::open(db_path).await?;
+    let results = &response.results;
+
+    if format == "json" {
+        if response.has_details() {
+            println!("{}", serde_json::to_string_pretty(response)?);
+        } else {
+            println!("{}", serde_json::to_string_pretty(results)?);
+        }
+        return Ok(());
+    }
+
+    if let Some(ref corrected) = response.corrected_query {
+        println!(
+            "No results for '{}', showing results for '{}'\n",
+            query, corrected
+        );
+    } else if !response.suggestions.is_empty() {
+        let terms: Vec<&str> = response
+            .suggestions
+            .iter()
+            .map(|s| s.suggestion.as_str())
+            .collect();
+        println!("Did you mean: {}?\n", terms.join(", "));
+    }
+
+    if results.is_empty() {
+        println!("No results found for '{}'", query);
+        return Ok(());
+    }
+
+    let shown_query = response.corrected_query.as_deref().unwrap_or(query);
+    println!("Found {} results for '{}':\n", results.len(), shown_query);
+    for (i, result) in results.iter().enumerate() {
+        let location = match (result.line_start, result.line_end) {
+            (Some(start), Some(end)) if end > start => {
+                format!("{}:{}-{}", result.path, start, end)
+            }
+            (Some(start), _) => format!("{}:{}", result.path, start),
+            _ => result.path.clone(),
+        };
+        let location = match result.page {
+            Some(page) => format!("{} [{} {}]", location, page_label(&result.mime_type), page),
+            None => location,
+        };
+        println!("{}. {} (score: {:.3})", i + 1, location, result.score);
+        if !result.snippets.is_empty() {
+            for snippet in &result.snippets {
+                println!("   L{}: {}", snippet.line, highlight.render(&snippet.text));
+            }
+        } else if let Some(ref snippet) = result.snippet {
+            println!("   {}", highlight.render(snippet).replace('\n', "\n   "));
+        }
+        println!();
+    }
+
+    if let Some(ref facet_counts) = response.facets {
+        print_facets(facet_counts);
+    }
+
+    if let Some(path) = response.vector_search {
+        match path {
+            qfs::store::VectorSearchPath::Native => println!("Vector search: native index"),
+            qfs::store::VectorSearchPath::Legacy => {
+                println!("Vector search: legacy scan (see 'qfs vector-index status')")
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print facet counts as indented groups
+fn print_facets(facets: &qfs::search::Facets) {
+    println!("Facets ({} matching documents):", facets.total);
+
+    let groups = [
+        ("Collections", &facets.collections),
+        ("File types", &facets.file_types),
+        ("Paths", &facets.path_prefixes),
+        ("Modified", &facets.modified),
+    ];
+
+    for (label, counts) in groups {
+        if counts.is_empty() {
+            continue;
+        }
+        println!("\n  {}:", label);
+        for facet in counts {
+            println!("    {:<32} {}", facet.value, facet.count);
+        }
+    }
+}
+
+async fn cmd_get(
+    db_path: &Path,
+    path: &str,
+    from_line: Option<usize>,
+    max_lines: Option<usize>,
+    line_numbers: bool,
+    format: &str,
+) -> Result<()> {
+    let store = Store::open(db_path).await?;
 
     // Parse :linenum suffix if --from not provided
     let (clean_path, suffix_line) = qfs::parse_path_with_line(path);
@@ -804,27 +1481,44 @@ async fn cmd_get(
         if parts.len() != 2 {
             anyhow::bail!("Path must be in format 'collection/relative_path' or docid");
         }
-        store.get_document(parts[0], parts[1]).await?
+        match store.get_document(parts[0], parts[1]).await {
+            Ok(doc) => doc,
+            Err(qfs::Error::DocumentNotFound(_)) => {
+                let candidates = store.find_paths(clean_path, None, 5).await?;
+                if candidates.is_empty() {
+                    anyhow::bail!("Document not found: {}", clean_path);
+                }
+                let paths: Vec<String> = candidates
+                    .iter()
+                    .map(|m| format!("  {}/{}", m.collection, m.path))
+                    .collect();
+                anyhow::bail!(
+                    "Document not found: {}\n\nDid you mean:\n{}",
+                    clean_path,
+                    paths.join("\n")
+                );
+            }
+            Err(e) => return Err(e.into()),
+        }
     };
 
     // Get content
-    let content = store.get_content(&doc.hash).await?;
-    let text = String::from_utf8(content.data.clone())
-        .map_err(|_| anyhow::anyhow!("Content is not valid UTF-8"))?;
+    let text = store
+        .get_text(&doc.hash)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Content is binary and has no extracted text"))?;
 
-    // Apply line extraction
-    let mut output = qfs::extract_lines(&text, effective_from, max_lines);
-
-    // Add line numbers if requested
-    if line_numbers {
-        let start = effective_from.unwrap_or(1);
-        output = qfs::add_line_numbers(&output, start);
-    }
+    // Apply line extraction, keeping table headers
+    let mime_type = store.get_content_type(&doc.hash).await?;
+    let output =
+        qfs::extract_document_lines(&text, &mime_type, effective_from, max_lines, line_numbers);
 
     if format == "json" {
+        let metadata = store.get_document_metadata(doc.id).await?;
         let result = serde_json::json!({
             "path": format!("{}/{}", doc.collection, doc.path),
             "title": doc.title,
+            "metadata": metadata,
             "fromLine": effective_from,
             "lineCount": output.lines().count(),
             "content": output,
@@ -844,6 +1538,242 @@ async fn cmd_get(
     Ok(())
 }
 
+async fn cmd_find(
+    db_path: &Path,
+    query: &str,
+    limit: usize,
+    collection: Option<&str>,
+    format: &str,
+) -> Result<()> {
+    let store = Store::open(db_path).await?;
+    let matches = store.find_paths(query, collection, limit).await?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+        return Ok(());
+    }
+
+    if matches.is_empty() {
+        println!("No files matching '{}'", query);
+        return Ok(());
+    }
+
+    for m in &matches {
+        match m.title {
+            Some(ref title) => println!("{}/{}  {}  {}", m.collection, m.path, m.docid, title),
+            None => println!("{}/{}  {}", m.collection, m.path, m.docid),
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_grep(
+    db_path: &Path,
+    pattern: &str,
+    options: &qfs::search::GrepOptions,
+    format: &str,
+) -> Result<()> {
+    let store = Store::open(db_path).await?;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let matches = searcher.grep(pattern, options).await?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+        return Ok(());
+    }
+
+    if matches.is_empty() {
+        println!("No matches found for '{}'", pattern);
+        return Ok(());
+    }
+
+    // grep-style output: "path:line:col:text" for matches, "path-line-text" for context
+    for (i, m) in matches.iter().enumerate() {
+        if options.context > 0 && i > 0 {
+            println!("--");
+        }
+        let first = m.line - m.before.len();
+        for (offset, line) in m.before.iter().enumerate() {
+            println!("{}-{}-{}", m.path, first + offset, line);
+        }
+        println!("{}:{}:{}:{}", m.path, m.line, m.column, m.text);
+        for (offset, line) in m.after.iter().enumerate() {
+            println!("{}-{}-{}", m.path, m.line + 1 + offset, line);
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_similar(
+    db_path: &Path,
+    path: &str,
+    limit: usize,
+    collection: Option<&str>,
+    format: &str,
+) -> Result<()> {
+    let store = Store::open(db_path).await?;
+
+    let options = SearchOptions {
+        limit,
+        collection: collection.map(String::from),
+        ..Default::default()
+    };
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let results = searcher.similar(path, options).await?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("No similar documents found for '{}'", path);
+        return Ok(());
+    }
+
+    println!("Documents similar to '{}':\n", path);
+    for (i, result) in results.iter().enumerate() {
+        println!("{}. {} (score: {:.3})", i + 1, result.path, result.score);
+        if let Some(ref title) = result.title {
+            println!("   {}", title);
+        }
+    }
+
+    Ok(())
+}
+
+/// Options for `qfs eval`
+struct EvalArgs {
+    k: usize,
+    modes: Vec<String>,
+    collections: Vec<String>,
+    baseline: Option<PathBuf>,
+    save: Option<PathBuf>,
+    per_query: bool,
+}
+
+async fn cmd_eval(db_path: &Path, queries_path: &Path, args: EvalArgs, format: &str) -> Result<()> {
+    use qfs::eval::{evaluate, load_queries, EvalOptions, EvalReport};
+
+    let queries = load_queries(queries_path)?;
+    if queries.is_empty() {
+        anyhow::bail!("No queries in {}", queries_path.display());
+    }
+    let baseline: Option<EvalReport> = match args.baseline {
+        Some(ref path) => Some(serde_json::from_str(&std::fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    let store = Store::open(db_path).await?;
+    let has_embeddings = store.count_embeddings(None).await? > 0;
+
+    let modes: Vec<SearchMode> = if args.modes.is_empty() {
+        if has_embeddings {
+            vec![SearchMode::Bm25, SearchMode::Vector, SearchMode::Hybrid]
+        } else {
+            vec![SearchMode::Bm25]
+        }
+    } else {
+        args.modes
+            .iter()
+            .map(|m| m.parse())
+            .collect::<qfs::Result<_>>()?
+    };
+    let needs_embedder = modes.iter().any(|m| *m != SearchMode::Bm25);
+    if needs_embedder && !has_embeddings {
+        anyhow::bail!(
+            "No embeddings found. Run 'qfs embed' first to evaluate vector or hybrid search."
+        );
+    }
+
+    let options = EvalOptions {
+        k: args.k,
+        modes,
+        search: SearchOptions {
+            collections: args.collections,
+            ..Default::default()
+        },
+    };
+
+    let report = if needs_embedder {
+        let embedder =
+            qfs_embed::Embedder::new().map_err(|e| anyhow::anyhow!("Embedder error: {}", e))?;
+        let embed = |text: &str| {
+            embedder
+                .embed_one(text)
+                .map_err(|e| qfs::Error::EmbeddingError(e.to_string()))
+        };
+        evaluate(&store, &queries, &options, Some(&embed)).await?
+    } else {
+        evaluate(&store, &queries, &options, None).await?
+    };
+
+    if let Some(ref path) = args.save {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    }
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("Evaluated {} queries at k={}\n", queries.len(), report.k);
+    if args.per_query {
+        println!(
+            "{:<20} {:<8} {:>7} {:>7} {:>7} {:>9}",
+            "query", "mode", "nDCG", "RR", "recall", "ms"
+        );
+        for q in &report.queries {
+            println!(
+                "{:<20} {:<8} {:>7.3} {:>7.3} {:>7.3} {:>9.1}",
+                q.id, q.mode, q.ndcg, q.reciprocal_rank, q.recall, q.latency_ms
+            );
+        }
+        println!();
+    }
+
+    let k = report.k;
+    println!(
+        "{:<8} {:>16} {:>16} {:>16} {:>9} {:>9}",
+        "mode",
+        format!("nDCG@{}", k),
+        "MRR",
+        format!("recall@{}", k),
+        "p50 ms",
+        "p95 ms"
+    );
+    for summary in &report.summaries {
+        let previous = baseline.as_ref().and_then(|b| b.summary(&summary.mode));
+        let metric = |value: f64, before: Option<f64>| match before {
+            Some(before) => format!("{:.3} ({:+.3})", value, value - before),
+            None => format!("{:.3}", value),
+        };
+        println!(
+            "{:<8} {:>16} {:>16} {:>16} {:>9.1} {:>9.1}",
+            summary.mode,
+            metric(summary.ndcg, previous.map(|p| p.ndcg)),
+            metric(summary.mrr, previous.map(|p| p.mrr)),
+            metric(summary.recall, previous.map(|p| p.recall)),
+            summary.latency_ms.p50,
+            summary.latency_ms.p95
+        );
+    }
+    if let Some(ref baseline) = baseline {
+        if baseline.k != report.k {
+            println!("\nNote: baseline was measured at k={}", baseline.k);
+        }
+    }
+    if let Some(ref path) = args.save {
+        println!("\nReport saved to {}", path.display());
+    }
+
+    Ok(())
+}
+
 async fn cmd_multi_get(
     db_path: &Path,
     pattern: &str,
@@ -928,6 +1858,298 @@ async fn cmd_status(db_path: &Path) -> Result<()> {
             "not indexed"
         }
     );
+    if !has_index && docs_with_embeddings > 0 {
+        println!("  (run 'qfs vector-index status' for details)");
+    }
+
+    Ok(())
+}
+
+async fn cmd_vector_index(db_path: &Path, action: VectorIndexAction) -> Result<()> {
+    let store = Store::open(db_path).await?;
+
+    match action {
+        VectorIndexAction::Status { format } => {
+            let status = store.vector_index_status().await?;
+
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+                return Ok(());
+            }
+
+            let state = if status.exists {
+                "present"
+            } else if status.disabled {
+                "dropped (run 'qfs vector-index rebuild')"
+            } else {
+                "missing"
+            };
+            println!("Vector index: {}", state);
+            println!("Search path: {}", status.search_path);
+            println!("Embedding column: {}", status.column_type);
+            println!("Embeddings: {}", status.embeddings);
+            if status.legacy_embeddings > 0 {
+                println!(
+                    "Legacy embeddings: {} (run 'qfs vector-index convert')",
+                    status.legacy_embeddings
+                );
+            }
+
+            let params = &status.params;
+            println!(
+                "\nParameters{}:",
+                if status.exists {
+                    ""
+                } else {
+                    " (for next build)"
+                }
+            );
+            println!("  metric: {}", params.metric);
+            println!(
+                "  compress_neighbors: {}",
+                params.compress_neighbors.as_deref().unwrap_or("none")
+            );
+            for (name, value) in [
+                ("max_neighbors", params.max_neighbors),
+                ("search_l", params.search_l),
+                ("insert_l", params.insert_l),
+            ] {
+                if let Some(value) = value {
+                    println!("  {}: {}", name, value);
+                }
+            }
+
+            if let Some(bytes) = status.index_bytes {
+                println!("\nIndex size: {}", format_bytes(bytes as i64));
+            }
+            if let Some(ref error) = status.last_error {
+                println!("\nLast build error: {}", error);
+            }
+        }
+
+        VectorIndexAction::Rebuild {
+            metric,
+            compress_neighbors,
+            max_neighbors,
+            search_l,
+            insert_l,
+        } => {
+            let params = qfs::store::VectorIndexParams {
+                metric,
+                compress_neighbors: (compress_neighbors != "none").then_some(compress_neighbors),
+                max_neighbors: Some(max_neighbors),
+                search_l,
+                insert_l,
+            };
+
+            let start = std::time::Instant::now();
+            store.rebuild_vector_index(&params).await?;
+            let status = store.vector_index_status().await?;
+            println!(
+                "Rebuilt vector index over {} embeddings in {:.1}s",
+                status.embeddings,
+                start.elapsed().as_secs_f64()
+            );
+        }
+
+        VectorIndexAction::Convert => {
+            let stats = store.convert_legacy_embeddings().await?;
+            if stats.converted == 0 && stats.skipped == 0 {
+                println!("No legacy embeddings to convert.");
+            } else {
+                println!(
+                    "Converted {} embeddings to F32_BLOB({})",
+                    stats.converted, stats.dimensions
+                );
+                if stats.skipped > 0 {
+                    println!(
+                        "Dropped {} embeddings with mismatched dimensions (run 'qfs embed --force' to regenerate)",
+                        stats.skipped
+                    );
+                }
+            }
+        }
+
+        VectorIndexAction::Drop => {
+            if store.drop_vector_index().await? {
+                println!(
+                    "Dropped vector index. Vector search uses the legacy scan until 'qfs vector-index rebuild'."
+                );
+            } else {
+                println!("No vector index to drop.");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse repeated `collection=weight` arguments
+fn parse_weights(weights: &[String]) -> Result<HashMap<String, f64>> {
+    weights
+        .iter()
+        .map(|arg| {
+            let (name, weight) = arg
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected COLLECTION=WEIGHT, got '{}'", arg))?;
+            let weight: f64 = weight
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid weight in '{}'", arg))?;
+            Ok((name.to_string(), weight))
+        })
+        .collect()
+}
+
+/// Parse repeated `key=value` metadata filters
+fn parse_meta(filters: &[String]) -> Result<Vec<(String, String)>> {
+    filters
+        .iter()
+        .map(|arg| {
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected KEY=VALUE, got '{}'", arg))?;
+            Ok((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+async fn cmd_ranking(db_path: &Path, action: RankingAction) -> Result<()> {
+    let store = Store::open(db_path).await?;
+
+    match action {
+        RankingAction::Set {
+            collection,
+            weight,
+            half_life,
+            recency_weight,
+            min_length,
+            depth_penalty,
+        } => {
+            let ranking = qfs::search::CollectionRanking {
+                collection,
+                weight,
+                signals: RankingOptions {
+                    half_life_days: half_life,
+                    recency_weight,
+                    min_length,
+                    depth_penalty,
+                    collection_weights: HashMap::new(),
+                },
+            };
+            store.set_collection_ranking(&ranking).await?;
+            println!("Saved ranking profile for '{}'", ranking.collection);
+        }
+
+        RankingAction::Show { format } => {
+            let rankings = store.list_collection_rankings().await?;
+
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&rankings)?);
+                return Ok(());
+            }
+
+            if rankings.is_empty() {
+                println!("No ranking profiles. Use 'qfs ranking set <collection>' to add one.");
+                return Ok(());
+            }
+
+            for ranking in rankings {
+                println!("{}", ranking.collection);
+                println!("  weight: {}", ranking.weight);
+                let signals = &ranking.signals;
+                if let Some(days) = signals.half_life_days {
+                    println!("  half-life: {} days", days);
+                }
+                if let Some(share) = signals.recency_weight {
+                    println!("  recency weight: {}", share);
+                }
+                if let Some(bytes) = signals.min_length {
+                    println!("  min length: {} bytes", bytes);
+                }
+                if let Some(penalty) = signals.depth_penalty {
+                    println!("  depth penalty: {}", penalty);
+                }
+            }
+        }
+
+        RankingAction::Clear { collection } => {
+            if store.remove_collection_ranking(&collection).await? {
+                println!("Removed ranking profile for '{}'", collection);
+            } else {
+                println!("No ranking profile for '{}'", collection);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_parsers(db_path: &Path, action: ParsersAction) -> Result<()> {
+    let store = Store::open(db_path).await?;
+
+    match action {
+        ParsersAction::Set {
+            collection,
+            extension,
+            command,
+            timeout,
+        } => {
+            let parser = qfs::parser::CollectionParser {
+                collection,
+                extension,
+                command,
+                timeout_secs: timeout,
+            };
+            store.set_collection_parser(&parser).await?;
+            println!(
+                "Saved parser for '{}' files in '{}'. Run 'qfs index {}' to re-parse them.",
+                parser
+                    .extension
+                    .trim_start_matches("*.")
+                    .trim_start_matches('.'),
+                parser.collection,
+                parser.collection
+            );
+        }
+
+        ParsersAction::Show { collection, format } => {
+            let parsers = store.list_collection_parsers(collection.as_deref()).await?;
+
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&parsers)?);
+                return Ok(());
+            }
+
+            if parsers.is_empty() {
+                println!("No external parsers. Use 'qfs parsers set <collection> <extension> <command>' to add one.");
+                return Ok(());
+            }
+
+            for parser in parsers {
+                println!(
+                    "{} .{}: {} (timeout {}s)",
+                    parser.collection, parser.extension, parser.command, parser.timeout_secs
+                );
+            }
+        }
+
+        ParsersAction::Clear {
+            collection,
+            extension,
+        } => {
+            if store
+                .remove_collection_parser(&collection, &extension)
+                .await?
+            {
+                println!(
+                    "Removed parser for '{}' files in '{}'",
+                    extension, collection
+                );
+            } else {
+                println!("No parser for '{}' files in '{}'", extension, collection);
+            }
+        }
+    }
 
     Ok(())
 }
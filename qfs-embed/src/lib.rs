@@ -196,6 +196,8 @@ pub struct TextChunk {
     pub text: String,
     /// Character offset in original document
     pub char_offset: usize,
+    /// Length of the chunk's span in the original document (same units as `char_offset`)
+    pub char_length: usize,
     /// Chunk index (0-based)
     pub index: usize,
 }
@@ -234,10 +236,13 @@ pub fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<TextChun
         let end = (i + chunk_size).min(words.len());
         let chunk_words: Vec<&str> = words[i..end].iter().map(|(_, w)| *w).collect();
         let char_offset = words[i].0;
+        let (last_start, last_word) = words[end - 1];
+        let char_length = last_start + last_word.len() - char_offset;
 
         chunks.push(TextChunk {
             text: chunk_words.join(" "),
             char_offset,
+            char_length,
             index: chunk_index,
         });
 
@@ -313,6 +318,18 @@ mod tests {
         assert_eq!(chunks[0].text, "hello world");
     }
 
+    #[test]
+    fn test_chunk_text_spans() {
+        let text = "one two\n\nthree   four five";
+        let chunks = chunk_text(text, 3, 1);
+
+        assert_eq!(chunks.len(), 2);
+        let span = &text[chunks[0].char_offset..chunks[0].char_offset + chunks[0].char_length];
+        assert_eq!(span, "one two\n\nthree");
+        let span = &text[chunks[1].char_offset..chunks[1].char_offset + chunks[1].char_length];
+        assert_eq!(span, "three   four five");
+    }
+
     #[test]
     fn test_chunk_text_empty() {
         let chunks = chunk_text("", 10, 2);
@@ -0,0 +1,498 @@
+//! Relevance evaluation against judged queries
+//!
+//! A query set is JSONL, one judged query per line:
+//!
+//! ```text
+//! {"id": "async", "query": "async await futures", "relevant": {"corpus/async-await.md": 2, "corpus/rust-guide.md": 1}}
+//! {"query": "connection pooling", "relevant": ["corpus/database-fundamentals.md"], "collection": "corpus"}
+//! ```
+//!
+//! `relevant` maps `collection/relative_path` (or a `#docid`) to a graded
+//! judgment (0 = not relevant), or lists relevant documents with grade 1.
+//! Each query is run in every requested mode and scored with nDCG@k (gain
+//! `2^grade - 1`), reciprocal rank and recall@k over the top `k` distinct
+//! documents. Reports serialize to JSON so runs can be compared.
+
+use crate::error::{Error, Result};
+use crate::search::{SearchMode, SearchOptions, SearchResult, Searcher};
+use crate::store::Store;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Instant;
+
+/// Results fetched per scored document when results are per chunk, so
+/// documents with several matching chunks don't crowd out the top `k`
+const CHUNK_OVERFETCH: usize = 4;
+
+/// Embeds query text for vector and hybrid modes
+pub type QueryEmbedder<'a> = &'a dyn Fn(&str) -> Result<Vec<f32>>;
+
+/// A query with relevance judgments
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EvalQuery {
+    /// Query identifier (defaults to the line number)
+    #[serde(default)]
+    pub id: String,
+    /// Query text
+    pub query: String,
+    /// Judged documents and their grades
+    pub relevant: Judgments,
+    /// Restrict this query to one collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+}
+
+/// Relevance judgments: graded, or a list of documents with grade 1
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Judgments {
+    /// Document to grade (0 = not relevant)
+    Graded(HashMap<String, u32>),
+    /// Relevant documents
+    Binary(Vec<String>),
+}
+
+impl Judgments {
+    /// Grade for each judged document, keyed as written
+    pub fn grades(&self) -> HashMap<String, u32> {
+        match self {
+            Judgments::Graded(grades) => grades.clone(),
+            Judgments::Binary(docs) => docs.iter().map(|d| (d.clone(), 1)).collect(),
+        }
+    }
+}
+
+/// Parse a JSONL query set; blank lines are skipped
+pub fn parse_queries(text: &str) -> Result<Vec<EvalQuery>> {
+    let mut queries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut query: EvalQuery = serde_json::from_str(line)
+            .map_err(|e| Error::ParseError(format!("query set line {}: {}", index + 1, e)))?;
+        if query.id.is_empty() {
+            query.id = (index + 1).to_string();
+        }
+        if !query.relevant.grades().values().any(|grade| *grade > 0) {
+            return Err(Error::ParseError(format!(
+                "query set line {}: '{}' has no relevant documents",
+                index + 1,
+                query.id
+            )));
+        }
+        queries.push(query);
+    }
+    Ok(queries)
+}
+
+/// Load a JSONL query set from disk
+pub fn load_queries(path: &Path) -> Result<Vec<EvalQuery>> {
+    parse_queries(&std::fs::read_to_string(path)?)
+}
+
+/// Normalized DCG of the grades of the top `k` results against the ideal
+/// ordering of all judged `grades`
+pub fn ndcg_at_k(ranked: &[u32], grades: &[u32], k: usize) -> f64 {
+    let mut ideal = grades.to_vec();
+    ideal.sort_unstable_by(|a, b| b.cmp(a));
+    let ideal = dcg(&ideal, k);
+    if ideal == 0.0 {
+        return 0.0;
+    }
+    dcg(ranked, k) / ideal
+}
+
+fn dcg(grades: &[u32], k: usize) -> f64 {
+    grades
+        .iter()
+        .take(k)
+        .enumerate()
+        .fold(0.0, |sum, (i, grade)| {
+            sum + (2f64.powi(*grade as i32) - 1.0) / (i as f64 + 2.0).log2()
+        })
+}
+
+/// 1 / rank of the first relevant result in the top `k`, or 0
+pub fn reciprocal_rank(ranked: &[u32], k: usize) -> f64 {
+    ranked
+        .iter()
+        .take(k)
+        .position(|grade| *grade > 0)
+        .map_or(0.0, |i| 1.0 / (i + 1) as f64)
+}
+
+/// Share of the `relevant` documents found in the top `k`
+pub fn recall_at_k(ranked: &[u32], relevant: usize, k: usize) -> f64 {
+    if relevant == 0 {
+        return 0.0;
+    }
+    let found = ranked.iter().take(k).filter(|grade| **grade > 0).count();
+    found as f64 / relevant as f64
+}
+
+/// How to run an evaluation
+#[derive(Debug, Clone)]
+pub struct EvalOptions {
+    /// Cutoff for all metrics, and the number of results fetched
+    pub k: usize,
+    /// Modes to evaluate, each over the whole query set
+    pub modes: Vec<SearchMode>,
+    /// Filters and ranking applied to every query (mode and limit are set per run)
+    pub search: SearchOptions,
+}
+
+impl Default for EvalOptions {
+    fn default() -> Self {
+        EvalOptions {
+            k: 10,
+            modes: vec![SearchMode::Bm25],
+            search: SearchOptions::default(),
+        }
+    }
+}
+
+/// Result of one query in one mode
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryEval {
+    /// Query identifier
+    pub id: String,
+    /// Search mode
+    pub mode: String,
+    /// nDCG@k
+    pub ndcg: f64,
+    /// Reciprocal rank of the first relevant result
+    pub reciprocal_rank: f64,
+    /// Recall@k
+    pub recall: f64,
+    /// Time to embed and run the query
+    pub latency_ms: f64,
+    /// Top k distinct documents returned
+    pub hits: Vec<String>,
+}
+
+/// Latency distribution in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Latency {
+    /// Mean
+    pub mean: f64,
+    /// Median
+    pub p50: f64,
+    /// 95th percentile
+    pub p95: f64,
+}
+
+impl Latency {
+    fn from_samples(samples: &[f64]) -> Self {
+        if samples.is_empty() {
+            return Latency::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        // Nearest-rank percentile
+        let percentile = |p: f64| {
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Latency {
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+        }
+    }
+}
+
+/// Mean metrics for one mode over the query set
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModeSummary {
+    /// Search mode
+    pub mode: String,
+    /// Number of queries run
+    pub queries: usize,
+    /// Mean nDCG@k
+    pub ndcg: f64,
+    /// Mean reciprocal rank
+    pub mrr: f64,
+    /// Mean recall@k
+    pub recall: f64,
+    /// Per-query latency
+    pub latency_ms: Latency,
+}
+
+/// Full evaluation report
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalReport {
+    /// Metric cutoff
+    pub k: usize,
+    /// When the run finished (RFC 3339)
+    pub created_at: String,
+    /// Summary per mode, in the order run
+    pub summaries: Vec<ModeSummary>,
+    /// Per-query results for every mode
+    pub queries: Vec<QueryEval>,
+}
+
+impl EvalReport {
+    /// Summary for `mode` (`bm25`, `vector` or `hybrid`)
+    pub fn summary(&self, mode: &str) -> Option<&ModeSummary> {
+        self.summaries.iter().find(|s| s.mode == mode)
+    }
+}
+
+/// Run every query in every mode of `options` and score the results.
+///
+/// Vector and hybrid modes need `embed`; its time counts toward latency.
+pub async fn evaluate(
+    store: &Store,
+    queries: &[EvalQuery],
+    options: &EvalOptions,
+    embed: Option<QueryEmbedder<'_>>,
+) -> Result<EvalReport> {
+    let searcher = Searcher::new(store);
+    let mut summaries = Vec::new();
+    let mut evals = Vec::new();
+
+    for mode in &options.modes {
+        if *mode != SearchMode::Bm25 && embed.is_none() {
+            return Err(Error::EmbeddingError(format!(
+                "{} evaluation needs a query embedder",
+                mode
+            )));
+        }
+
+        // Vector results and passages are per chunk; scoring keeps the top
+        // `k` distinct documents
+        let limit = if *mode == SearchMode::Vector || options.search.passages {
+            options.k.saturating_mul(CHUNK_OVERFETCH)
+        } else {
+            options.k
+        };
+
+        let mut mode_evals = Vec::with_capacity(queries.len());
+        for query in queries {
+            let mut search = SearchOptions {
+                mode: *mode,
+                limit,
+                ..options.search.clone()
+            };
+            if query.collection.is_some() {
+                search.collection = query.collection.clone();
+            }
+
+            let start = Instant::now();
+            let results = match (mode, embed) {
+                (SearchMode::Bm25, _) => searcher.search(&query.query, search).await?,
+                (SearchMode::Vector, Some(embed)) => {
+                    let embedding = embed(&query.query)?;
+                    searcher
+                        .search_vector_with_embedding(&embedding, &search)
+                        .await?
+                }
+                (SearchMode::Hybrid, Some(embed)) => {
+                    let embedding = embed(&query.query)?;
+                    searcher
+                        .search_hybrid_with_embedding(&query.query, &embedding, &search)
+                        .await?
+                }
+                (_, None) => unreachable!("checked above"),
+            };
+            let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+            mode_evals.push(score_query(query, mode, &results, options.k, latency_ms));
+        }
+
+        summaries.push(summarize(mode, &mode_evals));
+        evals.extend(mode_evals);
+    }
+
+    Ok(EvalReport {
+        k: options.k,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        summaries,
+        queries: evals,
+    })
+}
+
+/// Grade the distinct documents in `results` and compute the metrics
+fn score_query(
+    query: &EvalQuery,
+    mode: &SearchMode,
+    results: &[SearchResult],
+    k: usize,
+    latency_ms: f64,
+) -> QueryEval {
+    let grades: HashMap<String, u32> = query
+        .relevant
+        .grades()
+        .into_iter()
+        .map(|(doc, grade)| (judgment_key(&doc), grade))
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut hits = Vec::new();
+    let mut ranked = Vec::new();
+    for result in results {
+        if hits.len() == k || !seen.insert(result.path.clone()) {
+            continue;
+        }
+        let docid = result.docid.as_deref().map(judgment_key);
+        let grade = grades
+            .get(&result.path)
+            .or_else(|| docid.as_ref().and_then(|id| grades.get(id)))
+            .copied()
+            .unwrap_or(0);
+        hits.push(result.path.clone());
+        ranked.push(grade);
+    }
+
+    let judged: Vec<u32> = grades.values().copied().collect();
+    let relevant = judged.iter().filter(|grade| **grade > 0).count();
+
+    QueryEval {
+        id: query.id.clone(),
+        mode: mode.to_string(),
+        ndcg: ndcg_at_k(&ranked, &judged, k),
+        reciprocal_rank: reciprocal_rank(&ranked, k),
+        recall: recall_at_k(&ranked, relevant, k),
+        latency_ms,
+        hits,
+    }
+}
+
+/// Docids compare without their `#`; paths compare as written
+fn judgment_key(doc: &str) -> String {
+    match doc.strip_prefix('#') {
+        Some(docid) => docid.to_lowercase(),
+        None => doc.to_string(),
+    }
+}
+
+fn summarize(mode: &SearchMode, evals: &[QueryEval]) -> ModeSummary {
+    let mean = |metric: fn(&QueryEval) -> f64| {
+        if evals.is_empty() {
+            0.0
+        } else {
+            evals.iter().map(metric).sum::<f64>() / evals.len() as f64
+        }
+    };
+    let latencies: Vec<f64> = evals.iter().map(|e| e.latency_ms).collect();
+
+    ModeSummary {
+        mode: mode.to_string(),
+        queries: evals.len(),
+        ndcg: mean(|e| e.ndcg),
+        mrr: mean(|e| e.reciprocal_rank),
+        recall: mean(|e| e.recall),
+        latency_ms: Latency::from_samples(&latencies),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_queries() {
+        let text = r##"
+{"id": "a", "query": "rust", "relevant": {"docs/a.md": 2, "docs/b.md": 0}}
+
+{"query": "async", "relevant": ["docs/c.md", "#ABC123"], "collection": "docs"}
+"##;
+        let queries = parse_queries(text).unwrap();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].id, "a");
+        assert_eq!(queries[0].relevant.grades()["docs/a.md"], 2);
+        assert_eq!(queries[1].id, "4", "defaults to the line number");
+        assert_eq!(queries[1].relevant.grades()["#ABC123"], 1);
+        assert_eq!(queries[1].collection.as_deref(), Some("docs"));
+
+        let err = parse_queries(r#"{"query": "x", "relevant": {"a.md": 0}}"#).unwrap_err();
+        assert!(err.to_string().contains("no relevant documents"));
+        assert!(parse_queries("{not json}").is_err());
+    }
+
+    #[test]
+    fn test_ndcg() {
+        // Perfect ordering
+        assert!((ndcg_at_k(&[3, 2, 1], &[1, 2, 3], 10) - 1.0).abs() < 1e-9);
+        // Nothing relevant retrieved
+        assert_eq!(ndcg_at_k(&[0, 0], &[1], 10), 0.0);
+        // Single relevant doc at rank 2: 1 / log2(3)
+        assert!((ndcg_at_k(&[0, 1], &[1], 10) - 1.0 / 3f64.log2()).abs() < 1e-9);
+        // Cutoff drops the hit
+        assert_eq!(ndcg_at_k(&[0, 1], &[1], 1), 0.0);
+        // Unfound relevant docs still count toward the ideal
+        assert!(ndcg_at_k(&[1], &[1, 1], 10) < 1.0);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_and_recall() {
+        assert_eq!(reciprocal_rank(&[0, 0, 2], 10), 1.0 / 3.0);
+        assert_eq!(reciprocal_rank(&[0, 0, 2], 2), 0.0);
+        assert_eq!(reciprocal_rank(&[], 10), 0.0);
+
+        assert_eq!(recall_at_k(&[1, 0, 1], 4, 10), 0.5);
+        assert_eq!(recall_at_k(&[1, 0, 1], 4, 2), 0.25);
+        assert_eq!(recall_at_k(&[], 0, 10), 0.0);
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let samples: Vec<f64> = (1..=20).map(f64::from).collect();
+        let latency = Latency::from_samples(&samples);
+        assert_eq!(latency.mean, 10.5);
+        assert_eq!(latency.p50, 10.0);
+        assert_eq!(latency.p95, 19.0);
+        assert_eq!(Latency::from_samples(&[]), Latency::default());
+    }
+
+    #[test]
+    fn test_score_query_dedupes_and_matches_docids() {
+        let query = EvalQuery {
+            id: "q".to_string(),
+            query: "rust".to_string(),
+            relevant: Judgments::Graded(HashMap::from([
+                ("docs/a.md".to_string(), 2),
+                ("#BBB222".to_string(), 1),
+            ])),
+            collection: None,
+        };
+        let result = |path: &str, docid: &str| SearchResult {
+            id: 1,
+            path: path.to_string(),
+            name: path.to_string(),
+            mime_type: "text/markdown".to_string(),
+            file_size: 100,
+            is_binary: false,
+            score: 0.5,
+            content: None,
+            content_pointer: None,
+            snippet: None,
+            line_start: None,
+            line_end: None,
+            snippets: Vec::new(),
+            collection: "docs".to_string(),
+            title: None,
+            docid: Some(docid.to_string()),
+            chunk_index: None,
+            page: None,
+            context: None,
+        };
+        let results = vec![
+            result("docs/x.md", "#xxx000"),
+            result("docs/a.md", "#aaa111"),
+            result("docs/a.md", "#aaa111"),
+            result("docs/b.md", "#bbb222"),
+        ];
+
+        let eval = score_query(&query, &SearchMode::Vector, &results, 10, 1.5);
+        assert_eq!(eval.hits, vec!["docs/x.md", "docs/a.md", "docs/b.md"]);
+        assert_eq!(eval.reciprocal_rank, 0.5);
+        assert_eq!(eval.recall, 1.0);
+        assert_eq!(eval.mode, "vector");
+    }
+}
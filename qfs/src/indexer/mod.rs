@@ -1,10 +1,11 @@
 //! Document indexer
 
-use crate::error::Result;
-use crate::parser::parse_file;
+use crate::error::{Error, Result};
+use crate::parser::{CommandParser, DocumentPart, ParseOptions, ParsedDocument, ParserRegistry};
 use crate::scanner::Scanner;
 use crate::store::Store;
 use sha2::{Digest, Sha256};
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
 use std::time::{Duration, Instant};
 
@@ -49,12 +50,29 @@ pub enum FileStatus {
 /// Document indexer
 pub struct Indexer<'a> {
     store: &'a Store,
+    options: ParseOptions,
+    parsers: ParserRegistry,
 }
 
 impl<'a> Indexer<'a> {
     /// Create a new indexer
     pub fn new(store: &'a Store) -> Self {
-        Indexer { store }
+        Self::with_options(store, ParseOptions::default())
+    }
+
+    /// Create an indexer that parses files with `options`
+    pub fn with_options(store: &'a Store, options: ParseOptions) -> Self {
+        Self::with_parsers(store, options, ParserRegistry::new())
+    }
+
+    /// Create an indexer that also uses the parsers registered in `parsers`.
+    /// A collection's configured commands take precedence over them.
+    pub fn with_parsers(store: &'a Store, options: ParseOptions, parsers: ParserRegistry) -> Self {
+        Indexer {
+            store,
+            options,
+            parsers,
+        }
     }
 
     /// Index a collection
@@ -80,6 +98,16 @@ impl<'a> Indexer<'a> {
         let exclude: Vec<&str> = collection.exclude.iter().map(|s| s.as_str()).collect();
         let scanner = Scanner::new(&collection.path, &patterns, &exclude)?;
 
+        // Add the collection's external commands to the registered parsers
+        let mut parsers = self.parsers.clone();
+        for parser in self.store.list_collection_parsers(Some(name)).await? {
+            let timeout = Duration::from_secs(parser.timeout_secs);
+            parsers.register_extension(
+                &parser.extension,
+                CommandParser::new(&parser.command, timeout)?,
+            );
+        }
+
         // Track which files we've seen
         let mut seen_paths = std::collections::HashSet::new();
 
@@ -89,7 +117,12 @@ impl<'a> Indexer<'a> {
             seen_paths.insert(scan_result.relative_path.clone());
 
             match self
-                .index_file(name, &scan_result.path, &scan_result.relative_path)
+                .index_file(
+                    name,
+                    &scan_result.path,
+                    &scan_result.relative_path,
+                    &parsers,
+                )
                 .await
             {
                 Ok(indexed) => {
@@ -120,11 +153,21 @@ impl<'a> Indexer<'a> {
 
     /// Index all collections
     pub async fn index_all(&self) -> Result<IndexStats> {
+        self.index_all_with_progress(&mut NoopProgress).await
+    }
+
+    /// Index all collections with progress reporting
+    pub async fn index_all_with_progress(
+        &self,
+        progress: &mut dyn IndexProgress,
+    ) -> Result<IndexStats> {
         let mut total_stats = IndexStats::default();
         let start = Instant::now();
 
         for collection in self.store.list_collections().await? {
-            let stats = self.index_collection(&collection.name).await?;
+            let stats = self
+                .index_collection_with_progress(&collection.name, progress)
+                .await?;
             total_stats.files_scanned += stats.files_scanned;
             total_stats.files_indexed += stats.files_indexed;
             total_stats.files_skipped += stats.files_skipped;
@@ -144,6 +187,7 @@ impl<'a> Indexer<'a> {
         collection: &str,
         path: &Path,
         relative_path: &str,
+        parsers: &ParserRegistry,
     ) -> Result<bool> {
         // Read file content (sync file I/O is fine here)
         let content = std::fs::read(path)?;
@@ -155,41 +199,172 @@ impl<'a> Indexer<'a> {
         if self.store.content_exists(&hash).await? {
             // Check if document exists with same hash
             if let Ok(doc) = self.store.get_document(collection, relative_path).await {
-                if doc.hash == hash {
-                    return Ok(false); // Skip, unchanged
+                // Re-parse files indexed before their format had text extraction
+                // (or before a parser was registered for them, or before text in
+                // other encodings was decoded), and files whose parser has since
+                // been removed
+                let extracts = crate::parser::extracts_text(path, &content)
+                    || parsers.get(path).is_some()
+                    || is_transcoded(&content);
+                let needs_text = extracts != self.store.has_extracted_text(&hash).await?;
+                // ...and mailboxes indexed before they were split into messages,
+                // or archives whose files were indexed with other options
+                let is_archive = crate::parser::is_archive(path);
+                let needs_parts = if crate::parser::is_container(path) || is_archive {
+                    let wants_parts = !is_archive || self.options.archives;
+                    let has_parts = !self
+                        .store
+                        .list_part_paths(collection, relative_path)
+                        .await?
+                        .is_empty();
+                    wants_parts != has_parts
+                } else {
+                    false
+                };
+                if doc.hash == hash && !needs_text && !needs_parts {
+                    // Notebook text depends on the options, so compare it
+                    if !crate::parser::is_notebook(path) {
+                        return Ok(false); // Skip, unchanged
+                    }
+                    let parsed = self.parse(parsers, path, &content)?;
+                    if self.store.get_text(&hash).await?.as_deref() == Some(parsed.body.as_str()) {
+                        return Ok(false);
+                    }
+                    // Passages and embeddings point into the old text
+                    self.store.delete_chunks(&hash).await?;
+                    self.store.delete_embeddings(&hash).await?;
                 }
             }
         }
 
         // Parse the file
-        let parsed = parse_file(path, &content)?;
+        let parsed = self.parse(parsers, path, &content)?;
 
         // Store content
         self.store
             .insert_content(&hash, &content, &parsed.mime_type)
             .await?;
-
-        // Get file extension
-        let file_type = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| format!(".{}", e))
-            .unwrap_or_default();
+        self.store_text(&hash, &content, &parsed).await?;
 
         // Upsert document
-        self.store
+        let id = self
+            .store
             .upsert_document(
                 collection,
                 relative_path,
                 parsed.title.as_deref(),
                 &hash,
-                &file_type,
+                &file_type(path),
                 &parsed.body,
             )
             .await?;
+        self.store
+            .set_document_metadata(id, &parsed.metadata)
+            .await?;
+
+        self.index_parts(collection, relative_path, &parsed.parts)
+            .await?;
 
         Ok(true)
     }
+
+    /// Parse a file, reporting a parser that panics on it as an error so the
+    /// other files are still indexed
+    fn parse(
+        &self,
+        parsers: &ParserRegistry,
+        path: &Path,
+        content: &[u8],
+    ) -> Result<ParsedDocument> {
+        let parse = AssertUnwindSafe(|| parsers.parse(path, content, &self.options));
+        std::panic::catch_unwind(parse).unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown cause");
+            Err(Error::ParseError(format!("parser panicked: {}", message)))
+        })
+    }
+
+    /// Store the text that stands in for `content`: the parser's extracted
+    /// text, or text in another encoding decoded to UTF-8
+    async fn store_text(&self, hash: &str, content: &[u8], parsed: &ParsedDocument) -> Result<()> {
+        if parsed.text_extracted {
+            return self
+                .store
+                .insert_extracted_text(hash, &parsed.body, &parsed.pages)
+                .await;
+        }
+        match crate::parser::decode_text(content) {
+            Some((text, encoding)) if encoding != encoding_rs::UTF_8 => {
+                self.store.insert_extracted_text(hash, &text, &[]).await
+            }
+            _ => self.store.delete_extracted_text(hash).await,
+        }
+    }
+
+    /// Index the documents inside a container (and theirs in turn) at
+    /// `container!/name`, deactivating parts it no longer has
+    async fn index_parts(
+        &self,
+        collection: &str,
+        container: &str,
+        parts: &[DocumentPart],
+    ) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        let mut pending: Vec<(String, &DocumentPart)> = parts
+            .iter()
+            .map(|part| (format!("{}!/{}", container, part.name), part))
+            .collect();
+
+        while let Some((path, part)) = pending.pop() {
+            let parsed = &part.document;
+            let hash = calculate_hash(&part.content);
+            self.store
+                .insert_content(&hash, &part.content, &parsed.mime_type)
+                .await?;
+            self.store_text(&hash, &part.content, parsed).await?;
+            let id = self
+                .store
+                .upsert_document(
+                    collection,
+                    &path,
+                    parsed.title.as_deref(),
+                    &hash,
+                    &file_type(Path::new(&part.name)),
+                    &parsed.body,
+                )
+                .await?;
+            self.store
+                .set_document_metadata(id, &parsed.metadata)
+                .await?;
+
+            pending.extend(
+                parsed
+                    .parts
+                    .iter()
+                    .map(|inner| (format!("{}!/{}", path, inner.name), inner)),
+            );
+            seen.insert(path);
+        }
+
+        for path in self.store.list_part_paths(collection, container).await? {
+            if !seen.contains(&path) {
+                self.store.deactivate_document(collection, &path).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// File extension with its dot (`.md`), as stored in `documents.file_type`
+fn file_type(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default()
 }
 
 /// Calculate SHA-256 hash of content
@@ -200,6 +375,12 @@ fn calculate_hash(content: &[u8]) -> String {
     hex::encode(result)
 }
 
+/// Whether `content` is text in an encoding other than UTF-8, which is
+/// stored decoded
+fn is_transcoded(content: &[u8]) -> bool {
+    crate::parser::detect_encoding(content).is_some_and(|encoding| encoding != encoding_rs::UTF_8)
+}
+
 /// No-op progress reporter
 struct NoopProgress;
 
@@ -301,6 +482,247 @@ mod tests {
         assert_eq!(stats3.files_indexed, 1);
     }
 
+    #[tokio::test]
+    async fn test_notebook_outputs_option() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("analysis.ipynb"))
+            .unwrap()
+            .write_all(
+                br#"{"cells": [{"cell_type": "code", "source": "print(42)", "outputs": [{"output_type": "stream", "text": "forty-two"}]}], "metadata": {}}"#,
+            )
+            .unwrap();
+
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("test", dir.path().to_str().unwrap(), &["**/*.ipynb"])
+            .await
+            .unwrap();
+        let text = || async {
+            let doc = store.get_document("test", "analysis.ipynb").await.unwrap();
+            store.get_text(&doc.hash).await.unwrap().unwrap()
+        };
+
+        Indexer::new(&store).index_collection("test").await.unwrap();
+        assert_eq!(text().await, "```\nprint(42)\n```");
+
+        // Changing the option re-parses the unchanged notebook
+        let with_outputs = Indexer::with_options(
+            &store,
+            ParseOptions {
+                notebook_outputs: true,
+                ..Default::default()
+            },
+        );
+        let stats = with_outputs.index_collection("test").await.unwrap();
+        assert_eq!(stats.files_indexed, 1);
+        assert!(text().await.ends_with("```output\nforty-two\n```"));
+
+        let stats = with_outputs.index_collection("test").await.unwrap();
+        assert_eq!(stats.files_skipped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_mbox_messages_are_documents() {
+        let dir = tempdir().unwrap();
+        let mbox = dir.path().join("team.mbox");
+        let message = |n: u32| {
+            format!(
+                "From a@example.com Mon Mar  3 10:00:00 2025\n\
+                 From: Alice <a@example.com>\n\
+                 Subject: Update {}\n\
+                 Content-Type: multipart/mixed; boundary=b\n\
+                 \n\
+                 --b\n\
+                 \n\
+                 Weekly update number {}.\n\
+                 --b\n\
+                 Content-Disposition: attachment; filename=notes.md\n\
+                 \n\
+                 # Notes {}\n\
+                 --b--\n\n",
+                n, n, n
+            )
+        };
+        std::fs::write(&mbox, format!("{}{}", message(1), message(2))).unwrap();
+
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("mail", dir.path().to_str().unwrap(), &["**/*.mbox"])
+            .await
+            .unwrap();
+        let indexer = Indexer::new(&store);
+        indexer.index_collection("mail").await.unwrap();
+
+        let doc = store
+            .get_document("mail", "team.mbox!/2.eml")
+            .await
+            .unwrap();
+        assert_eq!(doc.title.as_deref(), Some("Update 2"));
+        assert_eq!(doc.file_type, ".eml");
+        let metadata = store.get_document_metadata(doc.id).await.unwrap();
+        assert_eq!(metadata["from"], vec!["Alice <a@example.com>"]);
+        let notes = store
+            .get_document("mail", "team.mbox!/2.eml!/notes.md")
+            .await
+            .unwrap();
+        assert_eq!(notes.title.as_deref(), Some("Notes 2"));
+        assert_eq!(store.count_documents(Some("mail")).await.unwrap(), 5);
+
+        // Messages that leave the mailbox leave the index
+        std::fs::write(&mbox, message(1)).unwrap();
+        indexer.index_collection("mail").await.unwrap();
+        assert!(store
+            .get_document("mail", "team.mbox!/2.eml")
+            .await
+            .is_err());
+        assert_eq!(
+            store.list_part_paths("mail", "team.mbox").await.unwrap(),
+            vec!["team.mbox!/1.eml", "team.mbox!/1.eml!/notes.md"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_collection_parser_commands() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("letter.rtf"), b"dear team").unwrap();
+        std::fs::write(dir.path().join("broken.log"), b"anything").unwrap();
+
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("docs", dir.path().to_str().unwrap(), &["**/*"])
+            .await
+            .unwrap();
+        let text = |path: &'static str| {
+            let store = &store;
+            async move {
+                let doc = store.get_document("docs", path).await.unwrap();
+                store.get_text(&doc.hash).await.unwrap().unwrap()
+            }
+        };
+
+        Indexer::new(&store).index_collection("docs").await.unwrap();
+        assert_eq!(text("letter.rtf").await, "dear team");
+
+        // A command set later re-parses the unchanged file
+        for (extension, command) in [
+            (".RTF", "tr a-z A-Z"),
+            ("log", "sh -c 'echo no converter >&2; exit 1'"),
+        ] {
+            store
+                .set_collection_parser(&crate::parser::CollectionParser {
+                    collection: "docs".to_string(),
+                    extension: extension.to_string(),
+                    command: command.to_string(),
+                    timeout_secs: 5,
+                })
+                .await
+                .unwrap();
+        }
+
+        struct Errors(Vec<String>);
+        impl IndexProgress for Errors {
+            fn on_file(&mut self, path: &Path, status: FileStatus) {
+                if let FileStatus::Error(e) = status {
+                    let name = path.file_name().unwrap().to_string_lossy();
+                    self.0.push(format!("{}: {}", name, e));
+                }
+            }
+            fn on_complete(&mut self, _stats: &IndexStats) {}
+        }
+        let mut errors = Errors(Vec::new());
+        let stats = Indexer::new(&store)
+            .index_all_with_progress(&mut errors)
+            .await
+            .unwrap();
+
+        assert_eq!(stats.files_indexed, 1);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(
+            errors.0,
+            vec!["broken.log: Parse error: `sh` failed (exit status: 1): no converter"]
+        );
+        assert_eq!(text("letter.rtf").await, "DEAR TEAM");
+
+        // Parsers registered by the library are overridden by the collection's
+        let mut parsers = ParserRegistry::new();
+        parsers.register_extension(
+            "rtf",
+            CommandParser::new("rev", Duration::from_secs(5)).unwrap(),
+        );
+        parsers.register_extension(
+            "log",
+            CommandParser::new("cat", Duration::from_secs(5)).unwrap(),
+        );
+        store.remove_collection_parser("docs", "log").await.unwrap();
+        Indexer::with_parsers(&store, ParseOptions::default(), parsers)
+            .index_collection("docs")
+            .await
+            .unwrap();
+        assert_eq!(text("broken.log").await, "anything");
+        assert_eq!(text("letter.rtf").await, "DEAR TEAM");
+    }
+
+    #[tokio::test]
+    async fn test_parser_panics_are_errors() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("broken.rtf"), b"{\\rtf1").unwrap();
+        std::fs::write(dir.path().join("notes.md"), b"# Notes").unwrap();
+
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("docs", dir.path().to_str().unwrap(), &["**/*"])
+            .await
+            .unwrap();
+        let mut parsers = ParserRegistry::new();
+        parsers.register_extension("rtf", |_: &Path, _: &[u8], _: &ParseOptions| {
+            panic!("malformed input")
+        });
+        let stats = Indexer::with_parsers(&store, ParseOptions::default(), parsers)
+            .index_collection("docs")
+            .await
+            .unwrap();
+
+        assert_eq!((stats.files_indexed, stats.errors), (1, 1));
+        assert!(store.get_document("docs", "notes.md").await.is_ok());
+        assert!(store.get_document("docs", "broken.rtf").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_text_is_decoded_once() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("menu.txt"), b"Caf\xe9 cr\xe8me").unwrap();
+
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("docs", dir.path().to_str().unwrap(), &["**/*.txt"])
+            .await
+            .unwrap();
+        let indexer = Indexer::new(&store);
+        indexer.index_collection("docs").await.unwrap();
+
+        // Files indexed before decoding have no decoded text stored
+        let doc = store.get_document("docs", "menu.txt").await.unwrap();
+        assert!(store.has_extracted_text(&doc.hash).await.unwrap());
+        store.delete_extracted_text(&doc.hash).await.unwrap();
+        store
+            .set_document_metadata(doc.id, &Default::default())
+            .await
+            .unwrap();
+
+        let stats = indexer.index_collection("docs").await.unwrap();
+        assert_eq!(stats.files_indexed, 1);
+        let metadata = store.get_document_metadata(doc.id).await.unwrap();
+        assert_eq!(metadata["encoding"], vec!["windows-1252"]);
+        assert_eq!(
+            store.get_text(&doc.hash).await.unwrap().unwrap(),
+            "Café crème"
+        );
+
+        let stats = indexer.index_collection("docs").await.unwrap();
+        assert_eq!(stats.files_skipped, 1);
+    }
+
     #[test]
     fn test_hash_calculation() {
         let content = b"Hello, World!";
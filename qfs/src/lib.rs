@@ -33,6 +33,7 @@
 //! ```
 
 pub mod error;
+pub mod eval;
 pub mod indexer;
 pub mod mcp;
 pub mod parser;
@@ -58,9 +59,11 @@ pub fn default_db_path() -> std::path::PathBuf {
 }
 
 /// Detect if content is binary using NUL-byte check (ripgrep strategy)
+///
+/// UTF-16 text, whose ASCII characters are paired with NUL bytes, isn't binary.
 pub fn is_binary(content: &[u8]) -> bool {
     // Check first 8KB for NUL bytes
-    content.iter().take(8192).any(|&b| b == 0)
+    content.iter().take(8192).any(|&b| b == 0) && !parser::is_utf16(content)
 }
 
 /// Parse a path that may contain a :linenum suffix.
@@ -138,6 +141,69 @@ pub fn add_line_numbers(text: &str, start_line: usize) -> String {
         .join("\n")
 }
 
+/// Extract lines as [`extract_lines`] does, numbering them if asked. Tables
+/// (see [`parser::is_table`]) keep their header line when extraction starts
+/// past it, so `path:row` excerpts stay readable.
+///
+/// # Examples
+/// ```
+/// use qfs::extract_document_lines;
+///
+/// let table = "city | country\ncity: Paris | country: France\ncity: Lyon | country: France";
+/// let result = extract_document_lines(table, "text/csv", Some(3), Some(1), true);
+/// assert_eq!(result, "1: city | country\n3: city: Lyon | country: France");
+/// ```
+pub fn extract_document_lines(
+    content: &str,
+    mime_type: &str,
+    from_line: Option<usize>,
+    max_lines: Option<usize>,
+    line_numbers: bool,
+) -> String {
+    let start = from_line.unwrap_or(1);
+    let mut output = extract_lines(content, from_line, max_lines);
+    if line_numbers {
+        output = add_line_numbers(&output, start);
+    }
+
+    if start > 1 && parser::is_table(mime_type) {
+        if let Some(header) = content.lines().next() {
+            let header = if line_numbers {
+                add_line_numbers(header, 1)
+            } else {
+                header.to_string()
+            };
+            output = format!("{}\n{}", header, output);
+        }
+    }
+    output
+}
+
+/// Get the 1-indexed, inclusive line range covered by a byte span of `content`.
+/// Offsets past the end are clamped.
+///
+/// # Examples
+/// ```
+/// use qfs::line_range;
+///
+/// let content = "line1\nline2\nline3\nline4";
+/// assert_eq!(line_range(content, 6, 11), (2, 3));
+/// assert_eq!(line_range(content, 0, 3), (1, 1));
+/// ```
+pub fn line_range(content: &str, offset: usize, length: usize) -> (usize, usize) {
+    let bytes = content.as_bytes();
+    let start = offset.min(bytes.len());
+    let end = (offset + length).min(bytes.len());
+
+    let count_newlines = |slice: &[u8]| slice.iter().filter(|&&b| b == b'\n').count();
+    let line_start = count_newlines(&bytes[..start]) + 1;
+    // A span ending in a newline doesn't reach the following line
+    let span = &bytes[start..end];
+    let span = span.strip_suffix(b"\n").unwrap_or(span);
+
+    (line_start, line_start + count_newlines(span))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,6 +220,16 @@ mod tests {
         assert!(is_binary(binary));
     }
 
+    #[test]
+    fn test_is_binary_utf16() {
+        let text: Vec<u8> = "Hello, world!"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        assert!(!is_binary(&text));
+        assert!(!is_binary(b"\xfe\xff\x00H\x00i"));
+    }
+
     #[test]
     fn test_is_binary_empty() {
         let empty: &[u8] = b"";
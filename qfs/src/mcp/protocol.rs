@@ -107,6 +107,12 @@ impl JsonRpcError {
     pub fn server_error(message: impl Into<String>) -> Self {
         Self::new(-32000, message)
     }
+
+    /// Attach additional data to the error
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
 }
 
 impl std::fmt::Display for JsonRpcError {
@@ -190,7 +190,7 @@ mod tests {
         let result = server.handle_tools_list().unwrap();
 
         let tools = result["tools"].as_array().unwrap();
-        assert_eq!(tools.len(), 6);
+        assert_eq!(tools.len(), 9);
 
         let names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
         assert!(names.contains(&"qfs_search"));
@@ -3,7 +3,10 @@
 //! Each tool handler processes a specific tool call and returns results.
 
 use super::protocol::{JsonRpcError, ToolDefinition, ToolResult};
-use crate::search::{SearchMode, SearchOptions, Searcher};
+use crate::search::{
+    GrepOptions, RankingOptions, SearchMode, SearchOptions, SearchResponse, Searcher,
+};
+use crate::store::dates::parse_date_filter;
 use crate::store::Store;
 use serde_json::{json, Value};
 
@@ -31,11 +34,90 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                     },
                     "from_date": {
                         "type": "string",
-                        "description": "Filter documents modified on or after this date (ISO 8601, e.g., '2025-01-01')"
+                        "description": "Filter documents modified on or after this date: ISO 8601 date or timestamp with offset, '2025-Q1', or relative ('7d', 'yesterday', 'last week')"
                     },
                     "to_date": {
                         "type": "string",
-                        "description": "Filter documents modified on or before this date (ISO 8601, e.g., '2025-12-31')"
+                        "description": "Filter documents modified on or before this date (same formats as from_date; periods such as '2025-12' include their last day)"
+                    },
+                    "collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Search only these collections (combined with 'collection')"
+                    },
+                    "exclude_collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Leave out these collections"
+                    },
+                    "file_types": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only these file extensions, e.g. ['md', 'rs']"
+                    },
+                    "mime_types": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only these MIME types or classes, e.g. ['text/markdown', 'image/*', 'text']"
+                    },
+                    "path_prefix": {
+                        "type": "string",
+                        "description": "Only paths starting with this prefix, relative to the collection root (e.g. 'notes/2025/')"
+                    },
+                    "path_glob": {
+                        "type": "string",
+                        "description": "Only paths matching this glob, relative to the collection root ('*' also matches '/')"
+                    },
+                    "metadata": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Only documents whose metadata contains these values (case-insensitive), e.g. {\"from\": \"alice@example.com\"}"
+                    },
+                    "facets": {
+                        "type": "boolean",
+                        "description": "Include facet counts (per collection, file type, path prefix and modified month) alongside results",
+                        "default": false
+                    },
+                    "fuzzy": {
+                        "type": "boolean",
+                        "description": "When no documents match, retry with spelling-corrected terms (did-you-mean suggestions are always returned)",
+                        "default": true
+                    },
+                    "passages": {
+                        "type": "boolean",
+                        "description": "Return matching passages with line ranges instead of whole documents (requires embeddings to have been generated)",
+                        "default": false
+                    },
+                    "snippets": {
+                        "type": "integer",
+                        "description": "Number of line-numbered snippets per keyword result (default: 1; more than 1 adds a 'snippets' array)",
+                        "default": 1
+                    },
+                    "snippet_size": {
+                        "type": "integer",
+                        "description": "Maximum characters per snippet (default: 160)",
+                        "default": 160
+                    },
+                    "half_life_days": {
+                        "type": "number",
+                        "description": "Boost recently modified documents; a document this many days old loses half its recency boost"
+                    },
+                    "recency_weight": {
+                        "type": "number",
+                        "description": "Share of the score subject to recency decay, 0.0-1.0 (default: 0.5)"
+                    },
+                    "min_length": {
+                        "type": "integer",
+                        "description": "Demote documents shorter than this many bytes"
+                    },
+                    "depth_penalty": {
+                        "type": "number",
+                        "description": "Demote deeply nested files by this much per directory level"
+                    },
+                    "collection_weights": {
+                        "type": "object",
+                        "additionalProperties": { "type": "number" },
+                        "description": "Score multipliers by collection name, e.g. {\"notes\": 2.0}"
                     }
                 },
                 "required": ["query"]
@@ -62,11 +144,49 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                     },
                     "from_date": {
                         "type": "string",
-                        "description": "Filter documents modified on or after this date (ISO 8601, e.g., '2025-01-01')"
+                        "description": "Filter documents modified on or after this date: ISO 8601 date or timestamp with offset, '2025-Q1', or relative ('7d', 'yesterday', 'last week')"
                     },
                     "to_date": {
                         "type": "string",
-                        "description": "Filter documents modified on or before this date (ISO 8601, e.g., '2025-12-31')"
+                        "description": "Filter documents modified on or before this date (same formats as from_date; periods such as '2025-12' include their last day)"
+                    },
+                    "collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Search only these collections (combined with 'collection')"
+                    },
+                    "exclude_collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Leave out these collections"
+                    },
+                    "file_types": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only these file extensions, e.g. ['md', 'rs']"
+                    },
+                    "mime_types": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only these MIME types or classes, e.g. ['text/markdown', 'image/*', 'text']"
+                    },
+                    "path_prefix": {
+                        "type": "string",
+                        "description": "Only paths starting with this prefix, relative to the collection root (e.g. 'notes/2025/')"
+                    },
+                    "path_glob": {
+                        "type": "string",
+                        "description": "Only paths matching this glob, relative to the collection root ('*' also matches '/')"
+                    },
+                    "metadata": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Only documents whose metadata contains these values (case-insensitive), e.g. {\"from\": \"alice@example.com\"}"
+                    },
+                    "facets": {
+                        "type": "boolean",
+                        "description": "Include facet counts (per collection, file type, path prefix and modified month) alongside results",
+                        "default": false
                     }
                 },
                 "required": ["query"]
@@ -99,11 +219,90 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                     },
                     "from_date": {
                         "type": "string",
-                        "description": "Filter documents modified on or after this date (ISO 8601, e.g., '2025-01-01')"
+                        "description": "Filter documents modified on or after this date: ISO 8601 date or timestamp with offset, '2025-Q1', or relative ('7d', 'yesterday', 'last week')"
                     },
                     "to_date": {
                         "type": "string",
-                        "description": "Filter documents modified on or before this date (ISO 8601, e.g., '2025-12-31')"
+                        "description": "Filter documents modified on or before this date (same formats as from_date; periods such as '2025-12' include their last day)"
+                    },
+                    "collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Search only these collections (combined with 'collection')"
+                    },
+                    "exclude_collections": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Leave out these collections"
+                    },
+                    "file_types": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only these file extensions, e.g. ['md', 'rs']"
+                    },
+                    "mime_types": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Only these MIME types or classes, e.g. ['text/markdown', 'image/*', 'text']"
+                    },
+                    "path_prefix": {
+                        "type": "string",
+                        "description": "Only paths starting with this prefix, relative to the collection root (e.g. 'notes/2025/')"
+                    },
+                    "path_glob": {
+                        "type": "string",
+                        "description": "Only paths matching this glob, relative to the collection root ('*' also matches '/')"
+                    },
+                    "metadata": {
+                        "type": "object",
+                        "additionalProperties": { "type": "string" },
+                        "description": "Only documents whose metadata contains these values (case-insensitive), e.g. {\"from\": \"alice@example.com\"}"
+                    },
+                    "facets": {
+                        "type": "boolean",
+                        "description": "Include facet counts (per collection, file type, path prefix and modified month) alongside results",
+                        "default": false
+                    },
+                    "fuzzy": {
+                        "type": "boolean",
+                        "description": "When no documents match, retry with spelling-corrected terms (did-you-mean suggestions are always returned)",
+                        "default": true
+                    },
+                    "passages": {
+                        "type": "boolean",
+                        "description": "Return matching passages with line ranges instead of whole documents (requires embeddings to have been generated)",
+                        "default": false
+                    },
+                    "snippets": {
+                        "type": "integer",
+                        "description": "Number of line-numbered snippets per keyword result (default: 1; more than 1 adds a 'snippets' array)",
+                        "default": 1
+                    },
+                    "snippet_size": {
+                        "type": "integer",
+                        "description": "Maximum characters per snippet (default: 160)",
+                        "default": 160
+                    },
+                    "half_life_days": {
+                        "type": "number",
+                        "description": "Boost recently modified documents; a document this many days old loses half its recency boost"
+                    },
+                    "recency_weight": {
+                        "type": "number",
+                        "description": "Share of the score subject to recency decay, 0.0-1.0 (default: 0.5)"
+                    },
+                    "min_length": {
+                        "type": "integer",
+                        "description": "Demote documents shorter than this many bytes"
+                    },
+                    "depth_penalty": {
+                        "type": "number",
+                        "description": "Demote deeply nested files by this much per directory level"
+                    },
+                    "collection_weights": {
+                        "type": "object",
+                        "additionalProperties": { "type": "number" },
+                        "description": "Score multipliers by collection name, e.g. {\"notes\": 2.0}"
                     }
                 },
                 "required": ["query"]
@@ -141,6 +340,98 @@ pub fn get_tool_definitions() -> Vec<ToolDefinition> {
                 "required": ["path"]
             }),
         },
+        ToolDefinition {
+            name: "qfs_grep".to_string(),
+            description: "Find exact strings or regex matches in indexed content. Returns path, line, column and matching line with optional context. Use for identifiers and punctuation that keyword search can't match.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Literal string to find (or regex when 'regex' is true), e.g. 'Error::EmbeddingsRequired'"
+                    },
+                    "regex": {
+                        "type": "boolean",
+                        "description": "Treat pattern as a regular expression",
+                        "default": false
+                    },
+                    "ignore_case": {
+                        "type": "boolean",
+                        "description": "Case-insensitive matching",
+                        "default": false
+                    },
+                    "context": {
+                        "type": "integer",
+                        "description": "Lines of context before and after each match (default: 0)",
+                        "default": 0
+                    },
+                    "collection": {
+                        "type": "string",
+                        "description": "Optional collection name"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of matching lines (default: 100)",
+                        "default": 100
+                    },
+                    "from_date": {
+                        "type": "string",
+                        "description": "Filter documents modified on or after this date: ISO 8601 date or timestamp with offset, '2025-Q1', or relative ('7d', 'yesterday', 'last week')"
+                    },
+                    "to_date": {
+                        "type": "string",
+                        "description": "Filter documents modified on or before this date (same formats as from_date; periods such as '2025-12' include their last day)"
+                    }
+                },
+                "required": ["pattern"]
+            }),
+        },
+        ToolDefinition {
+            name: "qfs_find".to_string(),
+            description: "Find files by approximate name or title (fuzzy, fzf-style). Use when you know roughly what a file is called but not its exact path.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Fuzzy path or title, e.g. 'apiref' or 'meeting jan'"
+                    },
+                    "collection": {
+                        "type": "string",
+                        "description": "Optional collection name"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of results (default: 20)",
+                        "default": 20
+                    }
+                },
+                "required": ["query"]
+            }),
+        },
+        ToolDefinition {
+            name: "qfs_similar".to_string(),
+            description: "Find documents similar to an existing one (\"more like this\"). Uses the document's embeddings when available, otherwise its most distinctive terms.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Document path (collection/relative_path) or docid (#abc123)"
+                    },
+                    "collection": {
+                        "type": "string",
+                        "description": "Optional collection name to search within"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of results (default: 10)",
+                        "default": 10
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
         ToolDefinition {
             name: "qfs_multi_get".to_string(),
             description: "Get multiple documents by glob pattern or comma-separated list. Skips files larger than maxBytes.".to_string(),
@@ -187,6 +478,9 @@ pub async fn handle_tool_call(
         "qfs_vsearch" => tool_search(store, arguments, SearchMode::Vector).await,
         "qfs_query" => tool_query(store, arguments).await,
         "qfs_get" => tool_get(store, arguments).await,
+        "qfs_find" => tool_find(store, arguments).await,
+        "qfs_similar" => tool_similar(store, arguments).await,
+        "qfs_grep" => tool_grep(store, arguments).await,
         "qfs_multi_get" => tool_multi_get(store, arguments).await,
         "qfs_status" => tool_status(store).await,
         _ => Err(JsonRpcError::invalid_params(format!(
@@ -209,29 +503,59 @@ async fn tool_search(
 
     let collection = args.get("collection").and_then(|v| v.as_str());
     let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
-    let from_date = args.get("from_date").and_then(|v| v.as_str());
-    let to_date = args.get("to_date").and_then(|v| v.as_str());
+    let from_date = date_arg(args, "from_date")?;
+    let to_date = date_arg(args, "to_date")?;
+    let facets = args
+        .get("facets")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let fuzzy = args.get("fuzzy").and_then(|v| v.as_bool()).unwrap_or(true);
+    let passages = args
+        .get("passages")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let snippets = args.get("snippets").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
+    let snippet_size = args
+        .get("snippet_size")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(160) as usize;
 
     let options = SearchOptions {
         mode,
         limit,
         min_score: 0.0,
         collection: collection.map(String::from),
+        collections: string_list(args, "collections"),
+        exclude_collections: string_list(args, "exclude_collections"),
+        file_types: string_list(args, "file_types"),
+        mime_types: string_list(args, "mime_types"),
+        path_prefix: args
+            .get("path_prefix")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        path_glob: args
+            .get("path_glob")
+            .and_then(|v| v.as_str())
+            .map(String::from),
         include_binary: false,
-        from_date: from_date.map(String::from),
-        to_date: to_date.map(String::from),
+        from_date,
+        to_date,
+        metadata: metadata_filters(args),
+        facets,
+        fuzzy,
+        passages,
+        snippets,
+        snippet_size,
+        ranking: ranking_options(args),
     };
 
     let searcher = Searcher::new(store);
-    let results = searcher
-        .search(query, options)
+    let response = searcher
+        .search_detailed(query, options)
         .await
         .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
 
-    let text = serde_json::to_string_pretty(&results)
-        .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
-
-    Ok(ToolResult::text(text))
+    search_response_text(&response)
 }
 
 /// Execute query tool with mode selection (qfs_query)
@@ -249,22 +573,205 @@ async fn tool_query(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcEr
 
     let collection = args.get("collection").and_then(|v| v.as_str());
     let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
-    let from_date = args.get("from_date").and_then(|v| v.as_str());
-    let to_date = args.get("to_date").and_then(|v| v.as_str());
+    let from_date = date_arg(args, "from_date")?;
+    let to_date = date_arg(args, "to_date")?;
+    let facets = args
+        .get("facets")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let fuzzy = args.get("fuzzy").and_then(|v| v.as_bool()).unwrap_or(true);
+    let passages = args
+        .get("passages")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let snippets = args.get("snippets").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
+    let snippet_size = args
+        .get("snippet_size")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(160) as usize;
 
     let options = SearchOptions {
         mode,
         limit,
         min_score: 0.0,
         collection: collection.map(String::from),
+        collections: string_list(args, "collections"),
+        exclude_collections: string_list(args, "exclude_collections"),
+        file_types: string_list(args, "file_types"),
+        mime_types: string_list(args, "mime_types"),
+        path_prefix: args
+            .get("path_prefix")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        path_glob: args
+            .get("path_glob")
+            .and_then(|v| v.as_str())
+            .map(String::from),
         include_binary: false,
-        from_date: from_date.map(String::from),
-        to_date: to_date.map(String::from),
+        from_date,
+        to_date,
+        metadata: metadata_filters(args),
+        facets,
+        fuzzy,
+        passages,
+        snippets,
+        snippet_size,
+        ranking: ranking_options(args),
+    };
+
+    let searcher = Searcher::new(store);
+    let response = searcher
+        .search_detailed(query, options)
+        .await
+        .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
+
+    search_response_text(&response)
+}
+
+/// A date filter argument, rejected up front if the date parser can't read it
+fn date_arg(args: &Value, key: &str) -> Result<Option<String>, JsonRpcError> {
+    let Some(date) = args.get(key).and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    parse_date_filter(date, chrono::Utc::now())
+        .map_err(|e| JsonRpcError::invalid_params(format!("{}: {}", key, e)))?;
+    Ok(Some(date.to_string()))
+}
+
+/// A list argument given as a JSON array or a comma-separated string
+fn string_list(args: &Value, key: &str) -> Vec<String> {
+    match args.get(key) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(String::from)
+            .collect(),
+        Some(Value::String(list)) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Metadata filters from an object argument, e.g. `{"from": "alice"}`
+fn metadata_filters(args: &Value) -> Vec<(String, String)> {
+    args.get("metadata")
+        .and_then(|v| v.as_object())
+        .map(|filters| {
+            filters
+                .iter()
+                .filter_map(|(key, value)| match value {
+                    Value::String(s) => Some((key.clone(), s.clone())),
+                    Value::Number(_) | Value::Bool(_) => Some((key.clone(), value.to_string())),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Ranking signals from the optional search tool arguments
+fn ranking_options(args: &Value) -> RankingOptions {
+    let collection_weights = args
+        .get("collection_weights")
+        .and_then(|v| v.as_object())
+        .map(|weights| {
+            weights
+                .iter()
+                .filter_map(|(name, w)| w.as_f64().map(|w| (name.clone(), w)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    RankingOptions {
+        half_life_days: args.get("half_life_days").and_then(|v| v.as_f64()),
+        recency_weight: args.get("recency_weight").and_then(|v| v.as_f64()),
+        min_length: args.get("min_length").and_then(|v| v.as_u64()),
+        depth_penalty: args.get("depth_penalty").and_then(|v| v.as_f64()),
+        collection_weights,
+    }
+}
+
+/// Serialize a search response: a plain result array, or an object with
+/// `results` plus `facets`/`suggestions`/`correctedQuery` when present
+fn search_response_text(response: &SearchResponse) -> Result<ToolResult, JsonRpcError> {
+    let text = if response.has_details() {
+        serde_json::to_string_pretty(response)
+    } else {
+        serde_json::to_string_pretty(&response.results)
+    }
+    .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
+
+    Ok(ToolResult::text(text))
+}
+
+/// Build a "document not found" error listing fuzzy path matches as
+/// did-you-mean candidates (also returned in `data.suggestions`)
+async fn not_found_with_suggestions(store: &Store, path: &str) -> JsonRpcError {
+    let candidates: Vec<String> = store
+        .find_paths(path, None, 5)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|m| format!("{}/{}", m.collection, m.path))
+        .collect();
+
+    let message = if candidates.is_empty() {
+        format!("Document not found: {}", path)
+    } else {
+        format!(
+            "Document not found: {}. Did you mean: {}?",
+            path,
+            candidates.join(", ")
+        )
+    };
+
+    JsonRpcError::server_error(message).with_data(json!({ "suggestions": candidates }))
+}
+
+/// Execute fuzzy path finder tool (qfs_find)
+async fn tool_find(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcError> {
+    let query = args
+        .get("query")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError::invalid_params("Missing query parameter"))?;
+
+    let collection = args.get("collection").and_then(|v| v.as_str());
+    let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
+
+    let matches = store
+        .find_paths(query, collection, limit)
+        .await
+        .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
+
+    let text = serde_json::to_string_pretty(&matches)
+        .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
+
+    Ok(ToolResult::text(text))
+}
+
+/// Execute similar-documents tool (qfs_similar)
+async fn tool_similar(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcError> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError::invalid_params("Missing path parameter"))?;
+
+    let collection = args.get("collection").and_then(|v| v.as_str());
+    let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+
+    let options = SearchOptions {
+        limit,
+        collection: collection.map(String::from),
+        ..Default::default()
     };
 
     let searcher = Searcher::new(store);
     let results = searcher
-        .search(query, options)
+        .similar(path, options)
         .await
         .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
 
@@ -274,6 +781,44 @@ async fn tool_query(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcEr
     Ok(ToolResult::text(text))
 }
 
+/// Execute grep tool (qfs_grep)
+async fn tool_grep(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcError> {
+    let pattern = args
+        .get("pattern")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError::invalid_params("Missing pattern parameter"))?;
+
+    let options = GrepOptions {
+        regex: args.get("regex").and_then(|v| v.as_bool()).unwrap_or(false),
+        ignore_case: args
+            .get("ignore_case")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        context: args.get("context").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+        limit: args.get("limit").and_then(|v| v.as_u64()).unwrap_or(100) as usize,
+        collection: args
+            .get("collection")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        from_date: date_arg(args, "from_date")?,
+        to_date: date_arg(args, "to_date")?,
+    };
+
+    let searcher = Searcher::new(store);
+    let matches = searcher
+        .grep(pattern, &options)
+        .await
+        .map_err(|e| match e {
+            crate::Error::InvalidQuery(msg) => JsonRpcError::invalid_params(msg),
+            e => JsonRpcError::server_error(e.to_string()),
+        })?;
+
+    let text = serde_json::to_string_pretty(&matches)
+        .map_err(|e| JsonRpcError::server_error(e.to_string()))?;
+
+    Ok(ToolResult::text(text))
+}
+
 /// Execute get tool (qfs_get)
 async fn tool_get(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcError> {
     let path = args
@@ -319,10 +864,13 @@ async fn tool_get(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcErro
                 "Path must be in format 'collection/relative_path' or docid (#abc123)",
             ));
         }
-        store
-            .get_document(parts[0], parts[1])
-            .await
-            .map_err(|e| JsonRpcError::server_error(e.to_string()))?
+        match store.get_document(parts[0], parts[1]).await {
+            Ok(doc) => doc,
+            Err(crate::Error::DocumentNotFound(_)) => {
+                return Err(not_found_with_suggestions(store, clean_path).await)
+            }
+            Err(e) => return Err(JsonRpcError::server_error(e.to_string())),
+        }
     };
 
     let mut result = json!({
@@ -336,17 +884,23 @@ async fn tool_get(store: &Store, args: &Value) -> Result<ToolResult, JsonRpcErro
         "modifiedAt": doc.modified_at
     });
 
+    if let Ok(metadata) = store.get_document_metadata(doc.id).await {
+        if !metadata.is_empty() {
+            result["metadata"] = json!(metadata);
+        }
+    }
+
     if include_content {
         if let Ok(content) = store.get_content(&doc.hash).await {
-            if let Ok(text) = String::from_utf8(content.data.clone()) {
-                // Apply line extraction
-                let mut output = crate::extract_lines(&text, effective_from, max_lines);
-
-                // Add line numbers if requested
-                if line_numbers {
-                    let start = effective_from.unwrap_or(1);
-                    output = crate::add_line_numbers(&output, start);
-                }
+            if let Ok(Some(text)) = store.get_text(&doc.hash).await {
+                // Apply line extraction, keeping table headers
+                let output = crate::extract_document_lines(
+                    &text,
+                    &content.content_type,
+                    effective_from,
+                    max_lines,
+                    line_numbers,
+                );
 
                 result["content"] = json!(output);
                 if let Some(from) = effective_from {
@@ -443,7 +997,7 @@ mod tests {
     #[test]
     fn test_tool_definitions_valid() {
         let tools = get_tool_definitions();
-        assert_eq!(tools.len(), 6);
+        assert_eq!(tools.len(), 9);
 
         // Verify all required tools are present
         let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
@@ -451,6 +1005,9 @@ mod tests {
         assert!(names.contains(&"qfs_vsearch"));
         assert!(names.contains(&"qfs_query"));
         assert!(names.contains(&"qfs_get"));
+        assert!(names.contains(&"qfs_similar"));
+        assert!(names.contains(&"qfs_grep"));
+        assert!(names.contains(&"qfs_find"));
         assert!(names.contains(&"qfs_multi_get"));
         assert!(names.contains(&"qfs_status"));
     }
@@ -510,6 +1067,61 @@ mod tests {
         assert!(tool_result.content[0].text.contains("[]"));
     }
 
+    #[tokio::test]
+    async fn test_search_with_facets() {
+        let store = Store::open_memory().await.unwrap();
+        let result = handle_tool_call(
+            &store,
+            "qfs_search",
+            &json!({"query": "nonexistent", "facets": true}),
+        )
+        .await
+        .unwrap();
+        let value: Value = serde_json::from_str(&result.content[0].text).unwrap();
+        assert!(value["results"].as_array().unwrap().is_empty());
+        assert_eq!(value["facets"]["total"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_grep_invalid_regex() {
+        let store = Store::open_memory().await.unwrap();
+        let result = handle_tool_call(
+            &store,
+            "qfs_grep",
+            &json!({"pattern": "foo(", "regex": true}),
+        )
+        .await;
+        let err = result.unwrap_err();
+        assert!(err.message.contains("Invalid regex"));
+
+        // The same pattern is fine as a literal
+        let result = handle_tool_call(&store, "qfs_grep", &json!({"pattern": "foo("})).await;
+        assert!(result.unwrap().content[0].text.contains("[]"));
+    }
+
+    #[tokio::test]
+    async fn test_search_date_params() {
+        let store = Store::open_memory().await.unwrap();
+
+        let result = handle_tool_call(
+            &store,
+            "qfs_search",
+            &json!({"query": "rust", "from_date": "last week", "to_date": "2026-Q4"}),
+        )
+        .await;
+        assert!(result.is_ok());
+
+        let err = handle_tool_call(
+            &store,
+            "qfs_query",
+            &json!({"query": "rust", "from_date": "sometime"}),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, -32602);
+        assert!(err.message.contains("from_date"));
+    }
+
     #[tokio::test]
     async fn test_get_invalid_path() {
         let store = Store::open_memory().await.unwrap();
@@ -0,0 +1,240 @@
+//! ZIP and tar archives
+//!
+//! The files in `.zip`, `.tar`, `.tar.gz` and `.tgz` archives are read into
+//! memory so each can be parsed and indexed as a document of its own.
+//! Directories, links, macOS resource forks and members with `..` in their
+//! path are skipped, as are members over `zip::MAX_MEMBER_SIZE`; at most
+//! `MAX_MEMBERS` files are read. An archive and the archives inside it share
+//! a budget of `MAX_INFLATED_SIZE` bytes: members that don't fit in what is
+//! left are skipped, and a compressed tar is inflated no further.
+
+use super::zip::{self, ZipArchive, MAX_MEMBER_SIZE};
+use crate::error::{Error, Result};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::Path;
+
+/// Files read from one archive
+const MAX_MEMBERS: usize = 10_000;
+
+/// Bytes read from an archive, including the archives nested in it
+pub(crate) const MAX_INFLATED_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// A file inside an archive
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ArchiveMember {
+    /// Path inside the archive, `/`-separated and relative
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Whether `path` names an archive whose files can be indexed
+pub fn is_archive(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    [".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+/// A member path made relative, `None` for entries that shouldn't be indexed
+fn member_name(name: &str) -> Option<String> {
+    let parts: Vec<&str> = name
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    let file = parts.last()?;
+    if parts.contains(&"..") || parts[0] == "__MACOSX" || file.starts_with("._") {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+fn read_zip(content: &[u8], budget: &mut u64) -> Result<Vec<ArchiveMember>> {
+    let archive = ZipArchive::new(content)?;
+    let mut members = Vec::new();
+    for entry in archive.entries() {
+        if members.len() >= MAX_MEMBERS {
+            break;
+        }
+        let Some(name) = member_name(&entry.name).filter(|_| !entry.is_dir()) else {
+            continue;
+        };
+        if entry.size > *budget {
+            continue;
+        }
+        // Encrypted, oversized and unsupported members are left out
+        match archive.read(entry) {
+            // A member larger than its declared size may not fit after all
+            Ok(data) if data.len() as u64 > *budget => break,
+            Ok(data) => {
+                *budget -= data.len() as u64;
+                members.push(ArchiveMember { name, data });
+            }
+            Err(e) => tracing::debug!("Skipping {}: {}", entry.name, e),
+        }
+    }
+    Ok(members)
+}
+
+fn read_tar(reader: impl Read, budget: &mut u64) -> Result<Vec<ArchiveMember>> {
+    let mut archive = tar::Archive::new(reader);
+    let entries = archive
+        .entries()
+        .map_err(|e| Error::ParseError(format!("corrupt tar archive: {}", e)))?;
+
+    let mut members = Vec::new();
+    for entry in entries {
+        if members.len() >= MAX_MEMBERS {
+            break;
+        }
+        // A truncated archive keeps the members read so far
+        let Ok(mut entry) = entry else {
+            break;
+        };
+        if !entry.header().entry_type().is_file() || entry.size() > MAX_MEMBER_SIZE.min(*budget) {
+            continue;
+        }
+        let Some(name) = entry
+            .path()
+            .ok()
+            .and_then(|path| member_name(&path.to_string_lossy()))
+        else {
+            continue;
+        };
+        let size = entry.size();
+        let mut data = Vec::with_capacity(size as usize);
+        if entry.read_to_end(&mut data).is_err() || data.len() as u64 != size {
+            break;
+        }
+        *budget -= size;
+        members.push(ArchiveMember { name, data });
+    }
+    Ok(members)
+}
+
+/// The files in an archive, in archive order, taking their size from
+/// `budget`
+pub(crate) fn members(path: &Path, content: &[u8], budget: &mut u64) -> Result<Vec<ArchiveMember>> {
+    if zip::is_zip(content) {
+        return read_zip(content, budget);
+    }
+    if content.starts_with(&[0x1f, 0x8b]) {
+        let limit = *budget;
+        return read_tar(GzDecoder::new(content).take(limit), budget);
+    }
+    if super::extension(path) == "tar" {
+        return read_tar(content, budget);
+    }
+    Err(Error::ParseError(format!(
+        "{} is not a ZIP or tar archive",
+        path.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::zip::tests::build_zip;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    fn build_tar(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn read(path: &str, content: &[u8]) -> Result<Vec<ArchiveMember>> {
+        let mut budget = MAX_INFLATED_SIZE;
+        members(Path::new(path), content, &mut budget)
+    }
+
+    fn names(members: &[ArchiveMember]) -> Vec<&str> {
+        members.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_zip_members() {
+        let data = build_zip(&[
+            ("docs/", b"", false),
+            ("docs/install.md", b"# Install", true),
+            ("./README.md", b"hello", false),
+            ("__MACOSX/docs/._install.md", b"\x00\x05", false),
+            ("docs/._install.md", b"\x00\x05", false),
+        ]);
+        let files = read("bundle.ZIP", &data).unwrap();
+
+        assert_eq!(names(&files), vec!["docs/install.md", "README.md"]);
+        assert_eq!(files[0].data, b"# Install");
+    }
+
+    #[test]
+    fn test_tar_members() {
+        let tar = build_tar(&[
+            ("exports/summary.csv", b"a,b\n1,2\n"),
+            ("exports/notes.txt", b"notes"),
+        ]);
+        let files = read("exports.tar", &tar).unwrap();
+        assert_eq!(
+            names(&files),
+            vec!["exports/summary.csv", "exports/notes.txt"]
+        );
+
+        let mut gz = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar).unwrap();
+        let gz = gz.finish().unwrap();
+        let files = read("exports.tgz", &gz).unwrap();
+        assert_eq!(files[1].data, b"notes");
+
+        // A truncated archive keeps the members before the cut
+        let files = read("exports.tar", &tar[..1536]).unwrap();
+        assert_eq!(names(&files), vec!["exports/summary.csv"]);
+    }
+
+    #[test]
+    fn test_inflated_budget() {
+        let data = build_zip(&[
+            ("a.txt", b"aaaa", true),
+            ("b.txt", b"bbbbbbbb", true),
+            ("c.txt", b"cc", false),
+        ]);
+        let mut budget = 7;
+        let files = members(Path::new("bundle.zip"), &data, &mut budget).unwrap();
+        assert_eq!(names(&files), vec!["a.txt", "c.txt"]);
+        assert_eq!(budget, 1);
+
+        let tar = build_tar(&[("a.txt", b"aaaa"), ("b.txt", b"bbbbbbbb"), ("c.txt", b"cc")]);
+        let mut budget = 7;
+        let files = members(Path::new("bundle.tar"), &tar, &mut budget).unwrap();
+        assert_eq!(names(&files), vec!["a.txt", "c.txt"]);
+        assert_eq!(budget, 1);
+
+        // A compressed tar is inflated no further than the budget
+        let mut gz = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar).unwrap();
+        let gz = gz.finish().unwrap();
+        let mut budget = 256;
+        assert!(members(Path::new("bundle.tgz"), &gz, &mut budget)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_member_names() {
+        assert!(is_archive(Path::new("release/v1.2.TAR.GZ")));
+        assert!(!is_archive(Path::new("report.docx")));
+        assert!(!is_archive(Path::new("notes.gz")));
+        assert_eq!(member_name("a\\b//./c.md").as_deref(), Some("a/b/c.md"));
+        assert_eq!(member_name("../etc/passwd"), None);
+        assert!(read("x.tar.gz", b"not an archive").is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! External-command parsers
+//!
+//! A command template such as `pandoc -t plain {file}` is split into
+//! arguments (single and double quotes group words) and run without a shell,
+//! with `{file}` replaced by the file's path; documents inside archives and
+//! messages have no file of their own and are written to a temporary file
+//! first. Without `{file}` the content is piped to the command's stdin. Its stdout, read as UTF-8, becomes the
+//! document body; a non-zero exit or a command running past its timeout is
+//! a parse error.
+
+use super::{ParseOptions, ParsedDocument, Parser};
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Default time a command may run before it is killed
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Placeholder replaced by the path of the file being parsed
+const FILE_PLACEHOLDER: &str = "{file}";
+
+/// A command configured to parse one extension in a collection
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionParser {
+    /// Collection name
+    pub collection: String,
+    /// File extension without the dot (`rtf`)
+    pub extension: String,
+    /// Command template, e.g. `pandoc -t plain {file}`
+    pub command: String,
+    /// Seconds the command may run
+    pub timeout_secs: u64,
+}
+
+/// Runs an external command and indexes its output
+#[derive(Debug, Clone)]
+pub struct CommandParser {
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+/// Split a command template into arguments, honouring quotes
+fn split_command(command: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => arg.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                arg.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(Error::ConfigError(format!(
+            "Unclosed quote in command: {}",
+            command
+        )));
+    }
+    if in_arg {
+        args.push(arg);
+    }
+    if args.is_empty() {
+        return Err(Error::ConfigError("Empty parser command".to_string()));
+    }
+    Ok(args)
+}
+
+/// A temporary copy of content that has no file of its own (such as an
+/// email attachment), removed when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create(name: &Path, content: &[u8]) -> Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let file_name = format!(
+            "qfs-{}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            name.file_name().and_then(|n| n.to_str()).unwrap_or("part")
+        );
+        let path = std::env::temp_dir().join(file_name);
+        std::fs::write(&path, content)?;
+        Ok(TempFile(path))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl CommandParser {
+    /// Parse with `command`, killing it after `timeout`
+    pub fn new(command: &str, timeout: Duration) -> Result<Self> {
+        Ok(CommandParser {
+            args: split_command(command)?,
+            timeout,
+        })
+    }
+
+    /// The program the command runs
+    pub fn program(&self) -> &str {
+        &self.args[0]
+    }
+
+    /// Run the command on a file and return its stdout. Unless `on_disk`,
+    /// `path` only names the content, which is written to a temporary file.
+    fn run(&self, path: &Path, content: &[u8], on_disk: bool) -> Result<String> {
+        let uses_file = self.args.iter().any(|a| a.contains(FILE_PLACEHOLDER));
+        let temp = if uses_file && !on_disk {
+            Some(TempFile::create(path, content)?)
+        } else {
+            None
+        };
+        let file = temp.as_ref().map_or(path, |t| t.0.as_path());
+        let args: Vec<String> = self.args[1..]
+            .iter()
+            .map(|a| a.replace(FILE_PLACEHOLDER, &file.to_string_lossy()))
+            .collect();
+
+        let mut child = Command::new(self.program())
+            .args(&args)
+            .stdin(if uses_file {
+                Stdio::null()
+            } else {
+                Stdio::piped()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::ParseError(format!("cannot run `{}`: {}", self.program(), e)))?;
+
+        // Read and write on threads so a full pipe can't stall the command
+        let stdin = child.stdin.take().map(|mut stdin| {
+            let content = content.to_vec();
+            std::thread::spawn(move || {
+                let _ = stdin.write_all(&content);
+            })
+        });
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let stdout = std::thread::spawn(move || {
+            let mut out = Vec::new();
+            let _ = stdout.read_to_end(&mut out);
+            out
+        });
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stderr = std::thread::spawn(move || {
+            let mut err = Vec::new();
+            let _ = stderr.read_to_end(&mut err);
+            err
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Error::ParseError(format!(
+                    "`{}` timed out after {}s",
+                    self.program(),
+                    self.timeout.as_secs_f64()
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        if let Some(stdin) = stdin {
+            let _ = stdin.join();
+        }
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&stderr);
+            let message = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+            return Err(Error::ParseError(format!(
+                "`{}` failed ({}){}{}",
+                self.program(),
+                status,
+                if message.is_empty() { "" } else { ": " },
+                message.trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+}
+
+impl Parser for CommandParser {
+    fn parse(&self, path: &Path, content: &[u8], options: &ParseOptions) -> Result<ParsedDocument> {
+        let body = self.run(path, content, options.depth == 0)?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert("parser".to_string(), self.program().into());
+        Ok(ParsedDocument {
+            title: path.file_stem().and_then(|s| s.to_str()).map(String::from),
+            body: body.trim_end().to_string(),
+            metadata,
+            is_binary: false,
+            mime_type: mime_guess::from_path(path)
+                .first_or_octet_stream()
+                .to_string(),
+            text_extracted: true,
+            pages: Vec::new(),
+            parts: Vec::new(),
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn parse(command: &str, path: &Path, content: &[u8]) -> Result<ParsedDocument> {
+        CommandParser::new(command, Duration::from_secs(5))?.parse(
+            path,
+            content,
+            &ParseOptions::default(),
+        )
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command("pandoc -t plain '{file}' --metadata \"title=A B\" ''").unwrap(),
+            vec![
+                "pandoc",
+                "-t",
+                "plain",
+                "{file}",
+                "--metadata",
+                "title=A B",
+                ""
+            ]
+        );
+        assert!(split_command("  ").is_err());
+        assert!(split_command("cat 'unclosed").is_err());
+    }
+
+    #[test]
+    fn test_stdout_becomes_the_body() {
+        // Content is piped to commands without {file}
+        let document = parse("tr a-z A-Z", Path::new("notes.rtf"), b"hello world\n").unwrap();
+        assert_eq!(document.body, "HELLO WORLD");
+        assert_eq!(document.title.as_deref(), Some("notes"));
+        assert_eq!(document.mime_type, "application/rtf");
+        assert_eq!(document.metadata["parser"], "tr");
+        assert!(document.text_extracted);
+    }
+
+    #[test]
+    fn test_file_placeholder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.rtf");
+        std::fs::write(&path, b"123").unwrap();
+
+        // Files on disk are passed by path
+        let document = parse("wc -c {file}", &path, b"123").unwrap();
+        assert_eq!(document.body, format!("3 {}", path.display()));
+
+        // Parts are written to a temporary file, even when a file with their
+        // relative name exists in the working directory
+        let part = CommandParser::new("wc -c {file}", Duration::from_secs(5))
+            .unwrap()
+            .parse(
+                Path::new("Cargo.toml"),
+                b"12345",
+                &ParseOptions {
+                    depth: 1,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(part.body.starts_with("5 "));
+        assert!(part.body.ends_with("Cargo.toml"));
+        assert_ne!(part.body, "5 Cargo.toml");
+    }
+
+    #[test]
+    fn test_failures_are_parse_errors() {
+        let err = parse(
+            "sh -c 'echo bad input >&2; exit 3'",
+            Path::new("x.rtf"),
+            b"",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error: `sh` failed (exit status: 3): bad input"
+        );
+
+        let err = CommandParser::new("sleep 5", Duration::from_millis(50))
+            .unwrap()
+            .parse(Path::new("x.rtf"), b"", &ParseOptions::default())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parse error: `sleep` timed out after 0.05s"
+        );
+
+        let err = parse("qfs-no-such-command {file}", Path::new("x.rtf"), b"").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Parse error: cannot run `qfs-no-such-command`"));
+    }
+}
@@ -0,0 +1,528 @@
+//! Email: single messages (`.eml`) and mbox archives
+//!
+//! Messages are decoded from MIME: headers are unfolded and their RFC 2047
+//! encoded words decoded, base64 and quoted-printable bodies are decoded and
+//! transcoded from their charset, and `multipart/alternative` prefers the
+//! plain-text part (HTML is reduced to readable text otherwise). Attachments
+//! are returned as decoded files for the other parsers. An mbox is split at
+//! its `From ` separator lines.
+
+use super::html;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// How deeply nested multiparts are followed
+const MAX_DEPTH: usize = 16;
+
+/// A decoded message
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Message {
+    /// Header names and decoded values, in order
+    pub headers: Vec<(String, String)>,
+    /// Text of the body parts
+    pub text: String,
+    pub attachments: Vec<Attachment>,
+}
+
+/// A file attached to a message
+#[derive(Debug, Clone)]
+pub(crate) struct Attachment {
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+impl Message {
+    /// First value of a header (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// Split an mbox into its messages, undoing `>From ` quoting
+///
+/// Data that doesn't start with a `From ` line is a single message.
+pub(crate) fn split_mbox(data: &[u8]) -> Vec<Vec<u8>> {
+    if !data.starts_with(b"From ") {
+        return vec![data.to_vec()];
+    }
+
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    let mut previous_blank = true;
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        if previous_blank && line.starts_with(b"From ") {
+            messages.extend(current.take());
+            current = Some(Vec::new());
+            previous_blank = false;
+            continue;
+        }
+        previous_blank = line.trim_ascii().is_empty();
+
+        if let Some(message) = current.as_mut() {
+            let quoted = line.iter().take_while(|&&b| b == b'>').count();
+            if quoted > 0 && line[quoted..].starts_with(b"From ") {
+                message.extend_from_slice(&line[1..]);
+            } else {
+                message.extend_from_slice(line);
+            }
+        }
+    }
+    messages.extend(current);
+
+    // The blank line before each separator belongs to the mbox, not the message
+    for message in &mut messages {
+        while message.last().is_some_and(|b| b.is_ascii_whitespace()) {
+            message.pop();
+        }
+        message.push(b'\n');
+    }
+    messages
+}
+
+/// Parse a message
+pub(crate) fn parse(data: &[u8]) -> Message {
+    let (head, _) = split_head(data);
+    let mut message = Message {
+        headers: parse_headers(head),
+        ..Default::default()
+    };
+    walk(data, &mut message, 0);
+    message.text = message.text.trim().to_string();
+    message
+}
+
+/// Split an entity at the blank line ending its headers
+fn split_head(data: &[u8]) -> (&[u8], &[u8]) {
+    let mut pos = 0;
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        if line == b"\n" || line == b"\r\n" {
+            return (&data[..pos], &data[pos + line.len()..]);
+        }
+        pos += line.len();
+    }
+    (data, &[])
+}
+
+/// Unfold and decode the header block
+fn parse_headers(head: &[u8]) -> Vec<(String, String)> {
+    let head = String::from_utf8_lossy(head);
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    for (_, value) in &mut headers {
+        *value = decode_words(value);
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Decode RFC 2047 encoded words (`=?utf-8?B?...?=`)
+fn decode_words(value: &str) -> String {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    let word = WORD.get_or_init(|| Regex::new(r"=\?([^?\s]+)\?([BbQq])\?([^?\s]*)\?=").unwrap());
+
+    let mut out = String::new();
+    let mut last = 0;
+    let mut after_word = false;
+    for caps in word.captures_iter(value) {
+        let whole = caps.get(0).unwrap();
+        let between = &value[last..whole.start()];
+        // Whitespace between adjacent encoded words is not part of the text
+        if !(after_word && between.trim().is_empty()) {
+            out.push_str(between);
+        }
+        let charset = caps[1].split('*').next().unwrap_or("utf-8");
+        let bytes = if caps[2].eq_ignore_ascii_case("b") {
+            decode_base64(caps[3].as_bytes())
+        } else {
+            decode_quoted_printable(caps[3].replace('_', " ").as_bytes())
+        };
+        out.push_str(&decode_charset(&bytes, Some(charset)));
+        last = whole.end();
+        after_word = true;
+    }
+    out.push_str(&value[last..]);
+    out
+}
+
+/// Split `type/subtype; key=value; ...` into the lowercased type and parameters
+fn parse_params(value: &str) -> (String, HashMap<String, String>) {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in value.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => parts.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    let mut parts = parts.into_iter();
+    let kind = parts.next().unwrap_or_default().trim().to_lowercase();
+    let mut params = HashMap::new();
+    for part in parts {
+        let Some((key, value)) = part.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        // RFC 2231 extended values: charset'language'percent-encoded
+        match key.strip_suffix('*') {
+            Some(key) => {
+                let mut pieces = value.splitn(3, '\'');
+                let (charset, _, encoded) = (pieces.next(), pieces.next(), pieces.next());
+                let decoded = match encoded {
+                    Some(encoded) => decode_charset(&percent_decode(encoded), charset),
+                    None => value.to_string(),
+                };
+                params.insert(key.to_string(), decoded);
+            }
+            None => {
+                params.entry(key).or_insert_with(|| value.to_string());
+            }
+        }
+    }
+    (kind, params)
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(b) = s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+fn decode_base64(data: &[u8]) -> Vec<u8> {
+    static ENGINE: OnceLock<GeneralPurpose> = OnceLock::new();
+    let engine = ENGINE.get_or_init(|| {
+        GeneralPurpose::new(
+            &base64::alphabet::STANDARD,
+            GeneralPurposeConfig::new()
+                .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+                .with_decode_allow_trailing_bits(true),
+        )
+    });
+    let compact: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    engine.decode(&compact).unwrap_or_default()
+}
+
+fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == b'=' {
+            // Soft line break
+            if data[i + 1..].starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if data[i + 1..].starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            let hex = data
+                .get(i + 1..i + 3)
+                .and_then(|h| std::str::from_utf8(h).ok());
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(data[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Decode text in `charset` (UTF-8 when missing or unknown)
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|c| encoding_rs::Encoding::for_label(c.trim().as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// Undo the transfer encoding of an entity body
+fn decode_transfer(body: &[u8], encoding: Option<&str>) -> Vec<u8> {
+    match encoding.map(|e| e.trim().to_lowercase()).as_deref() {
+        Some("base64") => decode_base64(body),
+        Some("quoted-printable") => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    }
+}
+
+/// The parts of a multipart body
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut pos = 0;
+    for line in body.split_inclusive(|&b| b == b'\n') {
+        let trimmed = line.trim_ascii_end();
+        let is_close = trimmed.strip_suffix(b"--") == Some(delimiter.as_bytes());
+        if trimmed == delimiter.as_bytes() || is_close {
+            if let Some(start) = start {
+                // The line break before a delimiter belongs to it
+                let mut end: usize = pos;
+                if body[..end].ends_with(b"\n") {
+                    end -= 1;
+                }
+                if body[..end].ends_with(b"\r") {
+                    end -= 1;
+                }
+                parts.push(&body[start..end.max(start)]);
+            }
+            if is_close {
+                return parts;
+            }
+            start = Some(pos + line.len());
+        }
+        pos += line.len();
+    }
+    // Unterminated: keep the last part
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// Collect the text and attachments of an entity
+fn walk(entity: &[u8], message: &mut Message, depth: usize) {
+    let (head, body) = split_head(entity);
+    let headers = parse_headers(head);
+    let (kind, params) = parse_params(header(&headers, "Content-Type").unwrap_or("text/plain"));
+    let kind = if kind.is_empty() {
+        "text/plain".to_string()
+    } else {
+        kind
+    };
+    let (disposition, disposition_params) =
+        parse_params(header(&headers, "Content-Disposition").unwrap_or(""));
+    let filename = disposition_params
+        .get("filename")
+        .or_else(|| params.get("name"))
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty());
+
+    if kind.starts_with("multipart/") && depth < MAX_DEPTH {
+        let parts = match params.get("boundary") {
+            Some(boundary) => split_multipart(body, boundary),
+            None => Vec::new(),
+        };
+        if kind == "multipart/alternative" {
+            // Prefer plain text, then HTML, then whatever comes last
+            let kinds: Vec<String> = parts
+                .iter()
+                .map(|part| {
+                    let (head, _) = split_head(part);
+                    let headers = parse_headers(head);
+                    parse_params(header(&headers, "Content-Type").unwrap_or("text/plain")).0
+                })
+                .collect();
+            let chosen = kinds
+                .iter()
+                .position(|k| k == "text/plain" || k.is_empty())
+                .or_else(|| kinds.iter().position(|k| k == "text/html"))
+                .or(parts.len().checked_sub(1));
+            if let Some(chosen) = chosen {
+                walk(parts[chosen], message, depth + 1);
+            }
+        } else {
+            for part in parts {
+                walk(part, message, depth + 1);
+            }
+        }
+        return;
+    }
+
+    let data = decode_transfer(body, header(&headers, "Content-Transfer-Encoding"));
+    let is_body_text = disposition != "attachment" && (kind == "text/plain" || kind == "text/html");
+    if !is_body_text {
+        if let Some(filename) = filename {
+            message.attachments.push(Attachment { filename, data });
+        } else if kind == "message/rfc822" {
+            message.attachments.push(Attachment {
+                filename: "message.eml".to_string(),
+                data,
+            });
+        }
+        return;
+    }
+
+    let text = decode_charset(&data, params.get("charset").map(String::as_str));
+    let text = if kind == "text/html" {
+        html::extract(&text).body
+    } else {
+        text.replace("\r\n", "\n")
+    };
+    if !text.trim().is_empty() {
+        if !message.text.is_empty() {
+            message.text.push_str("\n\n");
+        }
+        message.text.push_str(text.trim_end());
+    }
+}
+
+/// RFC 2822 date as RFC 3339 in UTC, if it parses
+pub(crate) fn normalize_date(date: &str) -> Option<String> {
+    // Drop trailing comments such as `(UTC)` or `(PST)`
+    let date = match date.find('(') {
+        Some(i) => date[..i].trim(),
+        None => date.trim(),
+    };
+    chrono::DateTime::parse_from_rfc2822(date)
+        .ok()
+        .map(|d| d.with_timezone(&chrono::Utc).to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPART: &[u8] = b"From: =?UTF-8?Q?Jos=C3=A9_Garc=C3=ADa?= <jose@example.com>\r\n\
+To: team@example.com,\r\n\
+\tops@example.com\r\n\
+Subject: =?utf-8?B?UXVhcnRlcmx5?= =?utf-8?B?IHJlcG9ydA==?=\r\n\
+Date: Tue, 4 Mar 2025 09:15:00 +0100 (CET)\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+\r\n\
+preamble\r\n\
+--outer\r\n\
+Content-Type: multipart/alternative; boundary=inner\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: text/plain; charset=iso-8859-1\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Revenue grew 12% this quarter. Caf=E9 expansion is on tr=\r\n\
+ack.\r\n\
+--inner\r\n\
+Content-Type: text/html\r\n\
+\r\n\
+<p>HTML version</p>\r\n\
+--inner--\r\n\
+--outer\r\n\
+Content-Type: text/csv; name=\"figures.csv\"\r\n\
+Content-Disposition: attachment; filename*=utf-8''q1%20figures.csv\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+cmVnaW9uLHJldmVudWUKRU1FQSwxMjAwCg==\r\n\
+--outer\r\n\
+Content-Type: image/png\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+iVBORw0KGgo=\r\n\
+--outer--\r\n\
+epilogue\r\n";
+
+    #[test]
+    fn test_multipart_message() {
+        let message = parse(MULTIPART);
+
+        assert_eq!(
+            message.header("from"),
+            Some("José García <jose@example.com>")
+        );
+        assert_eq!(
+            message.header("To"),
+            Some("team@example.com, ops@example.com")
+        );
+        assert_eq!(message.header("Subject"), Some("Quarterly report"));
+        assert_eq!(
+            message.text,
+            "Revenue grew 12% this quarter. Café expansion is on track."
+        );
+        assert_eq!(message.attachments.len(), 1);
+        assert_eq!(message.attachments[0].filename, "q1 figures.csv");
+        assert_eq!(message.attachments[0].data, b"region,revenue\nEMEA,1200\n");
+        assert_eq!(
+            normalize_date(message.header("date").unwrap()).as_deref(),
+            Some("2025-03-04T08:15:00+00:00")
+        );
+    }
+
+    #[test]
+    fn test_html_only_and_plain_messages() {
+        let html = parse(b"Subject: Hi\nContent-Type: text/html; charset=utf-8\n\n<html><body><p>Hello <b>there</b></p><script>x()</script></body></html>\n");
+        assert_eq!(html.text, "Hello there");
+
+        // No MIME headers at all
+        let plain = parse(b"Subject: Plain\n\nJust text.\n");
+        assert_eq!(plain.text, "Just text.");
+        assert!(plain.attachments.is_empty());
+        assert_eq!(plain.header("missing"), None);
+    }
+
+    #[test]
+    fn test_split_mbox() {
+        let mbox = b"From alice@example.com Mon Mar  3 10:00:00 2025\n\
+Subject: One\n\nFirst body\n>From the start\n\n\
+From bob@example.com Tue Mar  4 11:00:00 2025\n\
+Subject: Two\n\nSecond body, quoting\nFrom here on.\n";
+        let messages = split_mbox(mbox);
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0], b"Subject: One\n\nFirst body\nFrom the start\n");
+        // `From ` inside a paragraph isn't a separator
+        assert_eq!(
+            messages[1],
+            b"Subject: Two\n\nSecond body, quoting\nFrom here on.\n"
+        );
+
+        assert_eq!(split_mbox(b"Subject: Lone\n\nbody").len(), 1);
+    }
+
+    #[test]
+    fn test_decode_words() {
+        assert_eq!(
+            decode_words("=?ISO-8859-1?Q?Andr=E9?= Pirard"),
+            "André Pirard"
+        );
+        assert_eq!(decode_words("=?utf-8?q?a?= =?utf-8?q?b?= c"), "ab c");
+        assert_eq!(decode_words("plain =?bogus"), "plain =?bogus");
+    }
+}
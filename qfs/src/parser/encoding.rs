@@ -0,0 +1,234 @@
+//! Character encoding detection
+//!
+//! A byte order mark settles the encoding. Without one, text whose code
+//! units alternate with NUL bytes is read as UTF-16, other content with NUL
+//! bytes is binary, and valid UTF-8 is UTF-8. Anything else is tried as
+//! Shift_JIS, EUC-JP and EUC-KR, kept when it decodes without errors into
+//! enough kana or Hangul to be plausible. UTF-8 with a few stray bytes among
+//! at least as many multi-byte characters stays UTF-8, the stray bytes
+//! becoming replacement characters; everything else is read as Windows-1252.
+
+use encoding_rs::{Encoding, EUC_JP, EUC_KR, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::borrow::Cow;
+
+/// Bytes examined for NUL bytes and UTF-16 code units
+const SAMPLE_BYTES: usize = 8192;
+
+/// Whether a character belongs to the script an encoding is used for
+type IsScript = fn(char) -> bool;
+
+/// Legacy multi-byte encodings, with the characters that mark text in them
+const LEGACY: [(&Encoding, IsScript); 3] =
+    [(SHIFT_JIS, is_kana), (EUC_JP, is_kana), (EUC_KR, is_hangul)];
+
+/// Hiragana and full-width katakana; half-width katakana are single bytes in
+/// Shift_JIS, so text in other encodings decodes to them too easily
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}')
+}
+
+fn is_hangul(c: char) -> bool {
+    matches!(c, '\u{ac00}'..='\u{d7a3}')
+}
+
+/// UTF-16 without a byte order mark: most code units of ASCII text have a
+/// zero high byte, and the decoded sample has no control characters
+fn utf16(content: &[u8]) -> Option<&'static Encoding> {
+    let sample = &content[..content.len().min(SAMPLE_BYTES) & !1];
+    let units = sample.len() / 2;
+    if units == 0 {
+        return None;
+    }
+    let zero_high = |high: usize| {
+        sample
+            .chunks_exact(2)
+            .filter(|unit| unit[high] == 0 && unit[1 - high] != 0)
+            .count()
+    };
+    let (le, be) = (zero_high(1), zero_high(0));
+    let encoding = if le * 2 >= units && be == 0 {
+        UTF_16LE
+    } else if be * 2 >= units && le == 0 {
+        UTF_16BE
+    } else {
+        return None;
+    };
+
+    let (text, _) = encoding.decode_without_bom_handling(sample);
+    // A sample cut inside a surrogate pair ends in a replacement character
+    let text = text.strip_suffix('\u{fffd}').unwrap_or(&text);
+    let is_text = !text
+        .chars()
+        .any(|c| c == '\u{fffd}' || (c.is_control() && !c.is_whitespace()));
+    is_text.then_some(encoding)
+}
+
+/// The legacy multi-byte encoding `content` decodes cleanly in
+fn legacy(content: &[u8]) -> Option<&'static Encoding> {
+    for (encoding, is_script) in LEGACY {
+        let Some(text) = encoding.decode_without_bom_handling_and_without_replacement(content)
+        else {
+            continue;
+        };
+        let non_ascii = text.chars().filter(|c| !c.is_ascii()).count();
+        let script = text.chars().filter(|&c| is_script(c)).count();
+        if script > 0 && script * 5 >= non_ascii {
+            return Some(encoding);
+        }
+    }
+    None
+}
+
+/// UTF-8 with some invalid sequences, as left by a truncated or corrupted
+/// write: Windows-1252 text rarely forms valid multi-byte sequences, so
+/// those outnumbering the invalid ones mark damaged UTF-8
+fn mostly_utf8(content: &[u8]) -> bool {
+    let (mut multi_byte, mut invalid) = (0, 0);
+    for chunk in content.utf8_chunks() {
+        multi_byte += chunk.valid().chars().filter(|c| !c.is_ascii()).count();
+        invalid += usize::from(!chunk.invalid().is_empty());
+    }
+    multi_byte >= invalid
+}
+
+/// Whether `content` is UTF-16 text, which has NUL bytes but isn't binary
+pub(crate) fn is_utf16(content: &[u8]) -> bool {
+    match Encoding::for_bom(content) {
+        Some((encoding, _)) => encoding != UTF_8,
+        None => utf16(content).is_some(),
+    }
+}
+
+/// Detect the encoding of text, `None` if `content` is binary
+pub fn detect_encoding(content: &[u8]) -> Option<&'static Encoding> {
+    if let Some((encoding, _)) = Encoding::for_bom(content) {
+        return Some(encoding);
+    }
+    if let Some(encoding) = utf16(content) {
+        return Some(encoding);
+    }
+    if content.iter().take(SAMPLE_BYTES).any(|&b| b == 0) {
+        return None;
+    }
+    if std::str::from_utf8(content).is_ok() {
+        return Some(UTF_8);
+    }
+    if let Some(encoding) = legacy(content) {
+        return Some(encoding);
+    }
+    Some(if mostly_utf8(content) {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    })
+}
+
+/// Decode text in its detected encoding, without a byte order mark; `None`
+/// if `content` is binary
+pub fn decode_text(content: &[u8]) -> Option<(Cow<'_, str>, &'static Encoding)> {
+    let encoding = detect_encoding(content)?;
+    let (text, _) = encoding.decode_with_bom_removal(content);
+    Some((text, encoding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect()
+    }
+
+    fn decode(content: &[u8]) -> (String, &'static str) {
+        let (text, encoding) = decode_text(content).unwrap();
+        (text.into_owned(), encoding.name())
+    }
+
+    #[test]
+    fn test_byte_order_marks() {
+        let mut le = vec![0xff, 0xfe];
+        le.extend(utf16le("Grüße 日本"));
+        assert_eq!(decode(&le), ("Grüße 日本".to_string(), "UTF-16LE"));
+
+        let mut be = vec![0xfe, 0xff];
+        be.extend(utf16be("Grüße"));
+        assert_eq!(decode(&be), ("Grüße".to_string(), "UTF-16BE"));
+
+        assert_eq!(
+            decode(b"\xef\xbb\xbfcaf\xc3\xa9"),
+            ("café".to_string(), "UTF-8")
+        );
+    }
+
+    #[test]
+    fn test_utf16_without_bom() {
+        let text = "Release notes\r\n- Fixed the installer\r\n";
+        assert_eq!(decode(&utf16le(text)), (text.to_string(), "UTF-16LE"));
+        assert_eq!(decode(&utf16be(text)), (text.to_string(), "UTF-16BE"));
+        assert!(is_utf16(&utf16le(text)));
+
+        // Mostly non-ASCII text still needs its ASCII units to be detected
+        assert_eq!(decode(&utf16le("ab日本")).1, "UTF-16LE");
+
+        // Little-endian integers aren't text
+        let ints: Vec<u8> = (1u16..200).flat_map(|i| i.to_le_bytes()).collect();
+        assert!(detect_encoding(&ints).is_none());
+        assert!(!is_utf16(&ints));
+        assert!(detect_encoding(b"PK\x03\x04\x00\x00binary").is_none());
+    }
+
+    #[test]
+    fn test_legacy_encodings() {
+        let (bytes, _, _) =
+            WINDOWS_1252.encode("Caf\u{e9} cr\u{e8}me \u{2013} \u{201c}na\u{ef}ve\u{201d}");
+        assert_eq!(
+            decode(&bytes),
+            (
+                "Caf\u{e9} cr\u{e8}me \u{2013} \u{201c}na\u{ef}ve\u{201d}".to_string(),
+                "windows-1252"
+            )
+        );
+
+        let japanese = "議事録：来週のリリースについて";
+        let (bytes, _, _) = SHIFT_JIS.encode(japanese);
+        assert_eq!(decode(&bytes), (japanese.to_string(), "Shift_JIS"));
+        let (bytes, _, _) = EUC_JP.encode(japanese);
+        assert_eq!(decode(&bytes), (japanese.to_string(), "EUC-JP"));
+
+        let korean = "회의록: 다음 주 릴리스";
+        let (bytes, _, _) = EUC_KR.encode(korean);
+        assert_eq!(decode(&bytes), (korean.to_string(), "EUC-KR"));
+
+        // Latin text that happens to form valid Shift_JIS has no kana
+        assert_eq!(decode(b"\xe9a\xe8e").1, "windows-1252");
+        assert_eq!(decode(b"plain ascii").1, "UTF-8");
+        assert_eq!(decode(b"").1, "UTF-8");
+    }
+
+    #[test]
+    fn test_damaged_utf8() {
+        // A stray byte doesn't turn UTF-8 into mojibake
+        let mut bytes = "Caf\u{e9} na\u{ef}ve r\u{e9}sum\u{e9}".as_bytes().to_vec();
+        bytes.push(0xff);
+        assert_eq!(
+            decode(&bytes),
+            (
+                "Caf\u{e9} na\u{ef}ve r\u{e9}sum\u{e9}\u{fffd}".to_string(),
+                "UTF-8"
+            )
+        );
+
+        // Nor does a multi-byte character cut short
+        let mut bytes = "\u{201c}quoted\u{201d} and \u{2013} ".as_bytes().to_vec();
+        bytes.extend(&"\u{2026}".as_bytes()[..2]);
+        assert_eq!(decode(&bytes).1, "UTF-8");
+
+        // Windows-1252 with a single accidental multi-byte sequence isn't
+        let (bytes, _, _) = WINDOWS_1252.encode("\u{c3}\u{a9} caf\u{e9} cr\u{e8}me");
+        assert_eq!(decode(&bytes).1, "windows-1252");
+    }
+}
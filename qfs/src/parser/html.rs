@@ -0,0 +1,734 @@
+//! HTML readable-text extraction
+//!
+//! A forgiving tag scanner rather than a full HTML5 parser. Markup is
+//! stripped, scripts, styles and navigation boilerplate are dropped, block
+//! elements become line breaks and headings become Markdown-style `#` lines,
+//! so the text keeps the page outline. When a page marks its main content
+//! (`<main>` or `role="main"`), only that content is kept.
+
+use std::collections::HashMap;
+
+/// Elements whose content is never readable text
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "math", "iframe", "object", "canvas",
+    "select", "button", "datalist",
+];
+
+/// Page furniture around the content
+const BOILERPLATE: &[&str] = &["nav", "aside", "footer", "form", "dialog"];
+
+/// ARIA roles of page furniture
+const BOILERPLATE_ROLES: &[&str] = &[
+    "navigation",
+    "banner",
+    "contentinfo",
+    "complementary",
+    "search",
+];
+
+/// Elements that start and end a line
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "body",
+    "caption",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "tfoot",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Elements with no closing tag
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Readable content of an HTML page
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HtmlText {
+    /// `<title>`, or the first `<h1>`
+    pub title: Option<String>,
+    /// Text with headings as `#` lines
+    pub body: String,
+    /// Meta description, keywords and author, page language and headings
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// An element on the open-element stack
+struct Open {
+    name: String,
+    /// Content is dropped
+    skipped: bool,
+    /// Element is the page's main content
+    main: bool,
+}
+
+/// Extract the readable text of an HTML page
+pub(crate) fn extract(html: &str) -> HtmlText {
+    let mut extractor = Extractor::default();
+    let bytes = html.as_bytes();
+    let mut pos = 0;
+    let mut text_start = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos] != b'<' {
+            pos += 1;
+            continue;
+        }
+        let next = bytes.get(pos + 1).copied().unwrap_or(b' ');
+        let end = if html[pos..].starts_with("<!--") {
+            html[pos + 4..].find("-->").map(|i| pos + 4 + i + 3)
+        } else if next == b'!' || next == b'?' {
+            html[pos..].find('>').map(|i| pos + i + 1)
+        } else if next.is_ascii_alphabetic()
+            || (next == b'/' && bytes.get(pos + 2).is_some_and(u8::is_ascii_alphabetic))
+        {
+            tag_end(bytes, pos)
+        } else {
+            // A literal `<` in text
+            pos += 1;
+            continue;
+        };
+
+        extractor.text(&html[text_start..pos]);
+        let Some(end) = end else {
+            // Unterminated markup swallows the rest, like browsers do
+            text_start = bytes.len();
+            break;
+        };
+
+        let tag = &html[pos..end];
+        pos = end;
+        text_start = end;
+        if let Some(name) = tag.strip_prefix("</") {
+            extractor.end_tag(&tag_name(name));
+        } else if next.is_ascii_alphabetic() {
+            let name = tag_name(&tag[1..]);
+            let attributes = parse_attributes(&tag[1 + name.len()..tag.len() - 1]);
+            let self_closing = tag.ends_with("/>");
+
+            // Raw text: everything up to the closing tag is content, not markup
+            if matches!(name.as_str(), "script" | "style" | "title" | "textarea") {
+                let close = format!("</{}", name);
+                let content_end =
+                    find_ignore_case(&html[pos..], &close).map_or(html.len(), |i| pos + i);
+                let content = &html[pos..content_end];
+                pos = html[content_end..]
+                    .find('>')
+                    .map_or(html.len(), |i| content_end + i + 1);
+                text_start = pos;
+                match name.as_str() {
+                    "title" => extractor.title(content),
+                    "textarea" => {
+                        extractor.start_tag(&name, &attributes, false);
+                        extractor.text(content);
+                        extractor.end_tag(&name);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            extractor.start_tag(&name, &attributes, self_closing);
+        }
+    }
+    extractor.text(&html[text_start.min(html.len())..]);
+    extractor.finish()
+}
+
+/// End of the tag starting at `start`, honouring quoted attribute values
+fn tag_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, &b) in bytes.iter().enumerate().skip(start + 1) {
+        match (quote, b) {
+            (None, b'"' | b'\'') => quote = Some(b),
+            (Some(q), _) if b == q => quote = None,
+            (None, b'>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.chars()
+        .take_while(|c| !c.is_whitespace() && *c != '/' && *c != '>')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Attributes of a start tag, names lowercased and values entity-decoded
+fn parse_attributes(source: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = source.trim_end_matches('/').trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => match after[1..].find(q) {
+                    Some(i) => (&after[1..1 + i], &after[2 + i..]),
+                    None => (&after[1..], ""),
+                },
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining.trim_start();
+        }
+        if !name.is_empty() {
+            attributes.entry(name).or_insert(value);
+        } else {
+            // Stray `=` or quote; skip a character to make progress
+            rest = rest.get(1..).unwrap_or("").trim_start();
+        }
+    }
+    attributes
+}
+
+/// Decode character references such as `&amp;`, `&#233;` and `&#x2014;`
+pub(crate) fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let end = rest[1..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '#')
+            .map_or(rest.len(), |i| i + 1);
+        let reference = &rest[1..end];
+        let decoded = match reference.strip_prefix('#') {
+            Some(number) => match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => number.parse().ok(),
+            }
+            .and_then(char::from_u32),
+            None => named_entity(reference),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end..];
+                rest = rest.strip_prefix(';').unwrap_or(rest);
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn named_entity(name: &str) -> Option<char> {
+    let c = match name {
+        "amp" | "AMP" => '&',
+        "lt" | "LT" => '<',
+        "gt" | "GT" => '>',
+        "quot" | "QUOT" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "shy" => '\u{ad}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        "deg" => '°',
+        "times" => '×',
+        "divide" => '÷',
+        "plusmn" => '±',
+        "sect" => '§',
+        "para" => '¶',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "cent" => '¢',
+        "larr" => '←',
+        "rarr" => '→',
+        "uarr" => '↑',
+        "darr" => '↓',
+        "hearts" => '♥',
+        "check" => '✓',
+        "auml" => 'ä',
+        "ouml" => 'ö',
+        "uuml" => 'ü',
+        "Auml" => 'Ä',
+        "Ouml" => 'Ö',
+        "Uuml" => 'Ü',
+        "szlig" => 'ß',
+        "eacute" => 'é',
+        "egrave" => 'è',
+        "ecirc" => 'ê',
+        "Eacute" => 'É',
+        "aacute" => 'á',
+        "agrave" => 'à',
+        "acirc" => 'â',
+        "iacute" => 'í',
+        "oacute" => 'ó',
+        "uacute" => 'ú',
+        "ntilde" => 'ñ',
+        "ccedil" => 'ç',
+        "oslash" => 'ø',
+        "aring" => 'å',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Accumulates readable text with collapsed whitespace and pending breaks
+#[derive(Default)]
+struct TextBuffer {
+    out: String,
+    /// Line breaks owed before the next text (2 = blank line)
+    breaks: usize,
+    space: bool,
+}
+
+impl TextBuffer {
+    fn push(&mut self, text: &str, preformatted: bool) {
+        for c in text.chars() {
+            if !preformatted && (c.is_whitespace() || c == '\u{ad}') {
+                self.space |= c != '\u{ad}';
+                continue;
+            }
+            if self.breaks > 0 {
+                if !self.out.is_empty() {
+                    self.out.push_str(&"\n".repeat(self.breaks));
+                }
+                self.breaks = 0;
+                self.space = false;
+            } else if self.space {
+                if !self.out.is_empty() && !self.out.ends_with(char::is_whitespace) {
+                    self.out.push(' ');
+                }
+                self.space = false;
+            }
+            // `&nbsp;` reads as a space
+            self.out.push(if c == '\u{a0}' { ' ' } else { c });
+        }
+    }
+
+    fn line_break(&mut self, lines: usize) {
+        self.breaks = self.breaks.max(lines);
+    }
+}
+
+#[derive(Default)]
+struct Extractor {
+    stack: Vec<Open>,
+    /// All readable text
+    all: TextBuffer,
+    /// Text inside main content
+    main: TextBuffer,
+    has_main: bool,
+    title: Option<String>,
+    first_h1: Option<String>,
+    /// Heading being read: level, text, inside main
+    heading: Option<(usize, String, bool)>,
+    headings: Vec<(usize, String, bool)>,
+    metadata: HashMap<String, serde_json::Value>,
+    preformatted: usize,
+}
+
+impl Extractor {
+    fn skipping(&self) -> bool {
+        self.stack.last().is_some_and(|open| open.skipped)
+    }
+
+    fn in_main(&self) -> bool {
+        self.stack.last().is_some_and(|open| open.main)
+    }
+
+    fn title(&mut self, raw: &str) {
+        let title = collapse(&decode_entities(raw));
+        if self.title.is_none() && !title.is_empty() {
+            self.title = Some(title);
+        }
+    }
+
+    fn text(&mut self, raw: &str) {
+        if raw.is_empty() || self.skipping() {
+            return;
+        }
+        let text = decode_entities(raw);
+        let preformatted = self.preformatted > 0;
+        if let Some((_, heading, _)) = &mut self.heading {
+            heading.push_str(&text);
+        }
+        self.all.push(&text, preformatted);
+        if self.in_main() {
+            self.main.push(&text, preformatted);
+        }
+    }
+
+    fn line_break(&mut self, lines: usize) {
+        self.all.line_break(lines);
+        if self.in_main() {
+            self.main.line_break(lines);
+        }
+    }
+
+    fn start_tag(&mut self, name: &str, attributes: &HashMap<String, String>, self_closing: bool) {
+        if name == "meta" {
+            self.meta(attributes);
+        }
+        if name == "html" {
+            if let Some(lang) = attributes.get("lang").filter(|l| !l.is_empty()) {
+                self.metadata
+                    .insert("lang".to_string(), lang.clone().into());
+            }
+        }
+
+        let skipping = self.skipping();
+        let inside_content = self
+            .stack
+            .iter()
+            .any(|open| matches!(open.name.as_str(), "article" | "main"));
+        let role = attributes.get("role").map(|r| r.to_ascii_lowercase());
+        let skipped = skipping
+            || SKIPPED.contains(&name)
+            || BOILERPLATE.contains(&name)
+            || (name == "header" && !inside_content)
+            || role
+                .as_deref()
+                .is_some_and(|r| BOILERPLATE_ROLES.contains(&r))
+            || attributes.contains_key("hidden")
+            || attributes.get("aria-hidden").is_some_and(|v| v == "true");
+        let main =
+            !skipped && (self.in_main() || name == "main" || role.as_deref() == Some("main"));
+        if main && !self.in_main() {
+            self.has_main = true;
+        }
+
+        if VOID.contains(&name) || self_closing {
+            if !skipped && matches!(name, "br" | "hr") {
+                self.line_break(1);
+            }
+            return;
+        }
+
+        self.stack.push(Open {
+            name: name.to_string(),
+            skipped,
+            main,
+        });
+        if skipped {
+            return;
+        }
+
+        if let Some(level) = heading_level(name) {
+            self.line_break(2);
+            self.text(&format!("{} ", "#".repeat(level)));
+            self.heading = Some((level, String::new(), main));
+        } else if name == "li" {
+            self.line_break(1);
+            self.text("- ");
+        } else if name == "pre" {
+            self.line_break(2);
+            self.preformatted += 1;
+        } else if matches!(name, "p" | "blockquote" | "table" | "figure") {
+            self.line_break(2);
+        } else if BLOCKS.contains(&name) {
+            self.line_break(1);
+        } else if matches!(name, "td" | "th") {
+            self.all.space = true;
+            self.main.space = true;
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        // Close implicitly-closed children too; ignore stray end tags
+        let Some(index) = self.stack.iter().rposition(|open| open.name == name) else {
+            return;
+        };
+        while self.stack.len() > index {
+            let open = self.stack.last().expect("non-empty stack");
+            if !open.skipped {
+                let closed = open.name.clone();
+                self.close(&closed);
+            }
+            self.stack.pop();
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        if heading_level(name).is_some() {
+            if let Some((level, text, main)) = self.heading.take() {
+                let text = collapse(&text);
+                if !text.is_empty() {
+                    if level == 1 && self.first_h1.is_none() {
+                        self.first_h1 = Some(text.clone());
+                    }
+                    self.headings.push((level, text, main));
+                }
+            }
+            self.line_break(2);
+        } else if name == "pre" {
+            self.preformatted = self.preformatted.saturating_sub(1);
+            self.line_break(2);
+        } else if matches!(name, "p" | "blockquote" | "table" | "figure") {
+            self.line_break(2);
+        } else if BLOCKS.contains(&name) {
+            self.line_break(1);
+        }
+    }
+
+    fn meta(&mut self, attributes: &HashMap<String, String>) {
+        let key = attributes
+            .get("name")
+            .or_else(|| attributes.get("property"))
+            .map(|k| k.to_ascii_lowercase());
+        let Some(content) = attributes.get("content").map(|c| collapse(c)) else {
+            return;
+        };
+        if content.is_empty() {
+            return;
+        }
+        match key.as_deref() {
+            Some("description") => {
+                self.metadata
+                    .insert("description".to_string(), content.into());
+            }
+            Some("og:description") => {
+                self.metadata
+                    .entry("description".to_string())
+                    .or_insert(content.into());
+            }
+            Some("keywords") => {
+                let keywords: Vec<serde_json::Value> = content
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|k| !k.is_empty())
+                    .map(|k| k.to_string().into())
+                    .collect();
+                self.metadata
+                    .insert("keywords".to_string(), keywords.into());
+            }
+            Some("author") => {
+                self.metadata.insert("author".to_string(), content.into());
+            }
+            Some("og:title") => {
+                self.metadata.insert("og:title".to_string(), content.into());
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> HtmlText {
+        let use_main = self.has_main && !self.main.out.trim().is_empty();
+        let body = if use_main {
+            &self.main.out
+        } else {
+            &self.all.out
+        };
+        let body = body
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+
+        let headings: Vec<serde_json::Value> = self
+            .headings
+            .iter()
+            .filter(|(_, _, main)| !use_main || *main)
+            .map(|(level, text, _)| serde_json::json!({ "level": level, "text": text }))
+            .collect();
+        if !headings.is_empty() {
+            self.metadata
+                .insert("headings".to_string(), headings.into());
+        }
+
+        HtmlText {
+            title: self.title.or(self.first_h1),
+            body,
+            metadata: self.metadata,
+        }
+    }
+}
+
+fn heading_level(name: &str) -> Option<usize> {
+    match name {
+        "h1" => Some(1),
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
+
+/// Collapse whitespace runs to single spaces
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Deploy Guide &middot; Acme Docs</title>
+  <meta name="description" content="How to deploy   the service">
+  <meta name="keywords" content="deploy, kubernetes , ,helm">
+  <style>body { color: red; }</style>
+  <script>var nav = "<h1>not a heading</h1>";</script>
+</head>
+<body>
+  <header><a href="/">Acme</a> <input type=search placeholder="Search"></header>
+  <nav><ul><li><a href="/a">Home</a></li><li>Pricing</li></ul></nav>
+  <main>
+    <article>
+      <header><h1>Deploying   to production</h1></header>
+      <p>Run <code>helm upgrade</code> with the&nbsp;release &amp; values file.<br>Then wait.</p>
+      <h2 id="rollback">Rolling back</h2>
+      <ul><li>Find the revision<li>Run <b>helm rollback</b></ul>
+      <pre>helm rollback web 3
+  --wait</pre>
+      <div hidden>Secret draft</div>
+      <p aria-hidden="true">decoration</p>
+      <table><tr><th>Env</th><th>Replicas</th></tr><tr><td>prod</td><td>3</td></tr></table>
+      <!-- <p>commented out</p> -->
+    </article>
+  </main>
+  <aside>Related posts</aside>
+  <footer>&copy; 2026 Acme</footer>
+</body>
+</html>"##;
+
+    #[test]
+    fn test_extract_readable_main_content() {
+        let text = extract(PAGE);
+
+        assert_eq!(text.title.as_deref(), Some("Deploy Guide · Acme Docs"));
+        assert_eq!(
+            text.body,
+            "# Deploying to production\n\n\
+             Run helm upgrade with the release & values file.\n\
+             Then wait.\n\n\
+             ## Rolling back\n\n\
+             - Find the revision\n\
+             - Run helm rollback\n\n\
+             helm rollback web 3\n  --wait\n\n\
+             Env Replicas\n\
+             prod 3"
+        );
+        for boilerplate in [
+            "Home",
+            "Pricing",
+            "Related",
+            "Acme\n",
+            "color",
+            "not a heading",
+            "Secret",
+            "decoration",
+            "commented",
+        ] {
+            assert!(
+                !text.body.contains(boilerplate),
+                "{:?} in {:?}",
+                boilerplate,
+                text.body
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_metadata() {
+        let text = extract(PAGE);
+
+        assert_eq!(text.metadata["description"], "How to deploy the service");
+        assert_eq!(
+            text.metadata["keywords"],
+            serde_json::json!(["deploy", "kubernetes", "helm"])
+        );
+        assert_eq!(text.metadata["lang"], "en");
+        assert_eq!(
+            text.metadata["headings"],
+            serde_json::json!([
+                {"level": 1, "text": "Deploying to production"},
+                {"level": 2, "text": "Rolling back"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_title_falls_back_to_first_h1() {
+        let text =
+            extract("<body><div>Intro</div><h1>First <em>Heading</em></h1><h1>Second</h1></body>");
+        assert_eq!(text.title.as_deref(), Some("First Heading"));
+        // Without <main> the whole body is kept
+        assert_eq!(text.body, "Intro\n\n# First Heading\n\n# Second");
+    }
+
+    #[test]
+    fn test_malformed_markup() {
+        let text = extract("a < b and c<d> &unknown; &#233;&#x2014;</span></p><p title='x > y'>tail<p>end <!-- open");
+        assert_eq!(text.body, "a < b and c &unknown; é—\n\ntail\n\nend");
+        assert!(text.title.is_none());
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("Tom &amp; Jerry&apos;s &lt;3"),
+            "Tom & Jerry's <3"
+        );
+        assert_eq!(decode_entities("&#65;&#x42;&#X43;"), "ABC");
+        assert_eq!(decode_entities("AT&T &"), "AT&T &");
+    }
+}
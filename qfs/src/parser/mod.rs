@@ -1,9 +1,29 @@
 //! Content parsers for different file types
 
+mod archive;
+mod command;
+mod email;
+mod encoding;
+mod html;
+mod notebook;
+mod office;
+mod pdf;
+mod registry;
+mod table;
+mod transcript;
+mod xml;
+mod zip;
+
 use crate::error::Result;
 use std::collections::HashMap;
 use std::path::Path;
 
+pub use archive::is_archive;
+pub use command::{CollectionParser, CommandParser, DEFAULT_COMMAND_TIMEOUT};
+pub(crate) use encoding::is_utf16;
+pub use encoding::{decode_text, detect_encoding};
+pub use registry::{Parser, ParserRegistry};
+
 /// Parsed document content
 #[derive(Debug, Clone)]
 pub struct ParsedDocument {
@@ -17,41 +37,179 @@ pub struct ParsedDocument {
     pub is_binary: bool,
     /// MIME type
     pub mime_type: String,
+    /// Whether `body` was extracted from a non-text format, so it stands in
+    /// for the raw content when showing, grepping and embedding the file
+    pub text_extracted: bool,
+    /// 1-based line of `body` where each page starts (empty if unpaged)
+    pub pages: Vec<u32>,
+    /// Documents inside this one, such as the messages of an mbox or the
+    /// attachments of a message
+    pub parts: Vec<DocumentPart>,
 }
 
-/// Parse a file and extract searchable content
-pub fn parse_file(path: &Path, content: &[u8]) -> Result<ParsedDocument> {
-    // Check for binary content
-    if crate::is_binary(content) {
-        return Ok(ParsedDocument {
-            title: path.file_stem().and_then(|s| s.to_str()).map(String::from),
-            body: String::new(),
-            metadata: HashMap::new(),
-            is_binary: true,
-            mime_type: mime_guess::from_path(path)
-                .first_or_octet_stream()
-                .to_string(),
-        });
+/// A document inside a container file, indexed on its own as `path!/name`
+#[derive(Debug, Clone)]
+pub struct DocumentPart {
+    /// Name within the container
+    pub name: String,
+    /// Raw bytes, stored as the part's content
+    pub content: Vec<u8>,
+    /// The parsed part, which may have parts of its own
+    pub document: ParsedDocument,
+}
+
+/// Options that change how files are parsed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParseOptions {
+    /// Include the text outputs of notebook code cells
+    pub notebook_outputs: bool,
+    /// Index the files inside ZIP and tar archives as documents of their own
+    pub archives: bool,
+    /// How many containers (archives, messages) the file being parsed is in;
+    /// 0 for a file read from its path on disk. Set by the parsers of
+    /// containers for the documents inside them.
+    pub depth: usize,
+}
+
+/// Containers nested deeper than this aren't opened
+const MAX_NESTING: usize = 3;
+
+impl ParseOptions {
+    /// Options for parsing the documents inside a container, `None` once
+    /// containers are nested `MAX_NESTING` deep
+    fn nested(&self) -> Option<ParseOptions> {
+        (self.depth < MAX_NESTING).then(|| ParseOptions {
+            depth: self.depth + 1,
+            ..self.clone()
+        })
+    }
+}
+
+/// What a document's pages are called in citations: `slide` for
+/// presentations, `cell` for notebooks, `row` for tables, `message` for
+/// session transcripts and `page` otherwise
+pub fn page_label(mime_type: &str) -> &'static str {
+    match mime_type {
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => "slide",
+        NOTEBOOK_MIME => "cell",
+        TRANSCRIPT_MIME => "message",
+        m if is_table(m) => "row",
+        _ => "page",
     }
+}
+
+/// Whether `mime_type` is a delimited table, whose text starts with a header line
+pub fn is_table(mime_type: &str) -> bool {
+    matches!(mime_type, "text/csv" | "text/tab-separated-values")
+}
+
+const NOTEBOOK_MIME: &str = "application/x-ipynb+json";
+
+const TRANSCRIPT_MIME: &str = "application/x-transcript+jsonl";
+
+/// Whether `parse_file` extracts text from this file into a separate body,
+/// so the indexer must store it alongside the raw bytes
+pub fn extracts_text(path: &Path, content: &[u8]) -> bool {
+    let ext = extension(path);
+    pdf::is_pdf(content)
+        || matches!(
+            ext.as_str(),
+            "html" | "htm" | "xhtml" | "ipynb" | "csv" | "tsv" | "eml"
+        )
+        || (office::EXTENSIONS.contains(&ext.as_str()) && zip::is_zip(content))
+        || (ext == "jsonl"
+            && decode_text(content).is_some_and(|(text, _)| transcript::is_transcript(&text)))
+}
 
-    // Convert to string
-    let text = String::from_utf8_lossy(content);
+/// Whether `path` is a Jupyter notebook, whose text depends on `ParseOptions`
+pub fn is_notebook(path: &Path) -> bool {
+    extension(path) == "ipynb"
+}
+
+/// Whether `path` is a mailbox, indexed as one document per message
+pub fn is_container(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "mbox" | "mbx")
+}
 
-    // Get file extension
-    let ext = path
-        .extension()
+/// Lowercased file extension
+fn extension(path: &Path) -> String {
+    path.extension()
         .and_then(|e| e.to_str())
         .unwrap_or("")
-        .to_lowercase();
+        .to_lowercase()
+}
 
-    // Parse based on extension
+/// Parse a file and extract searchable content
+pub fn parse_file(path: &Path, content: &[u8]) -> Result<ParsedDocument> {
+    parse_file_with_options(path, content, &ParseOptions::default())
+}
+
+/// Parse a file with non-default options
+pub fn parse_file_with_options(
+    path: &Path,
+    content: &[u8],
+    options: &ParseOptions,
+) -> Result<ParsedDocument> {
+    ParserRegistry::new().parse(path, content, options)
+}
+
+/// Parse a file with the built-in parsers; the documents it contains are
+/// parsed with `parsers`, and the archives among them inflate at most
+/// `budget` bytes
+fn parse_builtin(
+    path: &Path,
+    content: &[u8],
+    options: &ParseOptions,
+    parsers: &ParserRegistry,
+    budget: &mut u64,
+) -> Result<ParsedDocument> {
+    // PDFs contain binary streams, so check for them first
+    if pdf::is_pdf(content) {
+        return Ok(parse_pdf(content, path));
+    }
+
+    // So are office documents and e-books, which are zipped XML
+    let ext = extension(path);
+    if office::EXTENSIONS.contains(&ext.as_str()) && zip::is_zip(content) {
+        return Ok(parse_office(&ext, content, path));
+    }
+
+    // Archives are indexed file by file when enabled, and by name otherwise
+    if options.archives && archive::is_archive(path) {
+        return Ok(parse_archive(content, path, options, parsers, budget));
+    }
+
+    // Mail may carry 8-bit attachments
     match ext.as_str() {
+        "eml" => return Ok(parse_email(content, path, options, parsers, budget)),
+        "mbox" | "mbx" => return Ok(parse_mbox(content, path, options, parsers, budget)),
+        _ => {}
+    }
+
+    // Check for binary content, and decode text to UTF-8
+    let Some((text, encoding)) = decode_text(content) else {
+        return Ok(binary_document(path));
+    };
+
+    // Parse based on extension
+    let mut document = match ext.as_str() {
         "md" | "mdx" => parse_markdown(&text, path),
+        "html" | "htm" | "xhtml" => parse_html(&text, path),
         "json" => parse_json(&text, path),
+        "ipynb" => parse_notebook(&text, path, options),
+        "csv" => Ok(parse_table(&text, path, None)),
+        "tsv" => Ok(parse_table(&text, path, Some('\t'))),
         "yaml" | "yml" => parse_yaml(&text, path),
         "jsonl" => parse_jsonl(&text, path),
         _ => parse_text(&text, path),
+    }?;
+
+    if encoding != encoding_rs::UTF_8 {
+        document
+            .metadata
+            .insert("encoding".to_string(), encoding.name().into());
     }
+    Ok(document)
 }
 
 /// Parse markdown content
@@ -122,6 +280,9 @@ fn parse_markdown(text: &str, path: &Path) -> Result<ParsedDocument> {
         metadata,
         is_binary: false,
         mime_type: "text/markdown".to_string(),
+        text_extracted: false,
+        pages: Vec::new(),
+        parts: Vec::new(),
     })
 }
 
@@ -142,9 +303,64 @@ fn parse_json(text: &str, path: &Path) -> Result<ParsedDocument> {
         metadata: HashMap::new(),
         is_binary: false,
         mime_type: "application/json".to_string(),
+        text_extracted: false,
+        pages: Vec::new(),
+        parts: Vec::new(),
     })
 }
 
+/// Parse a Jupyter notebook into its cells, one page per cell
+///
+/// Files that aren't valid notebooks are parsed as plain JSON.
+fn parse_notebook(text: &str, path: &Path, options: &ParseOptions) -> Result<ParsedDocument> {
+    let extracted = serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|value| notebook::extract(&value, options.notebook_outputs));
+    let Some(extracted) = extracted else {
+        tracing::warn!(
+            "{}: not a valid notebook, indexing it as JSON",
+            path.display()
+        );
+        // Keep the text so the indexer doesn't re-parse it on every run
+        let mut document = parse_json(text, path)?;
+        document.text_extracted = true;
+        return Ok(document);
+    };
+
+    Ok(ParsedDocument {
+        title: extracted
+            .title
+            .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(String::from)),
+        body: extracted.body,
+        metadata: extracted.metadata,
+        is_binary: false,
+        mime_type: NOTEBOOK_MIME.to_string(),
+        text_extracted: true,
+        pages: extracted.cells,
+        parts: Vec::new(),
+    })
+}
+
+/// Parse a CSV or TSV table into one labelled line per row, one page per row
+fn parse_table(text: &str, path: &Path, delimiter: Option<char>) -> ParsedDocument {
+    let extracted = table::extract(text, delimiter);
+
+    ParsedDocument {
+        title: path.file_stem().and_then(|s| s.to_str()).map(String::from),
+        body: extracted.body,
+        metadata: extracted.metadata,
+        is_binary: false,
+        mime_type: match delimiter {
+            Some('\t') => "text/tab-separated-values",
+            _ => "text/csv",
+        }
+        .to_string(),
+        text_extracted: true,
+        pages: extracted.rows,
+        parts: Vec::new(),
+    }
+}
+
 /// Parse YAML content
 fn parse_yaml(text: &str, path: &Path) -> Result<ParsedDocument> {
     let title = path.file_stem().and_then(|s| s.to_str()).map(String::from);
@@ -166,13 +382,30 @@ fn parse_yaml(text: &str, path: &Path) -> Result<ParsedDocument> {
         metadata: HashMap::new(),
         is_binary: false,
         mime_type: "text/yaml".to_string(),
+        text_extracted: false,
+        pages: Vec::new(),
+        parts: Vec::new(),
     })
 }
 
-/// Parse JSONL content (JSON Lines, used for Claude sessions)
+/// Parse JSONL content: agent session transcripts, or the text fields of each line
 fn parse_jsonl(text: &str, path: &Path) -> Result<ParsedDocument> {
     let title = path.file_stem().and_then(|s| s.to_str()).map(String::from);
 
+    // Session transcripts are indexed message by message
+    if let Some(extracted) = transcript::extract(text) {
+        return Ok(ParsedDocument {
+            title: extracted.title.or(title),
+            body: extracted.body,
+            metadata: extracted.metadata,
+            is_binary: false,
+            mime_type: TRANSCRIPT_MIME.to_string(),
+            text_extracted: true,
+            pages: extracted.messages,
+            parts: Vec::new(),
+        });
+    }
+
     // Parse each line as JSON and extract relevant content
     let mut body_parts = Vec::new();
 
@@ -213,6 +446,9 @@ fn parse_jsonl(text: &str, path: &Path) -> Result<ParsedDocument> {
         metadata: HashMap::new(),
         is_binary: false,
         mime_type: "application/x-jsonlines".to_string(),
+        text_extracted: false,
+        pages: Vec::new(),
+        parts: Vec::new(),
     })
 }
 
@@ -230,9 +466,283 @@ fn parse_text(text: &str, path: &Path) -> Result<ParsedDocument> {
         metadata: HashMap::new(),
         is_binary: false,
         mime_type,
+        text_extracted: false,
+        pages: Vec::new(),
+        parts: Vec::new(),
+    })
+}
+
+/// Parse HTML into readable text, keeping headings as `#` lines
+fn parse_html(text: &str, path: &Path) -> Result<ParsedDocument> {
+    let extracted = html::extract(text);
+
+    Ok(ParsedDocument {
+        title: extracted
+            .title
+            .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(String::from)),
+        body: extracted.body,
+        metadata: extracted.metadata,
+        is_binary: false,
+        mime_type: "text/html".to_string(),
+        text_extracted: true,
+        pages: Vec::new(),
+        parts: Vec::new(),
     })
 }
 
+/// Parse an email message; attachments become parts, parsed by their names
+///
+/// Attachments with no text (images, unknown binaries) are left out.
+fn parse_email(
+    content: &[u8],
+    path: &Path,
+    options: &ParseOptions,
+    parsers: &ParserRegistry,
+    budget: &mut u64,
+) -> ParsedDocument {
+    let message = email::parse(content);
+
+    let mut metadata = HashMap::new();
+    let mut head = Vec::new();
+    for (key, header) in [
+        ("from", "From"),
+        ("to", "To"),
+        ("cc", "Cc"),
+        ("date", "Date"),
+        ("subject", "Subject"),
+    ] {
+        if let Some(value) = message.header(header) {
+            head.push(format!("{}: {}", header, value));
+            let value = match key {
+                "date" => email::normalize_date(value).unwrap_or_else(|| value.to_string()),
+                _ => value.to_string(),
+            };
+            metadata.insert(key.to_string(), value.into());
+        }
+    }
+    if let Some(id) = message.header("Message-ID") {
+        metadata.insert("message_id".to_string(), id.trim_matches(['<', '>']).into());
+    }
+
+    let title = message
+        .header("Subject")
+        .map(String::from)
+        .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(String::from));
+
+    let nested = options.nested();
+    let mut parts: Vec<DocumentPart> = Vec::new();
+    let mut attachments = Vec::new();
+    for attachment in message.attachments {
+        // Names are path segments; keep them unique
+        let mut name = attachment.filename.replace(['/', '\\'], "_");
+        if parts.iter().any(|p| p.name == name) {
+            name = format!("{}-{}", parts.len() + 1, name);
+        }
+        attachments.push(serde_json::Value::from(attachment.filename.clone()));
+        // Too deep inside other containers, attachments are only listed
+        let Some(nested) = &nested else {
+            continue;
+        };
+        match parsers.parse_within(Path::new(&name), &attachment.data, nested, budget) {
+            Ok(document) if !document.is_binary => parts.push(DocumentPart {
+                name,
+                content: attachment.data,
+                document,
+            }),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("{}: attachment {}: {}", path.display(), name, e),
+        }
+    }
+    if !attachments.is_empty() {
+        metadata.insert("attachments".to_string(), attachments.into());
+    }
+
+    // Headers lead the body so senders and recipients are searchable
+    let mut body = head.join("\n");
+    if !message.text.is_empty() {
+        body.push_str("\n\n");
+        body.push_str(&message.text);
+    }
+
+    ParsedDocument {
+        title,
+        body,
+        metadata,
+        is_binary: false,
+        mime_type: "message/rfc822".to_string(),
+        text_extracted: true,
+        pages: Vec::new(),
+        parts,
+    }
+}
+
+/// Parse an mbox: each message is a part named by its position (`3.eml`)
+///
+/// The mbox itself has no body of its own, so searches find the messages.
+fn parse_mbox(
+    content: &[u8],
+    path: &Path,
+    options: &ParseOptions,
+    parsers: &ParserRegistry,
+    budget: &mut u64,
+) -> ParsedDocument {
+    let mut parts = Vec::new();
+    // Too deep inside other containers, a mailbox isn't split into messages
+    if let Some(nested) = options.nested() {
+        for (i, message) in email::split_mbox(content).into_iter().enumerate() {
+            let name = format!("{}.eml", i + 1);
+            let document = parse_email(&message, Path::new(&name), &nested, parsers, budget);
+            parts.push(DocumentPart {
+                name,
+                content: message,
+                document,
+            });
+        }
+    }
+
+    let mut metadata = HashMap::new();
+    metadata.insert("messages".to_string(), parts.len().into());
+    ParsedDocument {
+        title: path.file_stem().and_then(|s| s.to_str()).map(String::from),
+        body: String::new(),
+        metadata,
+        is_binary: false,
+        mime_type: "application/mbox".to_string(),
+        text_extracted: false,
+        pages: Vec::new(),
+        parts,
+    }
+}
+
+/// Parse a ZIP or tar archive into its files, each parsed for its own type
+///
+/// Binary members are left out unless they hold documents of their own, as
+/// nested archives do. Archives that can't be read, or are nested more than
+/// `MAX_NESTING` deep, are indexed by name.
+fn parse_archive(
+    content: &[u8],
+    path: &Path,
+    options: &ParseOptions,
+    parsers: &ParserRegistry,
+    budget: &mut u64,
+) -> ParsedDocument {
+    let Some(nested) = options.nested() else {
+        return binary_document(path);
+    };
+    let members = archive::members(path, content, budget).unwrap_or_else(|e| {
+        tracing::debug!("Not indexing the files in {}: {}", path.display(), e);
+        Vec::new()
+    });
+    let parts: Vec<DocumentPart> = members
+        .into_iter()
+        .filter_map(|member| {
+            let document = parsers
+                .parse_within(Path::new(&member.name), &member.data, &nested, budget)
+                .ok()?;
+            (!document.is_binary || !document.parts.is_empty()).then_some(DocumentPart {
+                name: member.name,
+                content: member.data,
+                document,
+            })
+        })
+        .collect();
+
+    let mut document = binary_document(path);
+    document
+        .metadata
+        .insert("members".to_string(), parts.len().into());
+    document.parts = parts;
+    document
+}
+
+/// A binary file, indexed by its name only
+fn binary_document(path: &Path) -> ParsedDocument {
+    ParsedDocument {
+        title: path.file_stem().and_then(|s| s.to_str()).map(String::from),
+        body: String::new(),
+        metadata: HashMap::new(),
+        is_binary: true,
+        mime_type: mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .to_string(),
+        text_extracted: false,
+        pages: Vec::new(),
+        parts: Vec::new(),
+    }
+}
+
+/// Parse a DOCX, PPTX, ODT or EPUB file; PPTX slides become pages
+///
+/// Damaged or encrypted archives fall back to a binary, title-only document.
+fn parse_office(ext: &str, content: &[u8], path: &Path) -> ParsedDocument {
+    let extracted = match office::extract(ext, content) {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            tracing::warn!("{}: {}, indexing its title only", path.display(), e);
+            return binary_document(path);
+        }
+    };
+
+    let has_text = !extracted.body.trim().is_empty();
+    ParsedDocument {
+        title: extracted
+            .title
+            .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(String::from)),
+        body: extracted.body,
+        metadata: extracted.metadata,
+        is_binary: !has_text,
+        mime_type: mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .to_string(),
+        text_extracted: true,
+        pages: extracted.pages,
+        parts: Vec::new(),
+    }
+}
+
+/// Parse a PDF into page-separated text
+///
+/// Encrypted and image-only PDFs still index by title, with an empty body.
+fn parse_pdf(content: &[u8], path: &Path) -> ParsedDocument {
+    let extracted = pdf::extract(content);
+
+    let mut metadata = HashMap::new();
+    metadata.insert("pages".to_string(), extracted.pages.len().into());
+
+    // Pages are separated by a blank line
+    let mut body = String::new();
+    let mut pages = Vec::with_capacity(extracted.pages.len());
+    for page in &extracted.pages {
+        if !body.is_empty() {
+            body.push_str("\n\n");
+        }
+        pages.push(body.matches('\n').count() as u32 + 1);
+        body.push_str(page);
+    }
+
+    if extracted.encrypted {
+        tracing::warn!("{}: encrypted PDF, indexing its title only", path.display());
+        metadata.insert("encrypted".to_string(), true.into());
+    } else if body.trim().is_empty() {
+        tracing::info!("{}: PDF has no text layer (image-only?)", path.display());
+        metadata.insert("image_only".to_string(), true.into());
+    }
+
+    let has_text = !body.trim().is_empty();
+    ParsedDocument {
+        title: extracted
+            .title
+            .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(String::from)),
+        body: if has_text { body } else { String::new() },
+        metadata,
+        is_binary: !has_text,
+        mime_type: "application/pdf".to_string(),
+        text_extracted: true,
+        pages: if has_text { pages } else { Vec::new() },
+        parts: Vec::new(),
+    }
+}
+
 /// Flatten JSON value to searchable text
 fn flatten_json(value: &serde_json::Value) -> String {
     let mut parts = Vec::new();
@@ -318,6 +828,229 @@ This is the body content.
         assert!(result.body.is_empty());
     }
 
+    #[test]
+    fn test_parse_pdf() {
+        // Detected by content even without the extension
+        let result = parse_file(Path::new("q3"), &pdf::tests::sample_pdf()).unwrap();
+
+        assert_eq!(result.title, Some("Q3 Report".to_string()));
+        assert_eq!(result.mime_type, "application/pdf");
+        assert!(result.text_extracted);
+        assert!(!result.is_binary);
+        assert_eq!(
+            result.body,
+            "Quarterly report\nRevenue grew (a lot)\n\nInternational shipping\nIt’s late"
+        );
+        assert_eq!(result.pages, vec![1, 4]);
+        assert_eq!(result.metadata["pages"], 2);
+    }
+
+    #[test]
+    fn test_parse_pdf_without_text() {
+        let encrypted = pdf::tests::build_pdf(
+            &[
+                b"<< /Type /Catalog >>".to_vec(),
+                b"<< /Filter /Standard >>".to_vec(),
+            ],
+            "/Root 1 0 R /Encrypt 2 0 R",
+        );
+
+        let result = parse_file(Path::new("locked.pdf"), &encrypted).unwrap();
+
+        assert_eq!(result.title, Some("locked".to_string()));
+        assert!(result.body.is_empty());
+        assert!(result.pages.is_empty());
+        assert!(result.is_binary);
+        assert_eq!(result.metadata["encrypted"], true);
+    }
+
+    #[test]
+    fn test_parse_html() {
+        let content = r#"<html><head><title>Guide</title><meta name="keywords" content="a, b"></head>
+<body><nav>Menu</nav><h1>Intro</h1><p>Hello <b>world</b></p></body></html>"#;
+
+        let result = parse_file(Path::new("guide.HTML"), content.as_bytes()).unwrap();
+
+        assert_eq!(result.title, Some("Guide".to_string()));
+        assert_eq!(result.body, "# Intro\n\nHello world");
+        assert_eq!(result.mime_type, "text/html");
+        assert!(result.text_extracted);
+        assert_eq!(result.metadata["keywords"], serde_json::json!(["a", "b"]));
+        assert!(extracts_text(Path::new("guide.HTML"), content.as_bytes()));
+        assert!(!extracts_text(Path::new("guide.md"), content.as_bytes()));
+    }
+
+    #[test]
+    fn test_parse_office() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/documents/roadmap.pptx");
+        let content = std::fs::read(&path).unwrap();
+
+        let result = parse_file(&path, &content).unwrap();
+
+        assert_eq!(result.title, Some("Product Roadmap 2026".to_string()));
+        assert!(result.body.starts_with("# Product Roadmap\n\nQ3 planning"));
+        assert_eq!(result.pages, vec![1, 5, 10]);
+        assert_eq!(
+            result.mime_type,
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+        );
+        assert!(result.text_extracted);
+        assert!(!result.is_binary);
+        assert!(extracts_text(&path, &content));
+
+        // A damaged document is still indexed by name
+        let damaged = zip::tests::build_zip(&[("word/other.xml", b"<x/>", true)]);
+        let result = parse_file(Path::new("broken.docx"), &damaged).unwrap();
+        assert!(result.is_binary);
+        assert_eq!(result.title, Some("broken".to_string()));
+    }
+
+    #[test]
+    fn test_parse_notebook() {
+        let content = br##"{"cells": [{"cell_type": "markdown", "source": ["# Report\n", "text"]},
+            {"cell_type": "code", "source": "x = 1", "outputs": [{"output_type": "stream", "text": "done"}]}],
+            "metadata": {"language_info": {"name": "python"}}}"##;
+        let path = Path::new("report.ipynb");
+
+        let result = parse_file(path, content).unwrap();
+        assert_eq!(result.title, Some("Report".to_string()));
+        assert_eq!(result.body, "# Report\ntext\n\n```python\nx = 1\n```");
+        assert_eq!(result.pages, vec![1, 4]);
+        assert!(result.text_extracted);
+        assert!(extracts_text(path, content));
+
+        let options = ParseOptions {
+            notebook_outputs: true,
+            ..Default::default()
+        };
+        let result = parse_file_with_options(path, content, &options).unwrap();
+        assert!(result.body.ends_with("```\n```output\ndone\n```"));
+
+        // Other JSON with a notebook extension is indexed as JSON
+        let result = parse_file(path, br#"{"name": "not a notebook"}"#).unwrap();
+        assert_eq!(result.mime_type, "application/json");
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let result =
+            parse_file(Path::new("people.tsv"), b"name\tage\nAda\t36\nAlan\t41\n").unwrap();
+
+        assert_eq!(
+            result.body,
+            "name | age\nname: Ada | age: 36\nname: Alan | age: 41"
+        );
+        assert_eq!(result.pages, vec![2, 3]);
+        assert_eq!(result.mime_type, "text/tab-separated-values");
+        assert_eq!(page_label(&result.mime_type), "row");
+        assert!(result.text_extracted);
+    }
+
+    #[test]
+    fn test_parse_mbox() {
+        let mbox = b"From alice@example.com Mon Mar  3 10:00:00 2025\n\
+Subject: Kickoff\n\
+From: Alice <alice@example.com>\n\
+\n\
+Agenda attached.\n\
+\n\
+From bob@example.com Tue Mar  4 11:00:00 2025\n\
+From: Bob <bob@example.com>\n\
+Date: Tue, 4 Mar 2025 11:00:00 +0000\n\
+Content-Type: multipart/mixed; boundary=b\n\
+\n\
+--b\n\
+Content-Type: text/plain\n\
+\n\
+Notes below.\n\
+--b\n\
+Content-Disposition: attachment; filename=notes/day 1.md\n\
+\n\
+# Day one\n\
+--b\n\
+Content-Disposition: attachment; filename=photo.png\n\
+Content-Transfer-Encoding: base64\n\
+\n\
+iVBORw0KGgoAAAANSUhEUg==\n\
+--b--\n";
+        let result = parse_file(Path::new("archive/team.mbox"), mbox).unwrap();
+
+        assert_eq!(result.title, Some("team".to_string()));
+        assert_eq!(result.mime_type, "application/mbox");
+        assert_eq!(result.metadata["messages"], 2);
+        assert!(result.body.is_empty() && !result.text_extracted);
+
+        let names: Vec<_> = result.parts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["1.eml", "2.eml"]);
+        let first = &result.parts[0].document;
+        assert_eq!(first.title, Some("Kickoff".to_string()));
+        assert_eq!(
+            first.body,
+            "From: Alice <alice@example.com>\nSubject: Kickoff\n\nAgenda attached."
+        );
+        assert_eq!(first.metadata["from"], "Alice <alice@example.com>");
+
+        // Untitled messages fall back to their name; binary attachments are left out
+        let second = &result.parts[1].document;
+        assert_eq!(second.title, Some("2".to_string()));
+        assert_eq!(second.metadata["date"], "2025-03-04T11:00:00+00:00");
+        assert_eq!(
+            second.metadata["attachments"],
+            serde_json::json!(["notes/day 1.md", "photo.png"])
+        );
+        assert_eq!(second.parts.len(), 1);
+        assert_eq!(second.parts[0].name, "notes_day 1.md");
+        assert_eq!(second.parts[0].document.title, Some("Day one".to_string()));
+    }
+
+    #[test]
+    fn test_parse_nested_archives() {
+        let options = ParseOptions {
+            archives: true,
+            ..Default::default()
+        };
+        let wrap = |levels: usize| {
+            let (mut data, mut name) = (b"deep".to_vec(), "deep.txt");
+            for _ in 0..levels {
+                data = zip::tests::build_zip(&[(name, &data, false)]);
+                name = "inner.zip";
+            }
+            data
+        };
+        let depth = |mut document: &ParsedDocument| {
+            let mut depth = 0;
+            while let Some(part) = document.parts.first() {
+                document = &part.document;
+                depth += 1;
+            }
+            depth
+        };
+
+        // Archives are opened up to MAX_NESTING deep
+        let result = parse_file_with_options(Path::new("a.zip"), &wrap(3), &options).unwrap();
+        assert_eq!(depth(&result), 3);
+        // Deeper ones are indexed by name, leaving nothing to index inside
+        for levels in [4, 3000] {
+            let result =
+                parse_file_with_options(Path::new("a.zip"), &wrap(levels), &options).unwrap();
+            assert!(result.parts.is_empty());
+        }
+
+        // Nested archives share the outer archive's budget
+        let first = zip::tests::build_zip(&[("first.txt", &[b'a'; 100], false)]);
+        let second = zip::tests::build_zip(&[("second.txt", &[b'b'; 100], false)]);
+        let outer =
+            zip::tests::build_zip(&[("first.zip", &first, false), ("second.zip", &second, false)]);
+        let mut budget = (first.len() + second.len() + 150) as u64;
+        let result = ParserRegistry::new()
+            .parse_within(Path::new("outer.zip"), &outer, &options, &mut budget)
+            .unwrap();
+        let names: Vec<&str> = result.parts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["first.zip"]);
+        assert_eq!(budget, 50);
+    }
+
     #[test]
     fn test_parse_code() {
         let content = r#"
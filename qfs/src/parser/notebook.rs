@@ -0,0 +1,227 @@
+//! Jupyter notebooks (`.ipynb`)
+//!
+//! Cells are indexed in order: markdown and raw cells as-is, code cells as
+//! fenced blocks in the kernel language. Text outputs (streams, plain-text
+//! results and error summaries) are included on request; images and other
+//! binary outputs never are. Each cell is reported as a page, so hits cite
+//! the cell they came from.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Text of a notebook, one page per cell
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NotebookText {
+    pub title: Option<String>,
+    pub body: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// 1-based line each cell starts on (empty cells share the next cell's line)
+    pub cells: Vec<u32>,
+}
+
+/// Multi-line strings in notebooks are either a string or a list of lines
+fn multiline(value: Option<&serde_json::Value>) -> String {
+    match value {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Array(lines)) => lines.iter().filter_map(|l| l.as_str()).collect(),
+        _ => String::new(),
+    }
+}
+
+fn strip_ansi(text: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap())
+        .replace_all(text, "")
+        .into_owned()
+}
+
+/// Text of a code cell's outputs, skipping images and other binary data
+fn output_text(outputs: &[serde_json::Value]) -> String {
+    let mut parts = Vec::new();
+    for output in outputs {
+        let text = match output.get("output_type").and_then(|t| t.as_str()) {
+            Some("stream") => multiline(output.get("text")),
+            Some("execute_result" | "display_data") => {
+                multiline(output.get("data").and_then(|d| d.get("text/plain")))
+            }
+            // The traceback repeats the code; the exception line is enough
+            Some("error") => format!(
+                "{}: {}",
+                output
+                    .get("ename")
+                    .and_then(|e| e.as_str())
+                    .unwrap_or("Error"),
+                output.get("evalue").and_then(|e| e.as_str()).unwrap_or("")
+            ),
+            _ => String::new(),
+        };
+        let text = strip_ansi(&text);
+        let text = text.trim_end();
+        if !text.trim().is_empty() {
+            parts.push(text.to_string());
+        }
+    }
+    parts.join("\n")
+}
+
+/// First Markdown heading in `source`
+fn heading(source: &str) -> Option<String> {
+    source.lines().find_map(|line| {
+        let rest = line.trim_start().strip_prefix('#')?;
+        let text = rest.trim_start_matches('#');
+        (text.starts_with(' ') && !text.trim().is_empty()).then(|| text.trim().to_string())
+    })
+}
+
+/// Extract the cells of a parsed notebook, `None` if it doesn't look like one
+pub(crate) fn extract(notebook: &serde_json::Value, include_outputs: bool) -> Option<NotebookText> {
+    let cells = notebook.get("cells")?.as_array()?;
+    let meta = notebook.get("metadata");
+    let language = meta
+        .and_then(|m| m.get("kernelspec"))
+        .and_then(|k| k.get("language"))
+        .or_else(|| {
+            meta.and_then(|m| m.get("language_info"))
+                .and_then(|l| l.get("name"))
+        })
+        .and_then(|l| l.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let mut text = NotebookText::default();
+    for cell in cells {
+        let start = if text.body.is_empty() {
+            1
+        } else {
+            text.body.matches('\n').count() as u32 + 3
+        };
+        text.cells.push(start);
+
+        let source = multiline(cell.get("source"));
+        let mut block = String::new();
+        match cell.get("cell_type").and_then(|t| t.as_str()) {
+            Some("code") => {
+                if !source.trim().is_empty() {
+                    block = format!("```{}\n{}\n```", language, source.trim_end());
+                }
+                if include_outputs {
+                    let outputs = cell
+                        .get("outputs")
+                        .and_then(|o| o.as_array())
+                        .map(|o| output_text(o))
+                        .unwrap_or_default();
+                    if !outputs.is_empty() {
+                        if !block.is_empty() {
+                            block.push('\n');
+                        }
+                        block.push_str(&format!("```output\n{}\n```", outputs));
+                    }
+                }
+            }
+            Some("markdown") => {
+                if text.title.is_none() {
+                    text.title = heading(&source);
+                }
+                block = source.trim().to_string();
+            }
+            _ => block = source.trim().to_string(),
+        }
+
+        if !block.is_empty() {
+            if !text.body.is_empty() {
+                text.body.push_str("\n\n");
+            }
+            text.body.push_str(&block);
+        }
+    }
+
+    // Trailing empty cells have no line to start on
+    let lines = text.body.matches('\n').count() as u32 + 1;
+    while text.cells.last().is_some_and(|&start| start > lines) {
+        text.cells.pop();
+    }
+
+    text.metadata
+        .insert("cells".to_string(), cells.len().into());
+    if !language.is_empty() {
+        text.metadata
+            .insert("language".to_string(), language.into());
+    }
+    if let Some(kernel) = meta
+        .and_then(|m| m.get("kernelspec"))
+        .and_then(|k| k.get("display_name"))
+        .and_then(|n| n.as_str())
+    {
+        text.metadata.insert("kernel".to_string(), kernel.into());
+    }
+    if text.title.is_none() {
+        text.title = meta
+            .and_then(|m| m.get("title"))
+            .and_then(|t| t.as_str())
+            .map(String::from);
+    }
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notebook() -> serde_json::Value {
+        json!({
+            "nbformat": 4,
+            "metadata": {
+                "kernelspec": {"name": "python3", "display_name": "Python 3", "language": "python"}
+            },
+            "cells": [
+                {"cell_type": "code", "source": [], "outputs": []},
+                {"cell_type": "markdown", "source": ["Intro text\n", "\n", "## Loading *data*\n"]},
+                {"cell_type": "code", "execution_count": 1, "source": "import pandas as pd\ndf = pd.read_csv('sales.csv')\n", "outputs": [
+                    {"output_type": "stream", "name": "stdout", "text": ["loaded 120 rows\n"]},
+                    {"output_type": "display_data", "data": {"image/png": "iVBORw0KGgo=", "text/plain": ["<Figure>"]}},
+                    {"output_type": "display_data", "data": {"image/png": "iVBORw0KGgo="}},
+                    {"output_type": "error", "ename": "KeyError", "evalue": "'region'", "traceback": ["\u{1b}[0;31mKeyError\u{1b}[0m"]}
+                ]},
+                {"cell_type": "raw", "source": "raw notes"},
+                {"cell_type": "markdown", "source": ""}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_cells_in_order_without_outputs() {
+        let text = extract(&notebook(), false).unwrap();
+
+        assert_eq!(
+            text.body,
+            "Intro text\n\n## Loading *data*\n\n\
+             ```python\nimport pandas as pd\ndf = pd.read_csv('sales.csv')\n```\n\n\
+             raw notes"
+        );
+        // The empty first cell shares the second's line; the trailing one is dropped
+        assert_eq!(text.cells, vec![1, 1, 5, 10]);
+        assert_eq!(text.title.as_deref(), Some("Loading *data*"));
+        assert_eq!(text.metadata["cells"], 5);
+        assert_eq!(text.metadata["language"], "python");
+        assert_eq!(text.metadata["kernel"], "Python 3");
+    }
+
+    #[test]
+    fn test_text_outputs() {
+        let text = extract(&notebook(), true).unwrap();
+
+        assert!(text.body.contains(
+            "```\n```output\nloaded 120 rows\n<Figure>\nKeyError: 'region'\n```\n\nraw notes"
+        ));
+        assert!(!text.body.contains("iVBOR"));
+        assert_eq!(text.cells, vec![1, 1, 5, 15]);
+    }
+
+    #[test]
+    fn test_not_a_notebook() {
+        assert!(extract(&json!({"name": "package"}), false).is_none());
+        assert_eq!(heading("#hashtag\n#  Title  \n"), Some("Title".to_string()));
+    }
+}
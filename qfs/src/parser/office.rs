@@ -0,0 +1,798 @@
+//! Office and e-book formats: DOCX, PPTX, ODT and EPUB
+//!
+//! All four are ZIP archives of XML. Body text is rebuilt as Markdown-like
+//! blocks (`#` headings, `-` list items, blank lines between paragraphs) and
+//! the core properties go into metadata. PPTX slides are reported as pages.
+
+use super::html;
+use super::xml::{element_texts, local_name, Event, Reader};
+use super::zip::ZipArchive;
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+
+/// Extensions handled here
+pub(crate) const EXTENSIONS: &[&str] = &["docx", "pptx", "odt", "epub"];
+
+/// Text and properties of an office document or e-book
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OfficeText {
+    pub title: Option<String>,
+    pub body: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// 1-based line each slide starts on (PPTX only)
+    pub pages: Vec<u32>,
+}
+
+/// Extract the text of a document in format `ext` (one of `EXTENSIONS`)
+pub(crate) fn extract(ext: &str, data: &[u8]) -> Result<OfficeText> {
+    let archive = ZipArchive::new(data)?;
+    match ext {
+        "docx" => docx(&archive),
+        "pptx" => pptx(&archive),
+        "odt" => odt(&archive),
+        "epub" => epub(&archive),
+        _ => Err(Error::ParseError(format!(
+            "unsupported document format: {}",
+            ext
+        ))),
+    }
+}
+
+fn read_xml(archive: &ZipArchive, name: &str) -> Result<Option<String>> {
+    Ok(archive
+        .read_name(name)?
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+fn require_xml(archive: &ZipArchive, name: &str, format: &str) -> Result<String> {
+    read_xml(archive, name)?
+        .ok_or_else(|| Error::ParseError(format!("not a {} document: {} is missing", format, name)))
+}
+
+fn first_text(xml: &str, name: &str) -> Option<String> {
+    element_texts(xml, name).into_iter().find(|t| !t.is_empty())
+}
+
+/// Store the first non-empty text of each `(element, key)` in metadata
+fn collect_properties(
+    xml: &str,
+    fields: &[(&str, &str)],
+    metadata: &mut HashMap<String, serde_json::Value>,
+) {
+    for (element, key) in fields {
+        if metadata.contains_key(*key) {
+            continue;
+        }
+        if let Some(value) = first_text(xml, element) {
+            metadata.insert(key.to_string(), value.into());
+        }
+    }
+}
+
+/// Split a keyword list (`a, b; c`) into a JSON array
+fn keywords(values: &[String]) -> Option<serde_json::Value> {
+    let keywords: Vec<serde_json::Value> = values
+        .iter()
+        .flat_map(|v| v.split([',', ';']))
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(|k| k.to_string().into())
+        .collect();
+    (!keywords.is_empty()).then(|| keywords.into())
+}
+
+/// OOXML `docProps/core.xml` properties
+fn ooxml_properties(archive: &ZipArchive) -> Result<HashMap<String, serde_json::Value>> {
+    let mut metadata = HashMap::new();
+    let Some(core) = read_xml(archive, "docProps/core.xml")? else {
+        return Ok(metadata);
+    };
+    collect_properties(
+        &core,
+        &[
+            ("dc:title", "title"),
+            ("dc:subject", "subject"),
+            ("dc:creator", "author"),
+            ("dc:description", "description"),
+            ("cp:lastModifiedBy", "last_modified_by"),
+            ("dcterms:created", "created"),
+            ("dcterms:modified", "modified"),
+            ("dc:language", "language"),
+        ],
+        &mut metadata,
+    );
+    if let Some(keywords) = keywords(&element_texts(&core, "cp:keywords")) {
+        metadata.insert("keywords".to_string(), keywords);
+    }
+    Ok(metadata)
+}
+
+fn title_from(metadata: &HashMap<String, serde_json::Value>) -> Option<String> {
+    metadata
+        .get("title")
+        .and_then(|t| t.as_str())
+        .map(String::from)
+}
+
+// ---------------------------------------------------------------------------
+// Body text
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Block {
+    Paragraph,
+    Heading(usize),
+    ListItem,
+}
+
+/// Builds the Markdown-like body from paragraphs
+#[derive(Default)]
+struct Body {
+    text: String,
+    last: Option<Block>,
+    headings: Vec<serde_json::Value>,
+}
+
+impl Body {
+    fn push(&mut self, kind: Block, text: &str) {
+        let text = text
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.is_empty() {
+            return;
+        }
+
+        if !self.text.is_empty() {
+            let tight = kind == Block::ListItem && self.last == Some(Block::ListItem);
+            self.text.push_str(if tight { "\n" } else { "\n\n" });
+        }
+        match kind {
+            Block::Heading(level) => {
+                self.headings
+                    .push(serde_json::json!({ "level": level, "text": text.replace('\n', " ") }));
+                self.text.push_str(&"#".repeat(level.clamp(1, 6)));
+                self.text.push(' ');
+                self.text.push_str(&text.replace('\n', " "));
+            }
+            Block::ListItem => {
+                self.text.push_str("- ");
+                self.text.push_str(&text);
+            }
+            Block::Paragraph => self.text.push_str(&text),
+        }
+        self.last = Some(kind);
+    }
+
+    /// 1-based line the next block will start on
+    fn next_line(&self) -> u32 {
+        if self.text.is_empty() {
+            1
+        } else {
+            self.text.matches('\n').count() as u32 + 3
+        }
+    }
+
+    fn first_heading(&self) -> Option<String> {
+        self.headings
+            .first()
+            .and_then(|h| h["text"].as_str())
+            .map(String::from)
+    }
+
+    fn finish(self, metadata: &mut HashMap<String, serde_json::Value>) -> String {
+        if !self.headings.is_empty() {
+            metadata.insert("headings".to_string(), self.headings.into());
+        }
+        self.text
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DOCX
+// ---------------------------------------------------------------------------
+
+/// Heading level of a paragraph style such as `Heading2` or `Title`
+fn docx_heading_level(style: &str) -> Option<usize> {
+    let style = style.to_ascii_lowercase().replace(' ', "");
+    if style == "title" {
+        return Some(1);
+    }
+    style
+        .strip_prefix("heading")
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|n| (1..=9).contains(n))
+}
+
+fn docx(archive: &ZipArchive) -> Result<OfficeText> {
+    let document = require_xml(archive, "word/document.xml", "DOCX")?;
+    let mut metadata = ooxml_properties(archive)?;
+
+    let mut body = Body::default();
+    let mut paragraph = String::new();
+    let mut kind = Block::Paragraph;
+    let mut in_text = false;
+
+    for event in Reader::new(&document) {
+        match event {
+            Event::Start {
+                name,
+                attributes,
+                empty,
+            } => match name.as_str() {
+                "w:p" if !empty => {
+                    paragraph.clear();
+                    kind = Block::Paragraph;
+                }
+                "w:pStyle" => {
+                    if let Some(level) = attributes.get("w:val").and_then(|v| docx_heading_level(v))
+                    {
+                        kind = Block::Heading(level);
+                    }
+                }
+                "w:outlineLvl" => {
+                    if let Some(level) = attributes
+                        .get("w:val")
+                        .and_then(|v| v.parse::<usize>().ok())
+                    {
+                        if level < 9 {
+                            kind = Block::Heading(level + 1);
+                        }
+                    }
+                }
+                "w:numPr" if kind == Block::Paragraph => kind = Block::ListItem,
+                "w:t" => in_text = !empty,
+                "w:tab" | "w:ptab" => paragraph.push('\t'),
+                "w:br" | "w:cr" => paragraph.push('\n'),
+                "w:noBreakHyphen" => paragraph.push('-'),
+                _ => {}
+            },
+            Event::End { name } => match name.as_str() {
+                "w:t" => in_text = false,
+                "w:p" => body.push(kind, &paragraph),
+                _ => {}
+            },
+            Event::Text(text) if in_text => paragraph.push_str(&text),
+            Event::Text(_) => {}
+        }
+    }
+
+    let title = title_from(&metadata).or_else(|| body.first_heading());
+    Ok(OfficeText {
+        title,
+        body: body.finish(&mut metadata),
+        metadata,
+        pages: Vec::new(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// PPTX
+// ---------------------------------------------------------------------------
+
+/// Slide parts in presentation order
+fn pptx_slides(archive: &ZipArchive) -> Result<Vec<String>> {
+    // presentation.xml lists slide relationship ids in order
+    if let (Some(presentation), Some(rels)) = (
+        read_xml(archive, "ppt/presentation.xml")?,
+        read_xml(archive, "ppt/_rels/presentation.xml.rels")?,
+    ) {
+        let targets: HashMap<String, String> = Reader::new(&rels)
+            .filter_map(|event| match event {
+                Event::Start {
+                    name, attributes, ..
+                } if local_name(&name) == "Relationship" => Some((
+                    attributes.get("Id")?.clone(),
+                    attributes.get("Target")?.clone(),
+                )),
+                _ => None,
+            })
+            .collect();
+        let slides: Vec<String> = Reader::new(&presentation)
+            .filter_map(|event| match event {
+                Event::Start {
+                    name, attributes, ..
+                } if name == "p:sldId" => {
+                    let target = targets.get(attributes.get("r:id")?)?;
+                    Some(match target.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None => format!("ppt/{}", target),
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        if !slides.is_empty() {
+            return Ok(slides);
+        }
+    }
+
+    // Otherwise, slide file numbers
+    let mut numbered: Vec<(u32, String)> = archive
+        .entries()
+        .iter()
+        .filter_map(|entry| {
+            let number = entry
+                .name
+                .strip_prefix("ppt/slides/slide")?
+                .strip_suffix(".xml")?
+                .parse()
+                .ok()?;
+            Some((number, entry.name.clone()))
+        })
+        .collect();
+    numbered.sort();
+    Ok(numbered.into_iter().map(|(_, name)| name).collect())
+}
+
+fn pptx(archive: &ZipArchive) -> Result<OfficeText> {
+    let slides = pptx_slides(archive)?;
+    if slides.is_empty() {
+        return Err(Error::ParseError(
+            "not a PPTX document: no slides".to_string(),
+        ));
+    }
+    let mut metadata = ooxml_properties(archive)?;
+    metadata.insert("slides".to_string(), slides.len().into());
+
+    let mut body = Body::default();
+    let mut pages = Vec::with_capacity(slides.len());
+    for slide in &slides {
+        pages.push(body.next_line());
+        let Some(xml) = read_xml(archive, slide)? else {
+            continue;
+        };
+
+        let mut paragraph = String::new();
+        let mut in_text = false;
+        let mut is_title = false;
+        for event in Reader::new(&xml) {
+            match event {
+                Event::Start {
+                    name,
+                    attributes,
+                    empty,
+                } => match name.as_str() {
+                    "p:sp" if !empty => is_title = false,
+                    "p:ph" => {
+                        is_title = matches!(
+                            attributes.get("type").map(String::as_str),
+                            Some("title" | "ctrTitle")
+                        );
+                    }
+                    "a:p" if !empty => paragraph.clear(),
+                    "a:t" => in_text = !empty,
+                    "a:br" => paragraph.push('\n'),
+                    _ => {}
+                },
+                Event::End { name } => match name.as_str() {
+                    "a:t" => in_text = false,
+                    "a:p" => {
+                        let kind = if is_title {
+                            Block::Heading(1)
+                        } else {
+                            Block::Paragraph
+                        };
+                        body.push(kind, &paragraph);
+                    }
+                    _ => {}
+                },
+                Event::Text(text) if in_text => paragraph.push_str(&text),
+                Event::Text(_) => {}
+            }
+        }
+    }
+
+    // Slides without text still count, so drop trailing starts past the end
+    let lines = body.text.matches('\n').count() as u32 + 1;
+    for start in pages.iter_mut() {
+        *start = (*start).min(lines);
+    }
+
+    let title = title_from(&metadata).or_else(|| body.first_heading());
+    Ok(OfficeText {
+        title,
+        body: body.finish(&mut metadata),
+        metadata,
+        pages,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// ODT
+// ---------------------------------------------------------------------------
+
+/// ODF elements whose text is not part of the document body
+const ODT_SKIPPED: &[&str] = &[
+    "text:tracked-changes",
+    "office:annotation",
+    "text:note-citation",
+    "text:sequence-decls",
+];
+
+fn odt(archive: &ZipArchive) -> Result<OfficeText> {
+    let content = require_xml(archive, "content.xml", "ODT")?;
+    let mut metadata = HashMap::new();
+    if let Some(meta) = read_xml(archive, "meta.xml")? {
+        collect_properties(
+            &meta,
+            &[
+                ("dc:title", "title"),
+                ("dc:subject", "subject"),
+                ("dc:description", "description"),
+                ("meta:initial-creator", "author"),
+                ("dc:creator", "last_modified_by"),
+                ("meta:creation-date", "created"),
+                ("dc:date", "modified"),
+                ("dc:language", "language"),
+            ],
+            &mut metadata,
+        );
+        if let Some(keywords) = keywords(&element_texts(&meta, "meta:keyword")) {
+            metadata.insert("keywords".to_string(), keywords);
+        }
+    }
+
+    let mut body = Body::default();
+    let mut paragraph = String::new();
+    let mut kind = Block::Paragraph;
+    // Paragraphs nest (notes, frames); only the outermost becomes a block
+    let mut depth = 0usize;
+    let mut list_depth = 0usize;
+    let mut skipping = 0usize;
+    let mut in_body = false;
+
+    for event in Reader::new(&content) {
+        match event {
+            Event::Start {
+                name,
+                attributes,
+                empty,
+            } => {
+                if skipping > 0 || ODT_SKIPPED.contains(&name.as_str()) {
+                    if !empty {
+                        skipping += 1;
+                    }
+                    continue;
+                }
+                match name.as_str() {
+                    "office:body" => in_body = true,
+                    "text:list-item" if !empty => list_depth += 1,
+                    "text:p" | "text:h" if !empty => {
+                        if depth == 0 {
+                            paragraph.clear();
+                            kind = if name == "text:h" {
+                                let level = attributes
+                                    .get("text:outline-level")
+                                    .and_then(|l| l.parse().ok())
+                                    .unwrap_or(1);
+                                Block::Heading(level)
+                            } else if list_depth > 0 {
+                                Block::ListItem
+                            } else {
+                                Block::Paragraph
+                            };
+                        } else {
+                            paragraph.push(' ');
+                        }
+                        depth += 1;
+                    }
+                    "text:s" => {
+                        let count = attributes
+                            .get("text:c")
+                            .and_then(|c| c.parse().ok())
+                            .unwrap_or(1);
+                        paragraph.push_str(&" ".repeat(count));
+                    }
+                    "text:tab" => paragraph.push('\t'),
+                    "text:line-break" => paragraph.push('\n'),
+                    _ => {}
+                }
+            }
+            Event::End { name } => {
+                if skipping > 0 {
+                    skipping -= 1;
+                    continue;
+                }
+                match name.as_str() {
+                    "office:body" => in_body = false,
+                    "text:list-item" => list_depth = list_depth.saturating_sub(1),
+                    "text:p" | "text:h" => {
+                        depth = depth.saturating_sub(1);
+                        if depth == 0 {
+                            body.push(kind, &paragraph);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(text) => {
+                if in_body && depth > 0 && skipping == 0 {
+                    paragraph.push_str(&text);
+                }
+            }
+        }
+    }
+
+    let title = title_from(&metadata).or_else(|| body.first_heading());
+    Ok(OfficeText {
+        title,
+        body: body.finish(&mut metadata),
+        metadata,
+        pages: Vec::new(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// EPUB
+// ---------------------------------------------------------------------------
+
+/// Decode `%XX` escapes in an href
+fn percent_decode(href: &str) -> String {
+    let bytes = href.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(value) = href
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(value);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Resolve `href` against the directory of the package document
+fn resolve_href(base_dir: &str, href: &str) -> String {
+    let href = percent_decode(href.split('#').next().unwrap_or(href));
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn epub(archive: &ZipArchive) -> Result<OfficeText> {
+    let container = require_xml(archive, "META-INF/container.xml", "EPUB")?;
+    let package_path = Reader::new(&container)
+        .find_map(|event| match event {
+            Event::Start {
+                name, attributes, ..
+            } if local_name(&name) == "rootfile" => attributes.get("full-path").cloned(),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            Error::ParseError("not an EPUB: container.xml has no rootfile".to_string())
+        })?;
+    let package = require_xml(archive, &package_path, "EPUB")?;
+    let base_dir = package_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+
+    let mut metadata = HashMap::new();
+    collect_properties(
+        &package,
+        &[
+            ("dc:title", "title"),
+            ("dc:creator", "author"),
+            ("dc:publisher", "publisher"),
+            ("dc:description", "description"),
+            ("dc:date", "created"),
+            ("dc:language", "language"),
+        ],
+        &mut metadata,
+    );
+    if let Some(subjects) = keywords(&element_texts(&package, "dc:subject")) {
+        metadata.insert("keywords".to_string(), subjects);
+    }
+    // EPUB 3 keeps the modification date in `<meta property="dcterms:modified">`
+    if let Some(modified) = Reader::new(&package)
+        .scan(false, |in_modified, event| {
+            Some(match event {
+                Event::Start {
+                    name, attributes, ..
+                } => {
+                    *in_modified = local_name(&name) == "meta"
+                        && attributes.get("property").map(String::as_str)
+                            == Some("dcterms:modified");
+                    None
+                }
+                Event::Text(text) if *in_modified => Some(text.trim().to_string()),
+                _ => {
+                    *in_modified = false;
+                    None
+                }
+            })
+        })
+        .flatten()
+        .find(|t| !t.is_empty())
+    {
+        metadata.insert("modified".to_string(), modified.into());
+    }
+
+    // Manifest id -> href for XHTML items (minus the navigation document), then the spine
+    let mut manifest = HashMap::new();
+    let mut spine = Vec::new();
+    for event in Reader::new(&package) {
+        if let Event::Start {
+            name, attributes, ..
+        } = event
+        {
+            match local_name(&name) {
+                "item" => {
+                    let media_type = attributes
+                        .get("media-type")
+                        .map(String::as_str)
+                        .unwrap_or("");
+                    let is_nav = attributes
+                        .get("properties")
+                        .is_some_and(|p| p.split_whitespace().any(|p| p == "nav"));
+                    if media_type.contains("html") && !is_nav {
+                        if let (Some(id), Some(href)) =
+                            (attributes.get("id"), attributes.get("href"))
+                        {
+                            manifest.insert(id.clone(), resolve_href(base_dir, href));
+                        }
+                    }
+                }
+                "itemref" => {
+                    if let Some(id) = attributes.get("idref") {
+                        spine.push(id.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut chapters = Vec::new();
+    let mut first_title = None;
+    for id in &spine {
+        let Some(path) = manifest.get(id) else {
+            continue;
+        };
+        let Some(xhtml) = read_xml(archive, path)? else {
+            continue;
+        };
+        let chapter = html::extract(&xhtml);
+        if first_title.is_none() {
+            first_title = chapter.title;
+        }
+        if !chapter.body.is_empty() {
+            chapters.push(chapter.body);
+        }
+    }
+    metadata.insert("chapters".to_string(), chapters.len().into());
+
+    let title = title_from(&metadata).or(first_title);
+    Ok(OfficeText {
+        title,
+        body: chapters.join("\n\n"),
+        metadata,
+        pages: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/documents")
+            .join(name);
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn test_docx() {
+        let text = extract("docx", &fixture("onboarding.docx")).unwrap();
+
+        assert_eq!(text.title.as_deref(), Some("Onboarding Checklist"));
+        assert_eq!(
+            text.body,
+            "# Onboarding Checklist\n\n\
+             Welcome aboard! Complete these steps in your first week.\n\n\
+             ## Accounts\n\n\
+             - Request VPN access\n\
+             - Enable two-factor authentication\n\n\
+             Questions? Ask the IT desk.\n\
+             Room 204."
+        );
+        assert_eq!(text.metadata["author"], "Dana Reyes");
+        assert_eq!(text.metadata["created"], "2025-03-04T09:00:00Z");
+        assert_eq!(text.metadata["modified"], "2025-06-10T16:30:00Z");
+        assert_eq!(
+            text.metadata["keywords"],
+            serde_json::json!(["onboarding", "it"])
+        );
+        assert_eq!(text.metadata["headings"][1]["level"], 2);
+        assert!(text.pages.is_empty());
+    }
+
+    #[test]
+    fn test_pptx_slides_are_pages() {
+        let text = extract("pptx", &fixture("roadmap.pptx")).unwrap();
+
+        // Presentation order differs from the slide file numbers
+        assert_eq!(
+            text.body,
+            "# Product Roadmap\n\nQ3 planning\n\n# Milestones\n\nBeta in August\nGA in October\n\n# Risks\n\nHiring delays"
+        );
+        assert_eq!(text.pages, vec![1, 5, 10]);
+        assert_eq!(text.title.as_deref(), Some("Product Roadmap 2026"));
+        assert_eq!(text.metadata["slides"], 3);
+        assert_eq!(text.metadata["author"], "Planning Team");
+    }
+
+    #[test]
+    fn test_odt() {
+        let text = extract("odt", &fixture("minutes.odt")).unwrap();
+
+        assert_eq!(text.title.as_deref(), Some("Team Minutes"));
+        assert_eq!(
+            text.body,
+            "# Weekly sync\n\n\
+             Attendees agreed to move the release by one week.\n\n\
+             ## Actions\n\n\
+             - Update the changelog\n\
+             - Notify support"
+        );
+        assert_eq!(text.metadata["author"], "Sam Ortiz");
+        assert_eq!(text.metadata["created"], "2025-05-02T10:00:00");
+        assert_eq!(
+            text.metadata["keywords"],
+            serde_json::json!(["minutes", "release"])
+        );
+    }
+
+    #[test]
+    fn test_epub() {
+        let text = extract("epub", &fixture("field-guide.epub")).unwrap();
+
+        assert_eq!(text.title.as_deref(), Some("Field Guide to Mushrooms"));
+        assert_eq!(
+            text.body,
+            "# Chapter 1: Morels\n\nMorels appear in spring after rain.\n\n# Chapter 2: Chanterelles\n\nChanterelles smell faintly of apricots."
+        );
+        assert_eq!(text.metadata["author"], "Lee Park");
+        assert_eq!(text.metadata["language"], "en");
+        assert_eq!(text.metadata["modified"], "2025-01-15T12:00:00Z");
+        assert_eq!(text.metadata["chapters"], 2);
+    }
+
+    #[test]
+    fn test_missing_parts_are_errors() {
+        let data = super::super::zip::tests::build_zip(&[("hello.txt", b"hi", false)]);
+        for ext in EXTENSIONS {
+            let err = extract(ext, &data).unwrap_err();
+            assert!(err.to_string().contains("not a"), "{}: {}", ext, err);
+        }
+    }
+
+    #[test]
+    fn test_resolve_href() {
+        assert_eq!(
+            resolve_href("OEBPS", "text/ch%201.xhtml#top"),
+            "OEBPS/text/ch 1.xhtml"
+        );
+        assert_eq!(
+            resolve_href("OEBPS/text", "../images/a.xhtml"),
+            "OEBPS/images/a.xhtml"
+        );
+        assert_eq!(resolve_href("", "ch1.xhtml"), "ch1.xhtml");
+    }
+}
@@ -0,0 +1,1704 @@
+//! PDF text extraction
+//!
+//! A small pure-Rust reader covering what search needs. Objects are found by
+//! scanning for `N G obj` (so damaged or missing xref tables don't matter) and
+//! by unpacking object streams; pages are walked from the catalog; and content
+//! streams are interpreted just far enough to collect the strings they show,
+//! decoded through each font's ToUnicode CMap or its simple encoding.
+//! Encrypted documents and pages that only contain images yield no text.
+
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use regex::bytes::Regex;
+use std::collections::HashMap;
+use std::io::Read;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+/// Limit on page tree, form XObject and reference nesting
+const MAX_DEPTH: usize = 32;
+
+/// Largest decoded stream; a small compressed stream can expand to
+/// gigabytes, so output past this is cut off
+const MAX_STREAM_SIZE: usize = 64 * 1024 * 1024;
+
+/// LZW codes are at most 12 bits wide
+const LZW_TABLE_SIZE: usize = 4096;
+
+/// TJ adjustments (thousandths of an em) wider than this separate words
+const TJ_SPACE_THRESHOLD: f64 = 200.0;
+
+/// Text extracted from a PDF
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PdfText {
+    /// Document info title
+    pub title: Option<String>,
+    /// Text of each page, in page order
+    pub pages: Vec<String>,
+    /// Whether the document is encrypted (no text is extracted)
+    pub encrypted: bool,
+}
+
+/// Whether `content` starts like a PDF file
+pub(crate) fn is_pdf(content: &[u8]) -> bool {
+    content.len() >= 5
+        && content[..content.len().min(1024)]
+            .windows(5)
+            .any(|w| w == b"%PDF-")
+}
+
+/// Extract the title and per-page text of a PDF
+pub(crate) fn extract(data: &[u8]) -> PdfText {
+    let document = Document::load(data);
+    let title = document
+        .info()
+        .and_then(|info| info.get("Title"))
+        .and_then(|title| match document.resolve(title) {
+            Object::Str(bytes) => Some(decode_text_string(bytes)),
+            _ => None,
+        })
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+
+    if document.trailer.contains_key("Encrypt") {
+        return PdfText {
+            title,
+            pages: Vec::new(),
+            encrypted: true,
+        };
+    }
+
+    let mut fonts = FontCache::default();
+    let pages = document
+        .pages()
+        .iter()
+        .map(|page| document.page_text(page, &mut fonts))
+        .collect();
+
+    PdfText {
+        title,
+        pages,
+        encrypted: false,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Objects and lexing
+// ---------------------------------------------------------------------------
+
+type Dict = HashMap<String, Object>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Real(f64),
+    Name(String),
+    Str(Vec<u8>),
+    Array(Vec<Object>),
+    Dict(Dict),
+    /// Dictionary and raw (still encoded) data
+    Stream(Dict, Vec<u8>),
+    Ref(u32),
+}
+
+impl Object {
+    fn as_dict(&self) -> Option<&Dict> {
+        match self {
+            Object::Dict(dict) | Object::Stream(dict, _) => Some(dict),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Object::Int(i) => Some(*i as f64),
+            Object::Real(r) => Some(*r),
+            _ => None,
+        }
+    }
+
+    fn as_name(&self) -> Option<&str> {
+        match self {
+            Object::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Real(f64),
+    Name(String),
+    Str(Vec<u8>),
+    ArrayStart,
+    ArrayEnd,
+    DictStart,
+    DictEnd,
+    Keyword(String),
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b'\0' | b'\t' | b'\n' | b'\x0c' | b'\r' | b' ')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Lexer { data, pos }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&b) = self.data.get(self.pos) {
+            if is_whitespace(b) {
+                self.pos += 1;
+            } else if b == b'%' {
+                while self.pos < self.data.len() && !matches!(self.data[self.pos], b'\r' | b'\n') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+        let &b = self.data.get(self.pos)?;
+        match b {
+            b'/' => {
+                self.pos += 1;
+                Some(Token::Name(self.name()))
+            }
+            b'(' => {
+                self.pos += 1;
+                Some(Token::Str(self.literal_string()))
+            }
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                Some(Token::DictStart)
+            }
+            b'<' => {
+                self.pos += 1;
+                Some(Token::Str(self.hex_string()))
+            }
+            b'>' if self.data.get(self.pos + 1) == Some(&b'>') => {
+                self.pos += 2;
+                Some(Token::DictEnd)
+            }
+            b'[' => {
+                self.pos += 1;
+                Some(Token::ArrayStart)
+            }
+            b']' => {
+                self.pos += 1;
+                Some(Token::ArrayEnd)
+            }
+            b'0'..=b'9' | b'+' | b'-' | b'.' => Some(self.number()),
+            _ => {
+                let start = self.pos;
+                self.pos += 1;
+                if !is_delimiter(b) {
+                    while self
+                        .data
+                        .get(self.pos)
+                        .is_some_and(|&c| !is_whitespace(c) && !is_delimiter(c))
+                    {
+                        self.pos += 1;
+                    }
+                }
+                Some(Token::Keyword(
+                    String::from_utf8_lossy(&self.data[start..self.pos]).into_owned(),
+                ))
+            }
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let mut name = Vec::new();
+        while let Some(&b) = self.data.get(self.pos) {
+            if is_whitespace(b) || is_delimiter(b) {
+                break;
+            }
+            self.pos += 1;
+            if b == b'#' {
+                let hex = self.data.get(self.pos..self.pos + 2);
+                if let Some(value) = hex
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                {
+                    name.push(value);
+                    self.pos += 2;
+                    continue;
+                }
+            }
+            name.push(b);
+        }
+        String::from_utf8_lossy(&name).into_owned()
+    }
+
+    fn literal_string(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut depth = 1;
+        while let Some(&b) = self.data.get(self.pos) {
+            self.pos += 1;
+            match b {
+                b'(' => {
+                    depth += 1;
+                    out.push(b);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    out.push(b);
+                }
+                b'\\' => {
+                    let Some(&escaped) = self.data.get(self.pos) else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut value = (escaped - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.data.get(self.pos) {
+                                    Some(&d @ b'0'..=b'7') => {
+                                        value = value * 8 + (d - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        // Line continuation
+                        b'\r' => {
+                            if self.data.get(self.pos) == Some(&b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(b),
+            }
+        }
+        out
+    }
+
+    fn hex_string(&mut self) -> Vec<u8> {
+        let mut digits = Vec::new();
+        while let Some(&b) = self.data.get(self.pos) {
+            self.pos += 1;
+            if b == b'>' {
+                break;
+            }
+            if let Some(d) = (b as char).to_digit(16) {
+                digits.push(d as u8);
+            }
+        }
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    }
+
+    fn number(&mut self) -> Token {
+        let start = self.pos;
+        self.pos += 1;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|&c| c.is_ascii_digit() || c == b'.' || c == b'-')
+        {
+            self.pos += 1;
+        }
+        let text = String::from_utf8_lossy(&self.data[start..self.pos]);
+        if let Ok(int) = text.parse::<i64>() {
+            Token::Int(int)
+        } else {
+            Token::Real(text.parse().unwrap_or(0.0))
+        }
+    }
+
+    /// Parse one object; `None` at end of input or on a stray keyword
+    fn object(&mut self, depth: usize) -> Option<Object> {
+        let token = self.next_token()?;
+        self.object_from(token, depth)
+    }
+
+    fn object_from(&mut self, token: Token, depth: usize) -> Option<Object> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(match token {
+            Token::Int(int) => {
+                // `N G R` is a reference
+                let saved = self.pos;
+                if let (Some(Token::Int(_)), Some(Token::Keyword(k))) =
+                    (self.next_token(), self.next_token())
+                {
+                    if k == "R" && int >= 0 {
+                        return Some(Object::Ref(int as u32));
+                    }
+                }
+                self.pos = saved;
+                Object::Int(int)
+            }
+            Token::Real(real) => Object::Real(real),
+            Token::Name(name) => Object::Name(name),
+            Token::Str(bytes) => Object::Str(bytes),
+            Token::ArrayStart => {
+                let mut items = Vec::new();
+                loop {
+                    match self.next_token()? {
+                        Token::ArrayEnd => break,
+                        token => items.push(self.object_from(token, depth + 1)?),
+                    }
+                }
+                Object::Array(items)
+            }
+            Token::DictStart => {
+                let mut dict = Dict::new();
+                loop {
+                    match self.next_token()? {
+                        Token::DictEnd => break,
+                        Token::Name(key) => {
+                            let value = self.object(depth + 1)?;
+                            dict.insert(key, value);
+                        }
+                        _ => {}
+                    }
+                }
+                Object::Dict(dict)
+            }
+            Token::Keyword(k) => match k.as_str() {
+                "true" => Object::Bool(true),
+                "false" => Object::Bool(false),
+                "null" => Object::Null,
+                _ => return None,
+            },
+            Token::ArrayEnd | Token::DictEnd => return None,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Document structure
+// ---------------------------------------------------------------------------
+
+struct Document {
+    objects: HashMap<u32, Object>,
+    trailer: Dict,
+}
+
+/// A page with the resources it inherits
+struct Page {
+    dict: Dict,
+    resources: Option<Object>,
+}
+
+fn object_header() -> &'static Regex {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    HEADER.get_or_init(|| Regex::new(r"(\d+)\s+\d+\s+obj\b").expect("valid regex"))
+}
+
+impl Document {
+    fn load(data: &[u8]) -> Document {
+        let mut objects = HashMap::new();
+        let mut trailer = Dict::new();
+
+        // Later definitions win, as incremental updates are appended
+        let mut pos = 0;
+        while let Some(captures) = object_header().captures_at(data, pos) {
+            let whole = captures.get(0).expect("match");
+            pos = whole.end();
+            let Some(number) = std::str::from_utf8(&captures[1])
+                .ok()
+                .and_then(|n| n.parse().ok())
+            else {
+                continue;
+            };
+            let mut lexer = Lexer::new(data, pos);
+            let Some(object) = lexer.object(0) else {
+                continue;
+            };
+            let object = match object {
+                Object::Dict(dict) => match stream_data(data, &mut lexer, &dict) {
+                    Some(raw) => Object::Stream(dict, raw),
+                    None => Object::Dict(dict),
+                },
+                other => other,
+            };
+            pos = lexer.pos;
+
+            if let Object::Stream(dict, _) = &object {
+                if dict.get("Type").and_then(Object::as_name) == Some("XRef") {
+                    merge_trailer(&mut trailer, dict);
+                }
+            }
+            objects.insert(number, object);
+        }
+
+        // Classic trailers
+        let mut search = 0;
+        while let Some(offset) = find(&data[search..], b"trailer") {
+            let mut lexer = Lexer::new(data, search + offset + b"trailer".len());
+            if let Some(Object::Dict(dict)) = lexer.object(0) {
+                merge_trailer(&mut trailer, &dict);
+            }
+            search += offset + 1;
+        }
+
+        let mut document = Document { objects, trailer };
+        document.unpack_object_streams();
+        document
+    }
+
+    /// Add objects stored inside object streams (unless defined directly)
+    fn unpack_object_streams(&mut self) {
+        let mut unpacked = Vec::new();
+        for object in self.objects.values() {
+            let Object::Stream(dict, _) = object else {
+                continue;
+            };
+            if dict.get("Type").and_then(Object::as_name) != Some("ObjStm") {
+                continue;
+            }
+            let Some(data) = decode_stream(object) else {
+                continue;
+            };
+            let count = dict.get("N").and_then(Object::as_number).unwrap_or(0.0) as usize;
+            let first = dict.get("First").and_then(Object::as_number).unwrap_or(0.0) as usize;
+
+            let mut header = Lexer::new(&data, 0);
+            let mut entries = Vec::new();
+            for _ in 0..count {
+                match (header.next_token(), header.next_token()) {
+                    (Some(Token::Int(number)), Some(Token::Int(offset))) => {
+                        // Offsets past the end, or negative, are skipped below
+                        let offset = usize::try_from(offset)
+                            .ok()
+                            .and_then(|o| first.checked_add(o));
+                        entries.push((number as u32, offset.unwrap_or(usize::MAX)))
+                    }
+                    _ => break,
+                }
+            }
+            for (number, offset) in entries {
+                if offset < data.len() {
+                    if let Some(object) = Lexer::new(&data, offset).object(0) {
+                        unpacked.push((number, object));
+                    }
+                }
+            }
+        }
+        for (number, object) in unpacked {
+            self.objects.entry(number).or_insert(object);
+        }
+    }
+
+    fn resolve<'a>(&'a self, mut object: &'a Object) -> &'a Object {
+        for _ in 0..MAX_DEPTH {
+            match object {
+                Object::Ref(number) => match self.objects.get(number) {
+                    Some(target) => object = target,
+                    None => return &Object::Null,
+                },
+                _ => return object,
+            }
+        }
+        &Object::Null
+    }
+
+    fn resolve_dict<'a>(&'a self, object: &'a Object) -> Option<&'a Dict> {
+        self.resolve(object).as_dict()
+    }
+
+    fn info(&self) -> Option<&Dict> {
+        self.trailer
+            .get("Info")
+            .and_then(|info| self.resolve_dict(info))
+    }
+
+    /// Pages in document order, falling back to every page object when the
+    /// catalog is missing or broken
+    fn pages(&self) -> Vec<Page> {
+        let mut pages = Vec::new();
+        let root = self
+            .trailer
+            .get("Root")
+            .and_then(|root| self.resolve_dict(root));
+        if let Some(tree) = root.and_then(|root| root.get("Pages")) {
+            self.collect_pages(tree, None, 0, &mut pages);
+        }
+
+        if pages.is_empty() {
+            let mut numbers: Vec<&u32> = self.objects.keys().collect();
+            numbers.sort();
+            for number in numbers {
+                if let Some(dict) = self.objects[number].as_dict() {
+                    if dict.get("Type").and_then(Object::as_name) == Some("Page") {
+                        pages.push(Page {
+                            dict: dict.clone(),
+                            resources: dict.get("Resources").cloned(),
+                        });
+                    }
+                }
+            }
+        }
+        pages
+    }
+
+    fn collect_pages(
+        &self,
+        node: &Object,
+        inherited: Option<&Object>,
+        depth: usize,
+        pages: &mut Vec<Page>,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let Some(dict) = self.resolve_dict(node) else {
+            return;
+        };
+        let resources = dict.get("Resources").or(inherited);
+        match dict.get("Kids").map(|kids| self.resolve(kids)) {
+            Some(Object::Array(kids)) => {
+                for kid in kids {
+                    self.collect_pages(kid, resources, depth + 1, pages);
+                }
+            }
+            _ => pages.push(Page {
+                dict: dict.clone(),
+                resources: resources.cloned(),
+            }),
+        }
+    }
+
+    fn page_text(&self, page: &Page, fonts: &mut FontCache) -> String {
+        let mut content = Vec::new();
+        match page.dict.get("Contents").map(|c| self.resolve(c)) {
+            Some(Object::Array(parts)) => {
+                for part in parts {
+                    if let Some(data) = decode_stream(self.resolve(part)) {
+                        content.extend_from_slice(&data);
+                        content.push(b'\n');
+                    }
+                }
+            }
+            Some(stream) => {
+                if let Some(data) = decode_stream(stream) {
+                    content = data;
+                }
+            }
+            None => {}
+        }
+
+        let mut writer = TextWriter::default();
+        self.run_content(&content, page.resources.as_ref(), fonts, &mut writer, 0);
+        writer.finish()
+    }
+
+    /// Interpret a content stream, writing shown text
+    fn run_content(
+        &self,
+        content: &[u8],
+        resources: Option<&Object>,
+        fonts: &mut FontCache,
+        writer: &mut TextWriter,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let inherited = resources;
+        let resources = resources.and_then(|r| self.resolve_dict(r));
+        let font_dict = resources
+            .and_then(|r| r.get("Font"))
+            .and_then(|f| self.resolve_dict(f));
+
+        let mut lexer = Lexer::new(content, 0);
+        let mut operands: Vec<Object> = Vec::new();
+        let mut font: Option<Rc<Font>> = None;
+
+        while let Some(token) = lexer.next_token() {
+            let Token::Keyword(op) = token else {
+                if let Some(object) = lexer.object_from(token, 0) {
+                    operands.push(object);
+                }
+                continue;
+            };
+
+            match op.as_str() {
+                "Tf" => {
+                    font = operands
+                        .first()
+                        .and_then(Object::as_name)
+                        .and_then(|name| font_dict?.get(name))
+                        .map(|f| fonts.get(self, f));
+                }
+                "Td" | "TD" => {
+                    let ty = operands.get(1).and_then(Object::as_number).unwrap_or(0.0);
+                    let tx = operands.first().and_then(Object::as_number).unwrap_or(0.0);
+                    writer.move_by(tx, ty);
+                }
+                "Tm" => {
+                    let y = operands.get(5).and_then(Object::as_number).unwrap_or(0.0);
+                    writer.move_to(y);
+                }
+                "T*" => writer.newline(),
+                "Tj" => {
+                    if let Some(Object::Str(bytes)) = operands.first() {
+                        writer.show(&decode_with_font(font.as_deref(), bytes));
+                    }
+                }
+                "'" | "\"" => {
+                    writer.newline();
+                    if let Some(Object::Str(bytes)) = operands.last() {
+                        writer.show(&decode_with_font(font.as_deref(), bytes));
+                    }
+                }
+                "TJ" => {
+                    if let Some(Object::Array(items)) = operands.first() {
+                        for item in items {
+                            match item {
+                                Object::Str(bytes) => {
+                                    writer.show(&decode_with_font(font.as_deref(), bytes))
+                                }
+                                other => {
+                                    if other.as_number().is_some_and(|n| n < -TJ_SPACE_THRESHOLD) {
+                                        writer.space();
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                "Do" => {
+                    let xobject = operands
+                        .first()
+                        .and_then(Object::as_name)
+                        .and_then(|name| {
+                            let xobjects = resources?.get("XObject")?;
+                            self.resolve_dict(xobjects)?.get(name)
+                        })
+                        .map(|x| self.resolve(x));
+                    if let Some(form @ Object::Stream(dict, _)) = xobject {
+                        if dict.get("Subtype").and_then(Object::as_name) == Some("Form") {
+                            if let Some(data) = decode_stream(form) {
+                                // Forms without resources use the page's
+                                self.run_content(
+                                    &data,
+                                    dict.get("Resources").or(inherited),
+                                    fonts,
+                                    writer,
+                                    depth + 1,
+                                );
+                            }
+                        }
+                    }
+                }
+                "ID" => skip_inline_image(&mut lexer),
+                _ => {}
+            }
+            operands.clear();
+        }
+    }
+}
+
+fn merge_trailer(trailer: &mut Dict, dict: &Dict) {
+    for key in ["Root", "Info", "Encrypt"] {
+        if let Some(value) = dict.get(key) {
+            trailer.insert(key.to_string(), value.clone());
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Read the data of a stream whose dictionary the lexer just parsed
+fn stream_data(data: &[u8], lexer: &mut Lexer, dict: &Dict) -> Option<Vec<u8>> {
+    let saved = lexer.pos;
+    if lexer.next_token() != Some(Token::Keyword("stream".to_string())) {
+        lexer.pos = saved;
+        return None;
+    }
+    let mut start = lexer.pos;
+    if data.get(start) == Some(&b'\r') {
+        start += 1;
+    }
+    if data.get(start) == Some(&b'\n') {
+        start += 1;
+    }
+
+    // Trust /Length when it is direct and lands on `endstream`
+    if let Some(length) = dict.get("Length").and_then(Object::as_number) {
+        let end = start.saturating_add(length as usize);
+        if end <= data.len() {
+            let after = &data[end..data.len().min(end + 32)];
+            if find(after, b"endstream").is_some() {
+                lexer.pos = end;
+                return Some(data[start..end].to_vec());
+            }
+        }
+    }
+
+    let end = start + find(&data[start..], b"endstream")?;
+    lexer.pos = end + b"endstream".len();
+    let mut raw = &data[start..end];
+    raw = raw.strip_suffix(b"\n").unwrap_or(raw);
+    raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+    Some(raw.to_vec())
+}
+
+/// Skip inline image data after `ID`, up to and including `EI`
+fn skip_inline_image(lexer: &mut Lexer) {
+    let data = lexer.data;
+    let mut pos = lexer.pos + 1;
+    while pos + 2 <= data.len() {
+        if &data[pos..pos + 2] == b"EI"
+            && is_whitespace(data[pos - 1])
+            && data.get(pos + 2).is_none_or(|&b| is_whitespace(b))
+        {
+            lexer.pos = pos + 2;
+            return;
+        }
+        pos += 1;
+    }
+    lexer.pos = data.len();
+}
+
+// ---------------------------------------------------------------------------
+// Stream filters
+// ---------------------------------------------------------------------------
+
+/// Decoded data of a stream object; `None` for unsupported (image) filters
+fn decode_stream(object: &Object) -> Option<Vec<u8>> {
+    let Object::Stream(dict, raw) = object else {
+        return None;
+    };
+    let filters: Vec<&str> = match dict.get("Filter") {
+        Some(Object::Name(name)) => vec![name.as_str()],
+        Some(Object::Array(names)) => names.iter().filter_map(Object::as_name).collect(),
+        _ => Vec::new(),
+    };
+
+    let mut data = raw.clone();
+    for filter in filters {
+        data = match filter {
+            "FlateDecode" | "Fl" => inflate(&data, MAX_STREAM_SIZE)?,
+            "ASCIIHexDecode" | "AHx" => Lexer::new(&data, 0).hex_string(),
+            "ASCII85Decode" | "A85" => ascii85(&data, MAX_STREAM_SIZE),
+            "LZWDecode" | "LZW" => lzw(&data, MAX_STREAM_SIZE),
+            _ => return None,
+        };
+    }
+    Some(data)
+}
+
+/// zlib, falling back to raw deflate; truncated streams keep what decoded.
+/// At most `limit` bytes are inflated.
+fn inflate(data: &[u8], limit: usize) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let _ = ZlibDecoder::new(data)
+        .take(limit as u64)
+        .read_to_end(&mut out);
+    if out.is_empty() {
+        let _ = DeflateDecoder::new(data)
+            .take(limit as u64)
+            .read_to_end(&mut out);
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+/// ASCII base-85, decoding at most `limit` bytes
+fn ascii85(data: &[u8], limit: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut group = Vec::with_capacity(5);
+    let data = data.strip_prefix(b"<~").unwrap_or(data);
+    for &b in data {
+        if out.len() >= limit {
+            out.truncate(limit);
+            return out;
+        }
+        match b {
+            b'~' => break,
+            b'z' if group.is_empty() => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group.push(b - b'!');
+                if group.len() == 5 {
+                    let value = group
+                        .iter()
+                        .fold(0u32, |acc, &d| acc.wrapping_mul(85).wrapping_add(d as u32));
+                    out.extend_from_slice(&value.to_be_bytes());
+                    group.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+    if group.len() > 1 {
+        let kept = group.len() - 1;
+        group.resize(5, 84);
+        let value = group
+            .iter()
+            .fold(0u32, |acc, &d| acc.wrapping_mul(85).wrapping_add(d as u32));
+        out.extend_from_slice(&value.to_be_bytes()[..kept]);
+    }
+    out
+}
+
+/// LZW with the default early change, decoding at most `limit` bytes
+fn lzw(data: &[u8], limit: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut table: Vec<Vec<u8>> = (0..=255u8).map(|b| vec![b]).collect();
+    table.push(Vec::new()); // 256: clear
+    table.push(Vec::new()); // 257: end
+    let mut width = 9;
+    let mut previous: Option<Vec<u8>> = None;
+    let (mut buffer, mut bits) = (0u32, 0u32);
+
+    for &byte in data {
+        buffer = buffer << 8 | byte as u32;
+        bits += 8;
+        while bits >= width {
+            let code = (buffer >> (bits - width)) as usize & ((1 << width) - 1);
+            bits -= width;
+            match code {
+                256 => {
+                    table.truncate(258);
+                    width = 9;
+                    previous = None;
+                    continue;
+                }
+                257 => return out,
+                _ => {}
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) if code < table.len() && (code < 256 || !entry.is_empty()) => {
+                    entry.clone()
+                }
+                (_, Some(prev)) => {
+                    let mut entry = prev.clone();
+                    entry.push(prev[0]);
+                    entry
+                }
+                _ => return out,
+            };
+            out.extend_from_slice(&entry);
+            if out.len() >= limit {
+                out.truncate(limit);
+                return out;
+            }
+            // A full table stays as it is until the next clear code
+            if let Some(prev) = previous.filter(|_| table.len() < LZW_TABLE_SIZE) {
+                let mut new = prev;
+                new.push(entry[0]);
+                table.push(new);
+            }
+            previous = Some(entry);
+            width = match table.len() + 1 {
+                n if n >= 2048 => 12,
+                n if n >= 1024 => 11,
+                n if n >= 512 => 10,
+                _ => 9,
+            };
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Fonts and text decoding
+// ---------------------------------------------------------------------------
+
+/// How a font's character codes map to text
+#[derive(Debug, Default)]
+struct Font {
+    /// ToUnicode mappings by code
+    to_unicode: HashMap<u32, String>,
+    /// Code width in bytes (2 for composite fonts)
+    code_bytes: usize,
+    /// Simple-font encoding, used when ToUnicode has no entry
+    encoding: Option<[Option<char>; 256]>,
+}
+
+#[derive(Default)]
+struct FontCache {
+    by_ref: HashMap<u32, Rc<Font>>,
+}
+
+impl FontCache {
+    fn get(&mut self, document: &Document, font: &Object) -> Rc<Font> {
+        if let Object::Ref(number) = font {
+            if let Some(cached) = self.by_ref.get(number) {
+                return cached.clone();
+            }
+            let loaded = Rc::new(load_font(document, font));
+            self.by_ref.insert(*number, loaded.clone());
+            return loaded;
+        }
+        Rc::new(load_font(document, font))
+    }
+}
+
+fn load_font(document: &Document, font: &Object) -> Font {
+    let Some(dict) = document.resolve_dict(font) else {
+        return Font {
+            code_bytes: 1,
+            encoding: Some(base_encoding("WinAnsiEncoding")),
+            ..Default::default()
+        };
+    };
+    let composite = dict.get("Subtype").and_then(Object::as_name) == Some("Type0");
+
+    let mut font = Font {
+        code_bytes: if composite { 2 } else { 1 },
+        ..Default::default()
+    };
+    if let Some(cmap) = dict
+        .get("ToUnicode")
+        .and_then(|c| decode_stream(document.resolve(c)))
+    {
+        parse_to_unicode(&cmap, &mut font);
+    }
+    if !composite {
+        font.encoding = Some(simple_encoding(document, dict.get("Encoding")));
+    }
+    font
+}
+
+fn simple_encoding(document: &Document, encoding: Option<&Object>) -> [Option<char>; 256] {
+    match encoding.map(|e| document.resolve(e)) {
+        Some(Object::Name(name)) => base_encoding(name),
+        Some(Object::Dict(dict)) => {
+            let base = dict
+                .get("BaseEncoding")
+                .and_then(Object::as_name)
+                .unwrap_or("StandardEncoding");
+            let mut table = base_encoding(base);
+            if let Some(Object::Array(differences)) =
+                dict.get("Differences").map(|d| document.resolve(d))
+            {
+                let mut code = 0usize;
+                for item in differences {
+                    match item {
+                        Object::Int(start) => code = (*start).clamp(0, 255) as usize,
+                        Object::Name(glyph) => {
+                            if code < 256 {
+                                table[code] = glyph_to_char(glyph);
+                            }
+                            code += 1;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            table
+        }
+        _ => base_encoding("StandardEncoding"),
+    }
+}
+
+/// Map a ToUnicode CMap's `bfchar` and `bfrange` entries into `font`
+fn parse_to_unicode(cmap: &[u8], font: &mut Font) {
+    let mut lexer = Lexer::new(cmap, 0);
+    let mut pending: Vec<Token> = Vec::new();
+    let mut section = "";
+
+    while let Some(token) = lexer.next_token() {
+        match &token {
+            Token::Keyword(k) if k.starts_with("begin") => {
+                section = match k.as_str() {
+                    "begincodespacerange" => "codespace",
+                    "beginbfchar" => "bfchar",
+                    "beginbfrange" => "bfrange",
+                    _ => "",
+                };
+                pending.clear();
+            }
+            Token::Keyword(k) if k.starts_with("end") => {
+                section = "";
+                pending.clear();
+            }
+            Token::ArrayStart if section == "bfrange" => {
+                // `<lo> <hi> [<dst> ...]`
+                let mut targets = Vec::new();
+                while let Some(t) = lexer.next_token() {
+                    match t {
+                        Token::Str(bytes) => targets.push(utf16_be(&bytes)),
+                        Token::ArrayEnd => break,
+                        _ => {}
+                    }
+                }
+                if let [Token::Str(lo), Token::Str(_)] = pending.as_slice() {
+                    let lo = code_value(lo);
+                    for (i, target) in targets.into_iter().enumerate() {
+                        font.to_unicode.insert(lo + i as u32, target);
+                    }
+                }
+                pending.clear();
+            }
+            Token::Str(bytes) => {
+                pending.push(token.clone());
+                match section {
+                    "codespace" if pending.len() == 2 => {
+                        font.code_bytes = bytes.len().clamp(1, 4);
+                        pending.clear();
+                    }
+                    "bfchar" if pending.len() == 2 => {
+                        if let [Token::Str(src), Token::Str(dst)] = pending.as_slice() {
+                            font.to_unicode.insert(code_value(src), utf16_be(dst));
+                        }
+                        pending.clear();
+                    }
+                    "bfrange" if pending.len() == 3 => {
+                        if let [Token::Str(lo), Token::Str(hi), Token::Str(dst)] =
+                            pending.as_slice()
+                        {
+                            let (lo, hi) = (code_value(lo), code_value(hi));
+                            let base: Vec<u16> = dst
+                                .chunks(2)
+                                .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+                                .collect();
+                            for (i, code) in (lo..=hi.min(lo + 0xFFFF)).enumerate() {
+                                let mut units = base.clone();
+                                if let Some(last) = units.last_mut() {
+                                    *last = last.wrapping_add(i as u16);
+                                }
+                                font.to_unicode
+                                    .insert(code, String::from_utf16_lossy(&units));
+                            }
+                        }
+                        pending.clear();
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |acc, &b| acc << 8 | b as u32)
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Decode a shown string through the current font
+fn decode_with_font(font: Option<&Font>, bytes: &[u8]) -> String {
+    let Some(font) = font else {
+        return bytes.iter().filter_map(|&b| win_ansi(b)).collect();
+    };
+
+    let mut out = String::new();
+    let width = font.code_bytes.max(1);
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = (i + width).min(bytes.len());
+        let code = code_value(&bytes[i..end]);
+        if let Some(text) = font.to_unicode.get(&code) {
+            out.push_str(text);
+        } else if let Some(table) = &font.encoding {
+            for &b in &bytes[i..end] {
+                out.extend(table[b as usize]);
+            }
+        }
+        i = end;
+    }
+    out
+}
+
+/// Text string in PDF metadata: UTF-16BE with BOM, UTF-8 with BOM or PDFDocEncoding
+fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        utf16_be(utf16)
+    } else if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        bytes.iter().filter_map(|&b| win_ansi(b)).collect()
+    }
+}
+
+/// Characters of Windows-1252 at 0x80-0x9F
+const WIN_ANSI_HIGH: [Option<char>; 32] = [
+    Some('€'),
+    None,
+    Some('‚'),
+    Some('ƒ'),
+    Some('„'),
+    Some('…'),
+    Some('†'),
+    Some('‡'),
+    Some('ˆ'),
+    Some('‰'),
+    Some('Š'),
+    Some('‹'),
+    Some('Œ'),
+    None,
+    Some('Ž'),
+    None,
+    None,
+    Some('‘'),
+    Some('’'),
+    Some('“'),
+    Some('”'),
+    Some('•'),
+    Some('–'),
+    Some('—'),
+    Some('˜'),
+    Some('™'),
+    Some('š'),
+    Some('›'),
+    Some('œ'),
+    None,
+    Some('ž'),
+    Some('Ÿ'),
+];
+
+fn win_ansi(b: u8) -> Option<char> {
+    match b {
+        0x80..=0x9F => WIN_ANSI_HIGH[(b - 0x80) as usize],
+        b'\t' | b'\n' | b'\r' | 0x20.. => Some(b as char),
+        _ => None,
+    }
+}
+
+/// Upper half of MacRomanEncoding
+const MAC_ROMAN_HIGH: &str = "ÄÅÇÉÑÖÜáàâäãåçéèêëíìîïñóòôöõúùûü†°¢£§•¶ß®©™´¨≠ÆØ∞±≤≥¥µ∂∑∏π∫ªºΩæø¿¡¬√ƒ≈∆«»… ÀÃÕŒœ–—“”‘’÷◊ÿŸ⁄€‹›ﬁﬂ‡·‚„‰ÂÊÁËÈÍÎÏÌÓÔ\u{F8FF}ÒÚÛÙıˆ˜¯˘˙˚¸˝˛ˇ";
+
+fn base_encoding(name: &str) -> [Option<char>; 256] {
+    let mut table = [None; 256];
+    for (code, slot) in table.iter_mut().enumerate() {
+        *slot = win_ansi(code as u8);
+    }
+    match name {
+        "MacRomanEncoding" => {
+            for (slot, c) in table[0x80..].iter_mut().zip(MAC_ROMAN_HIGH.chars()) {
+                *slot = Some(c);
+            }
+        }
+        "StandardEncoding" => {
+            table[0x27] = Some('’');
+            table[0x60] = Some('‘');
+        }
+        _ => {}
+    }
+    table
+}
+
+/// Glyph names of Latin-1 0xC0-0xFF, in code order
+const LATIN1_GLYPHS: [&str; 64] = [
+    "Agrave",
+    "Aacute",
+    "Acircumflex",
+    "Atilde",
+    "Adieresis",
+    "Aring",
+    "AE",
+    "Ccedilla",
+    "Egrave",
+    "Eacute",
+    "Ecircumflex",
+    "Edieresis",
+    "Igrave",
+    "Iacute",
+    "Icircumflex",
+    "Idieresis",
+    "Eth",
+    "Ntilde",
+    "Ograve",
+    "Oacute",
+    "Ocircumflex",
+    "Otilde",
+    "Odieresis",
+    "multiply",
+    "Oslash",
+    "Ugrave",
+    "Uacute",
+    "Ucircumflex",
+    "Udieresis",
+    "Yacute",
+    "Thorn",
+    "germandbls",
+    "agrave",
+    "aacute",
+    "acircumflex",
+    "atilde",
+    "adieresis",
+    "aring",
+    "ae",
+    "ccedilla",
+    "egrave",
+    "eacute",
+    "ecircumflex",
+    "edieresis",
+    "igrave",
+    "iacute",
+    "icircumflex",
+    "idieresis",
+    "eth",
+    "ntilde",
+    "ograve",
+    "oacute",
+    "ocircumflex",
+    "otilde",
+    "odieresis",
+    "divide",
+    "oslash",
+    "ugrave",
+    "uacute",
+    "ucircumflex",
+    "udieresis",
+    "yacute",
+    "thorn",
+    "ydieresis",
+];
+
+/// Unicode for a glyph name from an encoding's Differences array
+fn glyph_to_char(name: &str) -> Option<char> {
+    if name.len() == 1 {
+        return name.chars().next().filter(|c| c.is_ascii_alphabetic());
+    }
+    if let Some(hex) = name.strip_prefix("uni").or_else(|| name.strip_prefix('u')) {
+        if (4..=6).contains(&hex.len()) {
+            if let Some(c) = u32::from_str_radix(&hex[..hex.len().min(6)], 16)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                return Some(c);
+            }
+        }
+    }
+    if let Some(i) = LATIN1_GLYPHS.iter().position(|g| *g == name) {
+        return char::from_u32(0xC0 + i as u32);
+    }
+    let c = match name {
+        "space" => ' ',
+        "exclam" => '!',
+        "quotedbl" => '"',
+        "numbersign" => '#',
+        "dollar" => '$',
+        "percent" => '%',
+        "ampersand" => '&',
+        "quotesingle" => '\'',
+        "parenleft" => '(',
+        "parenright" => ')',
+        "asterisk" => '*',
+        "plus" => '+',
+        "comma" => ',',
+        "hyphen" | "minus" => '-',
+        "period" => '.',
+        "slash" => '/',
+        "zero" => '0',
+        "one" => '1',
+        "two" => '2',
+        "three" => '3',
+        "four" => '4',
+        "five" => '5',
+        "six" => '6',
+        "seven" => '7',
+        "eight" => '8',
+        "nine" => '9',
+        "colon" => ':',
+        "semicolon" => ';',
+        "less" => '<',
+        "equal" => '=',
+        "greater" => '>',
+        "question" => '?',
+        "at" => '@',
+        "bracketleft" => '[',
+        "backslash" => '\\',
+        "bracketright" => ']',
+        "asciicircum" => '^',
+        "underscore" => '_',
+        "grave" => '`',
+        "braceleft" => '{',
+        "bar" => '|',
+        "braceright" => '}',
+        "asciitilde" => '~',
+        "quoteleft" => '‘',
+        "quoteright" => '’',
+        "quotedblleft" => '“',
+        "quotedblright" => '”',
+        "quotesinglbase" => '‚',
+        "quotedblbase" => '„',
+        "endash" => '–',
+        "emdash" => '—',
+        "bullet" => '•',
+        "ellipsis" => '…',
+        "dagger" => '†',
+        "daggerdbl" => '‡',
+        "copyright" => '©',
+        "registered" => '®',
+        "trademark" => '™',
+        "degree" => '°',
+        "section" => '§',
+        "paragraph" => '¶',
+        "fi" => 'ﬁ',
+        "fl" => 'ﬂ',
+        "ff" => 'ﬀ',
+        "ffi" => 'ﬃ',
+        "ffl" => 'ﬄ',
+        "Euro" => '€',
+        "sterling" => '£',
+        "yen" => '¥',
+        "cent" => '¢',
+        "nbspace" => '\u{a0}',
+        _ => return None,
+    };
+    Some(c)
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+/// Accumulates shown text, turning text moves into spaces and line breaks
+#[derive(Default)]
+struct TextWriter {
+    out: String,
+    y: Option<f64>,
+}
+
+impl TextWriter {
+    fn show(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn space(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+            self.out.push(' ');
+        }
+    }
+
+    fn newline(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            while self.out.ends_with(' ') {
+                self.out.pop();
+            }
+            self.out.push('\n');
+        }
+    }
+
+    fn move_by(&mut self, tx: f64, ty: f64) {
+        if ty.abs() > 0.01 {
+            self.y = self.y.map(|y| y + ty);
+            self.newline();
+        } else if tx.abs() > 0.01 {
+            self.space();
+        }
+    }
+
+    fn move_to(&mut self, y: f64) {
+        match self.y {
+            Some(previous) if (previous - y).abs() > 1.0 => self.newline(),
+            Some(_) => self.space(),
+            None => {}
+        }
+        self.y = Some(y);
+    }
+
+    /// Page text with ligatures expanded and blank runs collapsed
+    fn finish(self) -> String {
+        let text = self
+            .out
+            .replace('ﬁ', "fi")
+            .replace('ﬂ', "fl")
+            .replace('ﬀ', "ff")
+            .replace('ﬃ', "ffi")
+            .replace('ﬄ', "ffl");
+        let mut lines: Vec<String> = Vec::new();
+        for line in text.lines() {
+            let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+            if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+                continue;
+            }
+            lines.push(line);
+        }
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    /// Build a PDF from object bodies (numbered from 1) with a valid xref
+    pub(crate) fn build_pdf(objects: &[Vec<u8>], trailer: &str) -> Vec<u8> {
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            pdf.extend_from_slice(body);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} {} >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                trailer,
+                xref
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+
+    pub(crate) fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+        let mut body = format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        body
+    }
+
+    pub(crate) fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Two pages: one plain, one compressed with TJ kerning and a WinAnsi quote
+    pub(crate) fn sample_pdf() -> Vec<u8> {
+        let page1 = b"BT /F1 12 Tf 72 720 Td (Quarterly report) Tj 0 -14 Td (Revenue grew \\(a lot\\)) Tj ET";
+        let page2 = b"BT /F1 12 Tf 1 0 0 1 72 720 Tm [(Inter)-20(national)-400(shipping)] TJ 1 0 0 1 72 700 Tm (It\x92s late) Tj ET";
+        build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /Resources << /Font << /F1 5 0 R >> >> >>".to_vec(),
+                b"<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>".to_vec(),
+                b"<< /Type /Page /Parent 2 0 R /Contents 7 0 R >>".to_vec(),
+                b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+                stream("", page1),
+                stream("/Filter /FlateDecode", &deflate(page2)),
+                b"<< /Title (Q3 Report) /Producer (test) >>".to_vec(),
+            ],
+            "/Root 1 0 R /Info 8 0 R",
+        )
+    }
+
+    #[test]
+    fn test_extract_pages_and_title() {
+        let pdf = sample_pdf();
+        assert!(is_pdf(&pdf));
+
+        let text = extract(&pdf);
+        assert_eq!(text.title.as_deref(), Some("Q3 Report"));
+        assert!(!text.encrypted);
+        assert_eq!(text.pages.len(), 2);
+        assert_eq!(text.pages[0], "Quarterly report\nRevenue grew (a lot)");
+        assert_eq!(text.pages[1], "International shipping\nIt’s late");
+    }
+
+    #[test]
+    fn test_to_unicode_cmap_for_composite_font() {
+        let cmap = b"/CIDInit /ProcSet findresource begin\n1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+            2 beginbfchar <0001> <0048> <0002> <0069> endbfchar\n\
+            1 beginbfrange <0010> <0012> <0061> endbfrange\n\
+            1 beginbfrange <0030> <0031> [<00E9> <FB01>] endbfrange\nendcmap";
+        let content = b"BT /F0 10 Tf [<00010002> -400 <001000110012>] TJ <00300031FFFF> Tj ET";
+        let pdf = build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+                b"<< /Type /Page /Parent 2 0 R /Resources << /Font << /F0 4 0 R >> >> /Contents 5 0 R >>".to_vec(),
+                b"<< /Type /Font /Subtype /Type0 /Encoding /Identity-H /ToUnicode 6 0 R >>".to_vec(),
+                stream("", content),
+                stream("/Filter /FlateDecode", &deflate(cmap)),
+            ],
+            "/Root 1 0 R",
+        );
+
+        // Unmapped codes are dropped; ligatures are expanded
+        assert_eq!(extract(&pdf).pages, vec!["Hi abcéfi"]);
+    }
+
+    #[test]
+    fn test_object_streams_and_differences() {
+        // Page and font live in an object stream; the font remaps 0x01-0x02
+        let page = b"<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>";
+        let font = b"<< /Type /Font /Subtype /Type1 /Encoding << /Differences [1 /eacute /quoteright] >> >>";
+        let header = format!("3 0 4 {} ", page.len() + 1);
+        let mut objstm = header.clone().into_bytes();
+        objstm.extend_from_slice(page);
+        objstm.push(b' ');
+        objstm.extend_from_slice(font);
+
+        let pdf = build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+                b"null".to_vec(),
+                b"null".to_vec(),
+                stream("", b"BT /F1 9 Tf (caf\x01 Bob\x02s) Tj ET"),
+                stream(
+                    &format!(
+                        "/Type /ObjStm /N 2 /First {} /Filter /FlateDecode",
+                        header.len()
+                    ),
+                    &deflate(&objstm),
+                ),
+            ],
+            "/Root 1 0 R",
+        );
+
+        // Directly defined `null` placeholders don't hide the packed objects
+        let mut document = Document::load(&pdf);
+        document.objects.remove(&3);
+        document.objects.remove(&4);
+        document.unpack_object_streams();
+        let mut fonts = FontCache::default();
+        let pages = document.pages();
+        assert_eq!(document.page_text(&pages[0], &mut fonts), "café Bob’s");
+    }
+
+    #[test]
+    fn test_encrypted_and_image_only() {
+        let encrypted = build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [] /Count 0 >>".to_vec(),
+                b"<< /Filter /Standard /V 2 /R 3 >>".to_vec(),
+                b"<< /Title <FEFF0053006500630072006500740073> >>".to_vec(),
+            ],
+            "/Root 1 0 R /Encrypt 3 0 R /Info 4 0 R",
+        );
+        let text = extract(&encrypted);
+        assert!(text.encrypted);
+        assert_eq!(text.title.as_deref(), Some("Secrets"));
+        assert!(text.pages.is_empty());
+
+        let scanned = build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+                b"<< /Type /Page /Parent 2 0 R /Resources << /XObject << /Im0 5 0 R >> >> /Contents 4 0 R >>".to_vec(),
+                stream("", b"q 612 0 0 792 0 0 cm /Im0 Do Q"),
+                stream("/Type /XObject /Subtype /Image /Width 1 /Height 1 /Filter /DCTDecode", b"\xFF\xD8\xFF"),
+            ],
+            "/Root 1 0 R",
+        );
+        assert_eq!(extract(&scanned).pages, vec![String::new()]);
+    }
+
+    #[test]
+    fn test_form_xobject_and_inline_image() {
+        let pdf = build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+                b"<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 4 0 R >> /XObject << /Fm0 6 0 R >> >> /Contents 5 0 R >>".to_vec(),
+                b"<< /Type /Font /Subtype /Type1 >>".to_vec(),
+                stream("", b"BI /W 2 /H 1 /BPC 8 /CS /G ID \x00) Tj EI\nEI Q BT /F1 9 Tf (Header) Tj ET /Fm0 Do"),
+                stream("/Type /XObject /Subtype /Form", b"BT /F1 9 Tf 0 -20 Td (Footer text) Tj ET"),
+            ],
+            "/Root 1 0 R",
+        );
+        assert_eq!(extract(&pdf).pages, vec!["Header\nFooter text"]);
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(ascii85(b"<~87cURD]i,\"Ebo80~>", 100), b"Hello World!");
+        // "-----A---B" encoded with LZW (example from the PDF specification)
+        let lzw_data = [0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+        assert_eq!(lzw(&lzw_data, 100), b"-----A---B");
+        assert_eq!(
+            inflate(&deflate(b"compressed"), 100).unwrap(),
+            b"compressed"
+        );
+
+        // Output is cut off at the limit
+        assert_eq!(inflate(&deflate(&[b'x'; 10_000]), 64).unwrap().len(), 64);
+        assert_eq!(ascii85(b"<~zzzzzz~>", 10), vec![0; 10]);
+        assert_eq!(lzw(&lzw_data, 4), b"----");
+    }
+
+    #[test]
+    fn test_bogus_lengths_and_offsets() {
+        // A /Length past the end falls back to scanning for `endstream`
+        let pdf = build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+                b"<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>".to_vec(),
+                b"<< /Length 99999999999999999999 >>\nstream\nBT (intact) Tj ET\nendstream"
+                    .to_vec(),
+            ],
+            "/Root 1 0 R",
+        );
+        assert_eq!(extract(&pdf).pages, vec!["intact"]);
+
+        // Object stream offsets that are negative or overflow are skipped
+        let objstm = b"3 -5 4 9223372036854775807 null null";
+        let pdf = build_pdf(
+            &[
+                b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+                b"<< /Type /Pages /Kids [] /Count 0 >>".to_vec(),
+                stream("/Type /ObjStm /N 2 /First 30", objstm),
+            ],
+            "/Root 1 0 R",
+        );
+        let document = Document::load(&pdf);
+        assert!(!document.objects.contains_key(&4));
+    }
+
+    #[test]
+    fn test_garbage_is_not_a_panic() {
+        for data in [
+            &b"%PDF-1.7\n1 0 obj << /Type /Page /Contents 2 0 R"[..],
+            b"%PDF-",
+            b"%PDF-1.4 (((( <<<< [[[[",
+        ] {
+            let text = extract(data);
+            assert!(text.pages.iter().all(|p| p.is_empty()));
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Pluggable parsers
+//!
+//! A `ParserRegistry` maps file extensions and MIME types to `Parser`s that
+//! take precedence over the built-in formats. Extensions are matched first,
+//! then the MIME type guessed from the path; anything unregistered goes to
+//! the built-in parsers, which also use the registry for the documents
+//! inside containers (such as email attachments).
+
+use super::{ParseOptions, ParsedDocument};
+use crate::error::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A parser for one or more file formats
+///
+/// Parsers that replace the raw content with extracted text should set
+/// `ParsedDocument::text_extracted`; the indexer re-parses files handled by
+/// a registered parser until their extracted text is stored.
+pub trait Parser: Send + Sync {
+    /// Parse `content`, read from `path`
+    fn parse(&self, path: &Path, content: &[u8], options: &ParseOptions) -> Result<ParsedDocument>;
+}
+
+impl<F> Parser for F
+where
+    F: Fn(&Path, &[u8], &ParseOptions) -> Result<ParsedDocument> + Send + Sync,
+{
+    fn parse(&self, path: &Path, content: &[u8], options: &ParseOptions) -> Result<ParsedDocument> {
+        self(path, content, options)
+    }
+}
+
+/// Parsers registered by extension or MIME type
+#[derive(Clone, Default)]
+pub struct ParserRegistry {
+    extensions: HashMap<String, Arc<dyn Parser>>,
+    mime_types: HashMap<String, Arc<dyn Parser>>,
+}
+
+impl std::fmt::Debug for ParserRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut extensions: Vec<_> = self.extensions.keys().collect();
+        let mut mime_types: Vec<_> = self.mime_types.keys().collect();
+        extensions.sort();
+        mime_types.sort();
+        f.debug_struct("ParserRegistry")
+            .field("extensions", &extensions)
+            .field("mime_types", &mime_types)
+            .finish()
+    }
+}
+
+/// `md`, `.MD` and `*.md` all become `md`
+fn normalize_extension(extension: &str) -> String {
+    extension
+        .trim()
+        .trim_start_matches('*')
+        .trim_start_matches('.')
+        .to_lowercase()
+}
+
+impl ParserRegistry {
+    /// An empty registry, leaving every format to the built-in parsers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `parser` for files with this extension (`md`, `.md` or `*.md`)
+    pub fn register_extension(&mut self, extension: &str, parser: impl Parser + 'static) {
+        self.extensions
+            .insert(normalize_extension(extension), Arc::new(parser));
+    }
+
+    /// Use `parser` for files whose extension maps to this MIME type
+    pub fn register_mime_type(&mut self, mime_type: &str, parser: impl Parser + 'static) {
+        self.mime_types
+            .insert(mime_type.trim().to_lowercase(), Arc::new(parser));
+    }
+
+    /// Whether no parser is registered
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty() && self.mime_types.is_empty()
+    }
+
+    /// The registered parser for `path`, if any
+    pub fn get(&self, path: &Path) -> Option<&dyn Parser> {
+        let extension = super::extension(path);
+        if let Some(parser) = self.extensions.get(&extension) {
+            return Some(parser.as_ref());
+        }
+        if self.mime_types.is_empty() || extension.is_empty() {
+            return None;
+        }
+        mime_guess::from_path(path)
+            .iter()
+            .find_map(|mime| self.mime_types.get(mime.essence_str()))
+            .map(|parser| parser.as_ref())
+    }
+
+    /// Parse a file with its registered parser, or the built-in one
+    pub fn parse(
+        &self,
+        path: &Path,
+        content: &[u8],
+        options: &ParseOptions,
+    ) -> Result<ParsedDocument> {
+        let mut budget = super::archive::MAX_INFLATED_SIZE;
+        self.parse_within(path, content, options, &mut budget)
+    }
+
+    /// Parse a document inside a container; the archives it holds take what
+    /// they inflate from the container's `budget`
+    pub(crate) fn parse_within(
+        &self,
+        path: &Path,
+        content: &[u8],
+        options: &ParseOptions,
+        budget: &mut u64,
+    ) -> Result<ParsedDocument> {
+        match self.get(path) {
+            Some(parser) => parser.parse(path, content, options),
+            None => super::parse_builtin(path, content, options, self, budget),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(body: &'static str) -> impl Parser {
+        move |path: &Path, _: &[u8], _: &ParseOptions| {
+            let mut document = crate::parser::parse_file(path, b"")?;
+            document.body = body.to_string();
+            document.text_extracted = true;
+            Ok(document)
+        }
+    }
+
+    #[test]
+    fn test_lookup_order() {
+        let mut registry = ParserRegistry::new();
+        assert!(registry.is_empty());
+        registry.register_extension("*.RTF", constant("by extension"));
+        registry.register_mime_type("application/x-tex", constant("by mime"));
+        registry.register_mime_type("text/plain", constant("plain"));
+        registry.register_extension("txt", constant("txt"));
+
+        let options = ParseOptions::default();
+        let parse = |path: &str| {
+            registry
+                .parse(Path::new(path), b"raw", &options)
+                .unwrap()
+                .body
+        };
+        assert_eq!(parse("letter.rtf"), "by extension");
+        assert_eq!(parse("paper.tex"), "by mime");
+        assert_eq!(parse("notes.txt"), "txt");
+        // Unregistered formats use the built-in parsers
+        assert_eq!(parse("readme.md"), "raw");
+        assert!(registry.get(Path::new("Makefile")).is_none());
+        assert_eq!(
+            format!("{:?}", registry),
+            "ParserRegistry { extensions: [\"rtf\", \"txt\"], mime_types: [\"application/x-tex\", \"text/plain\"] }"
+        );
+    }
+
+    #[test]
+    fn test_attachments_use_the_registry() {
+        let mut registry = ParserRegistry::new();
+        registry.register_extension("rtf", constant("converted"));
+        let message = b"Subject: Letter\n\
+Content-Type: multipart/mixed; boundary=b\n\
+\n\
+--b\n\
+\n\
+See attached.\n\
+--b\n\
+Content-Disposition: attachment; filename=letter.rtf\n\
+\n\
+{\\rtf1 Dear team}\n\
+--b--\n";
+
+        let document = registry
+            .parse(Path::new("letter.eml"), message, &ParseOptions::default())
+            .unwrap();
+        assert_eq!(document.parts[0].name, "letter.rtf");
+        assert_eq!(document.parts[0].document.body, "converted");
+    }
+}
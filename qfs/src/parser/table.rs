@@ -0,0 +1,219 @@
+//! Delimited tables (CSV and TSV)
+//!
+//! The first record is the header. The extracted text has the header on
+//! line 1 and one line per data row after it, each cell labelled with its
+//! column (`city: Paris | country: France`), so a hit on line `n + 1` is row
+//! `n` and reads on its own. Quoted fields may contain delimiters, quotes and
+//! newlines. Very large tables are capped at `MAX_ROWS` rows.
+
+use std::collections::HashMap;
+
+/// Rows indexed per table; the rest are counted but not indexed
+pub(crate) const MAX_ROWS: usize = 50_000;
+
+/// Characters kept per cell
+const MAX_CELL_CHARS: usize = 500;
+
+/// Text of a table, one line per row after the header
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TableText {
+    pub body: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// 1-based line each data row is on
+    pub rows: Vec<u32>,
+}
+
+/// Guess the delimiter of a CSV file from its first line
+fn sniff_delimiter(text: &str) -> char {
+    let first = text.lines().next().unwrap_or("");
+    let mut in_quotes = false;
+    let mut counts = [(',', 0), (';', 0), ('\t', 0), ('|', 0)];
+    for c in first.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some((_, n)) = counts.iter_mut().find(|(d, _)| *d == c) {
+                *n += 1;
+            }
+        }
+    }
+    // Ties go to the earlier (more common) delimiter
+    counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, n)| *n)
+        .filter(|(_, n)| *n > 0)
+        .map_or(',', |(d, _)| *d)
+}
+
+/// Iterator over the records of delimited text
+struct Records<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    delimiter: char,
+}
+
+impl Iterator for Records<'_> {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        self.chars.peek()?;
+        let mut record = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        while let Some(c) = self.chars.next() {
+            match c {
+                '"' if in_quotes => {
+                    if self.chars.peek() == Some(&'"') {
+                        self.chars.next();
+                        field.push('"');
+                    } else {
+                        in_quotes = false;
+                    }
+                }
+                '"' if field.is_empty() => in_quotes = true,
+                c if in_quotes => field.push(c),
+                c if c == self.delimiter => record.push(std::mem::take(&mut field)),
+                '\r' if self.chars.peek() == Some(&'\n') => {}
+                '\n' | '\r' => break,
+                c => field.push(c),
+            }
+        }
+        record.push(field);
+        Some(record)
+    }
+}
+
+/// A cell on one line, capped at `MAX_CELL_CHARS`
+fn clean_cell(cell: &str) -> String {
+    let cell = cell.split_whitespace().collect::<Vec<_>>().join(" ");
+    match cell.char_indices().nth(MAX_CELL_CHARS) {
+        Some((end, _)) => format!("{}…", &cell[..end]),
+        None => cell,
+    }
+}
+
+/// Parse delimited text; `delimiter` is sniffed when `None`
+pub(crate) fn extract(text: &str, delimiter: Option<char>) -> TableText {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let delimiter = delimiter.unwrap_or_else(|| sniff_delimiter(text));
+    let mut records = Records {
+        chars: text.chars().peekable(),
+        delimiter,
+    };
+
+    let mut table = TableText::default();
+    let headers: Vec<String> = records
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, h)| match clean_cell(h) {
+            h if h.is_empty() => format!("column {}", i + 1),
+            h => h,
+        })
+        .collect();
+    table.body = headers.join(" | ");
+
+    let mut count = 0usize;
+    for record in records {
+        // Blank lines between records are not rows
+        if record.len() == 1 && record[0].trim().is_empty() {
+            continue;
+        }
+        count += 1;
+        if count > MAX_ROWS {
+            continue;
+        }
+
+        let cells: Vec<String> = record
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| {
+                let value = clean_cell(value);
+                if value.is_empty() {
+                    return None;
+                }
+                Some(match headers.get(i) {
+                    Some(header) => format!("{}: {}", header, value),
+                    None => format!("column {}: {}", i + 1, value),
+                })
+            })
+            .collect();
+        table.body.push('\n');
+        table.body.push_str(&cells.join(" | "));
+        table.rows.push(count as u32 + 1);
+    }
+
+    table.metadata.insert("columns".to_string(), headers.into());
+    table.metadata.insert("rows".to_string(), count.into());
+    table
+        .metadata
+        .insert("delimiter".to_string(), delimiter.to_string().into());
+    if count > MAX_ROWS {
+        table.metadata.insert("truncated".to_string(), true.into());
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_are_labelled_lines() {
+        let csv = "\u{feff}city,country,notes\r\n\
+                   Paris,France,\"capital, \"\"city of light\"\"\"\r\n\
+                   \r\n\
+                   Lyon,France,\r\n\
+                   \"São\nPaulo\",Brazil,,extra\n";
+        let table = extract(csv, None);
+
+        assert_eq!(
+            table.body,
+            "city | country | notes\n\
+             city: Paris | country: France | notes: capital, \"city of light\"\n\
+             city: Lyon | country: France\n\
+             city: São Paulo | country: Brazil | column 4: extra"
+        );
+        assert_eq!(table.rows, vec![2, 3, 4]);
+        assert_eq!(
+            table.metadata["columns"],
+            serde_json::json!(["city", "country", "notes"])
+        );
+        assert_eq!(table.metadata["rows"], 3);
+        assert!(!table.metadata.contains_key("truncated"));
+    }
+
+    #[test]
+    fn test_delimiters() {
+        assert_eq!(sniff_delimiter("a;b;c\n1;2;3"), ';');
+        assert_eq!(sniff_delimiter("\"a,b\";c\n"), ';');
+        assert_eq!(sniff_delimiter("a,b;c"), ',');
+        assert_eq!(sniff_delimiter("single"), ',');
+
+        let table = extract("id\tname\n7\tAda, Countess", Some('\t'));
+        assert_eq!(table.body, "id | name\nid: 7 | name: Ada, Countess");
+        assert_eq!(table.metadata["delimiter"], "\t");
+    }
+
+    #[test]
+    fn test_large_tables_are_capped() {
+        let mut csv = String::from("n,,label\n");
+        for i in 0..MAX_ROWS + 10 {
+            csv.push_str(&format!(
+                "{},x,{}\n",
+                i,
+                "y".repeat(if i == 0 { 600 } else { 1 })
+            ));
+        }
+        let table = extract(&csv, None);
+
+        assert_eq!(table.rows.len(), MAX_ROWS);
+        assert_eq!(table.metadata["rows"], MAX_ROWS + 10);
+        assert_eq!(table.metadata["truncated"], true);
+        assert_eq!(table.body.lines().count(), MAX_ROWS + 1);
+        let first = table.body.lines().nth(1).unwrap();
+        assert!(first.starts_with("n: 0 | column 2: x | label: yyy"));
+        assert!(first.ends_with('…') && first.chars().count() < 600);
+    }
+}
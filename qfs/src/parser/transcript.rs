@@ -0,0 +1,317 @@
+//! Agent session transcripts (JSONL)
+//!
+//! Each line of a session log is a record; those carrying a message (a
+//! `message` object with a `role`, or a bare `{"role", "content"}` line) are
+//! indexed in order under a `## role (timestamp)` heading. Content may be a
+//! string or an array of blocks: text is kept, tool calls become a `Tool:`
+//! line with their inputs, and tool results are kept up to
+//! `MAX_RESULT_CHARS`. Thinking and image blocks are left out. Each message
+//! is reported as a page, so hits cite the message they came from.
+
+use std::collections::{BTreeSet, HashMap};
+
+/// Characters kept per tool result or tool input value
+const MAX_RESULT_CHARS: usize = 2000;
+
+/// Characters of the first user message kept as the title
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Text of a transcript, one page per message
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TranscriptText {
+    pub title: Option<String>,
+    pub body: String,
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// 1-based line each message starts on
+    pub messages: Vec<u32>,
+}
+
+/// The message of a record and its role, if it has one
+fn message(record: &serde_json::Value) -> Option<(&serde_json::Value, &str)> {
+    let message = match record.get("message") {
+        Some(message) if message.is_object() => message,
+        _ => record,
+    };
+    let role = message.get("role")?.as_str()?;
+    message.get("content")?;
+    Some((message, role))
+}
+
+/// Whether `text` looks like a session transcript: one of its first records
+/// is a message
+pub(crate) fn is_transcript(text: &str) -> bool {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(20)
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .any(|record| message(&record).is_some())
+}
+
+/// `text` capped at `max` characters
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Text of a tool result's content, a string or an array of text blocks
+fn result_text(content: Option<&serde_json::Value>) -> String {
+    match content {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Array(blocks)) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// A tool call's inputs, one `key: value` line each
+fn tool_input(input: Option<&serde_json::Value>) -> String {
+    match input {
+        Some(serde_json::Value::Object(fields)) => fields
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    value => value.to_string(),
+                };
+                format!("{}: {}", key, truncate(value.trim(), MAX_RESULT_CHARS))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Some(serde_json::Value::Null) | None => String::new(),
+        Some(value) => truncate(&value.to_string(), MAX_RESULT_CHARS),
+    }
+}
+
+/// The readable blocks of a message, and the names of the tools it calls
+fn blocks(content: &serde_json::Value, tools: &mut Vec<String>) -> Vec<String> {
+    let blocks = match content {
+        serde_json::Value::String(s) if s.trim().is_empty() => return Vec::new(),
+        serde_json::Value::String(s) => return vec![s.trim().to_string()],
+        serde_json::Value::Array(blocks) => blocks,
+        _ => return Vec::new(),
+    };
+
+    let mut texts = Vec::new();
+    for block in blocks {
+        let text = match block.get("type").and_then(|t| t.as_str()) {
+            Some("text") => block
+                .get("text")
+                .and_then(|t| t.as_str())
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+            Some("tool_use") => {
+                let name = block.get("name").and_then(|n| n.as_str()).unwrap_or("tool");
+                tools.push(name.to_string());
+                let input = tool_input(block.get("input"));
+                if input.is_empty() {
+                    format!("Tool: {}", name)
+                } else {
+                    format!("Tool: {}\n{}", name, input)
+                }
+            }
+            Some("tool_result") => {
+                let text = result_text(block.get("content"));
+                let text = truncate(text.trim(), MAX_RESULT_CHARS);
+                let label = match block.get("is_error").and_then(|e| e.as_bool()) {
+                    Some(true) => "Error",
+                    _ => "Result",
+                };
+                if text.is_empty() {
+                    continue;
+                }
+                format!("{}:\n{}", label, text)
+            }
+            // Thinking, images and unknown blocks aren't indexed
+            _ => continue,
+        };
+        if !text.is_empty() {
+            texts.push(text);
+        }
+    }
+    texts
+}
+
+/// Title from the first line a user wrote, skipping command wrappers and
+/// tool results
+fn title(content: &serde_json::Value) -> Option<String> {
+    let texts: Vec<&str> = match content {
+        serde_json::Value::String(s) => vec![s],
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    texts
+        .iter()
+        .map(|text| text.trim())
+        .filter(|text| !text.starts_with('<'))
+        .find_map(|text| text.lines().map(str::trim).find(|line| !line.is_empty()))
+        .map(|line| truncate(line.trim_start_matches('#').trim(), MAX_TITLE_CHARS))
+}
+
+/// Extract the messages of a transcript, `None` if it has none
+pub(crate) fn extract(text: &str) -> Option<TranscriptText> {
+    let mut transcript = TranscriptText::default();
+    let mut tools = Vec::new();
+    let mut models = BTreeSet::new();
+    let mut timestamps = Vec::new();
+    let mut summary = None;
+
+    for line in text.lines() {
+        let Ok(record) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let field = |key: &str| record.get(key).and_then(|v| v.as_str()).map(String::from);
+        if field("type").as_deref() == Some("summary") {
+            summary = summary.or_else(|| field("summary"));
+            continue;
+        }
+        for (key, name) in [
+            ("sessionId", "session_id"),
+            ("cwd", "cwd"),
+            ("gitBranch", "git_branch"),
+        ] {
+            if let Some(value) = field(key) {
+                transcript
+                    .metadata
+                    .entry(name.to_string())
+                    .or_insert(value.into());
+            }
+        }
+        let Some((message, role)) = message(&record) else {
+            continue;
+        };
+        if let Some(model) = message.get("model").and_then(|m| m.as_str()) {
+            models.insert(model.to_string());
+        }
+
+        let texts = blocks(&message["content"], &mut tools);
+        if texts.is_empty() {
+            continue;
+        }
+        // Injected context isn't what the user asked
+        let is_meta = record.get("isMeta").and_then(|m| m.as_bool()) == Some(true);
+        if role == "user" && !is_meta && transcript.title.is_none() {
+            transcript.title = title(&message["content"]);
+        }
+
+        let timestamp = field("timestamp");
+        let heading = match &timestamp {
+            Some(timestamp) => format!("## {} ({})", role, timestamp),
+            None => format!("## {}", role),
+        };
+        timestamps.extend(timestamp);
+
+        if !transcript.body.is_empty() {
+            transcript.body.push_str("\n\n");
+        }
+        transcript
+            .messages
+            .push(transcript.body.matches('\n').count() as u32 + 1);
+        transcript.body.push_str(&heading);
+        transcript.body.push_str("\n\n");
+        transcript.body.push_str(&texts.join("\n\n"));
+    }
+
+    if transcript.messages.is_empty() {
+        return None;
+    }
+
+    let metadata = &mut transcript.metadata;
+    metadata.insert("messages".to_string(), transcript.messages.len().into());
+    let tools: BTreeSet<String> = tools.into_iter().collect();
+    if !tools.is_empty() {
+        metadata.insert(
+            "tools".to_string(),
+            tools.into_iter().collect::<Vec<_>>().into(),
+        );
+    }
+    if !models.is_empty() {
+        metadata.insert(
+            "models".to_string(),
+            models.into_iter().collect::<Vec<_>>().into(),
+        );
+    }
+    if let (Some(first), Some(last)) = (timestamps.first(), timestamps.last()) {
+        metadata.insert("started".to_string(), first.clone().into());
+        metadata.insert("ended".to_string(), last.clone().into());
+    }
+    if let Some(summary) = summary {
+        metadata.insert("summary".to_string(), summary.into());
+    }
+    Some(transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = r#"{"type":"summary","summary":"Fix flaky login test","leafUuid":"a1"}
+{"type":"user","sessionId":"s-42","cwd":"/work/app","gitBranch":"main","isMeta":true,"timestamp":"2025-03-04T10:00:00Z","message":{"role":"user","content":"<command-name>/clear</command-name>"}}
+{"type":"user","sessionId":"s-42","timestamp":"2025-03-04T10:00:05Z","message":{"role":"user","content":[{"type":"text","text":"The login test fails on CI about once a day.\nCan you find out why?"}]}}
+{"type":"assistant","timestamp":"2025-03-04T10:00:09Z","message":{"role":"assistant","model":"model-a","content":[{"type":"thinking","thinking":"secret plan"},{"type":"text","text":"Let me look at the test."},{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test login","timeout":60000}}]}}
+{"type":"user","timestamp":"2025-03-04T10:00:20Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","is_error":true,"content":[{"type":"text","text":"test login ... FAILED\nsession cookie expired"}]}]}}
+{"type":"assistant","timestamp":"2025-03-04T10:00:31Z","message":{"role":"assistant","model":"model-a","content":[{"type":"thinking","thinking":"only thinking"}]}}
+not json
+{"role":"assistant","content":"The cookie lifetime is shorter than the test's retry window."}
+"#;
+
+    #[test]
+    fn test_messages_in_order() {
+        let text = extract(SESSION).unwrap();
+
+        assert_eq!(
+            text.body,
+            "## user (2025-03-04T10:00:00Z)\n\n<command-name>/clear</command-name>\n\n\
+             ## user (2025-03-04T10:00:05Z)\n\nThe login test fails on CI about once a day.\nCan you find out why?\n\n\
+             ## assistant (2025-03-04T10:00:09Z)\n\nLet me look at the test.\n\nTool: Bash\ncommand: cargo test login\ntimeout: 60000\n\n\
+             ## user (2025-03-04T10:00:20Z)\n\nError:\ntest login ... FAILED\nsession cookie expired\n\n\
+             ## assistant\n\nThe cookie lifetime is shorter than the test's retry window."
+        );
+        assert_eq!(text.messages, vec![1, 5, 10, 18, 24]);
+        assert_eq!(
+            text.title.as_deref(),
+            Some("The login test fails on CI about once a day.")
+        );
+        assert!(!text.body.contains("secret plan"));
+
+        assert_eq!(text.metadata["messages"], 5);
+        assert_eq!(text.metadata["tools"], serde_json::json!(["Bash"]));
+        assert_eq!(text.metadata["models"], serde_json::json!(["model-a"]));
+        assert_eq!(text.metadata["session_id"], "s-42");
+        assert_eq!(text.metadata["git_branch"], "main");
+        assert_eq!(text.metadata["started"], "2025-03-04T10:00:00Z");
+        assert_eq!(text.metadata["ended"], "2025-03-04T10:00:20Z");
+        assert_eq!(text.metadata["summary"], "Fix flaky login test");
+    }
+
+    #[test]
+    fn test_not_a_transcript() {
+        let log = "{\"level\":\"info\",\"msg\":\"started\"}\n{\"content\":\"hello\"}\n";
+        assert!(!is_transcript(log));
+        assert!(extract(log).is_none());
+        assert!(is_transcript(SESSION));
+    }
+
+    #[test]
+    fn test_long_results_are_truncated() {
+        let output = "x".repeat(MAX_RESULT_CHARS + 10);
+        let line = serde_json::json!({"message": {"role": "user", "content": [
+            {"type": "tool_result", "content": output},
+            {"type": "image", "source": {"data": "iVBORw0KGgo="}}
+        ]}});
+        let text = extract(&line.to_string()).unwrap();
+
+        assert!(text.body.ends_with("x…"));
+        assert!(text.body.len() < MAX_RESULT_CHARS + 50);
+        assert_eq!(text.title, None);
+    }
+}
@@ -0,0 +1,209 @@
+//! Minimal XML event reader
+//!
+//! Enough XML for document formats: start, end and empty-element tags with
+//! attributes, and entity-decoded text (CDATA included). Comments,
+//! processing instructions and doctypes are skipped; namespaces are left as
+//! prefixes on names.
+
+use super::html::decode_entities;
+use std::collections::HashMap;
+
+/// An XML parse event
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Event {
+    /// Opening tag; `empty` for `<tag/>`, which has no matching `End`
+    Start {
+        name: String,
+        attributes: HashMap<String, String>,
+        empty: bool,
+    },
+    End {
+        name: String,
+    },
+    Text(String),
+}
+
+/// Local part of a (possibly prefixed) name: `w:p` is `p`
+pub(crate) fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Iterator over the events of an XML document
+pub(crate) struct Reader<'a> {
+    xml: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(xml: &'a str) -> Self {
+        // Skip a byte order mark
+        Reader {
+            xml: xml.strip_prefix('\u{feff}').unwrap_or(xml),
+            pos: 0,
+        }
+    }
+}
+
+impl Iterator for Reader<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        loop {
+            let rest = &self.xml[self.pos..];
+            if rest.is_empty() {
+                return None;
+            }
+
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                return Some(Event::Text(decode_entities(&rest[..end])));
+            }
+
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").unwrap_or(cdata.len());
+                self.pos += 9 + (end + 3).min(cdata.len());
+                return Some(Event::Text(cdata[..end].to_string()));
+            }
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                self.pos += 4 + comment.find("-->").map_or(comment.len(), |i| i + 3);
+                continue;
+            }
+            let Some(close) = tag_close(rest) else {
+                self.pos = self.xml.len();
+                return None;
+            };
+            self.pos += close + 1;
+            let tag = &rest[1..close];
+
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                return Some(Event::End {
+                    name: name.trim().to_string(),
+                });
+            }
+
+            let empty = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            return Some(Event::Start {
+                name: tag[..name_end].to_string(),
+                attributes: attributes(&tag[name_end..]),
+                empty,
+            });
+        }
+    }
+}
+
+/// Position of the `>` closing the tag at the start of `rest`
+fn tag_close(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in rest.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn attributes(source: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = source.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim().to_string();
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(end) = after[1..].find(quote) else {
+            break;
+        };
+        attributes.insert(name, decode_entities(&after[1..1 + end]));
+        rest = after[end + 2..].trim_start();
+    }
+    attributes
+}
+
+/// Text content of every element named `name` (by qualified or local name)
+pub(crate) fn element_texts(xml: &str, name: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+    let mut depth = 0usize;
+    for event in Reader::new(xml) {
+        match event {
+            Event::Start {
+                name: tag, empty, ..
+            } => {
+                if !empty {
+                    depth += 1;
+                }
+                if current.is_none() && !empty && (tag == name || local_name(&tag) == name) {
+                    current = Some((String::new(), depth));
+                }
+            }
+            Event::End { .. } => {
+                if let Some((text, start)) = &current {
+                    if *start == depth {
+                        texts.push(text.trim().to_string());
+                        current = None;
+                    }
+                }
+                depth = depth.saturating_sub(1);
+            }
+            Event::Text(text) => {
+                if let Some((buffer, _)) = &mut current {
+                    buffer.push_str(&text);
+                }
+            }
+        }
+    }
+    texts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let xml = r#"<?xml version="1.0"?><!-- c --><w:p a="1 &amp; 2" b='x>y'><w:t>Tom &amp; <![CDATA[<Jerry>]]></w:t><w:br/></w:p>"#;
+        let events: Vec<Event> = Reader::new(xml).collect();
+
+        assert_eq!(
+            events[0],
+            Event::Start {
+                name: "w:p".to_string(),
+                attributes: HashMap::from([
+                    ("a".to_string(), "1 & 2".to_string()),
+                    ("b".to_string(), "x>y".to_string())
+                ]),
+                empty: false,
+            }
+        );
+        assert_eq!(events[2], Event::Text("Tom & ".to_string()));
+        assert_eq!(events[3], Event::Text("<Jerry>".to_string()));
+        assert!(
+            matches!(&events[5], Event::Start { name, empty: true, .. } if local_name(name) == "br")
+        );
+        assert_eq!(
+            events[6],
+            Event::End {
+                name: "w:p".to_string()
+            }
+        );
+        assert_eq!(events.len(), 7);
+    }
+
+    #[test]
+    fn test_element_texts() {
+        let xml = "<cp:coreProperties><dc:title> Annual  report </dc:title><dc:creator>Ana</dc:creator><dc:creator/></cp:coreProperties>";
+        assert_eq!(element_texts(xml, "title"), vec!["Annual  report"]);
+        assert_eq!(element_texts(xml, "dc:creator"), vec!["Ana"]);
+        assert!(element_texts(xml, "subject").is_empty());
+    }
+}
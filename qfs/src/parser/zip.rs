@@ -0,0 +1,324 @@
+//! Minimal ZIP archive reader
+//!
+//! Reads the central directory (including ZIP64 records) and inflates stored
+//! or deflated members, which covers office documents, EPUBs and ordinary
+//! `.zip` files. Encrypted members and other compression methods are
+//! reported as errors.
+
+use crate::error::{Error, Result};
+use flate2::read::DeflateDecoder;
+use std::io::Read;
+
+/// Largest member we inflate, to bound memory on zip bombs
+pub(crate) const MAX_MEMBER_SIZE: u64 = 64 * 1024 * 1024;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+
+/// A member of a ZIP archive
+#[derive(Debug, Clone)]
+pub(crate) struct ZipEntry {
+    /// Path inside the archive, `/`-separated
+    pub name: String,
+    /// Uncompressed size
+    pub size: u64,
+    compressed_size: u64,
+    method: u16,
+    flags: u16,
+    header_offset: u64,
+}
+
+/// A ZIP archive held in memory
+pub(crate) struct ZipArchive<'a> {
+    data: &'a [u8],
+    entries: Vec<ZipEntry>,
+}
+
+/// Whether `data` starts like a ZIP archive
+pub(crate) fn is_zip(data: &[u8]) -> bool {
+    data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06")
+}
+
+/// `len` bytes at `pos`; offsets read from the archive may be anything
+fn bytes_at(data: &[u8], pos: usize, len: usize) -> Option<&[u8]> {
+    data.get(pos..pos.checked_add(len)?)
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes_at(data, pos, 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes_at(data, pos, 4)?.try_into().ok()?))
+}
+
+fn u64_at(data: &[u8], pos: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes_at(data, pos, 8)?.try_into().ok()?))
+}
+
+fn corrupt(what: &str) -> Error {
+    Error::ParseError(format!("corrupt ZIP archive: {}", what))
+}
+
+impl ZipEntry {
+    /// Whether the entry is a directory rather than a file
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+impl<'a> ZipArchive<'a> {
+    /// Read the central directory of `data`
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        // The end record sits within the last 64 KiB (its comment is at most 65535 bytes)
+        let search_start = data.len().saturating_sub(22 + 0xFFFF);
+        let eocd = (search_start..data.len().saturating_sub(21))
+            .rev()
+            .find(|&pos| u32_at(data, pos) == Some(END_OF_CENTRAL_DIRECTORY))
+            .ok_or_else(|| corrupt("no end of central directory"))?;
+
+        let mut count = u16_at(data, eocd + 10).ok_or_else(|| corrupt("short end record"))? as u64;
+        let mut offset = u32_at(data, eocd + 16).ok_or_else(|| corrupt("short end record"))? as u64;
+
+        if offset == 0xFFFF_FFFF || count == 0xFFFF {
+            let locator = eocd
+                .checked_sub(20)
+                .filter(|&pos| u32_at(data, pos) == Some(ZIP64_LOCATOR))
+                .ok_or_else(|| corrupt("missing ZIP64 locator"))?;
+            let record =
+                u64_at(data, locator + 8).ok_or_else(|| corrupt("short ZIP64 locator"))? as usize;
+            if u32_at(data, record) != Some(ZIP64_END_OF_CENTRAL_DIRECTORY) {
+                return Err(corrupt("bad ZIP64 end record"));
+            }
+            count = u64_at(data, record + 32).ok_or_else(|| corrupt("short ZIP64 end record"))?;
+            offset = u64_at(data, record + 48).ok_or_else(|| corrupt("short ZIP64 end record"))?;
+        }
+
+        let mut entries = Vec::new();
+        let mut pos = offset as usize;
+        for _ in 0..count {
+            if u32_at(data, pos) != Some(CENTRAL_HEADER) {
+                return Err(corrupt("bad central directory entry"));
+            }
+            let field = |at: usize| {
+                u32_at(data, pos + at).ok_or_else(|| corrupt("short central directory"))
+            };
+            let short = |at: usize| {
+                u16_at(data, pos + at).ok_or_else(|| corrupt("short central directory"))
+            };
+
+            let flags = short(8)?;
+            let method = short(10)?;
+            let mut compressed_size = field(20)? as u64;
+            let mut size = field(24)? as u64;
+            let name_len = short(28)? as usize;
+            let extra_len = short(30)? as usize;
+            let comment_len = short(32)? as usize;
+            let mut header_offset = field(42)? as u64;
+
+            let name_start = pos
+                .checked_add(46)
+                .ok_or_else(|| corrupt("short central directory"))?;
+            let name =
+                bytes_at(data, name_start, name_len).ok_or_else(|| corrupt("short entry name"))?;
+            let name = String::from_utf8_lossy(name).replace('\\', "/");
+
+            // ZIP64 sizes and offset follow in the extra field, in this order
+            let extra = bytes_at(data, name_start + name_len, extra_len).unwrap_or(&[]);
+            let mut at = 0;
+            while at + 4 <= extra.len() {
+                let id = u16_at(extra, at).unwrap_or(0);
+                let len = u16_at(extra, at + 2).unwrap_or(0) as usize;
+                if id == 0x0001 {
+                    let mut value = at + 4;
+                    for slot in [&mut size, &mut compressed_size, &mut header_offset] {
+                        if *slot == 0xFFFF_FFFF {
+                            if let Some(v) = u64_at(extra, value) {
+                                *slot = v;
+                            }
+                            value += 8;
+                        }
+                    }
+                }
+                at += 4 + len;
+            }
+
+            entries.push(ZipEntry {
+                name,
+                size,
+                compressed_size,
+                method,
+                flags,
+                header_offset,
+            });
+            pos = name_start + name_len + extra_len + comment_len;
+        }
+
+        Ok(ZipArchive { data, entries })
+    }
+
+    /// Members in central directory order
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Find a member by exact path
+    pub fn entry(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Read a member by path, `None` if absent
+    pub fn read_name(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        self.entry(name).map(|entry| self.read(entry)).transpose()
+    }
+
+    /// Read and decompress a member
+    pub fn read(&self, entry: &ZipEntry) -> Result<Vec<u8>> {
+        if entry.flags & 1 != 0 {
+            return Err(Error::ParseError(format!("{} is encrypted", entry.name)));
+        }
+        if entry.size > MAX_MEMBER_SIZE {
+            return Err(Error::ParseError(format!(
+                "{} is too large ({} bytes)",
+                entry.name, entry.size
+            )));
+        }
+
+        let header = entry.header_offset as usize;
+        if u32_at(self.data, header) != Some(LOCAL_HEADER) {
+            return Err(corrupt("bad local header"));
+        }
+        let name_len =
+            u16_at(self.data, header + 26).ok_or_else(|| corrupt("short local header"))? as usize;
+        let extra_len =
+            u16_at(self.data, header + 28).ok_or_else(|| corrupt("short local header"))? as usize;
+        let start = header
+            .checked_add(30 + name_len + extra_len)
+            .ok_or_else(|| corrupt("short local header"))?;
+        let compressed = self
+            .data
+            .get(start..start.saturating_add(entry.compressed_size as usize))
+            .ok_or_else(|| corrupt("member data past end of archive"))?;
+
+        match entry.method {
+            0 => Ok(compressed.to_vec()),
+            8 => {
+                let mut out = Vec::with_capacity(entry.size as usize);
+                DeflateDecoder::new(compressed)
+                    .take(MAX_MEMBER_SIZE)
+                    .read_to_end(&mut out)
+                    .map_err(|e| Error::ParseError(format!("{}: {}", entry.name, e)))?;
+                Ok(out)
+            }
+            method => Err(Error::ParseError(format!(
+                "{} uses unsupported compression method {}",
+                entry.name, method
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use std::io::Write;
+
+    /// Build a ZIP archive from `(name, data, deflate)` members
+    pub(crate) fn build_zip(members: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data, deflate) in members {
+            let stored = if *deflate {
+                let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                data.to_vec()
+            };
+            let method: u16 = if *deflate { 8 } else { 0 };
+            let offset = out.len() as u32;
+
+            out.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0]);
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 8]); // time, date, crc (unchecked)
+            out.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&stored);
+
+            central.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            central.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let central_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(members.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    #[test]
+    fn test_read_stored_and_deflated_members() {
+        let data = build_zip(&[
+            ("mimetype", b"application/epub+zip", false),
+            ("docs/", b"", false),
+            ("docs/readme.md", b"# Readme\n\nHello, hello, hello.", true),
+        ]);
+        assert!(is_zip(&data));
+
+        let archive = ZipArchive::new(&data).unwrap();
+        let names: Vec<&str> = archive.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["mimetype", "docs/", "docs/readme.md"]);
+
+        assert_eq!(
+            archive.read_name("mimetype").unwrap().unwrap(),
+            b"application/epub+zip"
+        );
+        assert_eq!(
+            archive.read_name("docs/readme.md").unwrap().unwrap(),
+            b"# Readme\n\nHello, hello, hello."
+        );
+        assert!(archive.read_name("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_corrupt_archives_are_errors() {
+        assert!(ZipArchive::new(b"PK\x03\x04 not really a zip").is_err());
+
+        let mut data = build_zip(&[("a.txt", b"text", true)]);
+        // Point the member past the end of the data
+        let central = data.len() - 22 - 46 - 5;
+        data[central + 20..central + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        let archive = ZipArchive::new(&data).unwrap();
+        assert!(archive.read(&archive.entries()[0]).is_err());
+
+        // A ZIP64 locator pointing at the end of the address space
+        let mut data = ZIP64_LOCATOR.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(u64::MAX - 2).to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0, 0]);
+        assert!(ZipArchive::new(&data).is_err());
+    }
+}
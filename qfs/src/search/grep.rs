@@ -0,0 +1,275 @@
+//! Literal and regex grep over indexed content
+//!
+//! FTS tokenization drops punctuation, so exact strings like
+//! `Error::EmbeddingsRequired` or `foo_bar(` can't be found with BM25.
+//! Grep narrows candidates in SQLite (substring match on the stored content
+//! for the literals every match must contain, taken from the start or end of
+//! the pattern), then scans each candidate line by line.
+
+use super::Searcher;
+use crate::error::{Error, Result};
+use crate::store::DocumentFilter;
+use regex::{Regex, RegexBuilder};
+use regex_syntax::hir::literal::{ExtractKind, Extractor};
+
+/// Most alternative literals a pattern is narrowed by in SQLite
+const MAX_PREFILTER_LITERALS: usize = 16;
+
+/// Grep options
+#[derive(Debug, Clone)]
+pub struct GrepOptions {
+    /// Treat the pattern as a regular expression instead of a literal string
+    pub regex: bool,
+    /// Case-insensitive matching
+    pub ignore_case: bool,
+    /// Lines of context to include before and after each match
+    pub context: usize,
+    /// Maximum number of matching lines to return
+    pub limit: usize,
+    /// Filter by collection
+    pub collection: Option<String>,
+    /// Filter documents modified on or after this date (ISO 8601 format)
+    pub from_date: Option<String>,
+    /// Filter documents modified on or before this date (ISO 8601 format)
+    pub to_date: Option<String>,
+}
+
+impl Default for GrepOptions {
+    fn default() -> Self {
+        GrepOptions {
+            regex: false,
+            ignore_case: false,
+            context: 0,
+            limit: 100,
+            collection: None,
+            from_date: None,
+            to_date: None,
+        }
+    }
+}
+
+/// A single matching line
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrepMatch {
+    /// File path (collection/relative_path)
+    pub path: String,
+    /// Collection name
+    pub collection: String,
+    /// Short document ID (first 6 chars of hash)
+    pub docid: String,
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Column of the first match on the line (1-indexed, in characters)
+    pub column: usize,
+    /// The matching line
+    pub text: String,
+    /// Lines preceding the match
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    /// Lines following the match
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+impl Searcher<'_> {
+    /// Search indexed content for a literal string or regular expression
+    pub async fn grep(&self, pattern: &str, options: &GrepOptions) -> Result<Vec<GrepMatch>> {
+        if pattern.is_empty() {
+            return Err(Error::InvalidQuery("Grep pattern is empty".to_string()));
+        }
+
+        let source = if options.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let matcher = RegexBuilder::new(&source)
+            .case_insensitive(options.ignore_case)
+            .build()
+            .map_err(|e| Error::InvalidQuery(format!("Invalid regex: {}", e)))?;
+
+        let literals = required_literals(&source, options.ignore_case);
+
+        let candidates = self
+            .store
+            .grep_candidates(
+                &literals,
+                options.ignore_case,
+                &DocumentFilter::new(
+                    options.collection.as_deref(),
+                    options.from_date.as_deref(),
+                    options.to_date.as_deref(),
+                ),
+            )
+            .await?;
+
+        let mut matches = Vec::new();
+
+        for candidate in candidates {
+            if matches.len() >= options.limit {
+                break;
+            }
+
+            if is_binary_type(&candidate.content_type) {
+                continue;
+            }
+
+            let Some(text) = self.store.get_text(&candidate.hash).await? else {
+                continue;
+            };
+
+            let path = format!("{}/{}", candidate.collection, candidate.path);
+            let docid = format!("#{}", crate::store::get_docid(&candidate.hash));
+
+            for m in grep_text(&text, &matcher, options.context) {
+                if matches.len() >= options.limit {
+                    break;
+                }
+                matches.push(GrepMatch {
+                    path: path.clone(),
+                    collection: candidate.collection.clone(),
+                    docid: docid.clone(),
+                    ..m
+                });
+            }
+        }
+
+        Ok(matches)
+    }
+}
+
+/// Substrings one of which every match of the regex `source` contains, or
+/// none if it can't be narrowed that way.
+///
+/// Literals are extracted from both the start and the end of the pattern,
+/// keeping whichever set has the longer shortest literal. SQLite's lower()
+/// only folds ASCII, so case-insensitive patterns need ASCII literals, and
+/// ones without `s` or `k`, which Unicode folds with `ſ` and the Kelvin sign.
+fn required_literals(source: &str, ignore_case: bool) -> Vec<String> {
+    let Ok(hir) = regex_syntax::Parser::new().parse(source) else {
+        return Vec::new();
+    };
+    let best = [ExtractKind::Prefix, ExtractKind::Suffix]
+        .into_iter()
+        .filter_map(|kind| {
+            let seq = Extractor::new().kind(kind).extract(&hir);
+            let literals = seq.literals()?;
+            let shortest = seq.min_literal_len()?;
+            (shortest > 0 && literals.len() <= MAX_PREFILTER_LITERALS)
+                .then(|| (shortest, literals.to_vec()))
+        })
+        .max_by_key(|(shortest, _)| *shortest);
+    let Some((_, literals)) = best else {
+        return Vec::new();
+    };
+
+    let literals: Option<Vec<String>> = literals
+        .iter()
+        .map(|literal| String::from_utf8(literal.as_bytes().to_vec()).ok())
+        .collect();
+    match literals {
+        Some(literals) if !ignore_case || literals.iter().all(|l| folds_as_ascii(l)) => literals,
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `literal` matches case-insensitively only ASCII text
+fn folds_as_ascii(literal: &str) -> bool {
+    literal.is_ascii() && !literal.contains(['s', 'S', 'k', 'K'])
+}
+
+/// Whether a MIME type denotes binary content
+fn is_binary_type(content_type: &str) -> bool {
+    content_type.starts_with("application/octet")
+        || content_type.starts_with("image/")
+        || content_type.starts_with("audio/")
+        || content_type.starts_with("video/")
+}
+
+/// Find matching lines in `text`, leaving document fields empty
+fn grep_text(text: &str, matcher: &Regex, context: usize) -> Vec<GrepMatch> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut matches = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some(m) = matcher.find(line) else {
+            continue;
+        };
+
+        let before_start = i.saturating_sub(context);
+        let after_end = (i + 1 + context).min(lines.len());
+
+        matches.push(GrepMatch {
+            path: String::new(),
+            collection: String::new(),
+            docid: String::new(),
+            line: i + 1,
+            column: line[..m.start()].chars().count() + 1,
+            text: line.to_string(),
+            before: lines[before_start..i]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            after: lines[i + 1..after_end]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+        });
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grep_text_literal() {
+        let text = "fn main() {\n    foo_bar(1);\n}\n";
+        let matcher = Regex::new(&regex::escape("foo_bar(")).unwrap();
+
+        let matches = grep_text(text, &matcher, 1);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, 2);
+        assert_eq!(matches[0].column, 5);
+        assert_eq!(matches[0].text, "    foo_bar(1);");
+        assert_eq!(matches[0].before, vec!["fn main() {"]);
+        assert_eq!(matches[0].after, vec!["}"]);
+    }
+
+    #[test]
+    fn test_required_literals() {
+        let literals = |pattern: &str| required_literals(pattern, false);
+
+        assert_eq!(literals(&regex::escape("foo_bar(")), vec!["foo_bar("]);
+        assert_eq!(literals(r"fn \w+_test"), vec!["_test"]);
+        assert_eq!(literals(r"fn \w+\("), vec!["fn "]);
+        assert_eq!(literals(r"\w+Error::"), vec!["Error::"]);
+        let mut alternatives = literals("TODO|FIXME");
+        alternatives.sort();
+        assert_eq!(alternatives, vec!["FIXME", "TODO"]);
+
+        // Patterns that can match anywhere aren't narrowed
+        assert!(literals(r"\d+").is_empty());
+        assert!(literals("a?").is_empty());
+        assert!(required_literals("größe", true).is_empty());
+        assert!(required_literals("secret", true).is_empty());
+        assert!(required_literals("KEY", true).is_empty());
+        assert_eq!(required_literals("TODO", true), vec!["TODO"]);
+        assert_eq!(required_literals("Größe", false), vec!["Größe"]);
+    }
+
+    #[test]
+    fn test_grep_text_context_at_edges() {
+        let matcher = Regex::new("one|three").unwrap();
+        let matches = grep_text("one\ntwo\nthree", &matcher, 2);
+
+        assert_eq!(matches.len(), 2);
+        assert!(matches[0].before.is_empty());
+        assert_eq!(matches[0].after, vec!["two", "three"]);
+        assert_eq!(matches[1].before, vec!["one", "two"]);
+        assert!(matches[1].after.is_empty());
+    }
+}
@@ -1,13 +1,28 @@
 //! Search functionality for QFS
 //!
-//! Provides BM25, vector, and hybrid search across indexed documents.
+//! Provides BM25, vector, and hybrid search across indexed documents,
+//! plus literal/regex grep over raw content.
+
+mod grep;
+mod ranking;
+mod snippet;
+
+pub use grep::{GrepMatch, GrepOptions};
+pub(crate) use ranking::RankingFeatures;
+pub use ranking::{CollectionRanking, RankingOptions};
+pub use snippet::Snippet;
 
 use crate::error::{Error, Result};
-use crate::store::Store;
-use std::collections::HashMap;
+use crate::store::{DocumentFilter, FacetRow, Store, VectorSearchPath};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Candidate multiplier when ranking signals may reorder results
+const RANKING_OVERFETCH: usize = 3;
 
 /// Search mode
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SearchMode {
     /// BM25 full-text search (default)
     #[default]
@@ -31,8 +46,19 @@ impl std::str::FromStr for SearchMode {
     }
 }
 
+impl std::fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SearchMode::Bm25 => "bm25",
+            SearchMode::Vector => "vector",
+            SearchMode::Hybrid => "hybrid",
+        })
+    }
+}
+
 /// Search options
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct SearchOptions {
     /// Search mode
     pub mode: SearchMode,
@@ -42,12 +68,41 @@ pub struct SearchOptions {
     pub min_score: f64,
     /// Filter by collection
     pub collection: Option<String>,
+    /// Additional collections to include (combined with `collection`)
+    pub collections: Vec<String>,
+    /// Collections to leave out
+    pub exclude_collections: Vec<String>,
+    /// Only these file extensions (e.g. `md`, `.rs`)
+    pub file_types: Vec<String>,
+    /// Only these MIME types or classes (e.g. `text/markdown`, `image/*`, `text`)
+    pub mime_types: Vec<String>,
+    /// Only paths starting with this prefix, relative to the collection root
+    pub path_prefix: Option<String>,
+    /// Only paths matching this SQLite GLOB, relative to the collection root
+    pub path_glob: Option<String>,
     /// Include binary files in results
     pub include_binary: bool,
     /// Filter documents modified on or after this date (ISO 8601 format)
     pub from_date: Option<String>,
     /// Filter documents modified on or before this date (ISO 8601 format)
     pub to_date: Option<String>,
+    /// Only documents whose metadata has these `(key, value)` pairs (values
+    /// match as case-insensitive substrings)
+    pub metadata: Vec<(String, String)>,
+    /// Compute facet counts alongside results
+    pub facets: bool,
+    /// Retry with spelling-corrected terms when BM25 finds nothing
+    pub fuzzy: bool,
+    /// Return passages (chunks with line ranges) instead of whole documents.
+    /// Requires chunks from `qfs embed`; falls back to documents otherwise.
+    pub passages: bool,
+    /// Number of line snippets per BM25 result (more than 1 fills `snippets`)
+    pub snippets: usize,
+    /// Maximum characters per line snippet
+    pub snippet_size: usize,
+    /// Recency, length, path-depth and collection-weight signals. Unset
+    /// signals fall back to the saved collection profiles.
+    pub ranking: RankingOptions,
 }
 
 impl Default for SearchOptions {
@@ -57,9 +112,46 @@ impl Default for SearchOptions {
             limit: 20,
             min_score: 0.0,
             collection: None,
+            collections: Vec::new(),
+            exclude_collections: Vec::new(),
+            file_types: Vec::new(),
+            mime_types: Vec::new(),
+            path_prefix: None,
+            path_glob: None,
             include_binary: false,
             from_date: None,
             to_date: None,
+            metadata: Vec::new(),
+            facets: false,
+            fuzzy: true,
+            passages: false,
+            snippets: 1,
+            snippet_size: 160,
+            ranking: RankingOptions::default(),
+        }
+    }
+}
+
+impl SearchOptions {
+    /// The document filter these options describe
+    pub fn filter(&self) -> DocumentFilter {
+        let mut collections: Vec<String> = self.collection.iter().cloned().collect();
+        for collection in &self.collections {
+            if !collections.contains(collection) {
+                collections.push(collection.clone());
+            }
+        }
+
+        DocumentFilter {
+            collections,
+            exclude_collections: self.exclude_collections.clone(),
+            file_types: self.file_types.clone(),
+            mime_types: self.mime_types.clone(),
+            path_prefix: self.path_prefix.clone(),
+            path_glob: self.path_glob.clone(),
+            from_date: self.from_date.clone(),
+            to_date: self.to_date.clone(),
+            metadata: self.metadata.clone(),
         }
     }
 }
@@ -94,6 +186,12 @@ pub struct SearchResult {
     /// Line number where match starts
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line_start: Option<u32>,
+    /// Line number where match ends (inclusive, passage results only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_end: Option<u32>,
+    /// Best matching lines (BM25, when more than one snippet is requested)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<Snippet>,
     /// Collection name
     pub collection: String,
     /// Document title
@@ -105,31 +203,351 @@ pub struct SearchResult {
     /// Chunk index for vector search results
     #[serde(skip_serializing_if = "Option::is_none")]
     pub chunk_index: Option<i32>,
+    /// Page the match is on (paged formats such as PDF)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
     /// Context description for this document's location
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<String>,
 }
 
+/// A single facet value with its hit count
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FacetCount {
+    /// Facet value (collection name, file type, path prefix or month)
+    pub value: String,
+    /// Number of matching documents
+    pub count: usize,
+}
+
+/// Facet counts computed over a search candidate set
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Facets {
+    /// Total number of distinct matching documents
+    pub total: usize,
+    /// Hits per collection
+    pub collections: Vec<FacetCount>,
+    /// Hits per file type (extension)
+    pub file_types: Vec<FacetCount>,
+    /// Hits per top-level path prefix (collection/dir)
+    pub path_prefixes: Vec<FacetCount>,
+    /// Modified-date histogram, bucketed by month (YYYY-MM)
+    pub modified: Vec<FacetCount>,
+}
+
+impl Facets {
+    /// Build facet counts from document rows, counting each document once
+    pub fn from_rows(rows: &[FacetRow]) -> Self {
+        let mut seen = HashSet::new();
+        let mut collections: HashMap<String, usize> = HashMap::new();
+        let mut file_types: HashMap<String, usize> = HashMap::new();
+        let mut path_prefixes: HashMap<String, usize> = HashMap::new();
+        let mut modified: HashMap<String, usize> = HashMap::new();
+
+        for row in rows {
+            if !seen.insert(row.id) {
+                continue;
+            }
+
+            *collections.entry(row.collection.clone()).or_default() += 1;
+
+            let file_type = if row.file_type.is_empty() {
+                "(none)".to_string()
+            } else {
+                row.file_type.clone()
+            };
+            *file_types.entry(file_type).or_default() += 1;
+
+            let prefix = match row.path.split_once('/') {
+                Some((dir, _)) => format!("{}/{}/", row.collection, dir),
+                None => format!("{}/", row.collection),
+            };
+            *path_prefixes.entry(prefix).or_default() += 1;
+
+            let month = row.modified_at.get(..7).unwrap_or(&row.modified_at);
+            *modified.entry(month.to_string()).or_default() += 1;
+        }
+
+        // Date buckets read chronologically; other facets by descending count
+        let mut modified = sorted_counts(modified);
+        modified.sort_by(|a, b| a.value.cmp(&b.value));
+
+        Facets {
+            total: seen.len(),
+            collections: sorted_counts(collections),
+            file_types: sorted_counts(file_types),
+            path_prefixes: sorted_counts(path_prefixes),
+            modified,
+        }
+    }
+}
+
+/// Sort facet counts by descending count, then by value
+fn sorted_counts(counts: HashMap<String, usize>) -> Vec<FacetCount> {
+    let mut counts: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
+
+/// A "did you mean" correction for a query term not found in the index
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suggestion {
+    /// Query term as typed (lowercased)
+    pub term: String,
+    /// Closest indexed term
+    pub suggestion: String,
+    /// Edit distance between the two
+    pub distance: usize,
+    /// Number of documents containing the suggested term
+    pub doc_count: i64,
+}
+
+/// Search results together with optional facet counts and spelling hints
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResponse {
+    /// Ranked search results
+    pub results: Vec<SearchResult>,
+    /// Facet counts (present when `SearchOptions::facets` is set)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<Facets>,
+    /// Spelling suggestions for query terms that matched nothing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<Suggestion>,
+    /// Corrected query actually used for `results`, if the fuzzy fallback ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corrected_query: Option<String>,
+    /// Whether vector results came from the native index or the legacy scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_search: Option<VectorSearchPath>,
+}
+
+impl SearchResponse {
+    /// Whether the response carries anything besides the result list
+    pub fn has_details(&self) -> bool {
+        self.facets.is_some() || !self.suggestions.is_empty() || self.corrected_query.is_some()
+    }
+}
+
 /// Searcher for QFS
 pub struct Searcher<'a> {
     store: &'a Store,
+    /// Path taken by the most recent vector search
+    vector_path: Mutex<Option<VectorSearchPath>>,
+    /// Query the most recent fuzzy BM25 search was corrected to
+    corrected_query: Mutex<Option<String>>,
 }
 
 impl<'a> Searcher<'a> {
     /// Create a new searcher
     pub fn new(store: &'a Store) -> Self {
-        Searcher { store }
+        Searcher {
+            store,
+            vector_path: Mutex::new(None),
+            corrected_query: Mutex::new(None),
+        }
+    }
+
+    /// Which path (native index or legacy scan) served the most recent
+    /// vector or hybrid search, if any
+    pub fn last_vector_path(&self) -> Option<VectorSearchPath> {
+        *self.vector_path.lock().unwrap()
+    }
+
+    /// The corrected query the most recent BM25 or hybrid search fell back
+    /// to when the query as typed matched nothing, if it did
+    pub fn last_corrected_query(&self) -> Option<String> {
+        self.corrected_query.lock().unwrap().clone()
     }
 
     /// Search for documents
     pub async fn search(&self, query: &str, options: SearchOptions) -> Result<Vec<SearchResult>> {
         match options.mode {
-            SearchMode::Bm25 => self.search_bm25(query, &options).await,
+            SearchMode::Bm25 => {
+                let (fetch, profiles) = self.ranking_fetch(&options).await?;
+                let results = self.search_bm25_fuzzy(query, &fetch).await?;
+                self.apply_ranking(results, &options, profiles, true).await
+            }
             SearchMode::Vector => self.search_vector(query, &options).await,
             SearchMode::Hybrid => self.search_hybrid(query, &options).await,
         }
     }
 
+    /// Search for documents, computing facet counts when `options.facets` is set.
+    ///
+    /// When nothing matches, the response carries spelling suggestions and,
+    /// for BM25 with `options.fuzzy`, the results of the corrected query.
+    pub async fn search_detailed(
+        &self,
+        query: &str,
+        options: SearchOptions,
+    ) -> Result<SearchResponse> {
+        let (fetch, profiles) = self.ranking_fetch(&options).await?;
+        let mut results = match options.mode {
+            SearchMode::Bm25 => self.search_bm25(query, &fetch).await?,
+            _ => self.search(query, options.clone()).await?,
+        };
+
+        let mut suggestions = Vec::new();
+        let mut corrected_query = None;
+        if results.is_empty() && options.mode != SearchMode::Vector {
+            suggestions = self.suggest(query).await?;
+            if options.fuzzy && options.mode == SearchMode::Bm25 {
+                if let Some(corrected) = apply_suggestions(query, &suggestions) {
+                    results = self.search_bm25(&corrected, &fetch).await?;
+                    corrected_query = Some(corrected);
+                }
+            }
+        }
+
+        if options.mode == SearchMode::Bm25 {
+            results = self
+                .apply_ranking(results, &options, profiles, true)
+                .await?;
+        }
+
+        let facets = if options.facets {
+            let facet_query = corrected_query.as_deref().unwrap_or(query);
+            Some(self.facets(facet_query, &results, &options).await?)
+        } else {
+            None
+        };
+
+        Ok(SearchResponse {
+            results,
+            facets,
+            suggestions,
+            corrected_query,
+            vector_search: self.last_vector_path(),
+        })
+    }
+
+    /// Suggest corrections for query terms that match no documents.
+    ///
+    /// Candidates come from the trigram vocabulary index and are ranked by
+    /// edit distance, then by how many documents contain them.
+    pub async fn suggest(&self, query: &str) -> Result<Vec<Suggestion>> {
+        let mut suggestions: Vec<Suggestion> = Vec::new();
+
+        for term in query.split_whitespace().map(clean_term) {
+            let term = term.to_lowercase();
+            let len = term.chars().count();
+            if len < 3 || suggestions.iter().any(|s| s.term == term) {
+                continue;
+            }
+
+            if self
+                .store
+                .count_bm25_matches(&format!("\"{}\"*", term))
+                .await?
+                > 0
+            {
+                continue;
+            }
+
+            // Allow one edit for short terms, two for longer ones
+            let max_distance = if len <= 4 { 1 } else { 2 };
+            let mut best: Option<Suggestion> = None;
+
+            for candidate in self.store.spelling_candidates(&term, 50).await? {
+                let distance = edit_distance(&term, &candidate);
+                if distance == 0 || distance > max_distance {
+                    continue;
+                }
+                if best.as_ref().is_some_and(|b| distance > b.distance) {
+                    continue;
+                }
+
+                let doc_count = self
+                    .store
+                    .count_bm25_matches(&format!("\"{}\"", candidate))
+                    .await?;
+                if doc_count == 0 {
+                    continue;
+                }
+
+                let better = match &best {
+                    None => true,
+                    Some(b) => distance < b.distance || doc_count > b.doc_count,
+                };
+                if better {
+                    best = Some(Suggestion {
+                        term: term.clone(),
+                        suggestion: candidate,
+                        distance,
+                        doc_count,
+                    });
+                }
+            }
+
+            suggestions.extend(best);
+        }
+
+        Ok(suggestions)
+    }
+
+    /// Compute facet counts for a query.
+    ///
+    /// The candidate set is every document matching the BM25 query under the
+    /// same filters (for BM25 and hybrid modes), plus the documents in
+    /// `results` (which carry the vector candidates for vector/hybrid modes).
+    pub async fn facets(
+        &self,
+        query: &str,
+        results: &[SearchResult],
+        options: &SearchOptions,
+    ) -> Result<Facets> {
+        let mut rows = Vec::new();
+
+        if options.mode != SearchMode::Vector {
+            let fts_query = sanitize_fts_query(query);
+            rows.extend(
+                self.store
+                    .facet_rows_bm25(&fts_query, &options.filter(), options.include_binary)
+                    .await?,
+            );
+        }
+
+        let known: HashSet<i64> = rows.iter().map(|r| r.id).collect();
+        let missing: Vec<i64> = results
+            .iter()
+            .map(|r| r.id)
+            .filter(|id| !known.contains(id))
+            .collect();
+        if !missing.is_empty() {
+            rows.extend(self.store.facet_rows_for_ids(&missing).await?);
+        }
+
+        Ok(Facets::from_rows(&rows))
+    }
+
+    /// BM25 search that retries with spelling corrections when nothing
+    /// matches and `options.fuzzy` is set
+    async fn search_bm25_fuzzy(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        *self.corrected_query.lock().unwrap() = None;
+        let results = self.search_bm25(query, options).await?;
+        if !results.is_empty() || !options.fuzzy {
+            return Ok(results);
+        }
+
+        let suggestions = self.suggest(query).await?;
+        let Some(corrected) = apply_suggestions(query, &suggestions) else {
+            return Ok(results);
+        };
+        let results = self.search_bm25(&corrected, options).await?;
+        *self.corrected_query.lock().unwrap() = Some(corrected);
+        Ok(results)
+    }
+
     /// BM25 full-text search using FTS5
     async fn search_bm25(
         &self,
@@ -142,18 +560,125 @@ impl<'a> Searcher<'a> {
             return Ok(Vec::new());
         }
 
+        if options.passages && self.has_passages(options).await? {
+            return self.search_passages_bm25(query, &fts_query, options).await;
+        }
+
         let rows = self
             .store
             .search_bm25(
                 &fts_query,
-                options.collection.as_deref(),
+                &options.filter(),
+                options.limit,
+                options.include_binary,
+            )
+            .await?;
+
+        Ok(self.bm25_results(rows, options, &query_terms(query)).await)
+    }
+
+    /// Whether any document being searched has passage chunks
+    async fn has_passages(&self, options: &SearchOptions) -> Result<bool> {
+        Ok(self
+            .store
+            .count_chunked_documents(&options.filter())
+            .await?
+            > 0)
+    }
+
+    /// Passage-level BM25 search over chunk FTS. Documents without chunks
+    /// (not embedded yet, or embedded before chunks were indexed) match as
+    /// whole documents, ranked among the passages by BM25 score.
+    async fn search_passages_bm25(
+        &self,
+        query: &str,
+        fts_query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let rows = self
+            .store
+            .search_passages_bm25(
+                fts_query,
+                &options.filter(),
+                options.limit,
+                options.include_binary,
+            )
+            .await?;
+
+        let mut results = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let normalized_score = normalize_bm25_score(row.bm25_score);
+
+            if normalized_score < options.min_score {
+                continue;
+            }
+
+            let name = std::path::Path::new(&row.path)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(&row.path)
+                .to_string();
+
+            let context = self
+                .store
+                .get_all_contexts_for_path(&row.collection, &row.path)
+                .await
+                .ok()
+                .map(|contexts| contexts.join("\n\n"))
+                .filter(|s| !s.is_empty());
+
+            results.push(SearchResult {
+                id: row.id,
+                path: format!("{}/{}", row.collection, row.path),
+                name,
+                mime_type: row.content_type,
+                file_size: row.size,
+                is_binary: false,
+                score: normalized_score,
+                content: None,
+                content_pointer: None,
+                snippet: row.snippet,
+                line_start: Some(row.line_start as u32),
+                line_end: Some(row.line_end as u32),
+                snippets: Vec::new(),
+                collection: row.collection,
+                title: row.title,
+                docid: Some(format!("#{}", crate::store::get_docid(&row.hash))),
+                chunk_index: Some(row.chunk_index),
+                page: None,
+                context,
+            });
+        }
+
+        let unchunked = self
+            .store
+            .search_unchunked_bm25(
+                fts_query,
+                &options.filter(),
                 options.limit,
                 options.include_binary,
-                options.from_date.as_deref(),
-                options.to_date.as_deref(),
             )
             .await?;
+        results.extend(
+            self.bm25_results(unchunked, options, &query_terms(query))
+                .await,
+        );
+        // Normalized BM25: lower is better
+        results.sort_by(|a, b| a.score.total_cmp(&b.score));
+        results.truncate(options.limit);
+
+        Ok(results)
+    }
 
+    /// Convert BM25 rows into search results, applying the score threshold.
+    /// Text results get the line of their best match for `terms`.
+    async fn bm25_results(
+        &self,
+        rows: Vec<crate::store::SearchResultRow>,
+        options: &SearchOptions,
+        terms: &[String],
+    ) -> Vec<SearchResult> {
         let mut results = Vec::with_capacity(rows.len());
 
         for row in rows {
@@ -188,6 +713,16 @@ impl<'a> Searcher<'a> {
                 .map(|contexts| contexts.join("\n\n"))
                 .filter(|s| !s.is_empty());
 
+            let mut snippets = if is_binary {
+                Vec::new()
+            } else {
+                self.line_snippets(&row.hash, terms, options).await
+            };
+            let line_start = snippets.first().map(|s| s.line);
+            if options.snippets <= 1 {
+                snippets.clear();
+            }
+
             results.push(SearchResult {
                 id: row.id,
                 path: format!("{}/{}", row.collection, row.path),
@@ -199,16 +734,94 @@ impl<'a> Searcher<'a> {
                 content: None,
                 content_pointer,
                 snippet: row.snippet,
-                line_start: None,
+                line_start,
+                line_end: None,
+                snippets,
                 collection: row.collection,
                 title: row.title,
                 docid: Some(format!("#{}", crate::store::get_docid(&row.hash))),
                 chunk_index: None,
+                page: None,
                 context,
             });
         }
 
-        Ok(results)
+        results
+    }
+
+    /// Find the best matching lines of a document's stored content
+    async fn line_snippets(
+        &self,
+        hash: &str,
+        terms: &[String],
+        options: &SearchOptions,
+    ) -> Vec<Snippet> {
+        let Ok(Some(text)) = self.store.get_text(hash).await else {
+            return Vec::new();
+        };
+        snippet::find_snippets(&text, terms, options.snippets.max(1), options.snippet_size)
+    }
+
+    /// Find documents similar to an existing one ("more like this").
+    ///
+    /// `reference` is a docid (#abc123) or collection/relative_path. When the
+    /// document has embeddings, the centroid of its chunk vectors is used as
+    /// the query; otherwise falls back to BM25 over its top TF-IDF terms.
+    /// The document itself is never included in the results.
+    pub async fn similar(
+        &self,
+        reference: &str,
+        options: SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let doc = self.store.resolve_document(reference).await?;
+        let embeddings = self.store.get_embeddings(&doc.hash).await?;
+        let (fetch, profiles) = self.ranking_fetch(&options).await?;
+
+        if let Some(centroid) = embedding_centroid(&embeddings) {
+            // Results are per chunk, so over-fetch to fill the limit with distinct documents
+            let vector_options = SearchOptions {
+                limit: (fetch.limit + 1) * 4,
+                ..options.clone()
+            };
+            let candidates = self.vector_results(&centroid, &vector_options).await?;
+
+            let mut seen = HashSet::new();
+            let results = candidates
+                .into_iter()
+                .filter(|r| r.id != doc.id && seen.insert(r.id))
+                .take(fetch.limit)
+                .collect();
+            return self.apply_ranking(results, &options, profiles, false).await;
+        }
+
+        let terms = self.store.top_terms(doc.id, 10).await?;
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let fts_query = terms
+            .iter()
+            .map(|t| format!("\"{}\"", t))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        let rows = self
+            .store
+            .search_bm25(
+                &fts_query,
+                &options.filter(),
+                fetch.limit + 1,
+                options.include_binary,
+            )
+            .await?;
+
+        let results = self
+            .bm25_results(rows, &options, &terms)
+            .await
+            .into_iter()
+            .filter(|r| r.id != doc.id)
+            .collect();
+        self.apply_ranking(results, &options, profiles, true).await
     }
 
     /// Vector semantic search
@@ -219,7 +832,7 @@ impl<'a> Searcher<'a> {
     ) -> Result<Vec<SearchResult>> {
         let embed_count = self
             .store
-            .count_embeddings(options.collection.as_deref())
+            .count_embeddings(options.filter().single_collection())
             .await?;
         if embed_count == 0 {
             return Err(Error::EmbeddingsRequired);
@@ -238,49 +851,34 @@ impl<'a> Searcher<'a> {
         &self,
         query_embedding: &[f32],
         options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let (fetch, profiles) = self.ranking_fetch(options).await?;
+        let results = self.vector_results(query_embedding, &fetch).await?;
+        self.apply_ranking(results, options, profiles, false).await
+    }
+
+    /// Vector search results scored by cosine similarity alone
+    async fn vector_results(
+        &self,
+        query_embedding: &[f32],
+        options: &SearchOptions,
     ) -> Result<Vec<SearchResult>> {
         // Check if embeddings exist
         let embed_count = self
             .store
-            .count_embeddings(options.collection.as_deref())
+            .count_embeddings(options.filter().single_collection())
             .await?;
         if embed_count == 0 {
             return Err(Error::EmbeddingsRequired);
         }
 
-        // Try native vector search first, fall back to legacy if not available
-        // Over-fetch by 3x when date filtering to compensate for post-filtering
-        let fetch_limit = if options.from_date.is_some() || options.to_date.is_some() {
-            options.limit * 3
-        } else {
-            options.limit
-        };
-
-        let vector_results = match self
+        // Native vector search when available, legacy otherwise.
+        // Both apply the document filters before truncating to the limit.
+        let (vector_results, path) = self
             .store
-            .search_vector_native(
-                query_embedding,
-                options.collection.as_deref(),
-                fetch_limit,
-                options.from_date.as_deref(),
-                options.to_date.as_deref(),
-            )
-            .await?
-        {
-            Some(results) => results,
-            None => {
-                // Fall back to legacy search
-                self.store
-                    .search_vector_legacy(
-                        query_embedding,
-                        options.collection.as_deref(),
-                        fetch_limit,
-                        options.from_date.as_deref(),
-                        options.to_date.as_deref(),
-                    )
-                    .await?
-            }
-        };
+            .search_vector(query_embedding, &options.filter(), options.limit)
+            .await?;
+        *self.vector_path.lock().unwrap() = Some(path);
 
         let mut results = Vec::new();
 
@@ -320,10 +918,13 @@ impl<'a> Searcher<'a> {
                 content_pointer: None,
                 snippet: None,
                 line_start: None,
+                line_end: None,
+                snippets: Vec::new(),
                 collection: row.collection,
                 title: row.title,
                 docid: Some(format!("#{}", crate::store::get_docid(&row.hash))),
                 chunk_index: Some(row.chunk_index),
+                page: None,
                 context,
             });
         }
@@ -339,7 +940,7 @@ impl<'a> Searcher<'a> {
     ) -> Result<Vec<SearchResult>> {
         let embed_count = self
             .store
-            .count_embeddings(options.collection.as_deref())
+            .count_embeddings(options.filter().single_collection())
             .await?;
         if embed_count == 0 {
             return Err(Error::EmbeddingsRequired);
@@ -358,47 +959,230 @@ impl<'a> Searcher<'a> {
         query_embedding: &[f32],
         options: &SearchOptions,
     ) -> Result<Vec<SearchResult>> {
+        // With passage chunks, both sides rank chunks, so fuse passage-to-passage
+        let passage_level = self.has_passages(options).await?;
+        let (fetch, profiles) = self.ranking_fetch(options).await?;
+
         let bm25_options = SearchOptions {
-            limit: options.limit * 2,
+            limit: fetch.limit * 2,
+            passages: passage_level,
             ..options.clone()
         };
-        let bm25_results = self.search_bm25(query, &bm25_options).await?;
+        let bm25_results = self.search_bm25_fuzzy(query, &bm25_options).await?;
 
         let vector_options = SearchOptions {
-            limit: options.limit * 2,
+            limit: fetch.limit * 2,
             ..options.clone()
         };
         let vector_results = self
-            .search_vector_with_embedding(query_embedding, &vector_options)
+            .vector_results(query_embedding, &vector_options)
             .await?;
 
-        let fused = reciprocal_rank_fusion(&bm25_results, &vector_results, 60.0);
+        let fused: Vec<SearchResult> = if !passage_level {
+            reciprocal_rank_fusion(&bm25_results, &vector_results, 60.0)
+        } else {
+            // Documents without chunks match BM25 as a whole, so their best
+            // vector passage fuses with the document
+            let whole: HashSet<i64> = bm25_results
+                .iter()
+                .filter(|r| r.chunk_index.is_none())
+                .map(|r| r.id)
+                .collect();
+            let mut seen = HashSet::new();
+            let vector_results: Vec<SearchResult> = vector_results
+                .into_iter()
+                .filter(|r| !whole.contains(&r.id) || seen.insert(r.id))
+                .collect();
+            let fused = fuse_ranked(&bm25_results, &vector_results, 60.0, |r| {
+                (r.id, r.chunk_index.filter(|_| !whole.contains(&r.id)))
+            });
+
+            if options.passages {
+                fused
+            } else {
+                // Roll up to documents: each keeps its best-scoring passage
+                let mut seen = HashSet::new();
+                fused.into_iter().filter(|r| seen.insert(r.id)).collect()
+            }
+        };
+
+        self.apply_ranking(fused, options, profiles, false).await
+    }
+
+    /// Options to fetch candidates with before ranking signals are applied.
+    ///
+    /// When any signal can apply, candidates are over-fetched so boosted
+    /// documents from just past the limit can move up. Returns the collection
+    /// profiles to rank with, or None when ranking is a no-op.
+    async fn ranking_fetch(
+        &self,
+        options: &SearchOptions,
+    ) -> Result<(SearchOptions, Option<HashMap<String, CollectionRanking>>)> {
+        let filter = options.filter();
+        let profiles: HashMap<String, CollectionRanking> = self
+            .store
+            .list_collection_rankings()
+            .await?
+            .into_iter()
+            .filter(|p| filter.collections.is_empty() || filter.collections.contains(&p.collection))
+            .filter(|p| !filter.exclude_collections.contains(&p.collection))
+            .map(|p| (p.collection.clone(), p))
+            .collect();
+
+        if options.ranking.is_empty() && profiles.is_empty() {
+            return Ok((options.clone(), None));
+        }
+
+        let fetch = SearchOptions {
+            limit: options.limit.saturating_mul(RANKING_OVERFETCH),
+            ..options.clone()
+        };
+        Ok((fetch, Some(profiles)))
+    }
+
+    /// Scale scores by the ranking signals, re-sort, truncate to the limit and
+    /// cite pages.
+    ///
+    /// `bm25` results keep the normalized BM25 convention (lower is better):
+    /// the signals scale the raw BM25 magnitude, which is then re-normalized.
+    async fn apply_ranking(
+        &self,
+        mut results: Vec<SearchResult>,
+        options: &SearchOptions,
+        profiles: Option<HashMap<String, CollectionRanking>>,
+        bm25: bool,
+    ) -> Result<Vec<SearchResult>> {
+        let Some(profiles) = profiles else {
+            results.truncate(options.limit);
+            self.cite_pages(&mut results).await?;
+            return Ok(results);
+        };
+
+        let mut ids: Vec<i64> = results.iter().map(|r| r.id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let features = self.store.ranking_features(&ids).await?;
+        let now = chrono::Utc::now();
+
+        for result in &mut results {
+            let Some(doc) = features.get(&result.id) else {
+                continue;
+            };
+            let relative_path = result
+                .path
+                .strip_prefix(&format!("{}/", result.collection))
+                .unwrap_or(&result.path);
+            let factor = ranking::ranking_factor(
+                &options.ranking,
+                profiles.get(&result.collection),
+                &result.collection,
+                relative_path,
+                doc,
+                now,
+            );
+            result.score = if bm25 {
+                ranking::scale_normalized_bm25(result.score, factor)
+            } else {
+                result.score * factor
+            };
+        }
+
+        if bm25 {
+            results.sort_by(|a, b| a.score.total_cmp(&b.score));
+        } else {
+            results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        }
+        results.truncate(options.limit);
+        self.cite_pages(&mut results).await?;
+        Ok(results)
+    }
+
+    /// Set the page of results from paged documents, from the match line or,
+    /// for vector results, the first line of the matching chunk
+    async fn cite_pages(&self, results: &mut [SearchResult]) -> Result<()> {
+        let ids: Vec<i64> = results.iter().map(|r| r.id).collect();
+        let paged = self.store.document_pages(&ids).await?;
+        if paged.is_empty() {
+            return Ok(());
+        }
 
-        Ok(fused.into_iter().take(options.limit).collect())
+        for result in results.iter_mut() {
+            let Some((hash, pages)) = paged.get(&result.id) else {
+                continue;
+            };
+            let line = match (result.line_start, result.chunk_index) {
+                (Some(line), _) => Some(line),
+                (None, Some(chunk)) => self.store.chunk_line_start(hash, chunk).await?,
+                (None, None) => None,
+            };
+            result.page = line.map(|line| page_for_line(pages, line));
+        }
+        Ok(())
     }
 }
 
+/// 1-based page containing `line`, given the line each page starts on
+fn page_for_line(page_starts: &[u32], line: u32) -> u32 {
+    page_starts.partition_point(|&start| start <= line).max(1) as u32
+}
+
+/// Average a document's chunk embeddings into a single query vector
+fn embedding_centroid(embeddings: &[crate::store::EmbeddingRow]) -> Option<Vec<f32>> {
+    let vectors: Vec<Vec<f32>> = embeddings
+        .iter()
+        .map(|e| crate::store::bytes_to_embedding(&e.embedding))
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    let dims = vectors.first()?.len();
+    let mut centroid = vec![0.0f32; dims];
+    let mut count = 0;
+    for vector in vectors.iter().filter(|v| v.len() == dims) {
+        for (c, x) in centroid.iter_mut().zip(vector) {
+            *c += x;
+        }
+        count += 1;
+    }
+
+    for c in centroid.iter_mut() {
+        *c /= count as f32;
+    }
+
+    Some(centroid)
+}
+
 /// Apply Reciprocal Rank Fusion (RRF) to combine two result sets
 fn reciprocal_rank_fusion(
     bm25_results: &[SearchResult],
     vector_results: &[SearchResult],
     k: f64,
 ) -> Vec<SearchResult> {
-    let mut scores: HashMap<i64, (f64, Option<SearchResult>)> = HashMap::new();
-
-    for (rank, result) in bm25_results.iter().enumerate() {
-        let rrf_score = 1.0 / (k + rank as f64 + 1.0);
-        scores.entry(result.id).or_insert((0.0, None)).0 += rrf_score;
-        if scores[&result.id].1.is_none() {
-            scores.get_mut(&result.id).unwrap().1 = Some(result.clone());
-        }
-    }
+    fuse_ranked(bm25_results, vector_results, k, |r| r.id)
+}
 
-    for (rank, result) in vector_results.iter().enumerate() {
-        let rrf_score = 1.0 / (k + rank as f64 + 1.0);
-        scores.entry(result.id).or_insert((0.0, None)).0 += rrf_score;
-        if scores[&result.id].1.is_none() {
-            scores.get_mut(&result.id).unwrap().1 = Some(result.clone());
+/// Reciprocal Rank Fusion over results identified by `key`.
+/// Results sharing a key have their RRF scores summed; the first one seen
+/// (BM25 before vector) is kept as the representative.
+fn fuse_ranked<K, F>(
+    bm25_results: &[SearchResult],
+    vector_results: &[SearchResult],
+    k: f64,
+    key: F,
+) -> Vec<SearchResult>
+where
+    K: std::hash::Hash + Eq,
+    F: Fn(&SearchResult) -> K,
+{
+    let mut scores: HashMap<K, (f64, Option<SearchResult>)> = HashMap::new();
+
+    for results in [bm25_results, vector_results] {
+        for (rank, result) in results.iter().enumerate() {
+            let rrf_score = 1.0 / (k + rank as f64 + 1.0);
+            let entry = scores.entry(key(result)).or_insert((0.0, None));
+            entry.0 += rrf_score;
+            if entry.1.is_none() {
+                entry.1 = Some(result.clone());
+            }
         }
     }
 
@@ -438,10 +1222,7 @@ fn sanitize_fts_query(query: &str) -> String {
     let fts_terms: Vec<String> = terms
         .iter()
         .map(|term| {
-            let clean: String = term
-                .chars()
-                .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
-                .collect();
+            let clean = clean_term(term);
 
             if clean.is_empty() {
                 return String::new();
@@ -459,6 +1240,72 @@ fn sanitize_fts_query(query: &str) -> String {
     fts_terms.join(" AND ")
 }
 
+/// Lowercased query words, split the way the FTS tokenizer splits them
+fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+/// Strip characters that have meaning in FTS5 query syntax
+fn clean_term(term: &str) -> String {
+    term.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
+
+/// Rewrite a query with suggested terms substituted in.
+/// Returns None when no term was replaced.
+fn apply_suggestions(query: &str, suggestions: &[Suggestion]) -> Option<String> {
+    let mut changed = false;
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| {
+            let term = clean_term(word).to_lowercase();
+            match suggestions.iter().find(|s| s.term == term) {
+                Some(s) => {
+                    changed = true;
+                    s.suggestion.clone()
+                }
+                None => word.to_string(),
+            }
+        })
+        .collect();
+
+    changed.then(|| words.join(" "))
+}
+
+/// Damerau-Levenshtein distance (optimal string alignment variant)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (n, m) = (a.len(), b.len());
+
+    let mut d = vec![vec![0usize; m + 1]; n + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=n {
+        for j in 1..=m {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[n][m]
+}
+
 /// Normalize BM25 score to 0-1 range
 pub fn normalize_bm25_score(bm25_score: f64) -> f64 {
     1.0 / (1.0 + bm25_score.abs())
@@ -494,6 +1341,31 @@ mod tests {
         assert_eq!(sanitize_fts_query("@#$%"), "");
     }
 
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kubernets", "kubernetes"), 1);
+        assert_eq!(edit_distance("teh", "the"), 1);
+        assert_eq!(edit_distance("rust", "rust"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_apply_suggestions() {
+        let suggestions = vec![Suggestion {
+            term: "kubernets".to_string(),
+            suggestion: "kubernetes".to_string(),
+            distance: 1,
+            doc_count: 3,
+        }];
+
+        assert_eq!(
+            apply_suggestions("deploy Kubernets", &suggestions),
+            Some("deploy kubernetes".to_string())
+        );
+        assert_eq!(apply_suggestions("deploy docker", &suggestions), None);
+    }
+
     #[test]
     fn test_normalize_bm25_score() {
         assert!((normalize_bm25_score(0.0) - 1.0).abs() < 0.001);
@@ -501,6 +1373,16 @@ mod tests {
         assert!(normalize_bm25_score(-10.0) < normalize_bm25_score(-5.0));
     }
 
+    #[test]
+    fn test_page_for_line() {
+        let starts = [1, 4, 10];
+        assert_eq!(page_for_line(&starts, 1), 1);
+        assert_eq!(page_for_line(&starts, 3), 1);
+        assert_eq!(page_for_line(&starts, 4), 2);
+        assert_eq!(page_for_line(&starts, 25), 3);
+        assert_eq!(page_for_line(&[], 7), 1);
+    }
+
     #[test]
     fn test_search_mode_from_str() {
         assert_eq!("bm25".parse::<SearchMode>().unwrap(), SearchMode::Bm25);
@@ -513,6 +1395,69 @@ mod tests {
     // Note: cosine_similarity and bytes_to_embedding tests removed.
     // Vector similarity is now computed natively by libsql's vector_distance_cos().
 
+    fn facet_row(
+        id: i64,
+        collection: &str,
+        path: &str,
+        file_type: &str,
+        modified: &str,
+    ) -> FacetRow {
+        FacetRow {
+            id,
+            collection: collection.to_string(),
+            path: path.to_string(),
+            file_type: file_type.to_string(),
+            modified_at: modified.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_facets_from_rows() {
+        let rows = vec![
+            facet_row(
+                1,
+                "docs",
+                "guide/intro.md",
+                ".md",
+                "2025-01-10T12:00:00+00:00",
+            ),
+            facet_row(2, "docs", "readme.md", ".md", "2025-02-01T08:00:00+00:00"),
+            facet_row(3, "code", "src/main.rs", ".rs", "2025-01-20T09:30:00+00:00"),
+            // Duplicate candidate should only be counted once
+            facet_row(
+                1,
+                "docs",
+                "guide/intro.md",
+                ".md",
+                "2025-01-10T12:00:00+00:00",
+            ),
+        ];
+
+        let facets = Facets::from_rows(&rows);
+
+        assert_eq!(facets.total, 3);
+        assert_eq!(
+            facets.collections[0],
+            FacetCount {
+                value: "docs".to_string(),
+                count: 2
+            }
+        );
+        assert_eq!(facets.file_types[0].value, ".md");
+        assert!(facets
+            .path_prefixes
+            .iter()
+            .any(|f| f.value == "docs/guide/" && f.count == 1));
+        assert!(facets
+            .path_prefixes
+            .iter()
+            .any(|f| f.value == "docs/" && f.count == 1));
+
+        let months: Vec<&str> = facets.modified.iter().map(|f| f.value.as_str()).collect();
+        assert_eq!(months, vec!["2025-01", "2025-02"]);
+        assert_eq!(facets.modified[0].count, 2);
+    }
+
     #[test]
     fn test_reciprocal_rank_fusion() {
         let bm25 = vec![
@@ -528,10 +1473,13 @@ mod tests {
                 content_pointer: None,
                 snippet: None,
                 line_start: None,
+                line_end: None,
+                snippets: Vec::new(),
                 collection: "test".to_string(),
                 title: None,
                 docid: None,
                 chunk_index: None,
+                page: None,
                 context: None,
             },
             SearchResult {
@@ -546,10 +1494,13 @@ mod tests {
                 content_pointer: None,
                 snippet: None,
                 line_start: None,
+                line_end: None,
+                snippets: Vec::new(),
                 collection: "test".to_string(),
                 title: None,
                 docid: None,
                 chunk_index: None,
+                page: None,
                 context: None,
             },
         ];
@@ -567,10 +1518,13 @@ mod tests {
                 content_pointer: None,
                 snippet: None,
                 line_start: None,
+                line_end: None,
+                snippets: Vec::new(),
                 collection: "test".to_string(),
                 title: None,
                 docid: None,
                 chunk_index: None,
+                page: None,
                 context: None,
             },
             SearchResult {
@@ -585,10 +1539,13 @@ mod tests {
                 content_pointer: None,
                 snippet: None,
                 line_start: None,
+                line_end: None,
+                snippets: Vec::new(),
                 collection: "test".to_string(),
                 title: None,
                 docid: None,
                 chunk_index: None,
+                page: None,
                 context: None,
             },
         ];
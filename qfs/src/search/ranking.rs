@@ -0,0 +1,266 @@
+//! Optional ranking signals applied on top of BM25/cosine/RRF scores
+//!
+//! Each signal yields a multiplier in (0, 1] (collection weights may exceed
+//! 1), and the final score is the base score times all multipliers (for BM25,
+//! the raw BM25 magnitude before normalization):
+//!
+//! - **Recency**: exponential decay on `modified_at` with a configurable
+//!   half-life; `recency_weight` is the share of the score that decays.
+//! - **Length**: documents shorter than `min_length` bytes are demoted by
+//!   `sqrt(length / min_length)`, so near-empty stubs sink.
+//! - **Path depth**: `1 / (1 + depth_penalty * (depth - 1))`, favouring files
+//!   near the collection root.
+//! - **Collection weight**: a per-collection priority multiplier.
+//!
+//! Signals come from the query (`SearchOptions::ranking`) and fall back to
+//! per-collection profiles saved with `Store::set_collection_ranking`.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Default share of the score subject to recency decay
+const DEFAULT_RECENCY_WEIGHT: f64 = 0.5;
+
+/// Ranking signals; unset fields fall back to the collection's profile
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingOptions {
+    /// Half-life in days for the recency boost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub half_life_days: Option<f64>,
+    /// Share of the score that decays with age (0.0-1.0, default 0.5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recency_weight: Option<f64>,
+    /// Documents shorter than this many bytes are demoted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u64>,
+    /// Penalty per directory level below the collection root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_penalty: Option<f64>,
+    /// Score multipliers by collection name (query-level only)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub collection_weights: HashMap<String, f64>,
+}
+
+impl RankingOptions {
+    /// Whether any signal is set
+    pub fn is_empty(&self) -> bool {
+        self.half_life_days.is_none()
+            && self.min_length.is_none()
+            && self.depth_penalty.is_none()
+            && self.collection_weights.is_empty()
+    }
+
+    /// Fill unset signals from `fallback`
+    fn or(&self, fallback: &RankingOptions) -> RankingOptions {
+        RankingOptions {
+            half_life_days: self.half_life_days.or(fallback.half_life_days),
+            recency_weight: self.recency_weight.or(fallback.recency_weight),
+            min_length: self.min_length.or(fallback.min_length),
+            depth_penalty: self.depth_penalty.or(fallback.depth_penalty),
+            collection_weights: HashMap::new(),
+        }
+    }
+}
+
+/// Saved ranking profile for a collection
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionRanking {
+    /// Collection name
+    pub collection: String,
+    /// Priority multiplier for the collection's documents
+    pub weight: f64,
+    /// Default signals for the collection's documents
+    pub signals: RankingOptions,
+}
+
+/// Per-document inputs to the ranking signals
+#[derive(Debug, Clone)]
+pub(crate) struct RankingFeatures {
+    /// Last modification time (RFC 3339)
+    pub modified_at: String,
+    /// Content length in bytes
+    pub size: u64,
+}
+
+/// Score multiplier for a document in `collection` at `relative_path`
+pub(crate) fn ranking_factor(
+    query: &RankingOptions,
+    profile: Option<&CollectionRanking>,
+    collection: &str,
+    relative_path: &str,
+    features: &RankingFeatures,
+    now: DateTime<Utc>,
+) -> f64 {
+    let signals = match profile {
+        Some(profile) => query.or(&profile.signals),
+        None => query.or(&RankingOptions::default()),
+    };
+
+    let weight = query
+        .collection_weights
+        .get(collection)
+        .copied()
+        .or(profile.map(|p| p.weight))
+        .unwrap_or(1.0);
+
+    let mut factor = weight.max(0.0);
+
+    if let Some(half_life) = signals.half_life_days.filter(|h| *h > 0.0) {
+        if let Ok(modified) = DateTime::parse_from_rfc3339(&features.modified_at) {
+            let age_days =
+                (now - modified.with_timezone(&Utc)).num_seconds().max(0) as f64 / 86_400.0;
+            let decay = 0.5f64.powf(age_days / half_life);
+            let share = signals
+                .recency_weight
+                .unwrap_or(DEFAULT_RECENCY_WEIGHT)
+                .clamp(0.0, 1.0);
+            factor *= 1.0 - share + share * decay;
+        }
+    }
+
+    if let Some(min_length) = signals.min_length.filter(|m| *m > 0) {
+        if features.size < min_length {
+            factor *= (features.size.max(1) as f64 / min_length as f64).sqrt();
+        }
+    }
+
+    if let Some(penalty) = signals.depth_penalty.filter(|p| *p > 0.0) {
+        let depth = relative_path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .count()
+            .max(1);
+        factor /= 1.0 + penalty * (depth - 1) as f64;
+    }
+
+    factor
+}
+
+/// Apply `factor` to a score from `normalize_bm25_score` (1 / (1 + |bm25|),
+/// lower is better) by scaling the raw BM25 magnitude and re-normalizing, so
+/// the result stays in (0, 1] and keeps the same convention
+pub(crate) fn scale_normalized_bm25(score: f64, factor: f64) -> f64 {
+    if score <= 0.0 {
+        return score;
+    }
+    let magnitude = (1.0 / score - 1.0).max(0.0);
+    1.0 / (1.0 + magnitude * factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(days_old: i64, size: u64) -> (RankingFeatures, DateTime<Utc>) {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let modified = now - chrono::Duration::days(days_old);
+        (
+            RankingFeatures {
+                modified_at: modified.to_rfc3339(),
+                size,
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn test_no_signals_is_neutral() {
+        let (f, now) = features(400, 10);
+        let factor = ranking_factor(
+            &RankingOptions::default(),
+            None,
+            "docs",
+            "a/b/c.md",
+            &f,
+            now,
+        );
+        assert_eq!(factor, 1.0);
+    }
+
+    #[test]
+    fn test_recency_half_life() {
+        let query = RankingOptions {
+            half_life_days: Some(30.0),
+            recency_weight: Some(1.0),
+            ..Default::default()
+        };
+
+        let (fresh, now) = features(0, 1000);
+        let (month_old, _) = features(30, 1000);
+        assert!((ranking_factor(&query, None, "docs", "a.md", &fresh, now) - 1.0).abs() < 1e-9);
+        assert!((ranking_factor(&query, None, "docs", "a.md", &month_old, now) - 0.5).abs() < 1e-9);
+
+        // Default weight only decays half the score
+        let query = RankingOptions {
+            recency_weight: None,
+            ..query
+        };
+        assert!(
+            (ranking_factor(&query, None, "docs", "a.md", &month_old, now) - 0.75).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_length_and_depth() {
+        let query = RankingOptions {
+            min_length: Some(400),
+            depth_penalty: Some(0.5),
+            ..Default::default()
+        };
+
+        let (stub, now) = features(0, 100);
+        assert!((ranking_factor(&query, None, "docs", "a.md", &stub, now) - 0.5).abs() < 1e-9);
+
+        let (long, _) = features(0, 5000);
+        let nested = ranking_factor(&query, None, "docs", "x/y/a.md", &long, now);
+        assert!((nested - 0.5).abs() < 1e-9, "depth 3 halves the score");
+    }
+
+    #[test]
+    fn test_collection_profile_and_query_override() {
+        let profile = CollectionRanking {
+            collection: "notes".to_string(),
+            weight: 2.0,
+            signals: RankingOptions {
+                min_length: Some(400),
+                ..Default::default()
+            },
+        };
+        let (stub, now) = features(0, 100);
+
+        let factor = ranking_factor(
+            &RankingOptions::default(),
+            Some(&profile),
+            "notes",
+            "a.md",
+            &stub,
+            now,
+        );
+        assert!((factor - 1.0).abs() < 1e-9, "weight 2 x length 0.5");
+
+        let query = RankingOptions {
+            min_length: Some(100),
+            collection_weights: HashMap::from([("notes".to_string(), 3.0)]),
+            ..Default::default()
+        };
+        let factor = ranking_factor(&query, Some(&profile), "notes", "a.md", &stub, now);
+        assert!((factor - 3.0).abs() < 1e-9, "query settings win");
+    }
+
+    #[test]
+    fn test_scale_normalized_bm25() {
+        // 1 / (1 + 3) for a raw BM25 magnitude of 3
+        let score = 0.25;
+        assert!((scale_normalized_bm25(score, 1.0) - score).abs() < 1e-9);
+        assert!((scale_normalized_bm25(score, 2.0) - 1.0 / 7.0).abs() < 1e-9);
+        assert!(
+            scale_normalized_bm25(score, 0.5) > score,
+            "demoted scores rise"
+        );
+        assert_eq!(scale_normalized_bm25(score, 0.0), 1.0);
+    }
+}
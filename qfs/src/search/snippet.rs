@@ -0,0 +1,184 @@
+//! Line-addressed snippets for BM25 hits
+//!
+//! FTS5's `snippet()` returns one fragment with no position, so BM25 results
+//! can't point at a line. Here the stored content is scanned for the query
+//! terms instead: lines are scored by how many distinct terms they contain,
+//! and the best ones become snippets with 1-indexed line numbers that
+//! `qfs get path:line` can follow.
+
+/// A highlighted excerpt from a single line
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Snippet {
+    /// Line number (1-indexed)
+    pub line: u32,
+    /// Excerpt with matches wrapped in `<mark>`/`</mark>`
+    pub text: String,
+}
+
+/// Find up to `count` snippets for `terms` in `content`, best first.
+///
+/// Terms match case-insensitively as word prefixes (mirroring the `"term"*`
+/// FTS query). Each snippet is one line, trimmed to at most `size` characters
+/// around its first match. Lines are ranked by distinct terms matched, then
+/// total matches, then position.
+pub(crate) fn find_snippets(
+    content: &str,
+    terms: &[String],
+    count: usize,
+    size: usize,
+) -> Vec<Snippet> {
+    if terms.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    // (line index, distinct terms, match spans)
+    let mut scored = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let (distinct, spans) = match_spans(line, terms);
+        if distinct > 0 {
+            scored.push((i, distinct, spans));
+        }
+    }
+
+    scored.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then(b.2.len().cmp(&a.2.len()))
+            .then(a.0.cmp(&b.0))
+    });
+
+    let lines: Vec<&str> = content.lines().collect();
+    scored
+        .into_iter()
+        .take(count)
+        .map(|(i, _, spans)| Snippet {
+            line: i as u32 + 1,
+            text: excerpt(lines[i], &spans, size),
+        })
+        .collect()
+}
+
+/// Byte spans of words in `line` starting with any term, and how many
+/// distinct terms matched
+fn match_spans(line: &str, terms: &[String]) -> (usize, Vec<(usize, usize)>) {
+    let mut spans = Vec::new();
+    let mut matched = vec![false; terms.len()];
+
+    let mut start = None;
+    for (pos, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        let is_word = c.is_alphanumeric() || c == '_';
+        match (is_word, start) {
+            (true, None) => start = Some(pos),
+            (false, Some(s)) => {
+                let word = line[s..pos].to_lowercase();
+                let mut hit = false;
+                for (t, term) in terms.iter().enumerate() {
+                    if word.starts_with(term.as_str()) {
+                        matched[t] = true;
+                        hit = true;
+                    }
+                }
+                if hit {
+                    spans.push((s, pos));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    (matched.iter().filter(|&&m| m).count(), spans)
+}
+
+/// Trim `line` to about `size` characters around the first span and wrap
+/// spans in `<mark>` tags
+fn excerpt(line: &str, spans: &[(usize, usize)], size: usize) -> String {
+    let trimmed_start = line.len() - line.trim_start().len();
+    let trimmed_end = line.trim_end().len();
+
+    let (mut from, mut to) = (trimmed_start, trimmed_end);
+    let char_len = line[from..to].chars().count();
+
+    if size > 0 && char_len > size {
+        // Start a quarter of the window before the first match
+        let first = spans.first().map_or(from, |s| s.0);
+        let lead = size / 4;
+        let before: Vec<usize> = line[from..first]
+            .char_indices()
+            .map(|(i, _)| from + i)
+            .collect();
+        from = before.len().checked_sub(lead).map_or(from, |i| before[i]);
+        to = line[from..to]
+            .char_indices()
+            .nth(size)
+            .map_or(to, |(i, _)| from + i);
+    }
+
+    let mut out = String::new();
+    if from > trimmed_start {
+        out.push_str("...");
+    }
+
+    let mut pos = from;
+    for &(s, e) in spans {
+        if e <= from || s >= to {
+            continue;
+        }
+        let (s, e) = (s.max(from), e.min(to));
+        out.push_str(&line[pos..s]);
+        out.push_str("<mark>");
+        out.push_str(&line[s..e]);
+        out.push_str("</mark>");
+        pos = e;
+    }
+    out.push_str(&line[pos..to]);
+
+    if to < trimmed_end {
+        out.push_str("...");
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_find_snippets_ranks_lines() {
+        let content = "# Title\n\nRust is fast.\nRust ownership is safe.\nNothing here.";
+        let snippets = find_snippets(content, &terms(&["rust", "owner"]), 2, 200);
+
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].line, 4);
+        assert_eq!(
+            snippets[0].text,
+            "<mark>Rust</mark> <mark>ownership</mark> is safe."
+        );
+        assert_eq!(snippets[1].line, 3);
+    }
+
+    #[test]
+    fn test_find_snippets_no_match() {
+        assert!(find_snippets("hello world", &terms(&["rust"]), 3, 200).is_empty());
+        assert!(find_snippets("hello world", &[], 3, 200).is_empty());
+    }
+
+    #[test]
+    fn test_excerpt_truncates_around_match() {
+        let line = format!("{} needle {}", "a".repeat(100), "b".repeat(100));
+        let snippets = find_snippets(&line, &terms(&["needle"]), 1, 40);
+
+        let text = &snippets[0].text;
+        assert!(text.starts_with("..."));
+        assert!(text.ends_with("..."));
+        assert!(text.contains("<mark>needle</mark>"));
+        assert!(text.len() < 60);
+    }
+}
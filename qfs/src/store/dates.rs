@@ -0,0 +1,288 @@
+//! Date filter parsing
+//!
+//! `modified_at` is stored as an RFC 3339 UTC timestamp and compared lexically,
+//! so filter bounds must use the same format. Inputs may be dates, timestamps
+//! with any offset, or relative expressions; each resolves to a UTC period,
+//! whose start is used for `from` bounds and whose end for `to` bounds:
+//!
+//! - `2025-03-14`, `2025-03`, `2025`, `2025-Q1`: the whole day/month/year/quarter
+//! - `2025-03-14T09:30:00+02:00`, `2025-03-14 09:30`: that instant (naive = UTC)
+//! - `7d`, `12h`, `2w`, `3mo`, `1y`, `3 days ago`, `past 2 weeks`: that long before now
+//! - `now`, `today`, `yesterday`, `this week`, `last month`, `last quarter`, ...
+
+use crate::error::{Error, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc,
+};
+
+/// A resolved date filter: an instant (start == end) or an inclusive period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DateRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl DateRange {
+    fn instant(at: DateTime<Utc>) -> Self {
+        DateRange { start: at, end: at }
+    }
+
+    /// The period from `start` up to (not including) `next`
+    fn period(start: DateTime<Utc>, next: DateTime<Utc>) -> Self {
+        DateRange {
+            start,
+            end: next - Duration::nanoseconds(1),
+        }
+    }
+}
+
+/// Format a bound the way `modified_at` is stored
+pub(crate) fn to_sql_bound(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, false)
+}
+
+/// Parse a date filter expression relative to `now`
+pub(crate) fn parse_date_filter(input: &str, now: DateTime<Utc>) -> Result<DateRange> {
+    let trimmed = input.trim();
+    let text = trimmed.to_lowercase();
+
+    if let Some(range) = parse_keyword(&text, now) {
+        return Ok(range);
+    }
+    if let Some(range) = parse_relative(&text, now) {
+        return Ok(range);
+    }
+    if let Some(range) = parse_quarter(&text) {
+        return Ok(range);
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(DateRange::instant(at.with_timezone(&Utc)));
+    }
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(at) = NaiveDateTime::parse_from_str(trimmed, format) {
+            return Ok(DateRange::instant(Utc.from_utc_datetime(&at)));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return Ok(day(date));
+    }
+    if let Some(range) = parse_month_or_year(&text) {
+        return Ok(range);
+    }
+
+    Err(Error::InvalidQuery(format!(
+        "Unrecognized date '{}' (use YYYY-MM-DD, an RFC 3339 timestamp, 2025-Q1, 7d, yesterday or last week)",
+        input
+    )))
+}
+
+/// `now`, `today`, `yesterday` and `this`/`last` week, month, quarter or year
+fn parse_keyword(text: &str, now: DateTime<Utc>) -> Option<DateRange> {
+    let today = now.date_naive();
+    let range = match text {
+        "now" => DateRange::instant(now),
+        "today" => day(today),
+        "yesterday" => day(today.pred_opt()?),
+        "this week" | "last week" => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            let monday = if text == "last week" {
+                monday - Duration::days(7)
+            } else {
+                monday
+            };
+            DateRange::period(midnight(monday), midnight(monday + Duration::days(7)))
+        }
+        "this month" | "last month" => {
+            let first = today.with_day(1)?;
+            let first = if text == "last month" {
+                first.checked_sub_months(Months::new(1))?
+            } else {
+                first
+            };
+            months_from(first, 1)?
+        }
+        "this quarter" | "last quarter" => {
+            let first = NaiveDate::from_ymd_opt(today.year(), today.month0() / 3 * 3 + 1, 1)?;
+            let first = if text == "last quarter" {
+                first.checked_sub_months(Months::new(3))?
+            } else {
+                first
+            };
+            months_from(first, 3)?
+        }
+        "this year" | "last year" => {
+            let year = if text == "last year" {
+                today.year() - 1
+            } else {
+                today.year()
+            };
+            months_from(NaiveDate::from_ymd_opt(year, 1, 1)?, 12)?
+        }
+        _ => return None,
+    };
+    Some(range)
+}
+
+/// `7d`, `12h`, `2w`, `3mo`, `1y`, `3 days ago`, `past 2 weeks`, `last 10 days`
+fn parse_relative(text: &str, now: DateTime<Utc>) -> Option<DateRange> {
+    let text = text.strip_suffix(" ago").unwrap_or(text);
+    let text = ["past ", "last "]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))
+        .unwrap_or(text)
+        .trim();
+
+    let digits = text.find(|c: char| !c.is_ascii_digit())?;
+    if digits == 0 {
+        return None;
+    }
+    let amount: u32 = text[..digits].parse().ok()?;
+    let unit = text[digits..].trim();
+
+    let amount_i64 = amount as i64;
+    let at = match unit {
+        "h" | "hr" | "hrs" | "hour" | "hours" => {
+            now.checked_sub_signed(Duration::try_hours(amount_i64)?)?
+        }
+        "d" | "day" | "days" => now.checked_sub_signed(Duration::try_days(amount_i64)?)?,
+        "w" | "wk" | "wks" | "week" | "weeks" => {
+            now.checked_sub_signed(Duration::try_weeks(amount_i64)?)?
+        }
+        "mo" | "month" | "months" => now.checked_sub_months(Months::new(amount))?,
+        "y" | "yr" | "yrs" | "year" | "years" => {
+            now.checked_sub_months(Months::new(amount.checked_mul(12)?))?
+        }
+        _ => return None,
+    };
+    Some(DateRange::instant(at))
+}
+
+/// `2025-Q1` or `2025q1`
+fn parse_quarter(text: &str) -> Option<DateRange> {
+    let (year, quarter) = text.split_once('q')?;
+    let year: i32 = year.trim_end_matches('-').parse().ok()?;
+    let quarter: u32 = quarter.parse().ok()?;
+    if !(1..=4).contains(&quarter) {
+        return None;
+    }
+    months_from(NaiveDate::from_ymd_opt(year, (quarter - 1) * 3 + 1, 1)?, 3)
+}
+
+/// `2025-03` or `2025`
+fn parse_month_or_year(text: &str) -> Option<DateRange> {
+    match text.split_once('-') {
+        Some((year, month)) if year.len() == 4 && month.len() == 2 => {
+            let first = NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)?;
+            months_from(first, 1)
+        }
+        None if text.len() == 4 && text.bytes().all(|b| b.is_ascii_digit()) => {
+            months_from(NaiveDate::from_ymd_opt(text.parse().ok()?, 1, 1)?, 12)
+        }
+        _ => None,
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+}
+
+fn day(date: NaiveDate) -> DateRange {
+    DateRange::period(midnight(date), midnight(date) + Duration::days(1))
+}
+
+fn months_from(first: NaiveDate, months: u32) -> Option<DateRange> {
+    let next = first.checked_add_months(Months::new(months))?;
+    Some(DateRange::period(midnight(first), midnight(next)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2026-03-18 15:30 UTC
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 18, 15, 30, 0).unwrap()
+    }
+
+    fn bounds(input: &str) -> (String, String) {
+        let range = parse_date_filter(input, now()).unwrap();
+        (to_sql_bound(range.start), to_sql_bound(range.end))
+    }
+
+    #[test]
+    fn test_calendar_dates() {
+        assert_eq!(
+            bounds("2025-01-31"),
+            (
+                "2025-01-31T00:00:00+00:00".to_string(),
+                "2025-01-31T23:59:59.999999999+00:00".to_string()
+            )
+        );
+        assert_eq!(bounds("2024-02").1, "2024-02-29T23:59:59.999999999+00:00");
+        assert_eq!(bounds("2025").0, "2025-01-01T00:00:00+00:00");
+        assert_eq!(bounds("2025-Q2").0, "2025-04-01T00:00:00+00:00");
+        assert_eq!(bounds("2025q4").1, "2025-12-31T23:59:59.999999999+00:00");
+        assert!(parse_date_filter("2025-Q5", now()).is_err());
+    }
+
+    #[test]
+    fn test_timestamps_normalize_to_utc() {
+        let (start, end) = bounds("2025-06-01T08:00:00+02:00");
+        assert_eq!(start, "2025-06-01T06:00:00+00:00");
+        assert_eq!(start, end);
+
+        assert_eq!(
+            bounds("2025-06-01T08:00:00Z").0,
+            "2025-06-01T08:00:00+00:00"
+        );
+        assert_eq!(bounds("2025-06-01 08:15").0, "2025-06-01T08:15:00+00:00");
+    }
+
+    #[test]
+    fn test_relative_expressions() {
+        assert_eq!(bounds("7d").0, "2026-03-11T15:30:00+00:00");
+        assert_eq!(bounds("7 days ago").0, "2026-03-11T15:30:00+00:00");
+        assert_eq!(bounds("past 2 weeks").0, "2026-03-04T15:30:00+00:00");
+        assert_eq!(bounds("12h").0, "2026-03-18T03:30:00+00:00");
+        assert_eq!(bounds("3mo").0, "2025-12-18T15:30:00+00:00");
+        assert_eq!(bounds("1y").0, "2025-03-18T15:30:00+00:00");
+        assert!(parse_date_filter("7 fortnights", now()).is_err());
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(bounds("now").0, "2026-03-18T15:30:00+00:00");
+        assert_eq!(bounds("Yesterday").0, "2026-03-17T00:00:00+00:00");
+        assert_eq!(
+            bounds("last week"),
+            (
+                "2026-03-09T00:00:00+00:00".to_string(),
+                "2026-03-15T23:59:59.999999999+00:00".to_string()
+            )
+        );
+        assert_eq!(bounds("this week").0, "2026-03-16T00:00:00+00:00");
+        assert_eq!(bounds("last month").0, "2026-02-01T00:00:00+00:00");
+        assert_eq!(bounds("last quarter").0, "2025-10-01T00:00:00+00:00");
+        assert_eq!(bounds("this year").1, "2026-12-31T23:59:59.999999999+00:00");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        for input in [
+            "",
+            "soon",
+            "2025-13-01",
+            "-7d",
+            "4000000000d",
+            "4000000000w",
+            "4000000000h",
+        ] {
+            assert!(parse_date_filter(input, now()).is_err(), "{}", input);
+        }
+    }
+}
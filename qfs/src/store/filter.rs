@@ -0,0 +1,289 @@
+//! Document filters shared by the search queries
+//!
+//! BM25, passage, vector, facet and grep queries all select from `documents d`
+//! and narrow it the same way, so the filter renders itself as SQL conditions
+//! on `d` with positional parameters appended to the caller's list. Date
+//! bounds are resolved to UTC at that point (see `dates`).
+
+use super::dates::{parse_date_filter, to_sql_bound};
+use crate::error::Result;
+use chrono::Utc;
+
+/// Restricts which documents a search considers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentFilter {
+    /// Only these collections (any of them; empty = all)
+    pub collections: Vec<String>,
+    /// Never these collections
+    pub exclude_collections: Vec<String>,
+    /// File extensions such as `md` or `.rs` (case-insensitive; empty = all)
+    pub file_types: Vec<String>,
+    /// MIME types (`text/markdown`) or classes (`text`, `image/*`); empty = all
+    pub mime_types: Vec<String>,
+    /// Path prefix relative to the collection root (e.g. `notes/2025/`)
+    pub path_prefix: Option<String>,
+    /// SQLite GLOB on the path relative to the collection root (`*` also matches `/`)
+    pub path_glob: Option<String>,
+    /// Documents modified on or after this date: ISO 8601 date or timestamp,
+    /// `2025-Q1`, or relative (`7d`, `yesterday`, `last week`)
+    pub from_date: Option<String>,
+    /// Documents modified on or before this date (same formats; periods
+    /// such as `2025-03` include their last day)
+    pub to_date: Option<String>,
+    /// Metadata `(key, value)` pairs that must all match; values match as
+    /// case-insensitive substrings (`from`, `alice@example.com`)
+    pub metadata: Vec<(String, String)>,
+}
+
+impl DocumentFilter {
+    /// Filter on a single optional collection and date range
+    pub fn new(collection: Option<&str>, from_date: Option<&str>, to_date: Option<&str>) -> Self {
+        DocumentFilter {
+            collections: collection.map(String::from).into_iter().collect(),
+            from_date: from_date.map(String::from),
+            to_date: to_date.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    /// Whether no filter is set
+    pub fn is_empty(&self) -> bool {
+        *self == DocumentFilter::default()
+    }
+
+    /// The collection searched, when exactly one is included
+    pub fn single_collection(&self) -> Option<&str> {
+        match self.collections.as_slice() {
+            [collection] => Some(collection),
+            _ => None,
+        }
+    }
+
+    /// Append SQL conditions on `documents d` to `where_clauses`, numbering
+    /// placeholders after the parameters already in `params`. Fails on
+    /// unrecognized dates.
+    pub(crate) fn push_sql(
+        &self,
+        where_clauses: &mut Vec<String>,
+        params: &mut Vec<libsql::Value>,
+    ) -> Result<()> {
+        if !self.collections.is_empty() {
+            let list = placeholders(params, self.collections.iter().cloned());
+            where_clauses.push(format!("d.collection IN ({})", list));
+        }
+        if !self.exclude_collections.is_empty() {
+            let list = placeholders(params, self.exclude_collections.iter().cloned());
+            where_clauses.push(format!("d.collection NOT IN ({})", list));
+        }
+        if !self.file_types.is_empty() {
+            let list = placeholders(
+                params,
+                self.file_types.iter().map(|t| normalize_file_type(t)),
+            );
+            where_clauses.push(format!("lower(d.file_type) IN ({})", list));
+        }
+        if !self.mime_types.is_empty() {
+            let conditions: Vec<String> = self
+                .mime_types
+                .iter()
+                .map(|mime| match mime_pattern(mime) {
+                    MimePattern::Exact(mime) => {
+                        params.push(mime.into());
+                        format!("content_type = ?{}", params.len())
+                    }
+                    MimePattern::Class(prefix) => {
+                        params.push(format!("{}/%", escape_like(&prefix)).into());
+                        format!("content_type LIKE ?{} ESCAPE '\\'", params.len())
+                    }
+                })
+                .collect();
+            where_clauses.push(format!(
+                "d.hash IN (SELECT hash FROM content WHERE {})",
+                conditions.join(" OR ")
+            ));
+        }
+        if let Some(ref prefix) = self.path_prefix {
+            let prefix = prefix.trim_start_matches('/');
+            params.push(format!("{}%", escape_like(prefix)).into());
+            where_clauses.push(format!("d.path LIKE ?{} ESCAPE '\\'", params.len()));
+        }
+        if let Some(ref glob) = self.path_glob {
+            params.push(glob.trim_start_matches('/').into());
+            where_clauses.push(format!("d.path GLOB ?{}", params.len()));
+        }
+        for (key, value) in &self.metadata {
+            params.push(key.clone().into());
+            let key = params.len();
+            params.push(format!("%{}%", escape_like(value)).into());
+            where_clauses.push(format!(
+                "d.id IN (SELECT document_id FROM document_metadata WHERE key = ?{} AND value LIKE ?{} ESCAPE '\\')",
+                key,
+                params.len()
+            ));
+        }
+        let now = Utc::now();
+        if let Some(ref from) = self.from_date {
+            let start = parse_date_filter(from, now)?.start;
+            params.push(to_sql_bound(start).into());
+            where_clauses.push(format!("d.modified_at >= ?{}", params.len()));
+        }
+        if let Some(ref to) = self.to_date {
+            let end = parse_date_filter(to, now)?.end;
+            params.push(to_sql_bound(end).into());
+            where_clauses.push(format!("d.modified_at <= ?{}", params.len()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Push `values` as parameters and return their comma-separated placeholders
+fn placeholders(params: &mut Vec<libsql::Value>, values: impl Iterator<Item = String>) -> String {
+    values
+        .map(|value| {
+            params.push(value.into());
+            format!("?{}", params.len())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `md`, `.MD` and `*.md` all become `.md`, matching `documents.file_type`
+fn normalize_file_type(file_type: &str) -> String {
+    let ext = file_type
+        .trim()
+        .trim_start_matches('*')
+        .trim_start_matches('.');
+    format!(".{}", ext.to_lowercase())
+}
+
+enum MimePattern {
+    Exact(String),
+    Class(String),
+}
+
+/// `text/markdown` matches exactly; `text`, `text/` and `text/*` match the class
+fn mime_pattern(mime: &str) -> MimePattern {
+    let mime = mime.trim().to_lowercase();
+    match mime.split_once('/') {
+        Some((class, "" | "*")) => MimePattern::Class(class.to_string()),
+        Some(_) => MimePattern::Exact(mime),
+        None => MimePattern::Class(mime),
+    }
+}
+
+/// Escape LIKE wildcards so `_` and `%` in paths match literally
+pub(crate) fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(filter: &DocumentFilter) -> (String, usize) {
+        let mut clauses = Vec::new();
+        let mut params: Vec<libsql::Value> = vec!["query".into()];
+        filter.push_sql(&mut clauses, &mut params).unwrap();
+        (clauses.join(" AND "), params.len())
+    }
+
+    #[test]
+    fn test_empty_filter() {
+        let filter = DocumentFilter::default();
+        assert!(filter.is_empty());
+        assert_eq!(render(&filter), (String::new(), 1));
+    }
+
+    #[test]
+    fn test_placeholders_follow_existing_params() {
+        let filter = DocumentFilter {
+            collections: vec!["notes".to_string(), "docs".to_string()],
+            exclude_collections: vec!["archive".to_string()],
+            file_types: vec!["MD".to_string(), "*.rs".to_string()],
+            ..Default::default()
+        };
+        let (sql, count) = render(&filter);
+        assert_eq!(
+            sql,
+            "d.collection IN (?2, ?3) AND d.collection NOT IN (?4) AND lower(d.file_type) IN (?5, ?6)"
+        );
+        assert_eq!(count, 6);
+        assert_eq!(filter.single_collection(), None);
+    }
+
+    #[test]
+    fn test_mime_and_path_patterns() {
+        let filter = DocumentFilter {
+            mime_types: vec!["text/markdown".to_string(), "image/*".to_string()],
+            path_prefix: Some("/notes_2025/".to_string()),
+            path_glob: Some("**/*.md".to_string()),
+            ..Default::default()
+        };
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        filter.push_sql(&mut clauses, &mut params).unwrap();
+
+        assert_eq!(
+            clauses[0],
+            "d.hash IN (SELECT hash FROM content WHERE content_type = ?1 OR content_type LIKE ?2 ESCAPE '\\')"
+        );
+        assert_eq!(params[1], libsql::Value::Text("image/%".to_string()));
+        assert_eq!(params[2], libsql::Value::Text("notes\\_2025/%".to_string()));
+        assert_eq!(params[3], libsql::Value::Text("**/*.md".to_string()));
+    }
+
+    #[test]
+    fn test_dates_resolve_to_utc_bounds() {
+        let filter = DocumentFilter::new(None, Some("2025-03-01T10:00:00+02:00"), Some("2025-03"));
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        filter.push_sql(&mut clauses, &mut params).unwrap();
+
+        assert_eq!(clauses, vec!["d.modified_at >= ?1", "d.modified_at <= ?2"]);
+        assert_eq!(
+            params[0],
+            libsql::Value::Text("2025-03-01T08:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            params[1],
+            libsql::Value::Text("2025-03-31T23:59:59.999999999+00:00".to_string())
+        );
+
+        let invalid = DocumentFilter::new(None, Some("someday"), None);
+        assert!(invalid.push_sql(&mut clauses, &mut params).is_err());
+    }
+
+    #[test]
+    fn test_metadata_conditions() {
+        let filter = DocumentFilter {
+            metadata: vec![
+                ("from".to_string(), "alice@example.com".to_string()),
+                ("subject".to_string(), "100%".to_string()),
+            ],
+            ..Default::default()
+        };
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        filter.push_sql(&mut clauses, &mut params).unwrap();
+
+        assert_eq!(
+            clauses[1],
+            "d.id IN (SELECT document_id FROM document_metadata WHERE key = ?3 AND value LIKE ?4 ESCAPE '\\')"
+        );
+        assert_eq!(
+            params[1],
+            libsql::Value::Text("%alice@example.com%".to_string())
+        );
+        assert_eq!(params[3], libsql::Value::Text("%100\\%%".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_file_type() {
+        assert_eq!(normalize_file_type("md"), ".md");
+        assert_eq!(normalize_file_type(".MD"), ".md");
+        assert_eq!(normalize_file_type("*.rs"), ".rs");
+    }
+}
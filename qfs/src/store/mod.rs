@@ -12,12 +12,16 @@
 //! The store supports two vector search modes:
 //! - **Native** (via libsql): Uses `vector_top_k()` with an ANN index for efficient KNN search.
 //!   Requires embeddings to be stored in libsql's vector format.
-//! - **Legacy** (fallback): Loads all embeddings into memory and calculates cosine similarity.
-//!   Works with embeddings stored as raw BLOB data.
+//! - **Legacy** (fallback): Streams all candidate embeddings and keeps the best matches in a
+//!   bounded top-k heap. Works with embeddings stored as raw BLOB data.
 //!
 //! The search automatically falls back to legacy mode if native search is not available.
 
+pub(crate) mod dates;
+mod filter;
 mod schema;
+mod vector_index;
+mod vector_scan;
 
 use crate::error::{Error, Result};
 use chrono::Utc;
@@ -25,11 +29,22 @@ use glob::Pattern;
 use libsql::{params, Builder, Connection, Database};
 use std::path::{Path, PathBuf};
 
+pub use filter::DocumentFilter;
 pub use schema::SCHEMA_VERSION;
+pub use vector_index::{ConvertStats, VectorIndexParams, VectorIndexStatus, VectorSearchPath};
+
+use crate::parser::{CollectionParser, CommandParser, DEFAULT_COMMAND_TIMEOUT};
+use crate::search::{CollectionRanking, RankingFeatures};
 
 /// Default max bytes for multi-get (10KB)
 pub const DEFAULT_MULTI_GET_MAX_BYTES: usize = 10 * 1024;
 
+/// Growth factor for `vector_top_k` neighbours when filters discard results
+const VECTOR_OVERFETCH_FACTOR: usize = 4;
+
+/// Most query embeddings kept in the cache
+pub const QUERY_EMBEDDING_CACHE_SIZE: usize = 10_000;
+
 /// Document metadata stored in the database
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Document {
@@ -84,6 +99,73 @@ pub struct SearchResultRow {
     pub snippet: Option<String>,
 }
 
+/// Document attributes used to build search facets
+#[derive(Debug, Clone)]
+pub struct FacetRow {
+    pub id: i64,
+    pub collection: String,
+    pub path: String,
+    pub file_type: String,
+    pub modified_at: String,
+}
+
+/// Document whose content should be scanned by grep
+#[derive(Debug, Clone)]
+pub struct GrepCandidate {
+    pub id: i64,
+    pub collection: String,
+    pub path: String,
+    pub hash: String,
+    pub content_type: String,
+}
+
+/// Passage chunk position, aligned with embeddings by (hash, chunk_index)
+#[derive(Debug, Clone)]
+pub struct ChunkRow {
+    pub hash: String,
+    pub chunk_index: i32,
+    /// Byte offset of the chunk in the stored content
+    pub char_offset: i32,
+    /// Byte length of the chunk's span in the stored content
+    pub char_length: i32,
+    /// First line of the chunk (1-indexed)
+    pub line_start: i32,
+    /// Last line of the chunk (1-indexed, inclusive)
+    pub line_end: i32,
+}
+
+/// Row returned from passage-level BM25 search
+#[derive(Debug, Clone)]
+pub struct PassageResultRow {
+    pub id: i64,
+    pub collection: String,
+    pub path: String,
+    pub title: Option<String>,
+    pub hash: String,
+    pub file_type: String,
+    pub content_type: String,
+    pub size: i64,
+    pub chunk_index: i32,
+    pub line_start: i32,
+    pub line_end: i32,
+    pub bm25_score: f64,
+    pub snippet: Option<String>,
+}
+
+/// Document matched by the fuzzy path finder
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathMatch {
+    pub id: i64,
+    pub collection: String,
+    pub path: String,
+    pub title: Option<String>,
+    /// Short document ID (#abc123)
+    pub docid: String,
+    /// Match score (higher is better, not normalized)
+    pub score: i64,
+}
+
 /// Content stored in content-addressable storage
 #[derive(Debug, Clone)]
 pub struct Content {
@@ -311,6 +393,13 @@ impl Store {
     /// Remove a collection and its documents
     pub async fn remove_collection(&self, name: &str) -> Result<()> {
         // Delete documents first
+        self.conn
+            .execute(
+                "DELETE FROM document_metadata WHERE document_id IN
+                 (SELECT id FROM documents WHERE collection = ?1)",
+                params![name],
+            )
+            .await?;
         self.conn
             .execute(
                 "DELETE FROM documents WHERE collection = ?1",
@@ -318,14 +407,243 @@ impl Store {
             )
             .await?;
 
-        // Delete the collection
+        // Delete the collection and its ranking profile
         self.conn
             .execute("DELETE FROM collections WHERE name = ?1", params![name])
             .await?;
+        self.conn
+            .execute(
+                "DELETE FROM collection_ranking WHERE collection = ?1",
+                params![name],
+            )
+            .await?;
+        self.conn
+            .execute(
+                "DELETE FROM collection_parsers WHERE collection = ?1",
+                params![name],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Save the ranking profile for a collection (replacing any existing one)
+    pub async fn set_collection_ranking(&self, ranking: &CollectionRanking) -> Result<()> {
+        // Fail early for unknown collections
+        self.get_collection(&ranking.collection).await?;
+
+        let signals = serde_json::to_string(&ranking.signals)?;
+        let now = Utc::now().to_rfc3339();
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO collection_ranking (collection, weight, signals, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![ranking.collection.as_str(), ranking.weight, signals, now],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remove a collection's ranking profile, returning whether one existed
+    pub async fn remove_collection_ranking(&self, collection: &str) -> Result<bool> {
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM collection_ranking WHERE collection = ?1",
+                params![collection],
+            )
+            .await?;
+
+        Ok(removed > 0)
+    }
+
+    /// List all collection ranking profiles
+    pub async fn list_collection_rankings(&self) -> Result<Vec<CollectionRanking>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT collection, weight, signals FROM collection_ranking ORDER BY collection",
+                (),
+            )
+            .await?;
+
+        let mut rankings = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let signals: String = row.get(2)?;
+            rankings.push(CollectionRanking {
+                collection: row.get(0)?,
+                weight: row.get(1)?,
+                signals: serde_json::from_str(&signals)?,
+            });
+        }
+
+        Ok(rankings)
+    }
+
+    /// Save the external parser for an extension in a collection (replacing
+    /// any existing one). The extension is stored without its dot, lowercased.
+    ///
+    /// Text already extracted from the collection's files with that extension
+    /// is dropped, so the next index re-parses them with the new command.
+    pub async fn set_collection_parser(&self, parser: &CollectionParser) -> Result<()> {
+        // Fail early for unknown collections and unusable commands
+        self.get_collection(&parser.collection).await?;
+        CommandParser::new(&parser.command, DEFAULT_COMMAND_TIMEOUT)?;
+
+        let extension = parser
+            .extension
+            .trim()
+            .trim_start_matches("*.")
+            .trim_start_matches('.')
+            .to_lowercase();
+        let now = Utc::now().to_rfc3339();
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO collection_parsers (collection, extension, command, timeout_secs, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    parser.collection.as_str(),
+                    extension.as_str(),
+                    parser.command.as_str(),
+                    parser.timeout_secs as i64,
+                    now
+                ],
+            )
+            .await?;
+        self.conn
+            .execute(
+                "DELETE FROM extracted_text WHERE hash IN (
+                     SELECT hash FROM documents
+                     WHERE collection = ?1 AND path LIKE ?2 ESCAPE '\\' AND active = 1
+                 )",
+                params![
+                    parser.collection.as_str(),
+                    format!("%.{}", filter::escape_like(&extension))
+                ],
+            )
+            .await?;
 
         Ok(())
     }
 
+    /// Remove the external parser for an extension, returning whether one existed
+    pub async fn remove_collection_parser(
+        &self,
+        collection: &str,
+        extension: &str,
+    ) -> Result<bool> {
+        let extension = extension
+            .trim()
+            .trim_start_matches("*.")
+            .trim_start_matches('.');
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM collection_parsers WHERE collection = ?1 AND extension = ?2",
+                params![collection, extension.to_lowercase()],
+            )
+            .await?;
+
+        Ok(removed > 0)
+    }
+
+    /// List external parsers, for one collection or all
+    pub async fn list_collection_parsers(
+        &self,
+        collection: Option<&str>,
+    ) -> Result<Vec<CollectionParser>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT collection, extension, command, timeout_secs FROM collection_parsers
+                 WHERE ?1 IS NULL OR collection = ?1
+                 ORDER BY collection, extension",
+                params![collection],
+            )
+            .await?;
+
+        let mut parsers = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let timeout_secs: i64 = row.get(3)?;
+            parsers.push(CollectionParser {
+                collection: row.get(0)?,
+                extension: row.get(1)?,
+                command: row.get(2)?,
+                timeout_secs: timeout_secs.max(0) as u64,
+            });
+        }
+
+        Ok(parsers)
+    }
+
+    /// Modification time and content length of documents, keyed by id
+    pub(crate) async fn ranking_features(
+        &self,
+        ids: &[i64],
+    ) -> Result<std::collections::HashMap<i64, RankingFeatures>> {
+        let mut features = std::collections::HashMap::new();
+        if ids.is_empty() {
+            return Ok(features);
+        }
+
+        let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("?{}", i)).collect();
+        let query = format!(
+            "SELECT d.id, d.modified_at, c.size
+             FROM documents d
+             JOIN content c ON c.hash = d.hash
+             WHERE d.id IN ({})",
+            placeholders.join(", ")
+        );
+        let params: Vec<libsql::Value> = ids.iter().map(|&id| id.into()).collect();
+
+        let mut rows = self.conn.query(&query, params).await?;
+        while let Some(row) = rows.next().await? {
+            let size: i64 = row.get(2)?;
+            features.insert(
+                row.get::<i64>(0)?,
+                RankingFeatures {
+                    modified_at: row.get(1)?,
+                    size: size.max(0) as u64,
+                },
+            );
+        }
+
+        Ok(features)
+    }
+
+    /// Content hash and page start lines of paged documents (e.g. PDFs), by id
+    pub(crate) async fn document_pages(
+        &self,
+        ids: &[i64],
+    ) -> Result<std::collections::HashMap<i64, (String, Vec<u32>)>> {
+        let mut pages = std::collections::HashMap::new();
+        if ids.is_empty() {
+            return Ok(pages);
+        }
+
+        let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("?{}", i)).collect();
+        let query = format!(
+            "SELECT d.id, d.hash, e.pages
+             FROM documents d
+             JOIN extracted_text e ON e.hash = d.hash
+             WHERE e.pages IS NOT NULL AND d.id IN ({})",
+            placeholders.join(", ")
+        );
+        let params: Vec<libsql::Value> = ids.iter().map(|&id| id.into()).collect();
+
+        let mut rows = self.conn.query(&query, params).await?;
+        while let Some(row) = rows.next().await? {
+            let starts: String = row.get(2)?;
+            pages.insert(
+                row.get::<i64>(0)?,
+                (row.get(1)?, serde_json::from_str(&starts)?),
+            );
+        }
+
+        Ok(pages)
+    }
+
     // -------------------------------------------------------------------------
     // Content operations (content-addressable storage)
     // -------------------------------------------------------------------------
@@ -390,6 +708,99 @@ impl Store {
         }
     }
 
+    /// Store text extracted from non-text content, with the line each page starts on
+    pub async fn insert_extracted_text(&self, hash: &str, text: &str, pages: &[u32]) -> Result<()> {
+        let pages = if pages.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(pages)?)
+        };
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO extracted_text (hash, text, pages) VALUES (?1, ?2, ?3)",
+                params![hash, text, pages],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Drop the extracted text of content, which then reads as its raw bytes
+    pub async fn delete_extracted_text(&self, hash: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM extracted_text WHERE hash = ?1", params![hash])
+            .await?;
+
+        Ok(())
+    }
+
+    /// Check if text has been extracted for content
+    pub async fn has_extracted_text(&self, hash: &str) -> Result<bool> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT 1 FROM extracted_text WHERE hash = ?1",
+                params![hash],
+            )
+            .await?;
+
+        Ok(rows.next().await?.is_some())
+    }
+
+    /// Get the readable text of content: its extracted text if any, otherwise
+    /// the raw bytes decoded from their detected encoding. Returns `None` for
+    /// binary content.
+    pub async fn get_text(&self, hash: &str) -> Result<Option<String>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT text FROM extracted_text WHERE hash = ?1",
+                params![hash],
+            )
+            .await?;
+        if let Some(row) = rows.next().await? {
+            return Ok(Some(row.get(0)?));
+        }
+
+        let content = self.get_content(hash).await?;
+        Ok(crate::parser::decode_text(&content.data).map(|(text, _)| text.into_owned()))
+    }
+
+    /// MIME type of stored content
+    pub async fn get_content_type(&self, hash: &str) -> Result<String> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT content_type FROM content WHERE hash = ?1",
+                params![hash],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::DocumentNotFound(hash.to_string())),
+        }
+    }
+
+    /// Get the 1-based line each page starts on (empty for unpaged content)
+    pub async fn get_pages(&self, hash: &str) -> Result<Vec<u32>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT pages FROM extracted_text WHERE hash = ?1",
+                params![hash],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => match row.get::<Option<String>>(0)? {
+                Some(pages) => Ok(serde_json::from_str(&pages)?),
+                None => Ok(Vec::new()),
+            },
+            None => Ok(Vec::new()),
+        }
+    }
+
     // -------------------------------------------------------------------------
     // Document operations
     // -------------------------------------------------------------------------
@@ -450,6 +861,13 @@ impl Store {
             )
             .await?;
 
+        // Record new terms in the spelling vocabulary
+        let mut terms = std::collections::BTreeSet::new();
+        terms.extend(vocabulary_terms(title.unwrap_or("")));
+        terms.extend(vocabulary_terms(body));
+        let terms: Vec<String> = terms.into_iter().collect();
+        insert_vocabulary(&self.conn, &terms).await?;
+
         Ok(id)
     }
 
@@ -551,51 +969,236 @@ impl Store {
         }
     }
 
-    /// Mark a document as inactive (soft delete)
-    pub async fn deactivate_document(&self, collection: &str, path: &str) -> Result<()> {
-        // First get the document ID to remove from FTS
-        if let Ok(doc) = self.get_document(collection, path).await {
-            // Remove from FTS index
-            self.conn
-                .execute(
-                    "DELETE FROM documents_fts WHERE rowid = ?1",
-                    params![doc.id],
-                )
-                .await?;
+    /// Resolve a document reference: a docid (#abc123) or collection/relative_path
+    pub async fn resolve_document(&self, reference: &str) -> Result<Document> {
+        if is_docid(reference) {
+            return self.get_document_by_docid(reference).await;
         }
 
-        self.conn
-            .execute(
-                "UPDATE documents SET active = 0 WHERE collection = ?1 AND path = ?2",
-                params![collection, path],
-            )
-            .await?;
-        Ok(())
+        match reference.split_once('/') {
+            Some((collection, path)) => self.get_document(collection, path).await,
+            None => Err(Error::InvalidQuery(
+                "Path must be in format 'collection/relative_path' or docid (#abc123)".to_string(),
+            )),
+        }
     }
 
-    /// List all documents in a collection
-    pub async fn list_documents(&self, collection: &str) -> Result<Vec<Document>> {
+    /// Get the indexed (parsed) title and body text for a document
+    pub async fn get_indexed_text(&self, id: i64) -> Result<(String, String)> {
         let mut rows = self
             .conn
             .query(
-                "SELECT id, collection, path, title, hash, file_type, created_at, modified_at, indexed_at, active
-             FROM documents WHERE collection = ?1 AND active = 1
-             ORDER BY path",
-                params![collection],
+                "SELECT title, body FROM documents_fts WHERE rowid = ?1",
+                params![id],
             )
             .await?;
 
-        let mut docs = Vec::new();
-        while let Some(row) = rows.next().await? {
-            docs.push(Document {
-                id: row.get(0)?,
-                collection: row.get(1)?,
-                path: row.get(2)?,
-                title: row.get(3)?,
-                hash: row.get(4)?,
-                file_type: row.get(5)?,
-                created_at: row.get(6)?,
-                modified_at: row.get(7)?,
+        if let Some(row) = rows.next().await? {
+            let title: Option<String> = row.get(0)?;
+            let body: Option<String> = row.get(1)?;
+            Ok((title.unwrap_or_default(), body.unwrap_or_default()))
+        } else {
+            Err(Error::DocumentNotFound(format!("id:{}", id)))
+        }
+    }
+
+    /// Find documents by approximate path or title (fzf-style fuzzy matching).
+    ///
+    /// Every whitespace-separated term of `query` must match as a
+    /// case-insensitive subsequence of `collection/path` or the title.
+    /// Results are ordered by score, best first.
+    pub async fn find_paths(
+        &self,
+        query: &str,
+        collection: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<PathMatch>> {
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut rows = match collection {
+            Some(coll) => {
+                self.conn
+                    .query(
+                        "SELECT id, collection, path, title, hash FROM documents
+                     WHERE active = 1 AND collection = ?1",
+                        params![coll],
+                    )
+                    .await?
+            }
+            None => {
+                self.conn
+                    .query(
+                        "SELECT id, collection, path, title, hash FROM documents WHERE active = 1",
+                        (),
+                    )
+                    .await?
+            }
+        };
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let collection: String = row.get(1)?;
+            let path: String = row.get(2)?;
+            let title: Option<String> = row.get(3)?;
+            let full_path = format!("{}/{}", collection, path);
+
+            let score = terms.iter().try_fold(0i64, |total, term| {
+                let path_score = fuzzy_score(term, &full_path);
+                let title_score = title.as_deref().and_then(|t| fuzzy_score(term, t));
+                path_score.max(title_score).map(|s| total + s)
+            });
+
+            if let Some(score) = score {
+                let hash: String = row.get(4)?;
+                results.push(PathMatch {
+                    id: row.get(0)?,
+                    docid: format!("#{}", get_docid(&hash)),
+                    collection,
+                    path,
+                    title,
+                    score,
+                });
+            }
+        }
+
+        // Best score first; shorter paths win ties
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.path.len().cmp(&b.path.len()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        results.truncate(limit);
+
+        Ok(results)
+    }
+
+    /// Replace a document's filterable metadata. Strings, numbers and booleans
+    /// are stored as text; arrays store one row per scalar element.
+    pub async fn set_document_metadata(
+        &self,
+        id: i64,
+        metadata: &std::collections::HashMap<String, serde_json::Value>,
+    ) -> Result<()> {
+        self.conn
+            .execute(
+                "DELETE FROM document_metadata WHERE document_id = ?1",
+                params![id],
+            )
+            .await?;
+
+        for (key, value) in metadata {
+            let values = match value {
+                serde_json::Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Number(n) => n.to_string(),
+                    serde_json::Value::Bool(b) => b.to_string(),
+                    _ => continue,
+                };
+                self.conn
+                    .execute(
+                        "INSERT INTO document_metadata (document_id, key, value) VALUES (?1, ?2, ?3)",
+                        params![id, key.as_str(), value],
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a document's metadata, each key with its values in stored order
+    pub async fn get_document_metadata(
+        &self,
+        id: i64,
+    ) -> Result<std::collections::BTreeMap<String, Vec<String>>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT key, value FROM document_metadata WHERE document_id = ?1 ORDER BY rowid",
+                params![id],
+            )
+            .await?;
+
+        let mut metadata = std::collections::BTreeMap::<String, Vec<String>>::new();
+        while let Some(row) = rows.next().await? {
+            metadata.entry(row.get(0)?).or_default().push(row.get(1)?);
+        }
+        Ok(metadata)
+    }
+
+    /// Paths of the active documents inside a container (`path!/...`), such
+    /// as the messages of an mbox, at any depth
+    pub async fn list_part_paths(&self, collection: &str, container: &str) -> Result<Vec<String>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT path FROM documents
+             WHERE collection = ?1 AND path LIKE ?2 ESCAPE '\\' AND active = 1
+             ORDER BY path",
+                params![collection, format!("{}!/%", filter::escape_like(container))],
+            )
+            .await?;
+
+        let mut paths = Vec::new();
+        while let Some(row) = rows.next().await? {
+            paths.push(row.get(0)?);
+        }
+        Ok(paths)
+    }
+
+    /// Mark a document as inactive (soft delete)
+    pub async fn deactivate_document(&self, collection: &str, path: &str) -> Result<()> {
+        // First get the document ID to remove from FTS
+        if let Ok(doc) = self.get_document(collection, path).await {
+            // Remove from FTS index
+            self.conn
+                .execute(
+                    "DELETE FROM documents_fts WHERE rowid = ?1",
+                    params![doc.id],
+                )
+                .await?;
+        }
+
+        self.conn
+            .execute(
+                "UPDATE documents SET active = 0 WHERE collection = ?1 AND path = ?2",
+                params![collection, path],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// List all documents in a collection
+    pub async fn list_documents(&self, collection: &str) -> Result<Vec<Document>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT id, collection, path, title, hash, file_type, created_at, modified_at, indexed_at, active
+             FROM documents WHERE collection = ?1 AND active = 1
+             ORDER BY path",
+                params![collection],
+            )
+            .await?;
+
+        let mut docs = Vec::new();
+        while let Some(row) = rows.next().await? {
+            docs.push(Document {
+                id: row.get(0)?,
+                collection: row.get(1)?,
+                path: row.get(2)?,
+                title: row.get(3)?,
+                hash: row.get(4)?,
+                file_type: row.get(5)?,
+                created_at: row.get(6)?,
+                modified_at: row.get(7)?,
                 indexed_at: row.get(8)?,
                 active: row.get(9)?,
             });
@@ -943,16 +1546,44 @@ impl Store {
     // Search operations
     // -------------------------------------------------------------------------
 
-    /// Execute BM25 full-text search
-    /// Supports optional filtering by collection and date range (modified_at)
+    /// Execute BM25 full-text search over the documents matching `filter`
     pub async fn search_bm25(
         &self,
         fts_query: &str,
-        collection: Option<&str>,
+        filter: &DocumentFilter,
+        limit: usize,
+        include_binary: bool,
+    ) -> Result<Vec<SearchResultRow>> {
+        self.search_documents_bm25(fts_query, filter, None, limit, include_binary)
+            .await
+    }
+
+    /// Document-level BM25 search restricted to documents without passage
+    /// chunks, which passage search can't reach
+    pub async fn search_unchunked_bm25(
+        &self,
+        fts_query: &str,
+        filter: &DocumentFilter,
+        limit: usize,
+        include_binary: bool,
+    ) -> Result<Vec<SearchResultRow>> {
+        self.search_documents_bm25(
+            fts_query,
+            filter,
+            Some("NOT EXISTS (SELECT 1 FROM chunks ch WHERE ch.hash = d.hash)"),
+            limit,
+            include_binary,
+        )
+        .await
+    }
+
+    async fn search_documents_bm25(
+        &self,
+        fts_query: &str,
+        filter: &DocumentFilter,
+        condition: Option<&str>,
         limit: usize,
         include_binary: bool,
-        from_date: Option<&str>,
-        to_date: Option<&str>,
     ) -> Result<Vec<SearchResultRow>> {
         let mut results = Vec::new();
 
@@ -965,20 +1596,10 @@ impl Store {
             "documents_fts MATCH ?1".to_string(),
             "d.active = 1".to_string(),
         ];
-        let mut param_idx = 2;
-
-        if collection.is_some() {
-            where_clauses.push(format!("d.collection = ?{}", param_idx));
-            param_idx += 1;
-        }
-        if from_date.is_some() {
-            where_clauses.push(format!("d.modified_at >= ?{}", param_idx));
-            param_idx += 1;
-        }
-        if to_date.is_some() {
-            where_clauses.push(format!("d.modified_at <= ?{}", param_idx));
-            param_idx += 1;
-        }
+        where_clauses.extend(condition.map(String::from));
+        let mut params: Vec<libsql::Value> = vec![fts_query.into()];
+        filter.push_sql(&mut where_clauses, &mut params)?;
+        params.push((limit as i64).into());
 
         let query = format!(
             r#"
@@ -1001,22 +1622,9 @@ impl Store {
             LIMIT ?{}
             "#,
             where_clauses.join(" AND "),
-            param_idx
+            params.len()
         );
 
-        // Build params dynamically
-        let mut params: Vec<libsql::Value> = vec![fts_query.into()];
-        if let Some(coll) = collection {
-            params.push(coll.into());
-        }
-        if let Some(from) = from_date {
-            params.push(from.into());
-        }
-        if let Some(to) = to_date {
-            params.push(to.into());
-        }
-        params.push((limit as i64).into());
-
         let mut rows = self.conn.query(&query, params).await?;
 
         while let Some(row) = rows.next().await? {
@@ -1049,97 +1657,626 @@ impl Store {
         Ok(results)
     }
 
-    /// Get database file size in bytes
-    pub fn database_size(&self) -> Result<u64> {
-        if self.path.to_str() == Some(":memory:") {
-            return Ok(0);
+    /// Get facet attributes for every document matching a BM25 query.
+    /// Applies the same filters as search_bm25() but without a result limit,
+    /// so facet counts cover the full candidate set.
+    pub async fn facet_rows_bm25(
+        &self,
+        fts_query: &str,
+        filter: &DocumentFilter,
+        include_binary: bool,
+    ) -> Result<Vec<FacetRow>> {
+        let mut results = Vec::new();
+
+        if fts_query.is_empty() {
+            return Ok(results);
         }
-        let metadata = std::fs::metadata(&self.path)?;
-        Ok(metadata.len())
-    }
 
-    // -------------------------------------------------------------------------
-    // Embedding operations
-    // -------------------------------------------------------------------------
+        let mut where_clauses: Vec<String> = vec![
+            "documents_fts MATCH ?1".to_string(),
+            "d.active = 1".to_string(),
+        ];
+        let mut params: Vec<libsql::Value> = vec![fts_query.into()];
+        filter.push_sql(&mut where_clauses, &mut params)?;
 
-    /// Insert embeddings for a document chunk
-    /// Column is F32_BLOB(384) which automatically handles the vector format
-    pub async fn insert_embedding(
-        &self,
-        hash: &str,
-        chunk_index: i32,
-        char_offset: i32,
-        model: &str,
-        embedding: &[u8],
-    ) -> Result<()> {
-        let now = Utc::now().to_rfc3339();
+        let query = format!(
+            r#"
+            SELECT d.id, d.collection, d.path, d.file_type, d.modified_at, c.content_type
+            FROM documents_fts
+            JOIN documents d ON d.id = documents_fts.rowid
+            JOIN content c ON c.hash = d.hash
+            WHERE {}
+            "#,
+            where_clauses.join(" AND ")
+        );
 
-        // F32_BLOB column accepts raw bytes (little-endian f32 array)
-        self.conn
-            .execute(
-                "INSERT OR REPLACE INTO embeddings (hash, chunk_index, char_offset, model, embedding, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                params![hash, chunk_index, char_offset, model, embedding, now],
-            )
-            .await?;
+        let mut rows = self.conn.query(&query, params).await?;
 
-        Ok(())
-    }
+        while let Some(row) = rows.next().await? {
+            let content_type: String = row.get(5)?;
+            let is_binary = content_type.starts_with("application/octet")
+                || content_type.starts_with("image/")
+                || content_type.starts_with("audio/")
+                || content_type.starts_with("video/");
 
-    /// Get all embeddings for a document hash
-    pub async fn get_embeddings(&self, hash: &str) -> Result<Vec<EmbeddingRow>> {
-        let mut rows = self
-            .conn
-            .query(
-                "SELECT hash, chunk_index, char_offset, model, embedding, created_at
-             FROM embeddings WHERE hash = ?1 ORDER BY chunk_index",
-                params![hash],
-            )
-            .await?;
+            if is_binary && !include_binary {
+                continue;
+            }
 
-        let mut results = Vec::new();
-        while let Some(row) = rows.next().await? {
-            results.push(EmbeddingRow {
-                hash: row.get(0)?,
-                chunk_index: row.get(1)?,
-                char_offset: row.get(2)?,
-                model: row.get(3)?,
-                embedding: row.get(4)?,
-                created_at: row.get(5)?,
+            results.push(FacetRow {
+                id: row.get(0)?,
+                collection: row.get(1)?,
+                path: row.get(2)?,
+                file_type: row.get(3)?,
+                modified_at: row.get(4)?,
             });
         }
 
         Ok(results)
     }
 
-    /// Check if embeddings exist for a document hash
-    pub async fn has_embeddings(&self, hash: &str) -> Result<bool> {
-        let mut rows = self
-            .conn
-            .query(
-                "SELECT COUNT(*) FROM embeddings WHERE hash = ?1",
-                params![hash],
-            )
-            .await?;
+    /// Get facet attributes for a set of document IDs (e.g., vector candidates)
+    pub async fn facet_rows_for_ids(&self, ids: &[i64]) -> Result<Vec<FacetRow>> {
+        let mut results = Vec::new();
 
-        let count: i64 = if let Some(row) = rows.next().await? {
-            row.get(0)?
-        } else {
-            0
-        };
+        // Batch the IN clause to stay well under SQLite's parameter limit
+        for batch in ids.chunks(500) {
+            let placeholders: Vec<String> = (1..=batch.len()).map(|i| format!("?{}", i)).collect();
+            let query = format!(
+                "SELECT id, collection, path, file_type, modified_at
+             FROM documents WHERE id IN ({}) AND active = 1",
+                placeholders.join(", ")
+            );
+            let params: Vec<libsql::Value> = batch.iter().map(|id| (*id).into()).collect();
+
+            let mut rows = self.conn.query(&query, params).await?;
+            while let Some(row) = rows.next().await? {
+                results.push(FacetRow {
+                    id: row.get(0)?,
+                    collection: row.get(1)?,
+                    path: row.get(2)?,
+                    file_type: row.get(3)?,
+                    modified_at: row.get(4)?,
+                });
+            }
+        }
 
-        Ok(count > 0)
+        Ok(results)
     }
 
-    /// Delete embeddings for a document hash
-    pub async fn delete_embeddings(&self, hash: &str) -> Result<()> {
-        self.conn
-            .execute("DELETE FROM embeddings WHERE hash = ?1", params![hash])
-            .await?;
-        Ok(())
-    }
+    /// List active documents whose content should be scanned by grep.
+    ///
+    /// When `literals` is non-empty, only documents whose text (extracted text
+    /// if any, otherwise raw content) contains at least one of them are
+    /// returned (ASCII case-insensitively when `ignore_case` is set).
+    pub async fn grep_candidates(
+        &self,
+        literals: &[String],
+        ignore_case: bool,
+        filter: &DocumentFilter,
+    ) -> Result<Vec<GrepCandidate>> {
+        let mut where_clauses: Vec<String> = vec!["d.active = 1".to_string()];
+        let mut params: Vec<libsql::Value> = Vec::new();
 
-    /// Count documents with embeddings
+        let text = if ignore_case {
+            "lower(COALESCE(e.text, CAST(c.content AS TEXT)))"
+        } else {
+            "COALESCE(e.text, CAST(c.content AS TEXT))"
+        };
+        let mut any_of = Vec::new();
+        for literal in literals {
+            params.push(literal.as_str().into());
+            if ignore_case {
+                any_of.push(format!("instr({}, lower(?{})) > 0", text, params.len()));
+            } else {
+                any_of.push(format!("instr({}, ?{}) > 0", text, params.len()));
+            }
+        }
+        if !any_of.is_empty() {
+            where_clauses.push(format!("({})", any_of.join(" OR ")));
+        }
+        filter.push_sql(&mut where_clauses, &mut params)?;
+
+        let query = format!(
+            "SELECT d.id, d.collection, d.path, d.hash, c.content_type
+             FROM documents d
+             JOIN content c ON c.hash = d.hash
+             LEFT JOIN extracted_text e ON e.hash = d.hash
+             WHERE {}
+             ORDER BY d.collection, d.path",
+            where_clauses.join(" AND ")
+        );
+
+        let mut rows = self.conn.query(&query, params).await?;
+        let mut results = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            results.push(GrepCandidate {
+                id: row.get(0)?,
+                collection: row.get(1)?,
+                path: row.get(2)?,
+                hash: row.get(3)?,
+                content_type: row.get(4)?,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Find vocabulary terms that may be corrections for `term`.
+    ///
+    /// Combines terms sharing trigrams with `term` (best matches first) with
+    /// terms of similar length sharing its first two characters, which catches
+    /// short transpositions that share no trigram. Returns nothing for terms
+    /// shorter than three characters.
+    pub async fn spelling_candidates(&self, term: &str, limit: usize) -> Result<Vec<String>> {
+        let mut results: Vec<String> = Vec::new();
+
+        let chars: Vec<char> = term.to_lowercase().chars().collect();
+        if chars.len() < 3 {
+            return Ok(results);
+        }
+
+        let trigrams: Vec<String> = chars
+            .windows(3)
+            .map(|w| format!("\"{}\"", w.iter().collect::<String>().replace('"', "\"\"")))
+            .collect();
+
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT term FROM vocabulary_trigram
+             WHERE vocabulary_trigram MATCH ?1
+             ORDER BY rank
+             LIMIT ?2",
+                params![trigrams.join(" OR "), limit as i64],
+            )
+            .await?;
+
+        while let Some(row) = rows.next().await? {
+            results.push(row.get(0)?);
+        }
+
+        let prefix: String = chars[..2].iter().collect();
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT term FROM vocabulary
+             WHERE term >= ?1 AND term < ?1 || char(1114111)
+               AND length(term) BETWEEN ?2 AND ?3
+             LIMIT ?4",
+                params![
+                    prefix,
+                    chars.len() as i64 - 2,
+                    chars.len() as i64 + 2,
+                    limit as i64
+                ],
+            )
+            .await?;
+
+        while let Some(row) = rows.next().await? {
+            let candidate: String = row.get(0)?;
+            if !results.contains(&candidate) {
+                results.push(candidate);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Get the most distinctive terms of a document, ranked by TF-IDF.
+    /// Terms that occur in every document are skipped (unless it is the only one).
+    pub async fn top_terms(&self, id: i64, limit: usize) -> Result<Vec<String>> {
+        let (title, body) = self.get_indexed_text(id).await?;
+
+        let mut tf: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for term in vocabulary_terms(&title).chain(vocabulary_terms(&body)) {
+            *tf.entry(term).or_default() += 1;
+        }
+
+        // Bound the number of document-frequency lookups on long documents
+        let mut candidates: Vec<(String, usize)> = tf.into_iter().collect();
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        candidates.truncate(100);
+
+        let total = self.count_documents(None).await?.max(1) as f64;
+        let mut scored = Vec::with_capacity(candidates.len());
+        for (term, count) in candidates {
+            let df = self
+                .count_bm25_matches(&format!("\"{}\"", term))
+                .await?
+                .max(1) as f64;
+            if df >= total && total > 1.0 {
+                continue;
+            }
+            let idf = (1.0 + (total - df + 0.5) / (df + 0.5)).ln();
+            scored.push((count as f64 * idf, term));
+        }
+
+        scored.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.1.cmp(&b.1))
+        });
+
+        Ok(scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, term)| term)
+            .collect())
+    }
+
+    /// Count active documents matching an FTS query
+    pub async fn count_bm25_matches(&self, fts_query: &str) -> Result<i64> {
+        if fts_query.is_empty() {
+            return Ok(0);
+        }
+
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT COUNT(*) FROM documents_fts
+             JOIN documents d ON d.id = documents_fts.rowid
+             WHERE documents_fts MATCH ?1 AND d.active = 1",
+                params![fts_query],
+            )
+            .await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(row.get(0)?)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Get database file size in bytes
+    pub fn database_size(&self) -> Result<u64> {
+        if self.path.to_str() == Some(":memory:") {
+            return Ok(0);
+        }
+        let metadata = std::fs::metadata(&self.path)?;
+        Ok(metadata.len())
+    }
+
+    // -------------------------------------------------------------------------
+    // Embedding operations
+    // -------------------------------------------------------------------------
+
+    /// Insert embeddings for a document chunk
+    /// Column is F32_BLOB(384) which automatically handles the vector format
+    pub async fn insert_embedding(
+        &self,
+        hash: &str,
+        chunk_index: i32,
+        char_offset: i32,
+        model: &str,
+        embedding: &[u8],
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        // F32_BLOB column accepts raw bytes (little-endian f32 array)
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO embeddings (hash, chunk_index, char_offset, model, embedding, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![hash, chunk_index, char_offset, model, embedding, now],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Cached embedding of `query` for `model`, if any. Queries are keyed by
+    /// `normalize_query_key`, so spacing differences share an entry. Marking
+    /// the entry used is best-effort, so a read-only database still hits.
+    pub async fn get_query_embedding(&self, model: &str, query: &str) -> Result<Option<Vec<f32>>> {
+        let key = normalize_query_key(query);
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT embedding FROM query_embeddings WHERE model = ?1 AND query = ?2",
+                params![model, key.as_str()],
+            )
+            .await?;
+
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        let bytes: Vec<u8> = row.get(0)?;
+        drop(rows);
+
+        if let Err(e) = self
+            .conn
+            .execute(
+                "UPDATE query_embeddings SET last_used_at = ?3 WHERE model = ?1 AND query = ?2",
+                params![model, key.as_str(), Utc::now().to_rfc3339()],
+            )
+            .await
+        {
+            tracing::debug!("Could not mark cached query embedding used: {}", e);
+        }
+
+        Ok(Some(bytes_to_embedding(&bytes)))
+    }
+
+    /// Cache the embedding of `query` for `model`, evicting the least recently
+    /// used entries beyond `QUERY_EMBEDDING_CACHE_SIZE`
+    pub async fn put_query_embedding(
+        &self,
+        model: &str,
+        query: &str,
+        embedding: &[f32],
+    ) -> Result<()> {
+        let key = normalize_query_key(query);
+        let bytes: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+        let now = Utc::now().to_rfc3339();
+
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO query_embeddings (model, query, embedding, created_at, last_used_at)
+                 VALUES (?1, ?2, ?3, ?4, ?4)",
+                params![model, key.as_str(), bytes, now],
+            )
+            .await?;
+        self.conn
+            .execute(
+                "DELETE FROM query_embeddings WHERE rowid IN (
+                     SELECT rowid FROM query_embeddings
+                     ORDER BY last_used_at DESC LIMIT -1 OFFSET ?1
+                 )",
+                params![QUERY_EMBEDDING_CACHE_SIZE as i64],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Drop cached query embeddings, returning how many were removed
+    pub async fn clear_query_embeddings(&self) -> Result<u64> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM query_embeddings", ())
+            .await?)
+    }
+
+    /// Get all embeddings for a document hash
+    pub async fn get_embeddings(&self, hash: &str) -> Result<Vec<EmbeddingRow>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT hash, chunk_index, char_offset, model, embedding, created_at
+             FROM embeddings WHERE hash = ?1 ORDER BY chunk_index",
+                params![hash],
+            )
+            .await?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().await? {
+            results.push(EmbeddingRow {
+                hash: row.get(0)?,
+                chunk_index: row.get(1)?,
+                char_offset: row.get(2)?,
+                model: row.get(3)?,
+                embedding: row.get(4)?,
+                created_at: row.get(5)?,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Check if embeddings exist for a document hash
+    pub async fn has_embeddings(&self, hash: &str) -> Result<bool> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT COUNT(*) FROM embeddings WHERE hash = ?1",
+                params![hash],
+            )
+            .await?;
+
+        let count: i64 = if let Some(row) = rows.next().await? {
+            row.get(0)?
+        } else {
+            0
+        };
+
+        Ok(count > 0)
+    }
+
+    /// Delete embeddings for a document hash
+    pub async fn delete_embeddings(&self, hash: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM embeddings WHERE hash = ?1", params![hash])
+            .await?;
+        Ok(())
+    }
+
+    // -------------------------------------------------------------------------
+    // Passage chunk operations
+    // -------------------------------------------------------------------------
+
+    /// First line of a passage chunk, if the document has been chunked
+    pub async fn chunk_line_start(&self, hash: &str, chunk_index: i32) -> Result<Option<u32>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT line_start FROM chunks WHERE hash = ?1 AND chunk_index = ?2",
+                params![hash, chunk_index],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get::<i64>(0)?.max(1) as u32)),
+            None => Ok(None),
+        }
+    }
+
+    /// Insert or replace a passage chunk and its full-text entry
+    pub async fn insert_chunk(&self, chunk: &ChunkRow, body: &str) -> Result<()> {
+        let mut rows = self
+            .conn
+            .query(
+                "INSERT INTO chunks (hash, chunk_index, char_offset, char_length, line_start, line_end)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(hash, chunk_index) DO UPDATE SET
+               char_offset = excluded.char_offset,
+               char_length = excluded.char_length,
+               line_start = excluded.line_start,
+               line_end = excluded.line_end
+             RETURNING id",
+                params![
+                    chunk.hash.as_str(),
+                    chunk.chunk_index,
+                    chunk.char_offset,
+                    chunk.char_length,
+                    chunk.line_start,
+                    chunk.line_end
+                ],
+            )
+            .await?;
+
+        let id: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => return Err(Error::IndexError("Failed to insert chunk".to_string())),
+        };
+        drop(rows);
+
+        // FTS5 doesn't support ON CONFLICT, so delete first
+        self.conn
+            .execute("DELETE FROM chunks_fts WHERE rowid = ?1", params![id])
+            .await?;
+        self.conn
+            .execute(
+                "INSERT INTO chunks_fts (rowid, body) VALUES (?1, ?2)",
+                params![id, body],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete passage chunks for a document hash
+    pub async fn delete_chunks(&self, hash: &str) -> Result<()> {
+        self.conn
+            .execute(
+                "DELETE FROM chunks_fts WHERE rowid IN (SELECT id FROM chunks WHERE hash = ?1)",
+                params![hash],
+            )
+            .await?;
+        self.conn
+            .execute("DELETE FROM chunks WHERE hash = ?1", params![hash])
+            .await?;
+        Ok(())
+    }
+
+    /// Count active documents matching `filter` that have passage chunks
+    pub async fn count_chunked_documents(&self, filter: &DocumentFilter) -> Result<i64> {
+        let mut where_clauses: Vec<String> = vec![
+            "d.active = 1".to_string(),
+            "EXISTS (SELECT 1 FROM chunks ch WHERE ch.hash = d.hash)".to_string(),
+        ];
+        let mut params: Vec<libsql::Value> = Vec::new();
+        filter.push_sql(&mut where_clauses, &mut params)?;
+
+        let query = format!(
+            "SELECT COUNT(*) FROM documents d WHERE {}",
+            where_clauses.join(" AND ")
+        );
+        let mut rows = self.conn.query(&query, params).await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(row.get(0)?)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Passage-level BM25 search over chunks_fts.
+    /// Applies the same filters as search_bm25(); returns one row per matching chunk.
+    pub async fn search_passages_bm25(
+        &self,
+        fts_query: &str,
+        filter: &DocumentFilter,
+        limit: usize,
+        include_binary: bool,
+    ) -> Result<Vec<PassageResultRow>> {
+        let mut results = Vec::new();
+
+        if fts_query.is_empty() {
+            return Ok(results);
+        }
+
+        let mut where_clauses: Vec<String> = vec![
+            "chunks_fts MATCH ?1".to_string(),
+            "d.active = 1".to_string(),
+        ];
+        let mut params: Vec<libsql::Value> = vec![fts_query.into()];
+        filter.push_sql(&mut where_clauses, &mut params)?;
+        params.push((limit as i64).into());
+
+        let query = format!(
+            r#"
+            SELECT
+                d.id,
+                d.collection,
+                d.path,
+                d.title,
+                d.hash,
+                d.file_type,
+                c.content_type,
+                c.size,
+                ch.chunk_index,
+                ch.line_start,
+                ch.line_end,
+                bm25(chunks_fts) as bm25_score,
+                snippet(chunks_fts, 0, '<mark>', '</mark>', '...', 32) as snippet
+            FROM chunks_fts
+            JOIN chunks ch ON ch.id = chunks_fts.rowid
+            JOIN documents d ON d.hash = ch.hash
+            JOIN content c ON c.hash = d.hash
+            WHERE {}
+            ORDER BY bm25_score
+            LIMIT ?{}
+            "#,
+            where_clauses.join(" AND "),
+            params.len()
+        );
+
+        let mut rows = self.conn.query(&query, params).await?;
+
+        while let Some(row) = rows.next().await? {
+            let content_type: String = row.get(6)?;
+            let is_binary = content_type.starts_with("application/octet")
+                || content_type.starts_with("image/")
+                || content_type.starts_with("audio/")
+                || content_type.starts_with("video/");
+
+            if is_binary && !include_binary {
+                continue;
+            }
+
+            results.push(PassageResultRow {
+                id: row.get(0)?,
+                collection: row.get(1)?,
+                path: row.get(2)?,
+                title: row.get(3)?,
+                hash: row.get(4)?,
+                file_type: row.get(5)?,
+                content_type,
+                size: row.get(7)?,
+                chunk_index: row.get(8)?,
+                line_start: row.get(9)?,
+                line_end: row.get(10)?,
+                bm25_score: row.get(11)?,
+                snippet: row.get(12)?,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Count documents with embeddings
     pub async fn count_embeddings(&self, collection: Option<&str>) -> Result<i64> {
         let count: i64 = if let Some(coll) = collection {
             let mut rows = self
@@ -1178,27 +2315,14 @@ impl Store {
     /// DEPRECATED: Use search_vector_native() for efficient native vector search
     pub async fn get_all_embeddings_for_search(
         &self,
-        collection: Option<&str>,
-        from_date: Option<&str>,
-        to_date: Option<&str>,
+        filter: &DocumentFilter,
     ) -> Result<Vec<EmbeddingSearchRow>> {
         let mut results = Vec::new();
 
         // Build query dynamically based on filters
         let mut where_clauses: Vec<String> = vec!["d.active = 1".to_string()];
-        let mut param_idx = 1;
-
-        if collection.is_some() {
-            where_clauses.push(format!("d.collection = ?{}", param_idx));
-            param_idx += 1;
-        }
-        if from_date.is_some() {
-            where_clauses.push(format!("d.modified_at >= ?{}", param_idx));
-            param_idx += 1;
-        }
-        if to_date.is_some() {
-            where_clauses.push(format!("d.modified_at <= ?{}", param_idx));
-        }
+        let mut params: Vec<libsql::Value> = vec![];
+        filter.push_sql(&mut where_clauses, &mut params)?;
 
         let query = format!(
             r#"
@@ -1220,18 +2344,6 @@ impl Store {
             where_clauses.join(" AND ")
         );
 
-        // Build params dynamically
-        let mut params: Vec<libsql::Value> = vec![];
-        if let Some(coll) = collection {
-            params.push(coll.into());
-        }
-        if let Some(from) = from_date {
-            params.push(from.into());
-        }
-        if let Some(to) = to_date {
-            params.push(to.into());
-        }
-
         let mut rows = self.conn.query(&query, params).await?;
 
         while let Some(row) = rows.next().await? {
@@ -1257,57 +2369,244 @@ impl Store {
         schema::ensure_vector_index(&self.conn).await
     }
 
-    /// Check if native vector search is available
-    pub async fn has_vector_index(&self) -> bool {
-        schema::has_vector_index(&self.conn).await
+    /// Check if native vector search is available
+    pub async fn has_vector_index(&self) -> bool {
+        schema::has_vector_index(&self.conn).await
+    }
+
+    /// Inspect the native vector index: parameters, size, legacy embeddings
+    /// and which search path vector queries take
+    pub async fn vector_index_status(&self) -> Result<VectorIndexStatus> {
+        vector_index::status(&self.conn).await
+    }
+
+    /// Drop and recreate the vector index with the given parameters.
+    /// The parameters are saved and reused when the index is created lazily.
+    pub async fn rebuild_vector_index(&self, params: &VectorIndexParams) -> Result<()> {
+        vector_index::rebuild(&self.conn, params).await
+    }
+
+    /// Drop the vector index (searches use the legacy path until it is rebuilt)
+    pub async fn drop_vector_index(&self) -> Result<bool> {
+        vector_index::drop_index(&self.conn).await
+    }
+
+    /// Rewrite legacy raw-BLOB embeddings into libsql's vector format and
+    /// create the index over them
+    pub async fn convert_legacy_embeddings(&self) -> Result<ConvertStats> {
+        vector_index::convert_legacy(&self.conn).await
+    }
+
+    /// Vector search using the native index when available, legacy search
+    /// otherwise. Returns the results and the path that produced them.
+    pub async fn search_vector(
+        &self,
+        query_embedding: &[f32],
+        filter: &DocumentFilter,
+        limit: usize,
+    ) -> Result<(Vec<VectorSearchResult>, VectorSearchPath)> {
+        if let Some(results) = self
+            .search_vector_native(query_embedding, filter, limit)
+            .await?
+        {
+            return Ok((results, VectorSearchPath::Native));
+        }
+
+        let results = self
+            .search_vector_legacy(query_embedding, filter, limit)
+            .await?;
+        Ok((results, VectorSearchPath::Legacy))
+    }
+
+    /// Native vector search using libsql's vector_top_k()
+    /// Uses the idx_embeddings_vector index for efficient KNN search over the
+    /// documents matching `filter`.
+    /// Returns None if native search is not available (falls back to legacy).
+    ///
+    /// `vector_top_k` ranks the whole index, so filters are applied to its
+    /// neighbours afterwards. When that leaves fewer than `limit` rows the
+    /// neighbour count is grown geometrically until enough rows survive; if
+    /// the whole index has been asked for and a filtered search is still
+    /// short, the filtered candidates are scored exactly instead.
+    pub async fn search_vector_native(
+        &self,
+        query_embedding: &[f32],
+        filter: &DocumentFilter,
+        limit: usize,
+    ) -> Result<Option<Vec<VectorSearchResult>>> {
+        // Try to ensure vector index exists
+        self.ensure_vector_index().await?;
+
+        // Check if index is actually available
+        if !self.has_vector_index().await {
+            return Ok(None); // Fall back to legacy search
+        }
+
+        // Convert f32 embedding to bytes (little-endian)
+        let embedding_bytes: Vec<u8> = query_embedding
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+
+        // Rank by the metric the index was built with
+        let l2 = self.vector_metric_is_l2().await?;
+
+        // Build WHERE clause dynamically
+        // Note: ?1 = embedding bytes, ?2 = number of neighbours
+        let mut where_clauses: Vec<String> = vec!["d.active = 1".to_string()];
+        let mut filter_params: Vec<libsql::Value> = vec![libsql::Value::Null, libsql::Value::Null];
+        filter.push_sql(&mut where_clauses, &mut filter_params)?;
+
+        let query = format!(
+            r#"
+            SELECT
+                e.hash,
+                e.chunk_index,
+                e.char_offset,
+                d.id,
+                d.collection,
+                d.path,
+                d.title,
+                d.file_type,
+                {}(e.embedding, ?1) as distance
+            FROM vector_top_k('idx_embeddings_vector', ?1, ?2) AS top_k
+            JOIN embeddings e ON e.rowid = top_k.id
+            JOIN documents d ON d.hash = e.hash
+            WHERE {}
+            ORDER BY distance ASC
+            LIMIT {}
+            "#,
+            if l2 {
+                "vector_distance_l2"
+            } else {
+                "vector_distance_cos"
+            },
+            where_clauses.join(" AND "),
+            limit
+        );
+
+        let total = self.count_all_embeddings().await?;
+        let filtered = !filter.is_empty();
+
+        // Filters discard neighbours, so start wider than the limit
+        let mut neighbours = if filtered {
+            limit.saturating_mul(VECTOR_OVERFETCH_FACTOR)
+        } else {
+            limit
+        };
+
+        loop {
+            neighbours = neighbours.clamp(1, total.max(1));
+
+            let mut params = filter_params.clone();
+            params[0] = embedding_bytes.clone().into();
+            params[1] = (neighbours as i64).into();
+
+            let query_result = self.conn.query(&query, params).await;
+
+            // If the query fails (e.g., index not working), return None to trigger fallback
+            let mut rows = match query_result {
+                Ok(rows) => rows,
+                Err(e) => {
+                    tracing::debug!("Native vector search failed, using legacy: {}", e);
+                    return Ok(None);
+                }
+            };
+
+            let mut results = Vec::new();
+
+            while let Some(row) = rows.next().await? {
+                let distance: f64 = row.get(8)?;
+                let similarity = if l2 {
+                    1.0 / (1.0 + distance)
+                } else {
+                    1.0 - distance
+                };
+
+                results.push(VectorSearchResult {
+                    hash: row.get(0)?,
+                    chunk_index: row.get(1)?,
+                    char_offset: row.get(2)?,
+                    doc_id: row.get(3)?,
+                    collection: row.get(4)?,
+                    path: row.get(5)?,
+                    title: row.get(6)?,
+                    file_type: row.get(7)?,
+                    similarity,
+                });
+            }
+
+            if results.len() >= limit {
+                return Ok(Some(results));
+            }
+
+            // The index is approximate and may never surface some rows, so a
+            // selective filter finishes with an exact scan of its candidates
+            if neighbours >= total {
+                if !filtered {
+                    return Ok(Some(results));
+                }
+                tracing::debug!("Vector index exhausted for filtered search, scanning exactly");
+                let exact = self
+                    .search_vector_legacy(query_embedding, filter, limit)
+                    .await?;
+                return Ok(Some(exact));
+            }
+
+            tracing::debug!(
+                "Vector search kept {} of {} neighbours, widening",
+                results.len(),
+                neighbours
+            );
+            neighbours = neighbours.saturating_mul(VECTOR_OVERFETCH_FACTOR);
+        }
+    }
+
+    /// Whether the vector index ranks by Euclidean distance rather than cosine
+    async fn vector_metric_is_l2(&self) -> Result<bool> {
+        Ok(vector_index::current_params(&self.conn).await?.metric == "l2")
+    }
+
+    /// Count all stored embedding rows, including those of inactive documents
+    async fn count_all_embeddings(&self) -> Result<usize> {
+        let mut rows = self
+            .conn
+            .query("SELECT COUNT(*) FROM embeddings", ())
+            .await?;
+
+        let count: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+
+        Ok(count as usize)
     }
 
-    /// Native vector search using libsql's vector_top_k()
-    /// Uses the idx_embeddings_vector index for efficient KNN search.
-    /// Supports optional filtering by collection and date range (modified_at).
-    /// Returns None if native search is not available (falls back to legacy).
-    pub async fn search_vector_native(
+    /// Legacy vector search - scores embeddings in Rust without an index.
+    /// Used as fallback when native vector search is not available.
+    /// Only embeddings of documents matching `filter` are scored.
+    ///
+    /// Rows are streamed and scored one at a time against the normalized
+    /// query, keeping only the best `limit` in a bounded heap, so memory use
+    /// doesn't grow with the number of embeddings. Scores use the vector
+    /// index's metric, so the exact scan ranks like the index.
+    pub async fn search_vector_legacy(
         &self,
         query_embedding: &[f32],
-        collection: Option<&str>,
+        filter: &DocumentFilter,
         limit: usize,
-        from_date: Option<&str>,
-        to_date: Option<&str>,
-    ) -> Result<Option<Vec<VectorSearchResult>>> {
-        // Try to ensure vector index exists
-        self.ensure_vector_index().await?;
-
-        // Check if index is actually available
-        if !self.has_vector_index().await {
-            return Ok(None); // Fall back to legacy search
-        }
-
-        // Convert f32 embedding to bytes (little-endian)
-        let embedding_bytes: Vec<u8> = query_embedding
-            .iter()
-            .flat_map(|f| f.to_le_bytes())
-            .collect();
-
+    ) -> Result<Vec<VectorSearchResult>> {
         // Build WHERE clause dynamically
-        // Note: ?1 = embedding bytes, ?2 = limit
         let mut where_clauses: Vec<String> = vec!["d.active = 1".to_string()];
-        let mut param_idx = 3;
-
-        if collection.is_some() {
-            where_clauses.push(format!("d.collection = ?{}", param_idx));
-            param_idx += 1;
-        }
-        if from_date.is_some() {
-            where_clauses.push(format!("d.modified_at >= ?{}", param_idx));
-            param_idx += 1;
-        }
-        if to_date.is_some() {
-            where_clauses.push(format!("d.modified_at <= ?{}", param_idx));
-        }
+        let mut params: Vec<libsql::Value> = vec![];
+        filter.push_sql(&mut where_clauses, &mut params)?;
 
+        // No ORDER BY: sorting would make SQLite materialize every row, blobs
+        // included, before the first one streams. TopK breaks ties itself.
         let query = format!(
             r#"
             SELECT
+                e.embedding,
                 e.hash,
                 e.chunk_index,
                 e.char_offset,
@@ -1315,107 +2614,51 @@ impl Store {
                 d.collection,
                 d.path,
                 d.title,
-                d.file_type,
-                vector_distance_cos(e.embedding, ?1) as distance
-            FROM vector_top_k('idx_embeddings_vector', ?1, ?2) AS top_k
-            JOIN embeddings e ON e.rowid = top_k.id
+                d.file_type
+            FROM embeddings e
             JOIN documents d ON d.hash = e.hash
             WHERE {}
-            ORDER BY distance ASC
             "#,
             where_clauses.join(" AND ")
         );
 
-        // Build params dynamically
-        let mut params: Vec<libsql::Value> = vec![
-            embedding_bytes.into(),
-            (limit as i64).into(),
-        ];
-        if let Some(coll) = collection {
-            params.push(coll.into());
-        }
-        if let Some(from) = from_date {
-            params.push(from.into());
-        }
-        if let Some(to) = to_date {
-            params.push(to.into());
-        }
-
-        let query_result = self.conn.query(&query, params).await;
+        // Score like the native index would, so both paths rank alike
+        let l2 = self.vector_metric_is_l2().await?;
+        let unit_query = vector_scan::normalize(query_embedding);
+        let mut top = vector_scan::TopK::new(limit);
 
-        // If the query fails (e.g., index not working), return None to trigger fallback
-        let mut rows = match query_result {
-            Ok(rows) => rows,
-            Err(e) => {
-                tracing::debug!("Native vector search failed, using legacy: {}", e);
-                return Ok(None);
-            }
-        };
-
-        let mut results = Vec::new();
+        let mut rows = self.conn.query(&query, params).await?;
 
         while let Some(row) = rows.next().await? {
-            let distance: f64 = row.get(8)?;
-            // Convert cosine distance to similarity (1 - distance for cosine)
-            let similarity = 1.0 - distance;
+            let embedding: Vec<u8> = row.get(0)?;
+            let similarity = if l2 {
+                vector_scan::l2_similarity(query_embedding, &embedding)
+            } else {
+                vector_scan::cosine_to_unit(&unit_query, &embedding)
+            };
 
-            results.push(VectorSearchResult {
-                hash: row.get(0)?,
-                chunk_index: row.get(1)?,
-                char_offset: row.get(2)?,
-                doc_id: row.get(3)?,
-                collection: row.get(4)?,
-                path: row.get(5)?,
-                title: row.get(6)?,
-                file_type: row.get(7)?,
+            // Only materialize rows that make it into the top k
+            if !top.accepts(similarity) {
+                continue;
+            }
+
+            top.push(
                 similarity,
-            });
+                VectorSearchResult {
+                    hash: row.get(1)?,
+                    chunk_index: row.get(2)?,
+                    char_offset: row.get(3)?,
+                    doc_id: row.get(4)?,
+                    collection: row.get(5)?,
+                    path: row.get(6)?,
+                    title: row.get(7)?,
+                    file_type: row.get(8)?,
+                    similarity: similarity as f64,
+                },
+            );
         }
 
-        Ok(Some(results))
-    }
-
-    /// Legacy vector search - loads all embeddings and calculates similarity in Rust
-    /// Used as fallback when native vector search is not available
-    /// Supports optional date filtering (modified_at)
-    pub async fn search_vector_legacy(
-        &self,
-        query_embedding: &[f32],
-        collection: Option<&str>,
-        limit: usize,
-        from_date: Option<&str>,
-        to_date: Option<&str>,
-    ) -> Result<Vec<VectorSearchResult>> {
-        let embeddings = self
-            .get_all_embeddings_for_search(collection, from_date, to_date)
-            .await?;
-
-        let mut scored: Vec<(f64, EmbeddingSearchRow)> = embeddings
-            .into_iter()
-            .map(|row| {
-                let embedding = bytes_to_embedding(&row.embedding);
-                let similarity = cosine_similarity(query_embedding, &embedding);
-                (similarity as f64, row)
-            })
-            .collect();
-
-        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-
-        Ok(scored
-            .into_iter()
-            .take(limit)
-            .map(|(similarity, row)| VectorSearchResult {
-                hash: row.hash,
-                chunk_index: row.chunk_index,
-                char_offset: row.char_offset,
-                doc_id: row.doc_id,
-                collection: row.collection,
-                path: row.path,
-                title: row.title,
-                file_type: row.file_type,
-                similarity,
-            })
-            .collect())
+        Ok(top.into_sorted().into_iter().map(|(_, row)| row).collect())
     }
 
     // -------------------------------------------------------------------------
@@ -1556,9 +2799,8 @@ impl Store {
 
             // Get document and content
             if let Ok(doc) = self.get_document(&collection, &path).await {
-                if let Ok(content) = self.get_content(&doc.hash).await {
-                    let mut text = String::from_utf8(content.data.clone())
-                        .unwrap_or_else(|_| "[Binary content]".to_string());
+                if let Ok(text) = self.get_text(&doc.hash).await {
+                    let mut text = text.unwrap_or_else(|| "[Binary content]".to_string());
 
                     // Apply line limit if specified
                     if let Some(limit) = max_lines {
@@ -1612,36 +2854,144 @@ pub struct VectorSearchResult {
     pub path: String,
     pub title: Option<String>,
     pub file_type: String,
-    /// Cosine similarity score (0.0 - 1.0)
+    /// Similarity score (0.0 - 1.0): cosine similarity, or `1 / (1 + d)`
+    /// for the Euclidean distance `d` when the index uses `metric=l2`
     pub similarity: f64,
 }
 
-/// Convert bytes to f32 embedding (for legacy vector search)
-fn bytes_to_embedding(bytes: &[u8]) -> Vec<f32> {
-    bytes
-        .chunks_exact(4)
-        .map(|chunk| {
-            let arr: [u8; 4] = chunk.try_into().unwrap();
-            f32::from_le_bytes(arr)
+/// Score `pattern` as a case-insensitive subsequence of `candidate`, fzf-style.
+///
+/// Each matched character scores points, with bonuses for matches at word
+/// boundaries (after `/`, `_`, `-`, `.`, space, or a lower-to-upper case
+/// change), for consecutive runs, and for matches in the final path segment.
+/// Gaps between matches cost a point each. Punctuation in the pattern is
+/// ignored, so `rust-guide` matches `rust_guide.md`. The score is that of the
+/// best alignment, so `cfg` scores the same against `config/src/cfg.rs` as
+/// against `src/cfg.rs`. Returns None if the pattern is not a subsequence of
+/// the candidate.
+fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    const MATCH: i64 = 16;
+    const BOUNDARY: i64 = 8;
+    const CONSECUTIVE: i64 = 4;
+    const FILENAME: i64 = 2;
+
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let chars: Vec<char> = candidate.chars().collect();
+    let filename_start = chars.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+
+    let is_match = |i: usize, j: usize| chars[i].to_lowercase().eq(std::iter::once(pattern[j]));
+    let points = |i: usize| {
+        let at_boundary = match i.checked_sub(1).map(|p| chars[p]) {
+            None => true,
+            Some(p) => {
+                matches!(p, '/' | '_' | '-' | '.' | ' ')
+                    || (p.is_lowercase() && chars[i].is_uppercase())
+            }
+        };
+        MATCH
+            + if at_boundary { BOUNDARY } else { 0 }
+            + if i >= filename_start { FILENAME } else { 0 }
+    };
+
+    // best[i]: best score of the pattern so far with its last character
+    // matched at position i of the candidate
+    let mut best: Vec<Option<i64>> = (0..chars.len())
+        .map(|i| is_match(i, 0).then(|| points(i)))
+        .collect();
+    for j in 1..pattern.len() {
+        let mut next = vec![None; chars.len()];
+        // Best of best[k] + k over k < i - 1, to charge the gap from k to i
+        let mut before_gap: Option<i64> = None;
+        for i in 0..chars.len() {
+            if i >= 2 {
+                let gapped = best[i - 2].map(|score| score + (i - 2) as i64);
+                before_gap = before_gap.max(gapped);
+            }
+            if !is_match(i, j) {
+                continue;
+            }
+            let consecutive = i
+                .checked_sub(1)
+                .and_then(|k| best[k])
+                .map(|score| score + CONSECUTIVE);
+            let gapped = before_gap.map(|score| score + 1 - i as i64);
+            next[i] = consecutive.max(gapped).map(|score| score + points(i));
+        }
+        best = next;
+    }
+
+    best.into_iter().flatten().max()
+}
+
+/// Split text into lowercased terms for the spelling vocabulary.
+/// Keeps words of 3-40 characters that contain at least one letter.
+fn vocabulary_terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| {
+            let len = word.chars().count();
+            (3..=40).contains(&len) && word.chars().any(|c| c.is_alphabetic())
         })
-        .collect()
+        .map(|word| word.to_lowercase())
 }
 
-/// Calculate cosine similarity between two embeddings (for legacy vector search)
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    if a.len() != b.len() || a.is_empty() {
-        return 0.0;
+/// Add terms to the spelling vocabulary and its trigram index.
+/// Terms already present are skipped.
+async fn insert_vocabulary(conn: &Connection, terms: &[String]) -> Result<()> {
+    if terms.is_empty() {
+        return Ok(());
     }
 
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let mut rows = conn
+        .query("SELECT COALESCE(MAX(rowid), 0) FROM vocabulary", ())
+        .await?;
+    let last_rowid: i64 = match rows.next().await? {
+        Some(row) => row.get(0)?,
+        None => 0,
+    };
 
-    if norm_a == 0.0 || norm_b == 0.0 {
-        return 0.0;
+    for batch in terms.chunks(500) {
+        let placeholders: Vec<String> = (1..=batch.len()).map(|i| format!("(?{})", i)).collect();
+        let query = format!(
+            "INSERT OR IGNORE INTO vocabulary (term) VALUES {}",
+            placeholders.join(", ")
+        );
+        let params: Vec<libsql::Value> = batch.iter().map(|t| t.as_str().into()).collect();
+        conn.execute(&query, params).await?;
     }
 
-    dot / (norm_a * norm_b)
+    // Only newly inserted terms get a rowid above the previous maximum
+    conn.execute(
+        "INSERT INTO vocabulary_trigram (rowid, term)
+         SELECT rowid, term FROM vocabulary WHERE rowid > ?1",
+        params![last_rowid],
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Cache key for a query: trimmed, with runs of whitespace collapsed
+pub fn normalize_query_key(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Convert bytes to f32 embedding (for legacy vector search)
+pub(crate) fn bytes_to_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| {
+            let arr: [u8; 4] = chunk.try_into().unwrap();
+            f32::from_le_bytes(arr)
+        })
+        .collect()
 }
 
 /// Row for embedding search (joined with document info)
@@ -1714,6 +3064,109 @@ mod tests {
         assert_eq!(content.content_type, "text/plain");
     }
 
+    #[tokio::test]
+    async fn test_extracted_text() {
+        let store = Store::open_memory().await.unwrap();
+
+        store
+            .insert_content("text", b"plain text", "text/plain")
+            .await
+            .unwrap();
+        store
+            .insert_content("bin", b"\x00\x01", "application/octet-stream")
+            .await
+            .unwrap();
+        store
+            .insert_content("pdf", b"%PDF-1.4\n\x00", "application/pdf")
+            .await
+            .unwrap();
+        store
+            .insert_extracted_text("pdf", "page one\n\npage two", &[1, 3])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_text("text").await.unwrap().as_deref(),
+            Some("plain text")
+        );
+        assert_eq!(store.get_text("bin").await.unwrap(), None);
+        assert_eq!(
+            store.get_text("pdf").await.unwrap().as_deref(),
+            Some("page one\n\npage two")
+        );
+        assert!(store.has_extracted_text("pdf").await.unwrap());
+        assert!(!store.has_extracted_text("text").await.unwrap());
+        assert_eq!(store.get_pages("pdf").await.unwrap(), vec![1, 3]);
+        assert!(store.get_pages("text").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_document_metadata_and_parts() {
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("mail", "/tmp/mail", &["**/*.mbox"])
+            .await
+            .unwrap();
+        store
+            .insert_content("h", b"message", "message/rfc822")
+            .await
+            .unwrap();
+
+        let mut ids = Vec::new();
+        for path in [
+            "team_a.mbox",
+            "team_a.mbox!/1.eml",
+            "team_a.mbox!/1.eml!/notes.md",
+            "teamXa.mbox!/1.eml",
+        ] {
+            ids.push(
+                store
+                    .upsert_document("mail", path, None, "h", ".eml", "")
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let metadata = serde_json::json!({
+            "from": "Alice <alice@example.com>",
+            "attachments": ["notes.md", "plan.csv"],
+            "messages": 2,
+            "headers": {"x-mailer": "ignored"}
+        });
+        let metadata = metadata.as_object().unwrap().clone().into_iter().collect();
+        store
+            .set_document_metadata(ids[1], &metadata)
+            .await
+            .unwrap();
+        store
+            .set_document_metadata(ids[1], &metadata)
+            .await
+            .unwrap();
+
+        let stored = store.get_document_metadata(ids[1]).await.unwrap();
+        assert_eq!(stored["attachments"], vec!["notes.md", "plan.csv"]);
+        assert_eq!(stored["messages"], vec!["2"]);
+        assert!(!stored.contains_key("headers"));
+        assert!(store
+            .get_document_metadata(ids[0])
+            .await
+            .unwrap()
+            .is_empty());
+
+        // `_` in the container name is not a wildcard
+        assert_eq!(
+            store.list_part_paths("mail", "team_a.mbox").await.unwrap(),
+            vec!["team_a.mbox!/1.eml", "team_a.mbox!/1.eml!/notes.md"]
+        );
+
+        store.remove_collection("mail").await.unwrap();
+        assert!(store
+            .get_document_metadata(ids[1])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_document_operations() {
         let store = Store::open_memory().await.unwrap();
@@ -1831,7 +3284,7 @@ mod tests {
 
         // Search for "rust"
         let results = store
-            .search_bm25("\"rust\"*", None, 10, false, None, None)
+            .search_bm25("\"rust\"*", &DocumentFilter::default(), 10, false)
             .await
             .unwrap();
         assert!(!results.is_empty(), "Should find results for 'rust'");
@@ -1839,27 +3292,37 @@ mod tests {
 
         // Search for "programming"
         let results = store
-            .search_bm25("\"programming\"*", None, 10, false, None, None)
+            .search_bm25("\"programming\"*", &DocumentFilter::default(), 10, false)
             .await
             .unwrap();
         assert!(!results.is_empty(), "Should find results for 'programming'");
 
         // Search for "language"
         let results = store
-            .search_bm25("\"language\"*", None, 10, false, None, None)
+            .search_bm25("\"language\"*", &DocumentFilter::default(), 10, false)
             .await
             .unwrap();
         assert_eq!(results.len(), 2, "Should find 2 results for 'language'");
 
         // Search with collection filter
         let results = store
-            .search_bm25("\"rust\"*", Some("test"), 10, false, None, None)
+            .search_bm25(
+                "\"rust\"*",
+                &DocumentFilter::new(Some("test"), None, None),
+                10,
+                false,
+            )
             .await
             .unwrap();
         assert!(!results.is_empty());
 
         let results = store
-            .search_bm25("\"rust\"*", Some("nonexistent"), 10, false, None, None)
+            .search_bm25(
+                "\"rust\"*",
+                &DocumentFilter::new(Some("nonexistent"), None, None),
+                10,
+                false,
+            )
             .await
             .unwrap();
         assert!(results.is_empty());
@@ -1938,6 +3401,173 @@ mod tests {
         assert_eq!(doc.path, "file.md");
     }
 
+    #[tokio::test]
+    async fn test_spelling_candidates() {
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("test", "/tmp/test", &["**/*.md"])
+            .await
+            .unwrap();
+        store
+            .insert_content("hash1", b"Deploying to Kubernetes", "text/markdown")
+            .await
+            .unwrap();
+        store
+            .upsert_document(
+                "test",
+                "k8s.md",
+                Some("Kubernetes"),
+                "hash1",
+                ".md",
+                "Deploying to Kubernetes clusters",
+            )
+            .await
+            .unwrap();
+
+        let candidates = store.spelling_candidates("kubrenetes", 10).await.unwrap();
+        assert_eq!(candidates.first().map(String::as_str), Some("kubernetes"));
+
+        // Too short to form a trigram
+        assert!(store
+            .spelling_candidates("k8", 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(store.count_bm25_matches("\"kubernetes\"").await.unwrap(), 1);
+        assert_eq!(store.count_bm25_matches("\"kubrenetes\"").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_chunks_replace_and_delete() {
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("test", "/tmp/test", &["**/*.md"])
+            .await
+            .unwrap();
+        store
+            .insert_content("hash1", b"alpha", "text/markdown")
+            .await
+            .unwrap();
+        store
+            .upsert_document("test", "a.md", None, "hash1", ".md", "alpha")
+            .await
+            .unwrap();
+        let mut chunk = ChunkRow {
+            hash: "hash1".to_string(),
+            chunk_index: 0,
+            char_offset: 0,
+            char_length: 5,
+            line_start: 1,
+            line_end: 1,
+        };
+        store.insert_chunk(&chunk, "alpha").await.unwrap();
+
+        // Re-inserting the same chunk replaces its text
+        chunk.char_length = 4;
+        store.insert_chunk(&chunk, "beta").await.unwrap();
+        assert_eq!(
+            store
+                .count_chunked_documents(&DocumentFilter::default())
+                .await
+                .unwrap(),
+            1
+        );
+        // Only documents matching the filter count
+        let other = DocumentFilter::new(Some("other"), None, None);
+        assert_eq!(store.count_chunked_documents(&other).await.unwrap(), 0);
+
+        let mut rows = store
+            .conn
+            .query("SELECT body FROM chunks_fts", ())
+            .await
+            .unwrap();
+        let body: String = rows.next().await.unwrap().unwrap().get(0).unwrap();
+        assert_eq!(body, "beta");
+        assert!(rows.next().await.unwrap().is_none());
+        drop(rows);
+
+        store.delete_chunks("hash1").await.unwrap();
+        assert_eq!(
+            store
+                .count_chunked_documents(&DocumentFilter::default())
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("rdme", "docs/README.md").is_some());
+        assert!(fuzzy_score("xyz", "docs/README.md").is_none());
+        assert!(fuzzy_score("rust-guide", "docs/rust_guide.md").is_some());
+
+        // Boundary and consecutive matches beat scattered ones
+        let exact = fuzzy_score("readme", "docs/readme.md").unwrap();
+        let scattered = fuzzy_score("readme", "docs/rust/ead/me.md").unwrap();
+        assert!(exact > scattered);
+
+        // Matches in the file name beat matches in directories
+        let filename = fuzzy_score("api", "docs/notes/api.md").unwrap();
+        let dirname = fuzzy_score("api", "docs/api/notes.md").unwrap();
+        assert!(filename > dirname);
+
+        // An earlier scattered match doesn't hide a better one later on
+        assert_eq!(
+            fuzzy_score("cfg", "config/src/cfg.rs"),
+            fuzzy_score("cfg", "src/cfg.rs")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_paths() {
+        let store = Store::open_memory().await.unwrap();
+        store
+            .add_collection("docs", "/tmp/docs", &["**/*.md"])
+            .await
+            .unwrap();
+        store
+            .insert_content("abcdef123456", b"", "text/markdown")
+            .await
+            .unwrap();
+
+        for (path, title) in [
+            ("guide/getting-started.md", "Getting Started"),
+            ("reference/api.md", "API Reference"),
+            ("notes/meeting.md", "Weekly sync"),
+        ] {
+            store
+                .upsert_document("docs", path, Some(title), "abcdef123456", ".md", "")
+                .await
+                .unwrap();
+        }
+
+        let found = store.find_paths("getstart", None, 10).await.unwrap();
+        assert_eq!(found[0].path, "guide/getting-started.md");
+        assert_eq!(found[0].docid, "#abcdef");
+
+        // Title matches count too
+        let found = store.find_paths("weekly", None, 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, "notes/meeting.md");
+
+        // All terms must match
+        let found = store.find_paths("api ref", None, 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(store
+            .find_paths("api zzz", None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(store
+            .find_paths("api", Some("other"), 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_list_files() {
         let store = Store::open_memory().await.unwrap();
@@ -2219,4 +3849,71 @@ mod tests {
         assert_eq!(without.len(), 1);
         assert_eq!(without[0].name, "code");
     }
+
+    #[tokio::test]
+    async fn test_query_embedding_cache() {
+        let store = Store::open_memory().await.unwrap();
+        let embedding = vec![0.25f32, -1.0, 3.5];
+
+        assert_eq!(
+            store
+                .get_query_embedding("model-a", "rust async")
+                .await
+                .unwrap(),
+            None
+        );
+        store
+            .put_query_embedding("model-a", "  rust   async\n", &embedding)
+            .await
+            .unwrap();
+
+        // Whitespace is normalized, but the model is part of the key
+        assert_eq!(
+            store
+                .get_query_embedding("model-a", "rust async")
+                .await
+                .unwrap(),
+            Some(embedding)
+        );
+        assert_eq!(
+            store
+                .get_query_embedding("model-b", "rust async")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            store
+                .get_query_embedding("model-a", "Rust async")
+                .await
+                .unwrap(),
+            None
+        );
+
+        // A read-only database still serves hits
+        store
+            .conn
+            .execute("PRAGMA query_only = ON", ())
+            .await
+            .unwrap();
+        assert!(store
+            .get_query_embedding("model-a", "rust async")
+            .await
+            .unwrap()
+            .is_some());
+        store
+            .conn
+            .execute("PRAGMA query_only = OFF", ())
+            .await
+            .unwrap();
+
+        assert_eq!(store.clear_query_embeddings().await.unwrap(), 1);
+        assert_eq!(
+            store
+                .get_query_embedding("model-a", "rust async")
+                .await
+                .unwrap(),
+            None
+        );
+    }
 }
@@ -5,7 +5,14 @@ use libsql::Connection;
 
 /// Current schema version
 /// v4: Changed embeddings column from BLOB to F32_BLOB(384) for native vector indexing
-pub const SCHEMA_VERSION: i64 = 4;
+/// v5: Added spelling vocabulary with trigram index for typo-tolerant search
+/// v6: Added passage chunks with chunk-level FTS for passage BM25
+/// v7: Added per-collection ranking profiles
+/// v8: Added query embedding cache
+/// v9: Added extracted text for non-text formats (PDF pages)
+/// v10: Added filterable document metadata
+/// v11: Added per-collection external parser commands
+pub const SCHEMA_VERSION: i64 = 11;
 
 /// SQL to create the database schema
 const SCHEMA_SQL: &str = r#"
@@ -46,6 +53,18 @@ CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
     tokenize='porter unicode61'
 );
 
+-- Spelling vocabulary (unstemmed, lowercased terms seen in indexed documents)
+-- Used to build "did you mean" suggestions for misspelled query terms
+CREATE TABLE IF NOT EXISTS vocabulary (
+    term TEXT PRIMARY KEY
+);
+
+-- Trigram index over vocabulary terms for fuzzy candidate lookup
+CREATE VIRTUAL TABLE IF NOT EXISTS vocabulary_trigram USING fts5(
+    term,
+    tokenize='trigram'
+);
+
 -- Vector embeddings (for optional semantic search)
 -- Using libsql native F32_BLOB(384) for vector storage and indexing
 -- 384 dimensions matches all-MiniLM-L6-v2 (default model)
@@ -59,6 +78,44 @@ CREATE TABLE IF NOT EXISTS embeddings (
     PRIMARY KEY (hash, chunk_index)
 );
 
+-- Text extracted from non-text content (e.g. PDF), used in place of the raw bytes
+-- pages is a JSON array of the 1-based line where each page starts
+CREATE TABLE IF NOT EXISTS extracted_text (
+    hash TEXT PRIMARY KEY,
+    text TEXT NOT NULL,
+    pages TEXT
+);
+
+-- Document metadata (one row per scalar value, so arrays give several rows)
+-- Used to filter searches by fields such as an email's sender
+CREATE TABLE IF NOT EXISTS document_metadata (
+    document_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_metadata_key ON document_metadata(key, document_id);
+CREATE INDEX IF NOT EXISTS idx_document_metadata_document ON document_metadata(document_id);
+
+-- Passage chunks (content-addressed, aligned with embeddings by chunk_index)
+-- char_offset/char_length are byte positions in the document text (extracted text if any)
+CREATE TABLE IF NOT EXISTS chunks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    hash TEXT NOT NULL,
+    chunk_index INTEGER NOT NULL,
+    char_offset INTEGER NOT NULL,
+    char_length INTEGER NOT NULL,
+    line_start INTEGER NOT NULL,
+    line_end INTEGER NOT NULL,
+    UNIQUE(hash, chunk_index)
+);
+
+-- Chunk-level full-text search (rowid = chunks.id)
+CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
+    body,
+    tokenize='porter unicode61'
+);
+
 -- Collections (indexed directories)
 CREATE TABLE IF NOT EXISTS collections (
     name TEXT PRIMARY KEY,
@@ -71,6 +128,34 @@ CREATE TABLE IF NOT EXISTS collections (
     updated_at TEXT NOT NULL
 );
 
+-- Per-collection ranking profiles (priority weight and default signals as JSON)
+CREATE TABLE IF NOT EXISTS collection_ranking (
+    collection TEXT PRIMARY KEY,
+    weight REAL NOT NULL DEFAULT 1.0,
+    signals TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Per-collection external parsers (command template run for an extension)
+CREATE TABLE IF NOT EXISTS collection_parsers (
+    collection TEXT NOT NULL,
+    extension TEXT NOT NULL,
+    command TEXT NOT NULL,
+    timeout_secs INTEGER NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (collection, extension)
+);
+
+-- Query embeddings by model and whitespace-normalized query text
+CREATE TABLE IF NOT EXISTS query_embeddings (
+    model TEXT NOT NULL,
+    query TEXT NOT NULL,
+    embedding BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    last_used_at TEXT NOT NULL,
+    PRIMARY KEY (model, query)
+);
+
 -- Index state (for tracking schema version, last index time, etc.)
 CREATE TABLE IF NOT EXISTS index_state (
     key TEXT PRIMARY KEY,
@@ -161,6 +246,20 @@ async fn migrate(conn: &Connection, from_version: i64) -> Result<()> {
     // Add migration steps here as schema evolves
     // Version 2: libsql migration (schema compatible, just version bump)
     // Version 3: Vector index support (created lazily, see ensure_vector_index)
+    // Version 5: Spelling vocabulary, backfilled from the FTS index
+    // Version 6: Passage chunks (populated by `qfs embed`, no backfill)
+    // Version 7: Collection ranking profiles (empty until configured)
+    // Version 8: Query embedding cache (filled by vector and hybrid searches)
+    // Version 9: Extracted text (PDFs are re-parsed by the next `qfs index`)
+    // Version 10: Document metadata (filled in as documents are re-indexed)
+    // Version 11: Collection parser commands (empty until configured)
+
+    // All CREATE statements are IF NOT EXISTS, so this only adds new tables
+    conn.execute_batch(SCHEMA_SQL).await?;
+
+    if from_version < 5 {
+        backfill_vocabulary(conn).await?;
+    }
 
     // Update schema version
     conn.execute(
@@ -172,6 +271,27 @@ async fn migrate(conn: &Connection, from_version: i64) -> Result<()> {
     Ok(())
 }
 
+/// Populate the spelling vocabulary from documents already in the FTS index
+async fn backfill_vocabulary(conn: &Connection) -> Result<()> {
+    let mut rows = conn
+        .query("SELECT title, body FROM documents_fts", ())
+        .await?;
+
+    let mut terms = std::collections::BTreeSet::new();
+    while let Some(row) = rows.next().await? {
+        let title: Option<String> = row.get(0)?;
+        let body: Option<String> = row.get(1)?;
+        terms.extend(super::vocabulary_terms(title.as_deref().unwrap_or("")));
+        terms.extend(super::vocabulary_terms(body.as_deref().unwrap_or("")));
+    }
+
+    let terms: Vec<String> = terms.into_iter().collect();
+    super::insert_vocabulary(conn, &terms).await?;
+
+    tracing::info!("Backfilled spelling vocabulary ({} terms)", terms.len());
+    Ok(())
+}
+
 /// Ensure the vector index exists for native vector search.
 /// This is called lazily when embeddings are present, since libsql
 /// needs to detect vector dimensions from existing data.
@@ -198,6 +318,10 @@ pub async fn ensure_vector_index(conn: &Connection) -> Result<bool> {
         return Ok(false); // Index already exists
     }
 
+    if super::vector_index::is_disabled(conn).await? {
+        return Ok(false); // Dropped with `qfs vector-index drop`
+    }
+
     // Check if there are any embeddings to index
     let mut rows = conn.query("SELECT COUNT(*) FROM embeddings", ()).await?;
 
@@ -211,19 +335,11 @@ pub async fn ensure_vector_index(conn: &Connection) -> Result<bool> {
         return Ok(false); // No embeddings to index
     }
 
-    // Try to create the vector index
-    // Uses cosine similarity metric (recommended for text embeddings)
+    // Try to create the vector index with the saved (or default cosine) parameters
     // This may fail if embeddings were stored as raw BLOB (legacy format)
-    let result = conn
-        .execute(
-            "CREATE INDEX IF NOT EXISTS idx_embeddings_vector
-                ON embeddings(libsql_vector_idx(embedding, 'metric=cosine', 'compress_neighbors=float8', 'max_neighbors=32'))",
-            (),
-        )
-        .await;
-
-    match result {
-        Ok(_) => {
+    let params = super::vector_index::saved_params(conn).await?;
+    match super::vector_index::create(conn, &params).await {
+        Ok(()) => {
             tracing::info!(
                 "Created vector index for native semantic search ({} embeddings)",
                 count
@@ -231,12 +347,9 @@ pub async fn ensure_vector_index(conn: &Connection) -> Result<bool> {
             Ok(true)
         }
         Err(e) => {
-            // Index creation failed - likely due to legacy BLOB format
-            // This is expected for databases with pre-existing embeddings
-            tracing::debug!(
-                "Vector index creation skipped (embeddings may be in legacy format): {}",
-                e
-            );
+            // Index creation failed - likely due to legacy BLOB format.
+            // The reason is kept for `qfs vector-index status`.
+            tracing::debug!("Vector index creation skipped: {}", e);
             Ok(false)
         }
     }
@@ -293,6 +406,44 @@ mod tests {
         assert!(tables.contains(&"collections".to_string()));
         assert!(tables.contains(&"embeddings".to_string()));
         assert!(tables.contains(&"index_state".to_string()));
+        assert!(tables.contains(&"vocabulary".to_string()));
+        assert!(tables.contains(&"vocabulary_trigram".to_string()));
+        assert!(tables.contains(&"chunks".to_string()));
+        assert!(tables.contains(&"chunks_fts".to_string()));
+        assert!(tables.contains(&"collection_ranking".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_migration_backfills_vocabulary() {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        ensure_schema(&conn).await.unwrap();
+
+        // Simulate a v4 database with indexed content but no vocabulary
+        conn.execute(
+            "INSERT INTO documents_fts (rowid, filepath, title, body) VALUES (1, 'd/k.md', 'Kubernetes', 'cluster scheduling')",
+            (),
+        )
+        .await
+        .unwrap();
+        conn.execute(
+            "UPDATE index_state SET value = '4' WHERE key = 'schema_version'",
+            (),
+        )
+        .await
+        .unwrap();
+
+        ensure_schema(&conn).await.unwrap();
+
+        let mut rows = conn
+            .query("SELECT term FROM vocabulary ORDER BY term", ())
+            .await
+            .unwrap();
+        let mut terms = Vec::new();
+        while let Some(row) = rows.next().await.unwrap() {
+            terms.push(row.get::<String>(0).unwrap());
+        }
+        assert_eq!(terms, vec!["cluster", "kubernetes", "scheduling"]);
     }
 
     #[tokio::test]
@@ -0,0 +1,610 @@
+//! Native vector index management
+//!
+//! `idx_embeddings_vector` is a libsql DiskANN index over `embeddings.embedding`.
+//! It is created lazily (see `schema::ensure_vector_index`) with the parameters
+//! saved here, and can be dropped, rebuilt with different parameters, or
+//! inspected. Databases created before schema v4 store embeddings in a plain
+//! `BLOB` column that libsql refuses to index; `convert_legacy` rewrites them
+//! into an `F32_BLOB` column so native search can be used.
+
+use crate::error::{Error, Result};
+use libsql::{params, Connection};
+
+/// Name of the native vector index
+pub(crate) const INDEX_NAME: &str = "idx_embeddings_vector";
+
+/// `index_state` key holding the parameters for (re)creating the index
+const PARAMS_KEY: &str = "vector_index_params";
+
+/// `index_state` key holding the last index creation failure
+const ERROR_KEY: &str = "vector_index_error";
+
+/// `index_state` key set while the index is dropped on purpose
+const DISABLED_KEY: &str = "vector_index_disabled";
+
+/// Embedding dimensions assumed when the table has no rows to measure
+const DEFAULT_DIMENSIONS: usize = 384;
+
+/// Distance metrics supported by `libsql_vector_idx`
+const METRICS: &[&str] = &["cosine", "l2"];
+
+/// Neighbour compression types supported by `libsql_vector_idx`
+const COMPRESSIONS: &[&str] = &["float1bit", "float8", "float16", "floatb16", "float32"];
+
+/// Parameters passed to `libsql_vector_idx()`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorIndexParams {
+    /// Distance metric (cosine or l2)
+    pub metric: String,
+    /// Compression for stored neighbour vectors (None stores them uncompressed)
+    pub compress_neighbors: Option<String>,
+    /// Maximum graph neighbours per node
+    pub max_neighbors: Option<u32>,
+    /// Candidate list size while searching
+    pub search_l: Option<u32>,
+    /// Candidate list size while inserting
+    pub insert_l: Option<u32>,
+}
+
+impl Default for VectorIndexParams {
+    fn default() -> Self {
+        VectorIndexParams {
+            metric: "cosine".to_string(),
+            compress_neighbors: Some("float8".to_string()),
+            max_neighbors: Some(32),
+            search_l: None,
+            insert_l: None,
+        }
+    }
+}
+
+impl VectorIndexParams {
+    /// Check values against what libsql accepts
+    pub fn validate(&self) -> Result<()> {
+        if !METRICS.contains(&self.metric.as_str()) {
+            return Err(Error::ConfigError(format!(
+                "Invalid vector metric: {} (expected {})",
+                self.metric,
+                METRICS.join(", ")
+            )));
+        }
+        if let Some(ref compression) = self.compress_neighbors {
+            if !COMPRESSIONS.contains(&compression.as_str()) {
+                return Err(Error::ConfigError(format!(
+                    "Invalid neighbour compression: {} (expected {})",
+                    compression,
+                    COMPRESSIONS.join(", ")
+                )));
+            }
+        }
+        for (name, value) in [
+            ("max_neighbors", self.max_neighbors),
+            ("search_l", self.search_l),
+            ("insert_l", self.insert_l),
+        ] {
+            if value == Some(0) {
+                return Err(Error::ConfigError(format!("{} must be positive", name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// `'key=value'` arguments for `libsql_vector_idx()`
+    fn sql_args(&self) -> String {
+        let mut args = vec![format!("'metric={}'", self.metric)];
+        if let Some(ref compression) = self.compress_neighbors {
+            args.push(format!("'compress_neighbors={}'", compression));
+        }
+        if let Some(n) = self.max_neighbors {
+            args.push(format!("'max_neighbors={}'", n));
+        }
+        if let Some(n) = self.search_l {
+            args.push(format!("'search_l={}'", n));
+        }
+        if let Some(n) = self.insert_l {
+            args.push(format!("'insert_l={}'", n));
+        }
+        args.join(", ")
+    }
+
+    /// Recover parameters from an index's `CREATE INDEX` statement
+    fn from_index_sql(sql: &str) -> Self {
+        let mut params = VectorIndexParams {
+            metric: "cosine".to_string(),
+            compress_neighbors: None,
+            max_neighbors: None,
+            search_l: None,
+            insert_l: None,
+        };
+
+        for arg in sql.split('\'').skip(1).step_by(2) {
+            let Some((key, value)) = arg.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "metric" => params.metric = value.to_string(),
+                "compress_neighbors" => params.compress_neighbors = Some(value.to_string()),
+                "max_neighbors" => params.max_neighbors = value.parse().ok(),
+                "search_l" => params.search_l = value.parse().ok(),
+                "insert_l" => params.insert_l = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        params
+    }
+}
+
+/// Which implementation served a vector search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorSearchPath {
+    /// `vector_top_k()` over the native index
+    Native,
+    /// In-memory cosine similarity over every candidate embedding
+    Legacy,
+}
+
+impl std::fmt::Display for VectorSearchPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VectorSearchPath::Native => write!(f, "native"),
+            VectorSearchPath::Legacy => write!(f, "legacy"),
+        }
+    }
+}
+
+/// State of the native vector index and the embeddings it covers
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorIndexStatus {
+    /// Whether `idx_embeddings_vector` exists
+    pub exists: bool,
+    /// Whether the index was dropped on purpose (not recreated lazily)
+    pub disabled: bool,
+    /// Parameters of the existing index, or those it will be created with
+    pub params: VectorIndexParams,
+    /// Declared type of the embedding column (e.g. F32_BLOB(384))
+    pub column_type: String,
+    /// Number of stored embeddings
+    pub embeddings: u64,
+    /// Embeddings the native index can't use (raw BLOB column or malformed rows)
+    pub legacy_embeddings: u64,
+    /// On-disk size of the index and its shadow tables, if measurable
+    pub index_bytes: Option<u64>,
+    /// Why the index could not be created the last time it was attempted
+    pub last_error: Option<String>,
+    /// Which path vector searches currently take
+    pub search_path: VectorSearchPath,
+}
+
+/// Result of converting legacy embeddings
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertStats {
+    /// Embeddings rewritten into the native format
+    pub converted: u64,
+    /// Embeddings dropped because their size didn't match the dimensions
+    pub skipped: u64,
+    /// Vector dimensions of the converted column
+    pub dimensions: usize,
+}
+
+/// Parameters to create the index with (saved by the last rebuild)
+pub(crate) async fn saved_params(conn: &Connection) -> Result<VectorIndexParams> {
+    let mut rows = conn
+        .query(
+            "SELECT value FROM index_state WHERE key = ?1",
+            params![PARAMS_KEY],
+        )
+        .await?;
+
+    match rows.next().await? {
+        Some(row) => {
+            let value: String = row.get(0)?;
+            Ok(serde_json::from_str(&value)?)
+        }
+        None => Ok(VectorIndexParams::default()),
+    }
+}
+
+/// Create the index with `params`, recording the failure reason if libsql rejects it
+pub(crate) async fn create(conn: &Connection, params: &VectorIndexParams) -> Result<()> {
+    params.validate()?;
+
+    let sql = format!(
+        "CREATE INDEX IF NOT EXISTS {} ON embeddings(libsql_vector_idx(embedding, {}))",
+        INDEX_NAME,
+        params.sql_args()
+    );
+
+    if let Err(e) = conn.execute(&sql, ()).await {
+        let message = e.to_string();
+        set_state(conn, ERROR_KEY, Some(&message)).await?;
+
+        let hint = if column_type(conn)
+            .await?
+            .to_uppercase()
+            .starts_with("F32_BLOB")
+        {
+            ""
+        } else {
+            " (embeddings use the legacy BLOB format; run `qfs vector-index convert`)"
+        };
+        return Err(Error::IndexError(format!(
+            "Vector index creation failed: {}{}",
+            message, hint
+        )));
+    }
+
+    set_state(conn, ERROR_KEY, None).await?;
+    Ok(())
+}
+
+/// Drop the index, returning whether it existed.
+/// Lazy creation stays off until the next rebuild.
+pub(crate) async fn drop_index(conn: &Connection) -> Result<bool> {
+    let existed = index_sql(conn).await?.is_some();
+    conn.execute(&format!("DROP INDEX IF EXISTS {}", INDEX_NAME), ())
+        .await?;
+    set_state(conn, DISABLED_KEY, Some("1")).await?;
+    Ok(existed)
+}
+
+/// Whether the index was dropped on purpose and shouldn't be created lazily
+pub(crate) async fn is_disabled(conn: &Connection) -> Result<bool> {
+    Ok(count(
+        conn,
+        "SELECT COUNT(*) FROM index_state WHERE key = ?1",
+        params![DISABLED_KEY],
+    )
+    .await?
+        > 0)
+}
+
+/// Drop and recreate the index with `params`, saving them for lazy creation
+pub(crate) async fn rebuild(conn: &Connection, params: &VectorIndexParams) -> Result<()> {
+    params.validate()?;
+    conn.execute(&format!("DROP INDEX IF EXISTS {}", INDEX_NAME), ())
+        .await?;
+    create(conn, params).await?;
+    set_state(conn, PARAMS_KEY, Some(&serde_json::to_string(params)?)).await?;
+    set_state(conn, DISABLED_KEY, None).await
+}
+
+/// Parameters of the existing index, or those it will be created with
+pub(crate) async fn current_params(conn: &Connection) -> Result<VectorIndexParams> {
+    match index_sql(conn).await? {
+        Some(sql) => Ok(VectorIndexParams::from_index_sql(&sql)),
+        None => saved_params(conn).await,
+    }
+}
+
+/// Inspect the index and the embeddings table
+pub(crate) async fn status(conn: &Connection) -> Result<VectorIndexStatus> {
+    let sql = index_sql(conn).await?;
+    let params = current_params(conn).await?;
+
+    let column_type = column_type(conn).await?;
+    let dimensions = declared_dimensions(&column_type);
+
+    let embeddings = count(conn, "SELECT COUNT(*) FROM embeddings", ()).await?;
+    let legacy_embeddings = match dimensions {
+        Some(dims) => {
+            count(
+                conn,
+                "SELECT COUNT(*) FROM embeddings
+                 WHERE typeof(embedding) != 'blob' OR length(embedding) != ?1",
+                params![(dims * 4) as i64],
+            )
+            .await?
+        }
+        None => embeddings,
+    };
+
+    // dbstat is optional in SQLite builds; the size is informational only
+    let index_bytes = match conn
+        .query(
+            "SELECT SUM(pgsize) FROM dbstat WHERE name = ?1 OR name LIKE ?2",
+            params![INDEX_NAME, format!("{}_shadow%", INDEX_NAME)],
+        )
+        .await
+    {
+        Ok(mut rows) => match rows.next().await {
+            Ok(Some(row)) => row.get::<Option<i64>>(0).ok().flatten().map(|n| n as u64),
+            _ => None,
+        },
+        Err(_) => None,
+    };
+
+    let mut rows = conn
+        .query(
+            "SELECT value FROM index_state WHERE key = ?1",
+            params![ERROR_KEY],
+        )
+        .await?;
+    let last_error = match rows.next().await? {
+        Some(row) => Some(row.get::<String>(0)?),
+        None => None,
+    };
+
+    let exists = sql.is_some();
+    Ok(VectorIndexStatus {
+        exists,
+        disabled: is_disabled(conn).await?,
+        params,
+        column_type,
+        embeddings,
+        legacy_embeddings,
+        index_bytes: index_bytes.filter(|_| exists),
+        last_error,
+        search_path: if exists {
+            VectorSearchPath::Native
+        } else {
+            VectorSearchPath::Legacy
+        },
+    })
+}
+
+/// Rewrite the embeddings table with an `F32_BLOB` column using `vector32()`.
+///
+/// Dimensions are taken from the stored vectors. Rows whose size doesn't
+/// match are dropped (they could never be searched) and counted as skipped.
+/// The index is recreated afterwards with the saved parameters.
+pub(crate) async fn convert_legacy(conn: &Connection) -> Result<ConvertStats> {
+    let column_type = column_type(conn).await?;
+    let total = count(conn, "SELECT COUNT(*) FROM embeddings", ()).await?;
+
+    if let Some(dimensions) = declared_dimensions(&column_type) {
+        if status(conn).await?.legacy_embeddings == 0 {
+            return Ok(ConvertStats {
+                converted: 0,
+                skipped: 0,
+                dimensions,
+            });
+        }
+    }
+
+    // Most common blob size wins; text rows are JSON arrays vector32() can parse
+    let mut rows = conn
+        .query(
+            "SELECT length(embedding) / 4 FROM embeddings
+             WHERE typeof(embedding) = 'blob' AND length(embedding) % 4 = 0
+             GROUP BY length(embedding) ORDER BY COUNT(*) DESC LIMIT 1",
+            (),
+        )
+        .await?;
+    let dimensions = match rows.next().await? {
+        Some(row) => row.get::<i64>(0)? as usize,
+        None => declared_dimensions(&column_type).unwrap_or(DEFAULT_DIMENSIONS),
+    };
+    // An open statement keeps the table locked against DROP
+    drop(rows);
+
+    let tx = conn.transaction().await?;
+    tx.execute("DROP TABLE IF EXISTS embeddings_converted", ())
+        .await?;
+    tx.execute(
+        &format!(
+            "CREATE TABLE embeddings_converted (
+                hash TEXT NOT NULL,
+                chunk_index INTEGER NOT NULL,
+                char_offset INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                embedding F32_BLOB({}),
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (hash, chunk_index)
+            )",
+            dimensions
+        ),
+        (),
+    )
+    .await?;
+    let converted = tx
+        .execute(
+            "INSERT INTO embeddings_converted
+             SELECT hash, chunk_index, char_offset, model, vector32(embedding), created_at
+             FROM embeddings
+             WHERE typeof(embedding) = 'text'
+                OR (typeof(embedding) = 'blob' AND length(embedding) = ?1)",
+            params![(dimensions * 4) as i64],
+        )
+        .await?;
+    tx.execute("DROP TABLE embeddings", ()).await?;
+    tx.execute("ALTER TABLE embeddings_converted RENAME TO embeddings", ())
+        .await?;
+    tx.commit().await?;
+
+    let params = saved_params(conn).await?;
+    create(conn, &params).await?;
+    set_state(conn, DISABLED_KEY, None).await?;
+
+    Ok(ConvertStats {
+        converted,
+        skipped: total.saturating_sub(converted),
+        dimensions,
+    })
+}
+
+/// `CREATE INDEX` statement of the vector index, if it exists
+async fn index_sql(conn: &Connection) -> Result<Option<String>> {
+    let mut rows = conn
+        .query(
+            "SELECT sql FROM sqlite_master WHERE type = 'index' AND name = ?1",
+            params![INDEX_NAME],
+        )
+        .await?;
+
+    match rows.next().await? {
+        Some(row) => Ok(row.get::<Option<String>>(0)?),
+        None => Ok(None),
+    }
+}
+
+/// Declared type of `embeddings.embedding`
+async fn column_type(conn: &Connection) -> Result<String> {
+    let mut rows = conn
+        .query(
+            "SELECT type FROM pragma_table_info('embeddings') WHERE name = 'embedding'",
+            (),
+        )
+        .await?;
+
+    match rows.next().await? {
+        Some(row) => Ok(row.get(0)?),
+        None => Ok(String::new()),
+    }
+}
+
+/// Dimensions of an `F32_BLOB(n)` column type, None for anything else
+fn declared_dimensions(column_type: &str) -> Option<usize> {
+    column_type
+        .to_uppercase()
+        .strip_prefix("F32_BLOB(")?
+        .strip_suffix(')')?
+        .trim()
+        .parse()
+        .ok()
+}
+
+async fn count(
+    conn: &Connection,
+    sql: &str,
+    params: impl libsql::params::IntoParams,
+) -> Result<u64> {
+    let mut rows = conn.query(sql, params).await?;
+    match rows.next().await? {
+        Some(row) => Ok(row.get::<i64>(0)? as u64),
+        None => Ok(0),
+    }
+}
+
+async fn set_state(conn: &Connection, key: &str, value: Option<&str>) -> Result<()> {
+    match value {
+        Some(value) => {
+            conn.execute(
+                "INSERT OR REPLACE INTO index_state (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .await?
+        }
+        None => {
+            conn.execute("DELETE FROM index_state WHERE key = ?1", params![key])
+                .await?
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libsql::Builder;
+
+    /// Connection with the current schema but a pre-v4 `BLOB` embedding column
+    async fn legacy_connection() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        super::super::schema::ensure_schema(&conn).await.unwrap();
+
+        conn.execute_batch(
+            "DROP TABLE embeddings;
+             CREATE TABLE embeddings (
+                 hash TEXT NOT NULL,
+                 chunk_index INTEGER NOT NULL,
+                 char_offset INTEGER NOT NULL,
+                 model TEXT NOT NULL,
+                 embedding BLOB,
+                 created_at TEXT NOT NULL,
+                 PRIMARY KEY (hash, chunk_index)
+             );",
+        )
+        .await
+        .unwrap();
+
+        for (i, dims) in [(0, 8), (1, 8), (2, 5)] {
+            let bytes: Vec<u8> = (0..dims).flat_map(|d| (d as f32).to_le_bytes()).collect();
+            conn.execute(
+                "INSERT INTO embeddings VALUES ('abc', ?1, 0, 'test', ?2, 'now')",
+                params![i, bytes],
+            )
+            .await
+            .unwrap();
+        }
+
+        conn
+    }
+
+    #[tokio::test]
+    async fn test_legacy_blob_column_is_reported_and_converted() {
+        let conn = legacy_connection().await;
+
+        let before = status(&conn).await.unwrap();
+        assert!(!before.exists);
+        assert_eq!(before.column_type, "BLOB");
+        assert_eq!(before.legacy_embeddings, 3);
+        assert_eq!(before.search_path, VectorSearchPath::Legacy);
+
+        let err = create(&conn, &VectorIndexParams::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("qfs vector-index convert"));
+        assert!(status(&conn).await.unwrap().last_error.is_some());
+
+        let stats = convert_legacy(&conn).await.unwrap();
+        assert_eq!(stats.converted, 2);
+        assert_eq!(stats.skipped, 1, "5-dimension row doesn't match");
+        assert_eq!(stats.dimensions, 8);
+
+        let after = status(&conn).await.unwrap();
+        assert!(after.exists);
+        assert_eq!(after.column_type, "F32_BLOB(8)");
+        assert_eq!(after.embeddings, 2);
+        assert_eq!(after.legacy_embeddings, 0);
+        assert_eq!(after.last_error, None);
+        assert_eq!(after.search_path, VectorSearchPath::Native);
+    }
+
+    #[test]
+    fn test_params_round_trip_through_index_sql() {
+        let params = VectorIndexParams {
+            metric: "l2".to_string(),
+            compress_neighbors: None,
+            max_neighbors: Some(64),
+            search_l: Some(200),
+            insert_l: None,
+        };
+        let sql = format!(
+            "CREATE INDEX {} ON embeddings(libsql_vector_idx(embedding, {}))",
+            INDEX_NAME,
+            params.sql_args()
+        );
+
+        assert_eq!(VectorIndexParams::from_index_sql(&sql), params);
+    }
+
+    #[test]
+    fn test_params_validate() {
+        assert!(VectorIndexParams::default().validate().is_ok());
+
+        let bad_metric = VectorIndexParams {
+            metric: "dot".to_string(),
+            ..Default::default()
+        };
+        assert!(bad_metric.validate().is_err());
+
+        let bad_neighbors = VectorIndexParams {
+            max_neighbors: Some(0),
+            ..Default::default()
+        };
+        assert!(bad_neighbors.validate().is_err());
+    }
+
+    #[test]
+    fn test_declared_dimensions() {
+        assert_eq!(declared_dimensions("F32_BLOB(384)"), Some(384));
+        assert_eq!(declared_dimensions("f32_blob( 768 )"), Some(768));
+        assert_eq!(declared_dimensions("BLOB"), None);
+    }
+}
@@ -0,0 +1,254 @@
+//! Bounded-memory scoring for the legacy vector search
+//!
+//! Without a native index every candidate embedding has to be scored. Rows are
+//! streamed from SQLite and scored straight from their little-endian bytes
+//! against a pre-normalized query, and only the best `k` are kept in a
+//! min-heap, so memory stays at O(k) regardless of index size.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Accumulator lanes; wide enough for the compiler to vectorize the loop
+const LANES: usize = 8;
+
+/// Scale `vector` to unit length (zero vectors are returned unchanged)
+pub(crate) fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|x| x / norm).collect()
+}
+
+/// Cosine similarity between a unit-length `query` and a vector stored as
+/// little-endian f32 `bytes`, computed in one pass without decoding into a
+/// temporary vector. Returns 0.0 on dimension mismatch or a zero vector.
+pub(crate) fn cosine_to_unit(query: &[f32], bytes: &[u8]) -> f32 {
+    if query.is_empty() || bytes.len() != query.len() * 4 {
+        return 0.0;
+    }
+
+    let mut dot = [0.0f32; LANES];
+    let mut norm = [0.0f32; LANES];
+
+    let query_chunks = query.chunks_exact(LANES);
+    let byte_chunks = bytes.chunks_exact(LANES * 4);
+    let (query_rest, byte_rest) = (query_chunks.remainder(), byte_chunks.remainder());
+
+    for (q, b) in query_chunks.zip(byte_chunks) {
+        let lanes = dot.iter_mut().zip(norm.iter_mut());
+        for ((d, n), (q, v)) in lanes.zip(q.iter().zip(b.chunks_exact(4))) {
+            let v = f32::from_le_bytes([v[0], v[1], v[2], v[3]]);
+            *d += q * v;
+            *n += v * v;
+        }
+    }
+
+    let mut dot: f32 = dot.iter().sum();
+    let mut norm: f32 = norm.iter().sum();
+    for (q, v) in query_rest.iter().zip(byte_rest.chunks_exact(4)) {
+        let v = f32::from_le_bytes([v[0], v[1], v[2], v[3]]);
+        dot += q * v;
+        norm += v * v;
+    }
+
+    if norm == 0.0 {
+        return 0.0;
+    }
+    dot / norm.sqrt()
+}
+
+/// Similarity `1 / (1 + d)` from the Euclidean distance `d` between `query`
+/// and a vector stored as little-endian f32 `bytes`, for indexes using the
+/// `l2` metric. Returns 0.0 on dimension mismatch.
+pub(crate) fn l2_similarity(query: &[f32], bytes: &[u8]) -> f32 {
+    if query.is_empty() || bytes.len() != query.len() * 4 {
+        return 0.0;
+    }
+
+    let mut sum = [0.0f32; LANES];
+
+    let query_chunks = query.chunks_exact(LANES);
+    let byte_chunks = bytes.chunks_exact(LANES * 4);
+    let (query_rest, byte_rest) = (query_chunks.remainder(), byte_chunks.remainder());
+
+    for (q, b) in query_chunks.zip(byte_chunks) {
+        for (s, (q, v)) in sum.iter_mut().zip(q.iter().zip(b.chunks_exact(4))) {
+            let d = q - f32::from_le_bytes([v[0], v[1], v[2], v[3]]);
+            *s += d * d;
+        }
+    }
+
+    let mut sum: f32 = sum.iter().sum();
+    for (q, v) in query_rest.iter().zip(byte_rest.chunks_exact(4)) {
+        let d = q - f32::from_le_bytes([v[0], v[1], v[2], v[3]]);
+        sum += d * d;
+    }
+
+    1.0 / (1.0 + sum.sqrt())
+}
+
+/// Keeps the `k` highest-scoring items seen so far
+pub(crate) struct TopK<T> {
+    k: usize,
+    /// Min-heap on score; the root is the weakest item kept
+    heap: BinaryHeap<Reverse<Scored<T>>>,
+    /// Insertion counter, so equal scores keep their scan order
+    seq: u64,
+}
+
+struct Scored<T> {
+    score: f32,
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Scored<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Scored<T> {}
+
+impl<T> PartialOrd for Scored<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Scored<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Earlier items rank higher among ties
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl<T> TopK<T> {
+    pub(crate) fn new(k: usize) -> Self {
+        TopK {
+            k,
+            heap: BinaryHeap::with_capacity(k.saturating_add(1).min(4096)),
+            seq: 0,
+        }
+    }
+
+    /// Whether an item with `score` would be kept; lets callers skip
+    /// building items that would be discarded
+    pub(crate) fn accepts(&self, score: f32) -> bool {
+        if self.k == 0 {
+            return false;
+        }
+        match self.heap.peek() {
+            Some(Reverse(weakest)) if self.heap.len() >= self.k => score > weakest.score,
+            _ => true,
+        }
+    }
+
+    pub(crate) fn push(&mut self, score: f32, item: T) {
+        if !self.accepts(score) {
+            return;
+        }
+        self.heap.push(Reverse(Scored {
+            score,
+            seq: self.seq,
+            item,
+        }));
+        self.seq += 1;
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    /// Kept items, best first
+    pub(crate) fn into_sorted(self) -> Vec<(f32, T)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(s)| (s.score, s.item))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_bytes(vector: &[f32]) -> Vec<u8> {
+        vector.iter().flat_map(|f| f.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_cosine_to_unit_matches_cosine() {
+        // 19 dimensions exercises both the lane loop and the remainder
+        let a: Vec<f32> = (0..19).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b: Vec<f32> = (0..19).map(|i| (i as f32 * 0.11).cos() * 3.0).collect();
+
+        let dot: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let expected = dot / (norm(&a) * norm(&b));
+
+        let actual = cosine_to_unit(&normalize(&a), &to_bytes(&b));
+        assert!((actual - expected).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_cosine_to_unit_degenerate() {
+        let query = normalize(&[1.0, 0.0, 0.0]);
+        assert_eq!(cosine_to_unit(&query, &to_bytes(&[0.0, 0.0, 0.0])), 0.0);
+        assert_eq!(cosine_to_unit(&query, &to_bytes(&[1.0, 0.0])), 0.0);
+        assert_eq!(cosine_to_unit(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn test_l2_similarity() {
+        let a: Vec<f32> = (0..19).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b: Vec<f32> = (0..19).map(|i| (i as f32 * 0.11).cos() * 3.0).collect();
+
+        let distance: f32 = a
+            .iter()
+            .zip(&b)
+            .map(|(x, y)| (x - y).powi(2))
+            .sum::<f32>()
+            .sqrt();
+        let actual = l2_similarity(&a, &to_bytes(&b));
+        assert!((actual - 1.0 / (1.0 + distance)).abs() < 1e-5);
+
+        assert_eq!(l2_similarity(&a, &to_bytes(&a)), 1.0);
+        assert_eq!(l2_similarity(&[1.0, 0.0, 0.0], &to_bytes(&[1.0, 0.0])), 0.0);
+    }
+
+    #[test]
+    fn test_top_k_keeps_best_in_order() {
+        let mut top = TopK::new(3);
+        for (score, name) in [(0.2, "a"), (0.9, "b"), (0.5, "c"), (0.1, "d"), (0.7, "e")] {
+            top.push(score, name);
+        }
+
+        assert!(!top.accepts(0.4));
+        assert!(top.accepts(0.6));
+
+        let kept: Vec<&str> = top.into_sorted().into_iter().map(|(_, n)| n).collect();
+        assert_eq!(kept, vec!["b", "e", "c"]);
+    }
+
+    #[test]
+    fn test_top_k_ties_keep_scan_order() {
+        let mut top = TopK::new(2);
+        for name in ["first", "second", "third"] {
+            top.push(0.5, name);
+        }
+
+        let kept: Vec<&str> = top.into_sorted().into_iter().map(|(_, n)| n).collect();
+        assert_eq!(kept, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_top_k_zero() {
+        let mut top = TopK::new(0);
+        top.push(1.0, ());
+        assert!(top.into_sorted().is_empty());
+    }
+}
@@ -6,6 +6,7 @@
 //! - Score normalization
 //! - Result ranking
 
+use qfs::store::DocumentFilter;
 use qfs::{Indexer, SearchMode, SearchOptions, Store};
 use std::fs::File;
 use std::io::Write;
@@ -108,6 +109,100 @@ Backend technologies handle server-side logic including Node.js, Python, Django,
 Common database choices include PostgreSQL, MongoDB, Redis, and SQLite.
 "#;
 
+/// A fixture document: collection, path, content, and the embedding of its
+/// first chunk if it has one
+type FixtureDoc<'a> = (&'a str, &'a str, &'a str, Option<Vec<f32>>);
+
+/// A deterministic fixture embedding, `f(d)` in each of 384 dimensions
+fn embedding(f: impl Fn(usize) -> f32) -> Vec<f32> {
+    (0..384).map(f).collect()
+}
+
+/// Content hash of a fixture document, as the indexer computes it
+fn content_hash(content: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Store indexing `tests/fixtures/<dir>` as `collection`
+async fn create_indexed_store(
+    collection: &str,
+    dir: &str,
+    patterns: &[&str],
+) -> (Store, tempfile::TempDir) {
+    let db_dir = tempdir().unwrap();
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(dir);
+
+    let store = Store::open(db_dir.path().join("test.sqlite"))
+        .await
+        .unwrap();
+    store
+        .add_collection(collection, fixtures.to_str().unwrap(), patterns)
+        .await
+        .unwrap();
+    Indexer::new(&store)
+        .index_collection(collection)
+        .await
+        .unwrap();
+
+    (store, db_dir)
+}
+
+/// In-memory store holding `docs`
+async fn create_fixture_store(docs: &[FixtureDoc<'_>]) -> Store {
+    let store = Store::open_memory().await.unwrap();
+    add_fixture_docs(&store, docs).await;
+    store
+}
+
+/// Add `docs` to `store`, creating their collections on first use. Each is
+/// titled by its leading `# ` heading and typed by its extension, and its
+/// embedding is stored for chunk 0 with model "test-model".
+async fn add_fixture_docs(store: &Store, docs: &[FixtureDoc<'_>]) {
+    for (collection, path, content, embedding) in docs {
+        if store.get_collection(collection).await.is_err() {
+            store
+                .add_collection(collection, &format!("/tmp/{}", collection), &["**/*"])
+                .await
+                .unwrap();
+        }
+
+        let extension = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+        let mime = match extension {
+            "md" => "text/markdown",
+            "rs" => "text/x-rust",
+            _ => "text/plain",
+        };
+        let title = content.lines().next().and_then(|l| l.strip_prefix("# "));
+        let hash = content_hash(content);
+        store
+            .insert_content(&hash, content.as_bytes(), mime)
+            .await
+            .unwrap();
+        store
+            .upsert_document(
+                collection,
+                path,
+                title,
+                &hash,
+                &format!(".{}", extension),
+                content,
+            )
+            .await
+            .unwrap();
+
+        if let Some(embedding) = embedding {
+            let bytes: Vec<u8> = embedding.iter().flat_map(|f| f.to_le_bytes()).collect();
+            store
+                .insert_embedding(&hash, 0, 0, "test-model", &bytes)
+                .await
+                .unwrap();
+        }
+    }
+}
+
 #[tokio::test]
 async fn test_basic_search() {
     let (store, _db_dir, _content_dir) = create_test_store().await;
@@ -1161,7 +1256,7 @@ async fn test_vector_search_fallback() {
     // Native vector search may not be available (depends on libsql version and data format)
     // But the legacy fallback should always work
     let results = store
-        .search_vector_legacy(&embedding, None, 10, None, None)
+        .search_vector_legacy(&embedding, &DocumentFilter::default(), 10)
         .await
         .unwrap();
 
@@ -1178,14 +1273,19 @@ async fn test_search_with_date_filter() {
 
     // Search without date filter should return results
     let results = store
-        .search_bm25("rust", None, 10, false, None, None)
+        .search_bm25("rust", &DocumentFilter::default(), 10, false)
         .await
         .unwrap();
     assert!(!results.is_empty(), "Should find results without date filter");
 
     // Search with future from_date should return no results (documents were created "now")
     let results = store
-        .search_bm25("rust", None, 10, false, Some("2099-01-01"), None)
+        .search_bm25(
+            "rust",
+            &DocumentFilter::new(None, Some("2099-01-01"), None),
+            10,
+            false,
+        )
         .await
         .unwrap();
     assert!(
@@ -1195,7 +1295,12 @@ async fn test_search_with_date_filter() {
 
     // Search with past to_date should return no results
     let results = store
-        .search_bm25("rust", None, 10, false, None, Some("2000-01-01"))
+        .search_bm25(
+            "rust",
+            &DocumentFilter::new(None, None, Some("2000-01-01")),
+            10,
+            false,
+        )
         .await
         .unwrap();
     assert!(
@@ -1205,7 +1310,12 @@ async fn test_search_with_date_filter() {
 
     // Search with valid date range covering "now" should return results
     let results = store
-        .search_bm25("rust", None, 10, false, Some("2020-01-01"), Some("2099-12-31"))
+        .search_bm25(
+            "rust",
+            &DocumentFilter::new(None, Some("2020-01-01"), Some("2099-12-31")),
+            10,
+            false,
+        )
         .await
         .unwrap();
     assert!(
@@ -1213,3 +1323,1863 @@ async fn test_search_with_date_filter() {
         "Should find results within valid date range"
     );
 }
+
+#[tokio::test]
+async fn test_search_with_relative_date_filter() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let search = |from: Option<&str>, to: Option<&str>| {
+        let options = SearchOptions {
+            from_date: from.map(String::from),
+            to_date: to.map(String::from),
+            ..Default::default()
+        };
+        searcher.search("rust", options)
+    };
+
+    // Documents were indexed just now
+    assert!(!search(Some("7d"), None).await.unwrap().is_empty());
+    assert!(!search(Some("this week"), Some("now"))
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(search(None, Some("yesterday")).await.unwrap().is_empty());
+    assert!(search(Some("2099-Q1"), None).await.unwrap().is_empty());
+
+    // A plain date as the upper bound includes that whole day
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    assert!(!search(Some(&today), Some(&today)).await.unwrap().is_empty());
+
+    // Offsets are normalized to UTC before comparing
+    let later = (chrono::Utc::now() + chrono::Duration::hours(1))
+        .with_timezone(&chrono::FixedOffset::west_opt(5 * 3600).unwrap())
+        .to_rfc3339();
+    assert!(!search(None, Some(&later)).await.unwrap().is_empty());
+    assert!(search(Some(&later), None).await.unwrap().is_empty());
+
+    let err = search(Some("whenever"), None).await.unwrap_err();
+    assert!(err.to_string().contains("Unrecognized date"));
+}
+
+// =============================================================================
+// Facet Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_search_with_facets() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let response = searcher
+        .search_detailed(
+            "rust",
+            SearchOptions {
+                mode: SearchMode::Bm25,
+                limit: 1,
+                facets: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.results.len(), 1);
+
+    // Facets cover the full candidate set, not just the returned page
+    let facets = response.facets.expect("facets should be computed");
+    assert_eq!(
+        facets.total, 2,
+        "rust_guide and web_development mention rust"
+    );
+    assert_eq!(facets.collections.len(), 1);
+    assert_eq!(facets.collections[0].value, "docs");
+    assert_eq!(facets.collections[0].count, 2);
+    assert_eq!(facets.file_types[0].value, ".md");
+    assert_eq!(facets.modified.iter().map(|f| f.count).sum::<usize>(), 2);
+}
+
+#[tokio::test]
+async fn test_search_without_facets() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let response = searcher
+        .search_detailed("rust", SearchOptions::default())
+        .await
+        .unwrap();
+
+    assert!(!response.results.is_empty());
+    assert!(response.facets.is_none());
+}
+
+// =============================================================================
+// Spelling Suggestion Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_search_suggests_misspelled_terms() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let response = searcher
+        .search_detailed(
+            "owership",
+            SearchOptions {
+                fuzzy: false,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(response.results.is_empty());
+    assert!(response.corrected_query.is_none());
+    assert_eq!(response.suggestions.len(), 1);
+    assert_eq!(response.suggestions[0].term, "owership");
+    assert_eq!(response.suggestions[0].suggestion, "ownership");
+    assert_eq!(response.suggestions[0].distance, 1);
+}
+
+#[tokio::test]
+async fn test_search_fuzzy_fallback() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let response = searcher
+        .search_detailed("Pyhton", SearchOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(response.corrected_query.as_deref(), Some("python"));
+    assert!(response
+        .results
+        .iter()
+        .any(|r| r.path.contains("python_basics")));
+
+    // Plain search() applies the same fallback
+    let results = searcher
+        .search("Pyhton", SearchOptions::default())
+        .await
+        .unwrap();
+    assert!(!results.is_empty());
+}
+
+#[tokio::test]
+async fn test_hybrid_reports_fuzzy_fallback() {
+    let store = create_fixture_store(&[
+        (
+            "docs",
+            "python_basics.md",
+            PYTHON_BASICS,
+            Some(embedding(|d| if d == 0 { 1.0 } else { 0.0 })),
+        ),
+        (
+            "docs",
+            "rust_guide.md",
+            RUST_GUIDE,
+            Some(embedding(|d| if d == 1 { 1.0 } else { 0.0 })),
+        ),
+    ])
+    .await;
+    let searcher = qfs::search::Searcher::new(&store);
+    let query = embedding(|d| if d == 2 { 1.0 } else { 0.0 });
+
+    let results = searcher
+        .search_hybrid_with_embedding("Pyhton", &query, &SearchOptions::default())
+        .await
+        .unwrap();
+    assert!(results.iter().any(|r| r.path.contains("python_basics")));
+    assert_eq!(searcher.last_corrected_query().as_deref(), Some("python"));
+
+    searcher
+        .search_hybrid_with_embedding("python", &query, &SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(searcher.last_corrected_query(), None);
+}
+
+#[tokio::test]
+async fn test_search_no_suggestion_for_known_terms() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let response = searcher
+        .search_detailed("rust", SearchOptions::default())
+        .await
+        .unwrap();
+
+    assert!(!response.results.is_empty());
+    assert!(response.suggestions.is_empty());
+    assert!(response.corrected_query.is_none());
+}
+
+// =============================================================================
+// Similar Document Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_similar_bm25_fallback() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    // No embeddings: falls back to the document's top TF-IDF terms
+    let searcher = qfs::search::Searcher::new(&store);
+    let results = searcher
+        .similar("docs/rust_guide.md", SearchOptions::default())
+        .await
+        .unwrap();
+
+    assert!(!results.is_empty(), "Should find related documents");
+    assert!(
+        results.iter().all(|r| r.path != "docs/rust_guide.md"),
+        "Source document must be excluded"
+    );
+}
+
+#[tokio::test]
+async fn test_similar_with_embeddings() {
+    // Three documents: b points almost the same way as a, c is orthogonal
+    let store = create_fixture_store(&[
+        (
+            "test",
+            "a.md",
+            "a",
+            Some(embedding(|d| if d < 10 { 1.0 } else { 0.0 })),
+        ),
+        (
+            "test",
+            "b.md",
+            "b",
+            Some(embedding(|d| if d < 11 { 1.0 } else { 0.0 })),
+        ),
+        (
+            "test",
+            "c.md",
+            "c",
+            Some(embedding(|d| if d >= 300 { 1.0 } else { 0.0 })),
+        ),
+    ])
+    .await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let results = searcher
+        .similar("test/a.md", SearchOptions::default())
+        .await
+        .unwrap();
+
+    assert!(results.iter().all(|r| r.path != "test/a.md"));
+    assert_eq!(results[0].path, "test/b.md");
+}
+
+#[tokio::test]
+async fn test_similar_unknown_document() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let result = searcher
+        .similar("docs/missing.md", SearchOptions::default())
+        .await;
+    assert!(result.is_err());
+}
+
+// =============================================================================
+// Grep Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_grep_literal_with_punctuation() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let matches = searcher
+        .grep("Rust's ownership", &qfs::search::GrepOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].path, "docs/rust_guide.md");
+    assert_eq!(matches[0].column, 1);
+    assert!(matches[0].text.starts_with("Rust's ownership system"));
+    assert!(matches[0].docid.starts_with('#'));
+}
+
+#[tokio::test]
+async fn test_grep_regex_and_context() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let options = qfs::search::GrepOptions {
+        regex: true,
+        context: 1,
+        ..Default::default()
+    };
+    let matches = searcher.grep(r"blazingly\s+fast", &options).await.unwrap();
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].line, 3);
+    assert_eq!(matches[0].before, vec![""]);
+    assert_eq!(matches[0].after, vec![""]);
+}
+
+#[tokio::test]
+async fn test_grep_ignore_case_and_filters() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+
+    // Case-sensitive literal misses lowercase spelling
+    let matches = searcher
+        .grep("PYTHON", &qfs::search::GrepOptions::default())
+        .await
+        .unwrap();
+    assert!(matches.is_empty());
+
+    let options = qfs::search::GrepOptions {
+        ignore_case: true,
+        ..Default::default()
+    };
+    let matches = searcher.grep("PYTHON", &options).await.unwrap();
+    assert!(matches.iter().any(|m| m.path == "docs/python_basics.md"));
+
+    let options = qfs::search::GrepOptions {
+        ignore_case: true,
+        collection: Some("other".to_string()),
+        ..Default::default()
+    };
+    assert!(searcher.grep("PYTHON", &options).await.unwrap().is_empty());
+
+    let options = qfs::search::GrepOptions {
+        ignore_case: true,
+        limit: 1,
+        ..Default::default()
+    };
+    assert_eq!(searcher.grep("python", &options).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_grep_ignore_case_folds_unicode() {
+    // `ſ` folds to `s` and the Kelvin sign to `k`, which SQLite's lower()
+    // doesn't know
+    let store = create_fixture_store(&[
+        ("docs", "long-s.md", "The ſecret handshake", None),
+        ("docs", "kelvin.md", "Absolute zero is 0 \u{212a}", None),
+    ])
+    .await;
+    let searcher = qfs::search::Searcher::new(&store);
+    let options = qfs::search::GrepOptions {
+        ignore_case: true,
+        ..Default::default()
+    };
+
+    let matches = searcher.grep("secret", &options).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].path, "docs/long-s.md");
+    let matches = searcher.grep("0 k", &options).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].path, "docs/kelvin.md");
+}
+
+// =============================================================================
+// Passage Search Tests
+// =============================================================================
+
+/// Handbook whose passages fixture documents are checked against
+const HANDBOOK: &str = "# Handbook\n\nDeploys run every Tuesday.\nRollbacks need approval.\n\nOn-call rotates weekly.\nPagers escalate after ten minutes.\n";
+
+/// Store with one long document split into two chunks, each with an embedding
+async fn create_passage_store() -> Store {
+    let store = create_fixture_store(&[("docs", "handbook.md", HANDBOOK, None)]).await;
+    let hash = content_hash(HANDBOOK);
+
+    let split = HANDBOOK.find("On-call").unwrap();
+    let spans = [(0, &HANDBOOK[..split]), (split, &HANDBOOK[split..])];
+    for (index, (offset, text)) in spans.iter().enumerate() {
+        let (line_start, line_end) = qfs::line_range(HANDBOOK, *offset, text.len());
+        let chunk = qfs::store::ChunkRow {
+            hash: hash.clone(),
+            chunk_index: index as i32,
+            char_offset: *offset as i32,
+            char_length: text.len() as i32,
+            line_start: line_start as i32,
+            line_end: line_end as i32,
+        };
+        store.insert_chunk(&chunk, text).await.unwrap();
+
+        let bytes: Vec<u8> = embedding(|d| if d % 2 == index { 1.0 } else { 0.0 })
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        store
+            .insert_embedding(&hash, index as i32, *offset as i32, "test-model", &bytes)
+            .await
+            .unwrap();
+    }
+
+    store
+}
+
+#[tokio::test]
+async fn test_passage_bm25_line_ranges() {
+    let store = create_passage_store().await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search(
+            "pagers",
+            SearchOptions {
+                passages: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].chunk_index, Some(1));
+    assert_eq!(results[0].line_start, Some(6));
+    assert_eq!(results[0].line_end, Some(7));
+
+    // Document-level search is unchanged
+    let results = searcher
+        .search("pagers", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].chunk_index, None);
+    assert_eq!(results[0].line_start, Some(7));
+    assert_eq!(results[0].line_end, None);
+}
+
+#[tokio::test]
+async fn test_passage_search_falls_back_without_chunks() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search(
+            "rust",
+            SearchOptions {
+                passages: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| r.chunk_index.is_none()));
+}
+
+#[tokio::test]
+async fn test_hybrid_fuses_passages() {
+    let store = create_passage_store().await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    // Both BM25 ("rotates") and the vector point at the second chunk
+    let query_embedding = embedding(|d| if d % 2 == 1 { 1.0 } else { 0.0 });
+
+    let passages = searcher
+        .search_hybrid_with_embedding(
+            "rotates",
+            &query_embedding,
+            &SearchOptions {
+                passages: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(passages.len(), 2, "Both chunks are returned as passages");
+    assert_eq!(passages[0].chunk_index, Some(1));
+    assert_eq!(passages[0].line_start, Some(6));
+
+    // Default rolls passages up to one result per document
+    let documents = searcher
+        .search_hybrid_with_embedding("rotates", &query_embedding, &SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0].chunk_index, Some(1));
+}
+
+#[tokio::test]
+async fn test_passages_include_unchunked_documents() {
+    let store = create_passage_store().await;
+
+    // Embedded before chunks were indexed: an embedding but no chunks
+    let vector = embedding(|d| if d % 2 == 1 { 1.0 } else { 0.0 });
+    add_fixture_docs(
+        &store,
+        &[(
+            "docs",
+            "runbook.md",
+            "# Runbook\n\nPagers page the secondary after the primary.\n",
+            Some(vector.clone()),
+        )],
+    )
+    .await;
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let passages = SearchOptions {
+        passages: true,
+        ..Default::default()
+    };
+
+    let results = searcher.search("pagers", passages.clone()).await.unwrap();
+    let mut paths: Vec<(&str, Option<i32>)> = results
+        .iter()
+        .map(|r| (r.path.as_str(), r.chunk_index))
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![("docs/handbook.md", Some(1)), ("docs/runbook.md", None)]
+    );
+
+    let results = searcher
+        .search_hybrid_with_embedding("pagers", &vector, &passages)
+        .await
+        .unwrap();
+    let runbook: Vec<_> = results
+        .iter()
+        .filter(|r| r.path == "docs/runbook.md")
+        .collect();
+    assert_eq!(runbook.len(), 1, "Its BM25 and vector hits fuse");
+    assert_eq!(runbook[0].chunk_index, None);
+
+    let documents = searcher
+        .search_hybrid_with_embedding("pagers", &vector, &SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(documents.len(), 2);
+}
+
+// =============================================================================
+// Snippet Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_bm25_reports_line_numbers() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search("ownership", SearchOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].line_start, Some(18));
+    assert!(results[0].snippets.is_empty(), "One snippet by default");
+}
+
+#[tokio::test]
+async fn test_bm25_multiple_snippets() {
+    let (store, _db_dir, _content_dir) = create_test_store().await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search(
+            "ownership",
+            SearchOptions {
+                snippets: 3,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let snippets = &results[0].snippets;
+    assert_eq!(snippets.len(), 2);
+    assert_eq!(snippets[0].line, 18);
+    assert_eq!(snippets[1].line, 22);
+    assert!(snippets[1].text.contains("<mark>ownership</mark>"));
+    assert_eq!(results[0].line_start, Some(18));
+}
+
+// =============================================================================
+// Filtered Vector Search Tests
+// =============================================================================
+
+/// Store where a large collection crowds the query's nearest neighbours and
+/// a small collection sits further away from it
+async fn create_skewed_vector_store(small_docs: usize) -> Store {
+    let collections = [
+        ("large_collection", 40, 0),
+        ("small_collection", small_docs, 200),
+    ];
+    let mut docs = Vec::new();
+    for (collection, count, axis) in collections {
+        for i in 0..count {
+            let vector = embedding(|d| {
+                if d == axis {
+                    1.0
+                } else if d == axis + 1 + i {
+                    0.1
+                } else if d == 0 {
+                    // Keep the small collection weakly similar to the query
+                    0.3
+                } else {
+                    0.0
+                }
+            });
+            let path = format!("doc{}.md", i);
+            let content = format!("{} document {}", collection, i);
+            docs.push((collection, path, content, vector));
+        }
+    }
+
+    let docs: Vec<FixtureDoc> = docs
+        .iter()
+        .map(|(collection, path, content, vector)| {
+            (
+                *collection,
+                path.as_str(),
+                content.as_str(),
+                Some(vector.clone()),
+            )
+        })
+        .collect();
+    create_fixture_store(&docs).await
+}
+
+#[tokio::test]
+async fn test_vector_search_collection_filter_fills_limit() {
+    let store = create_skewed_vector_store(8).await;
+    let query = embedding(|d| if d == 0 { 1.0 } else { 0.0 });
+
+    // All 40 nearest neighbours belong to the large collection
+    let native = store
+        .search_vector_native(
+            &query,
+            &DocumentFilter::new(Some("small_collection"), None, None),
+            5,
+        )
+        .await
+        .unwrap();
+    if let Some(rows) = native {
+        assert_eq!(
+            rows.len(),
+            5,
+            "Native search should widen past filtered neighbours"
+        );
+        assert!(rows.iter().all(|r| r.collection == "small_collection"));
+    }
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let results = searcher
+        .search_vector_with_embedding(
+            &query,
+            &SearchOptions {
+                mode: SearchMode::Vector,
+                limit: 5,
+                collection: Some("small_collection".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 5);
+    assert!(results.iter().all(|r| r.collection == "small_collection"));
+}
+
+#[tokio::test]
+async fn test_vector_search_collection_filter_smaller_than_limit() {
+    let store = create_skewed_vector_store(3).await;
+    let query = embedding(|d| if d == 0 { 1.0 } else { 0.0 });
+
+    let searcher = qfs::search::Searcher::new(&store);
+    let results = searcher
+        .search_vector_with_embedding(
+            &query,
+            &SearchOptions {
+                mode: SearchMode::Vector,
+                limit: 10,
+                collection: Some("small_collection".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        results.len(),
+        3,
+        "Every document in the collection is returned"
+    );
+}
+
+// =============================================================================
+// Vector Index Management Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_vector_index_rebuild_with_params() {
+    let store = create_skewed_vector_store(3).await;
+    store.ensure_vector_index().await.unwrap();
+
+    let status = store.vector_index_status().await.unwrap();
+    assert!(status.exists);
+    assert_eq!(status.params, qfs::store::VectorIndexParams::default());
+    assert_eq!(status.embeddings, 43);
+    assert_eq!(status.legacy_embeddings, 0);
+    assert_eq!(status.column_type, "F32_BLOB(384)");
+
+    let params = qfs::store::VectorIndexParams {
+        metric: "l2".to_string(),
+        compress_neighbors: None,
+        max_neighbors: Some(16),
+        search_l: Some(100),
+        insert_l: None,
+    };
+    store.rebuild_vector_index(&params).await.unwrap();
+
+    let status = store.vector_index_status().await.unwrap();
+    assert!(status.exists);
+    assert_eq!(status.params, params);
+
+    // Saved parameters are reported while the index is dropped
+    store.drop_vector_index().await.unwrap();
+    let status = store.vector_index_status().await.unwrap();
+    assert!(!status.exists);
+    assert_eq!(status.params, params);
+
+    let invalid = qfs::store::VectorIndexParams {
+        metric: "manhattan".to_string(),
+        ..Default::default()
+    };
+    assert!(store.rebuild_vector_index(&invalid).await.is_err());
+}
+
+#[tokio::test]
+async fn test_exact_vector_scan_uses_index_metric() {
+    // `far` points the query's way but is long; `near` is close but off-angle
+    let store = create_fixture_store(&[
+        (
+            "docs",
+            "far.md",
+            "far",
+            Some(embedding(|d| [10.0, 5.0].get(d).copied().unwrap_or(0.0))),
+        ),
+        (
+            "docs",
+            "near.md",
+            "near",
+            Some(embedding(|d| [0.5, 0.5].get(d).copied().unwrap_or(0.0))),
+        ),
+    ])
+    .await;
+    let query = embedding(|d| if d == 0 { 1.0 } else { 0.0 });
+    let filter = DocumentFilter::default();
+    let order = |rows: Vec<qfs::store::VectorSearchResult>| -> Vec<&str> {
+        rows.iter()
+            .map(|r| {
+                if r.hash == content_hash("far") {
+                    "far"
+                } else {
+                    "near"
+                }
+            })
+            .collect()
+    };
+
+    let cosine = store
+        .search_vector_legacy(&query, &filter, 2)
+        .await
+        .unwrap();
+    assert_eq!(order(cosine), vec!["far", "near"]);
+
+    store
+        .rebuild_vector_index(&qfs::store::VectorIndexParams {
+            metric: "l2".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let exact = store
+        .search_vector_legacy(&query, &filter, 2)
+        .await
+        .unwrap();
+    assert_eq!(order(exact), vec!["near", "far"]);
+    let native = store
+        .search_vector_native(&query, &filter, 2)
+        .await
+        .unwrap();
+    if let Some(rows) = native {
+        assert_eq!(order(rows), vec!["near", "far"]);
+    }
+}
+
+#[tokio::test]
+async fn test_vector_index_drop_reports_legacy_path() {
+    let store = create_skewed_vector_store(3).await;
+    let query = embedding(|d| if d == 0 { 1.0 } else { 0.0 });
+    let options = SearchOptions {
+        mode: SearchMode::Vector,
+        limit: 5,
+        ..Default::default()
+    };
+
+    let searcher = qfs::search::Searcher::new(&store);
+    assert_eq!(searcher.last_vector_path(), None);
+
+    searcher
+        .search_vector_with_embedding(&query, &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        searcher.last_vector_path(),
+        Some(qfs::store::VectorSearchPath::Native)
+    );
+
+    assert!(store.drop_vector_index().await.unwrap());
+    let status = store.vector_index_status().await.unwrap();
+    assert!(!status.exists);
+    assert!(status.disabled);
+    assert_eq!(status.search_path, qfs::store::VectorSearchPath::Legacy);
+
+    // A dropped index is not recreated lazily by the next search
+    let results = searcher
+        .search_vector_with_embedding(&query, &options)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 5);
+    assert_eq!(
+        searcher.last_vector_path(),
+        Some(qfs::store::VectorSearchPath::Legacy)
+    );
+    assert!(!store.has_vector_index().await);
+}
+
+// =============================================================================
+// Ranking Signal Tests
+// =============================================================================
+
+/// Two collections holding identical documents at various depths and lengths
+async fn create_ranking_store() -> Store {
+    let long_body = format!(
+        "Rust ownership explained. {}",
+        "Borrowing and lifetimes keep memory safe. ".repeat(40)
+    );
+    create_fixture_store(&[
+        ("docs", "ownership.md", "Rust ownership explained.", None),
+        ("notes", "ownership.md", "Rust ownership explained.", None),
+        (
+            "notes",
+            "deep/nested/dir/ownership.md",
+            "Rust ownership explained.",
+            None,
+        ),
+        ("notes", "long.md", &long_body, None),
+    ])
+    .await
+}
+
+async fn ranked_paths(store: &Store, options: SearchOptions) -> Vec<String> {
+    qfs::search::Searcher::new(store)
+        .search("ownership", options)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.path)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_ranking_collection_profile_weight() {
+    let store = create_ranking_store().await;
+    let options = SearchOptions {
+        limit: 10,
+        ..Default::default()
+    };
+
+    let profile = qfs::search::CollectionRanking {
+        collection: "docs".to_string(),
+        weight: 3.0,
+        signals: Default::default(),
+    };
+    store.set_collection_ranking(&profile).await.unwrap();
+    assert_eq!(
+        store.list_collection_rankings().await.unwrap(),
+        vec![profile]
+    );
+
+    let paths = ranked_paths(&store, options.clone()).await;
+    assert_eq!(paths[0], "docs/ownership.md");
+
+    // A query-level weight overrides the saved profile
+    let demoted = SearchOptions {
+        ranking: qfs::search::RankingOptions {
+            collection_weights: [("docs".to_string(), 0.1)].into(),
+            ..Default::default()
+        },
+        ..options.clone()
+    };
+    let paths = ranked_paths(&store, demoted).await;
+    assert_eq!(paths.last().unwrap(), "docs/ownership.md");
+
+    assert!(store.remove_collection_ranking("docs").await.unwrap());
+    assert!(!store.remove_collection_ranking("docs").await.unwrap());
+
+    let unknown = qfs::search::CollectionRanking {
+        collection: "missing".to_string(),
+        weight: 2.0,
+        signals: Default::default(),
+    };
+    assert!(store.set_collection_ranking(&unknown).await.is_err());
+}
+
+#[tokio::test]
+async fn test_ranking_length_and_depth() {
+    let store = create_ranking_store().await;
+    let base = SearchOptions {
+        limit: 10,
+        collection: Some("notes".to_string()),
+        ..Default::default()
+    };
+
+    // BM25 alone prefers the short stubs over the long document
+    let paths = ranked_paths(&store, base.clone()).await;
+    assert_eq!(paths.last().unwrap(), "notes/long.md");
+
+    let length = SearchOptions {
+        ranking: qfs::search::RankingOptions {
+            min_length: Some(1000),
+            ..Default::default()
+        },
+        ..base.clone()
+    };
+    let paths = ranked_paths(&store, length).await;
+    assert_eq!(paths[0], "notes/long.md");
+
+    let depth = SearchOptions {
+        ranking: qfs::search::RankingOptions {
+            depth_penalty: Some(1.0),
+            ..Default::default()
+        },
+        ..base.clone()
+    };
+    let paths = ranked_paths(&store, depth).await;
+    assert_eq!(paths[0], "notes/ownership.md");
+    assert_eq!(paths.last().unwrap(), "notes/deep/nested/dir/ownership.md");
+}
+
+#[tokio::test]
+async fn test_ranking_profile_signals_apply_without_query_options() {
+    let store = create_ranking_store().await;
+    store
+        .set_collection_ranking(&qfs::search::CollectionRanking {
+            collection: "notes".to_string(),
+            weight: 1.0,
+            signals: qfs::search::RankingOptions {
+                min_length: Some(1000),
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap();
+
+    let paths = ranked_paths(
+        &store,
+        SearchOptions {
+            limit: 1,
+            collection: Some("notes".to_string()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(paths, vec!["notes/long.md"]);
+
+    // Removing the collection removes its profile
+    store.remove_collection("notes").await.unwrap();
+    assert!(store.list_collection_rankings().await.unwrap().is_empty());
+}
+
+// =============================================================================
+// Document Filter Tests
+// =============================================================================
+
+/// Documents of several types across two collections, each with an embedding
+async fn create_filter_store() -> Store {
+    let paths = [
+        ("notes", "guide/intro.md"),
+        ("notes", "journal/2025_01.md"),
+        ("notes", "journal/2025x01.md"),
+        ("notes", "todo.txt"),
+        ("code", "src/deploy.rs"),
+        ("archive", "old/deploy.md"),
+    ];
+    let contents: Vec<String> = paths
+        .iter()
+        .map(|(_, path)| format!("deploy pipeline notes for {}", path))
+        .collect();
+    let docs: Vec<FixtureDoc> = paths
+        .iter()
+        .zip(&contents)
+        .enumerate()
+        .map(|(i, ((collection, path), content))| {
+            let vector = embedding(|d| match d {
+                0 => 1.0,
+                d if d == i + 1 => 0.2,
+                _ => 0.0,
+            });
+            (*collection, *path, content.as_str(), Some(vector))
+        })
+        .collect();
+    create_fixture_store(&docs).await
+}
+
+/// Sorted result paths for BM25 and vector search with the same options
+async fn filtered_paths(store: &Store, options: SearchOptions) -> (Vec<String>, Vec<String>) {
+    let searcher = qfs::search::Searcher::new(store);
+    let query = embedding(|d| if d == 0 { 1.0 } else { 0.0 });
+
+    let mut bm25: Vec<String> = searcher
+        .search("deploy", options.clone())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.path)
+        .collect();
+    let mut vector: Vec<String> = searcher
+        .search_vector_with_embedding(&query, &options)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.path)
+        .collect();
+
+    bm25.sort();
+    vector.sort();
+    (bm25, vector)
+}
+
+#[tokio::test]
+async fn test_filter_collections_include_and_exclude() {
+    let store = create_filter_store().await;
+
+    let options = SearchOptions {
+        collection: Some("code".to_string()),
+        collections: vec!["archive".to_string()],
+        ..Default::default()
+    };
+    let (bm25, vector) = filtered_paths(&store, options).await;
+    assert_eq!(bm25, vec!["archive/old/deploy.md", "code/src/deploy.rs"]);
+    assert_eq!(vector, bm25);
+
+    let options = SearchOptions {
+        exclude_collections: vec!["notes".to_string(), "archive".to_string()],
+        ..Default::default()
+    };
+    let (bm25, vector) = filtered_paths(&store, options).await;
+    assert_eq!(bm25, vec!["code/src/deploy.rs"]);
+    assert_eq!(vector, bm25);
+}
+
+#[tokio::test]
+async fn test_filter_file_and_mime_types() {
+    let store = create_filter_store().await;
+
+    let options = SearchOptions {
+        file_types: vec!["RS".to_string(), ".txt".to_string()],
+        ..Default::default()
+    };
+    let (bm25, vector) = filtered_paths(&store, options).await;
+    assert_eq!(bm25, vec!["code/src/deploy.rs", "notes/todo.txt"]);
+    assert_eq!(vector, bm25);
+
+    let options = SearchOptions {
+        mime_types: vec!["text/plain".to_string(), "text/x-rust".to_string()],
+        ..Default::default()
+    };
+    let (bm25, vector) = filtered_paths(&store, options).await;
+    assert_eq!(bm25, vec!["code/src/deploy.rs", "notes/todo.txt"]);
+    assert_eq!(vector, bm25);
+
+    // A bare class matches every subtype
+    let options = SearchOptions {
+        mime_types: vec!["text".to_string()],
+        ..Default::default()
+    };
+    let (bm25, vector) = filtered_paths(&store, options).await;
+    assert_eq!(bm25.len(), 6);
+    assert_eq!(vector, bm25);
+}
+
+#[tokio::test]
+async fn test_filter_path_prefix_and_glob() {
+    let store = create_filter_store().await;
+
+    // `_` is literal in prefixes, not a LIKE wildcard
+    let options = SearchOptions {
+        collection: Some("notes".to_string()),
+        path_prefix: Some("journal/2025_".to_string()),
+        ..Default::default()
+    };
+    let (bm25, vector) = filtered_paths(&store, options).await;
+    assert_eq!(bm25, vec!["notes/journal/2025_01.md"]);
+    assert_eq!(vector, bm25);
+
+    let options = SearchOptions {
+        path_glob: Some("*/deploy.*".to_string()),
+        ..Default::default()
+    };
+    let (bm25, vector) = filtered_paths(&store, options).await;
+    assert_eq!(bm25, vec!["archive/old/deploy.md", "code/src/deploy.rs"]);
+    assert_eq!(vector, bm25);
+}
+
+#[tokio::test]
+async fn test_filter_applies_to_facets() {
+    let store = create_filter_store().await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let response = searcher
+        .search_detailed(
+            "deploy",
+            SearchOptions {
+                limit: 1,
+                facets: true,
+                file_types: vec!["md".to_string()],
+                exclude_collections: vec!["archive".to_string()],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let facets = response.facets.unwrap();
+    assert_eq!(facets.total, 3);
+    assert_eq!(facets.file_types.len(), 1);
+    assert_eq!(facets.collections.len(), 1);
+}
+
+// ============================================================================
+// Relevance Evaluation Tests
+// ============================================================================
+
+/// Index the golden test corpus as collection "corpus"
+async fn create_corpus_store() -> (Store, tempfile::TempDir) {
+    create_indexed_store("corpus", "corpus", &["**/*.md", "**/*.rs", "**/*.ts"]).await
+}
+
+#[tokio::test]
+async fn test_eval_bm25_on_corpus_queries() {
+    let (store, _db_dir) = create_corpus_store().await;
+    let queries = qfs::eval::load_queries(
+        &std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/eval/queries.jsonl"),
+    )
+    .unwrap();
+    assert_eq!(queries.len(), 8);
+
+    let options = qfs::eval::EvalOptions {
+        k: 5,
+        ..Default::default()
+    };
+    let report = qfs::eval::evaluate(&store, &queries, &options, None)
+        .await
+        .unwrap();
+
+    assert_eq!(report.k, 5);
+    assert_eq!(report.queries.len(), 8);
+    let summary = report.summary("bm25").unwrap();
+    assert_eq!(summary.queries, 8);
+    assert_eq!(summary.mrr, 1.0, "every query has an obvious best document");
+    // Two queries miss their secondary document
+    assert!((summary.recall - 0.875).abs() < 1e-9, "{:?}", summary);
+    assert!(summary.ndcg > 0.9 && summary.ndcg < 1.0, "{:?}", summary);
+    assert!(summary.latency_ms.p95 >= summary.latency_ms.p50);
+    assert!(report.queries.iter().all(|q| q.hits.len() <= 5));
+
+    // Reports round-trip for comparison against later runs
+    let json = serde_json::to_string(&report).unwrap();
+    let parsed: qfs::eval::EvalReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report);
+}
+
+#[tokio::test]
+async fn test_eval_vector_modes_need_embedder() {
+    let (store, _db_dir) = create_corpus_store().await;
+    let queries =
+        qfs::eval::parse_queries(r#"{"query": "rust", "relevant": ["corpus/rust-guide.md"]}"#)
+            .unwrap();
+
+    let options = qfs::eval::EvalOptions {
+        modes: vec![SearchMode::Bm25, SearchMode::Hybrid],
+        ..Default::default()
+    };
+    let err = qfs::eval::evaluate(&store, &queries, &options, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("hybrid"));
+}
+
+#[tokio::test]
+async fn test_eval_vector_counts_distinct_documents() {
+    // The handbook's two chunks both outrank the runbook
+    let store = create_passage_store().await;
+    let vector = embedding(|d| if d % 2 == 0 { 1.0 } else { -0.2 });
+    add_fixture_docs(
+        &store,
+        &[(
+            "docs",
+            "runbook.md",
+            "# Runbook\n\nRestart the service.\n",
+            Some(vector),
+        )],
+    )
+    .await;
+
+    let queries = qfs::eval::parse_queries(
+        r#"{"query": "on-call", "relevant": ["docs/handbook.md", "docs/runbook.md"]}"#,
+    )
+    .unwrap();
+    let options = qfs::eval::EvalOptions {
+        k: 2,
+        modes: vec![SearchMode::Vector],
+        ..Default::default()
+    };
+    let embed = |_: &str| -> qfs::Result<Vec<f32>> {
+        Ok(embedding(|d| if d % 2 == 1 { 1.0 } else { 0.5 }))
+    };
+    let report = qfs::eval::evaluate(&store, &queries, &options, Some(&embed))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        report.queries[0].hits,
+        vec!["docs/handbook.md", "docs/runbook.md"]
+    );
+    assert_eq!(report.summary("vector").unwrap().recall, 1.0);
+}
+
+// ============================================================================
+// Document Format Tests
+// ============================================================================
+
+/// Index `tests/fixtures/documents` as collection "docs"
+async fn create_documents_store(patterns: &[&str]) -> (Store, tempfile::TempDir) {
+    create_indexed_store("docs", "documents", patterns).await
+}
+
+#[tokio::test]
+async fn test_pdf_search_cites_pages() {
+    let (store, _db_dir) = create_documents_store(&["**/*.pdf"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search("warranty", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "docs/handbook.pdf");
+    assert_eq!(results[0].title.as_deref(), Some("Employee Handbook"));
+    assert_eq!(results[0].mime_type, "application/pdf");
+    assert_eq!(results[0].page, Some(3));
+    assert!(results[0].snippet.as_deref().unwrap().contains("warranty"));
+
+    let results = searcher
+        .search("dental insurance", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].page, Some(2));
+
+    // Grep and get read the extracted text
+    let matches = searcher
+        .grep("help desk", &qfs::search::GrepOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+    assert!(matches[0].text.contains("three year warranty"));
+
+    let docs = store
+        .multi_get("docs/handbook.pdf", 1 << 20, None)
+        .await
+        .unwrap();
+    let content = docs[0].content.as_deref().unwrap();
+    assert!(content.starts_with("Employee Handbook\nWelcome to the company."));
+}
+
+#[tokio::test]
+async fn test_pdf_unchanged_is_skipped() {
+    let (store, _db_dir) = create_documents_store(&["**/*.pdf"]).await;
+
+    let stats = Indexer::new(&store).index_collection("docs").await.unwrap();
+    assert_eq!(stats.files_indexed, 0);
+    assert_eq!(stats.files_skipped, 1);
+}
+
+#[tokio::test]
+async fn test_html_indexes_readable_text() {
+    let (store, _db_dir) = create_documents_store(&["**/*.html"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search("rollback", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title.as_deref(), Some("Deploy Guide"));
+    assert_eq!(results[0].mime_type, "text/html");
+    // The match is in the content, not the nav link, stylesheet or markup
+    let snippet = results[0].snippet.as_deref().unwrap();
+    assert!(
+        snippet.contains("helm <mark>rollback</mark> web"),
+        "{}",
+        snippet
+    );
+    assert!(
+        !snippet.contains("<code>") && !snippet.contains("class="),
+        "{}",
+        snippet
+    );
+    assert_eq!(results[0].line_start, Some(7));
+
+    // Markup, scripts and boilerplate are not indexed
+    for query in ["analytics", "stylesheet", "copyright", "blog"] {
+        let results = searcher
+            .search(query, SearchOptions::default())
+            .await
+            .unwrap();
+        assert!(results.is_empty(), "{} matched {:?}", query, results);
+    }
+
+    let docs = store
+        .multi_get("docs/deploy.html", 1 << 20, None)
+        .await
+        .unwrap();
+    assert!(docs[0]
+        .content
+        .as_deref()
+        .unwrap()
+        .starts_with("# Deploying the web service\n\nReleases go out"));
+}
+
+#[tokio::test]
+async fn test_office_documents_and_ebooks() {
+    let (store, _db_dir) =
+        create_documents_store(&["**/*.docx", "**/*.pptx", "**/*.odt", "**/*.epub"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    for (query, path, title) in [
+        ("VPN access", "docs/onboarding.docx", "Onboarding Checklist"),
+        ("hiring delays", "docs/roadmap.pptx", "Product Roadmap 2026"),
+        ("changelog", "docs/minutes.odt", "Team Minutes"),
+        (
+            "apricots",
+            "docs/field-guide.epub",
+            "Field Guide to Mushrooms",
+        ),
+    ] {
+        let results = searcher
+            .search(query, SearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1, "{}: {:?}", query, results);
+        assert_eq!(results[0].path, path);
+        assert_eq!(results[0].title.as_deref(), Some(title));
+        assert!(!results[0].is_binary);
+    }
+
+    // Slides are cited like PDF pages, in presentation order
+    let results = searcher
+        .search("hiring", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].page, Some(3));
+    assert_eq!(
+        results[0].mime_type,
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+    );
+
+    // Deleted revisions and comments are not indexed
+    for query in ["hello", "check date"] {
+        let results = searcher
+            .search(query, SearchOptions::default())
+            .await
+            .unwrap();
+        assert!(results.is_empty(), "{} matched {:?}", query, results);
+    }
+
+    let docs = store
+        .multi_get("docs/minutes.odt", 1 << 20, None)
+        .await
+        .unwrap();
+    assert!(docs[0]
+        .content
+        .as_deref()
+        .unwrap()
+        .ends_with("## Actions\n\n- Update the changelog\n- Notify support"));
+}
+
+#[tokio::test]
+async fn test_notebook_cells() {
+    let (store, _db_dir) = create_documents_store(&["**/*.ipynb"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search("retained", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].title.as_deref(), Some("Churn Analysis"));
+    assert_eq!(results[0].mime_type, "application/x-ipynb+json");
+    assert_eq!(qfs::parser::page_label(&results[0].mime_type), "cell");
+    // The fourth cell holds the groupby
+    assert_eq!(results[0].page, Some(4));
+
+    // Outputs, execution counts and image data are left out by default
+    for query in ["execution_count", "0.87", "figure"] {
+        let results = searcher
+            .search(query, SearchOptions::default())
+            .await
+            .unwrap();
+        assert!(results.is_empty(), "{} matched {:?}", query, results);
+    }
+
+    let docs = store
+        .multi_get("docs/churn.ipynb", 1 << 20, None)
+        .await
+        .unwrap();
+    let content = docs[0].content.as_deref().unwrap();
+    assert!(content.starts_with(
+        "# Churn Analysis\n\nMonthly customer churn by plan tier.\n\n```python\nimport pandas as pd\n"
+    ));
+    assert!(!content.contains("iVBOR"));
+}
+
+#[tokio::test]
+async fn test_table_rows_are_addressable() {
+    let (store, _db_dir) = create_documents_store(&["**/*.csv", "**/*.tsv"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    let results = searcher
+        .search("discontinued", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "docs/inventory.csv");
+    assert_eq!(results[0].mime_type, "text/csv");
+    // The quoted multi-line note belongs to the fourth data row, on line 5
+    assert_eq!(results[0].line_start, Some(5));
+    assert_eq!(results[0].page, Some(4));
+    assert_eq!(qfs::parser::page_label(&results[0].mime_type), "row");
+
+    let results = searcher
+        .search("Amira", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].path, "docs/teams.tsv");
+    assert_eq!(results[0].page, Some(1));
+    let snippet = results[0].snippet.as_deref().unwrap();
+    assert!(
+        snippet.contains("manager: <mark>Amira</mark> Haddad | headcount: 14"),
+        "{}",
+        snippet
+    );
+
+    // `get path:line` returns the header with the row
+    let doc = store.get_document("docs", "inventory.csv").await.unwrap();
+    let text = store.get_text(&doc.hash).await.unwrap().unwrap();
+    let mime_type = store.get_content_type(&doc.hash).await.unwrap();
+    assert_eq!(
+        qfs::extract_document_lines(&text, &mime_type, Some(5), Some(1), false),
+        "sku | name | warehouse | quantity | notes\n\
+         sku: B-201 | name: Pine shelf | warehouse: Leipzig | quantity: 0 | notes: Discontinued replaced by B-200"
+    );
+}
+
+#[tokio::test]
+async fn test_email_messages_are_documents() {
+    let (store, _db_dir) = create_documents_store(&["**/*.mbox", "**/*.eml"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    // Each message of the mbox is its own document, titled by its subject
+    let results = searcher
+        .search("replication lag", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "docs/project.mbox!/2.eml");
+    assert_eq!(
+        results[0].title.as_deref(),
+        Some("Re: Migration timeline – draft")
+    );
+    assert_eq!(results[0].mime_type, "message/rfc822");
+
+    // Senders are searchable and filterable
+    let results = searcher
+        .search(
+            "migration",
+            SearchOptions {
+                metadata: vec![("from".to_string(), "PRIYA@example.org".to_string())],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, vec!["docs/project.mbox!/1.eml"]);
+
+    let message = store
+        .get_document("docs", "project.mbox!/2.eml")
+        .await
+        .unwrap();
+    let metadata = store.get_document_metadata(message.id).await.unwrap();
+    assert_eq!(
+        metadata["from"],
+        vec!["Marcus Jörgensen <marcus@example.org>"]
+    );
+    assert_eq!(metadata["date"], vec!["2025-03-05T16:40:02+00:00"]);
+    assert_eq!(metadata["attachments"], vec!["timeline.csv"]);
+    let text = store.get_text(&message.hash).await.unwrap().unwrap();
+    assert!(text.contains("five seconds — we will rehearse"), "{}", text);
+    assert!(!text.contains("bWlsZXN0b25l"));
+
+    // Attachments go to their own parsers
+    let results = searcher
+        .search("cutover Marcus", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].path, "docs/project.mbox!/2.eml!/timeline.csv");
+    assert_eq!(results[0].mime_type, "text/csv");
+
+    // HTML-only messages are reduced to text
+    let results = searcher
+        .search("checksum mismatch", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].path, "docs/retrospective.eml");
+    assert_eq!(results[0].title.as_deref(), Some("Retrospective notes"));
+    let doc = store
+        .get_document("docs", "retrospective.eml")
+        .await
+        .unwrap();
+    let text = store.get_text(&doc.hash).await.unwrap().unwrap();
+    assert!(!text.contains("<b>"));
+}
+
+#[tokio::test]
+async fn test_session_transcripts_cite_messages() {
+    let (store, _db_dir) = create_documents_store(&["**/*.jsonl"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    // Tool inputs are searchable, and hits cite their message
+    let results = searcher
+        .search("TokenBucket", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "docs/session.jsonl");
+    assert_eq!(
+        results[0].title.as_deref(),
+        Some("Add a token bucket rate limiter to the gateway and make the burst size configura…")
+    );
+    assert_eq!(results[0].page, Some(4));
+    assert_eq!(qfs::parser::page_label(&results[0].mime_type), "message");
+
+    let results = searcher
+        .search("E0425", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].page, Some(5));
+    let results = searcher
+        .search("GatewayConfig", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].page, Some(6));
+
+    // Roles and timestamps head each message; thinking is left out
+    let doc = store.get_document("docs", "session.jsonl").await.unwrap();
+    let text = store.get_text(&doc.hash).await.unwrap().unwrap();
+    assert!(text.starts_with("## user (2025-06-02T08:30:00Z)\n\nAdd a token bucket"));
+    assert!(text.contains("## assistant (2025-06-02T08:30:12Z)"));
+    assert!(text.contains("Tool: Grep\npath: src/\npattern: fn handle_request"));
+    assert!(!text.contains("private scratch notes"));
+
+    let metadata = store.get_document_metadata(doc.id).await.unwrap();
+    assert_eq!(metadata["tools"], vec!["Edit", "Grep"]);
+    assert_eq!(metadata["git_branch"], vec!["rate-limits"]);
+    let results = searcher
+        .search(
+            "limiter",
+            SearchOptions {
+                metadata: vec![("tools".to_string(), "edit".to_string())],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_collection_parser_commands() {
+    let (store, _db_dir) = create_documents_store(&["**/*.rtf"]).await;
+    let doc = store.get_document("docs", "memo.rtf").await.unwrap();
+    assert!(store
+        .get_text(&doc.hash)
+        .await
+        .unwrap()
+        .unwrap()
+        .contains("\\rtf1"));
+
+    // Strip control words and braces instead of indexing the markup
+    let parser = qfs::parser::CollectionParser {
+        collection: "docs".to_string(),
+        extension: ".RTF".to_string(),
+        command: "sed -E 's/\\\\[a-z0-9]+ ?//g; s/[{}]//g' {file}".to_string(),
+        timeout_secs: 10,
+    };
+    store.set_collection_parser(&parser).await.unwrap();
+    let saved = store.list_collection_parsers(Some("docs")).await.unwrap();
+    assert_eq!(saved[0].extension, "rtf");
+    assert_eq!(saved[0].command, parser.command);
+
+    let stats = Indexer::new(&store).index_collection("docs").await.unwrap();
+    assert_eq!((stats.files_indexed, stats.errors), (1, 0));
+    let doc = store.get_document("docs", "memo.rtf").await.unwrap();
+    let text = store.get_text(&doc.hash).await.unwrap().unwrap();
+    assert_eq!(
+        text,
+        "Times;\nMemo: the quarterly offsite moves to the lighthouse venue."
+    );
+    assert_eq!(
+        store.get_document_metadata(doc.id).await.unwrap()["parser"],
+        vec!["sed"]
+    );
+
+    let results = qfs::search::Searcher::new(&store)
+        .search("lighthouse", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].path, "docs/memo.rtf");
+
+    // Changing or clearing the command re-parses the unchanged file
+    let upper = qfs::parser::CollectionParser {
+        command: "tr a-z A-Z".to_string(),
+        ..parser.clone()
+    };
+    store.set_collection_parser(&upper).await.unwrap();
+    let stats = Indexer::new(&store).index_collection("docs").await.unwrap();
+    assert_eq!(stats.files_indexed, 1);
+    assert!(store
+        .get_text(&doc.hash)
+        .await
+        .unwrap()
+        .unwrap()
+        .contains("LIGHTHOUSE VENUE"));
+
+    assert!(store.remove_collection_parser("docs", "rtf").await.unwrap());
+    let stats = Indexer::new(&store).index_collection("docs").await.unwrap();
+    assert_eq!(stats.files_indexed, 1);
+    assert!(store
+        .get_text(&doc.hash)
+        .await
+        .unwrap()
+        .unwrap()
+        .starts_with("{\\rtf1"));
+    let stats = Indexer::new(&store).index_collection("docs").await.unwrap();
+    assert_eq!(stats.files_skipped, 1);
+
+    let unknown = qfs::parser::CollectionParser {
+        collection: "missing".to_string(),
+        ..parser.clone()
+    };
+    assert!(store.set_collection_parser(&unknown).await.is_err());
+    let empty = qfs::parser::CollectionParser {
+        command: "  ".to_string(),
+        ..parser
+    };
+    assert!(store.set_collection_parser(&empty).await.is_err());
+
+    // Parsers go with their collection
+    store.set_collection_parser(&upper).await.unwrap();
+    store.remove_collection("docs").await.unwrap();
+    assert!(store
+        .list_collection_parsers(None)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(!store.remove_collection_parser("docs", "rtf").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_text_encodings_are_decoded() {
+    let (store, _db_dir) = create_documents_store(&["**/*.txt"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    // UTF-16 is text, not binary
+    let results = searcher
+        .search("checksums", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, "docs/release-notes.txt");
+    let results = searcher
+        .search("brûlée", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].path, "docs/menu.txt");
+
+    let expected = [
+        (
+            "release-notes.txt",
+            "UTF-16LE",
+            "Release notes\r\n\r\nThe installer now verifies checksums",
+        ),
+        (
+            "agenda.txt",
+            "Shift_JIS",
+            "会議の議題\n\nKickoff: 新しいダッシュボードのレビュー",
+        ),
+        ("menu.txt", "windows-1252", "Café menu\n\nCrème brûlée – €6"),
+    ];
+    for (path, encoding, text) in expected {
+        let doc = store.get_document("docs", path).await.unwrap();
+        assert!(store
+            .get_text(&doc.hash)
+            .await
+            .unwrap()
+            .unwrap()
+            .starts_with(text));
+        let metadata = store.get_document_metadata(doc.id).await.unwrap();
+        assert_eq!(metadata["encoding"], vec![encoding]);
+    }
+
+    let results = searcher
+        .search(
+            "kickoff",
+            SearchOptions {
+                metadata: vec![("encoding".to_string(), "shift_jis".to_string())],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(results[0].path, "docs/agenda.txt");
+    assert_eq!(results[0].title.as_deref(), Some("agenda"));
+
+    // Grep reads the decoded text
+    let options = qfs::search::GrepOptions::default();
+    for (pattern, path, line) in [
+        ("checksums", "docs/release-notes.txt", 3),
+        ("brûlée", "docs/menu.txt", 3),
+        ("ダッシュボード", "docs/agenda.txt", 3),
+    ] {
+        let matches = searcher.grep(pattern, &options).await.unwrap();
+        assert_eq!(matches.len(), 1, "{}", pattern);
+        assert_eq!((matches[0].path.as_str(), matches[0].line), (path, line));
+    }
+}
+
+#[tokio::test]
+async fn test_archive_members_are_documents() {
+    let (store, _db_dir) = create_documents_store(&["**/*.zip", "**/*.tar.gz"]).await;
+    let searcher = qfs::search::Searcher::new(&store);
+
+    // Archives are indexed by name unless their files are asked for
+    assert!(store
+        .list_part_paths("docs", "release-bundle.zip")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(searcher
+        .search("scheduler", SearchOptions::default())
+        .await
+        .unwrap()
+        .is_empty());
+
+    let archives = Indexer::with_options(
+        &store,
+        qfs::parser::ParseOptions {
+            archives: true,
+            ..Default::default()
+        },
+    );
+    let stats = archives.index_collection("docs").await.unwrap();
+    assert_eq!(stats.files_indexed, 2);
+
+    // Directories, binaries and resource forks are left out; nested archives
+    // are opened too
+    assert_eq!(
+        store
+            .list_part_paths("docs", "release-bundle.zip")
+            .await
+            .unwrap(),
+        vec![
+            "release-bundle.zip!/release-bundle/README.md",
+            "release-bundle.zip!/release-bundle/docs/upgrade.md",
+        ]
+    );
+    assert_eq!(
+        store
+            .list_part_paths("docs", "exports.tar.gz")
+            .await
+            .unwrap(),
+        vec![
+            "exports.tar.gz!/exports/ops.zip",
+            "exports.tar.gz!/exports/ops.zip!/runbook.md",
+            "exports.tar.gz!/exports/regions.csv",
+        ]
+    );
+
+    // Members are parsed for their own type
+    let results = searcher
+        .search("scheduler", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        results[0].path,
+        "docs/release-bundle.zip!/release-bundle/docs/upgrade.md"
+    );
+    assert_eq!(results[0].title.as_deref(), Some("Upgrading"));
+    let results = searcher
+        .search("paused", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(results[0].path, "docs/exports.tar.gz!/exports/regions.csv");
+    assert_eq!(results[0].page, Some(2));
+    let results = searcher
+        .search("standby replica", SearchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        results[0].path,
+        "docs/exports.tar.gz!/exports/ops.zip!/runbook.md"
+    );
+
+    let doc = store
+        .get_document("docs", "release-bundle.zip!/release-bundle/docs/upgrade.md")
+        .await
+        .unwrap();
+    let text = store.get_text(&doc.hash).await.unwrap().unwrap();
+    assert!(text.starts_with("# Upgrading\n\nStop the scheduler"));
+    let doc = store
+        .get_document("docs", "release-bundle.zip")
+        .await
+        .unwrap();
+    assert_eq!(
+        store.get_document_metadata(doc.id).await.unwrap()["members"],
+        vec!["2"]
+    );
+
+    // Unchanged archives are skipped, and indexing without the option drops
+    // their files
+    let stats = archives.index_collection("docs").await.unwrap();
+    assert_eq!(stats.files_skipped, 2);
+    let stats = Indexer::new(&store).index_collection("docs").await.unwrap();
+    assert_eq!(stats.files_indexed, 2);
+    assert!(store
+        .list_part_paths("docs", "exports.tar.gz")
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(searcher
+        .search("scheduler", SearchOptions::default())
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -56,9 +56,9 @@ async fn test_mcp_server_creation() {
 
 #[tokio::test]
 async fn test_mcp_tools_list() {
-    // The tools list should contain 6 tools
+    // The tools list should contain 9 tools
     let tools = qfs::mcp::tools::get_tool_definitions();
-    assert_eq!(tools.len(), 6);
+    assert_eq!(tools.len(), 9);
 
     // Verify required tools
     let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
@@ -66,6 +66,9 @@ async fn test_mcp_tools_list() {
     assert!(names.contains(&"qfs_vsearch"));
     assert!(names.contains(&"qfs_query"));
     assert!(names.contains(&"qfs_get"));
+    assert!(names.contains(&"qfs_similar"));
+    assert!(names.contains(&"qfs_grep"));
+    assert!(names.contains(&"qfs_find"));
     assert!(names.contains(&"qfs_multi_get"));
     assert!(names.contains(&"qfs_status"));
 }
@@ -194,6 +197,46 @@ async fn test_mcp_get_invalid_path() {
     assert!(err.message.contains("format"));
 }
 
+#[tokio::test]
+async fn test_mcp_get_not_found_suggests_paths() {
+    let (store, _db_dir, _content_dir) = create_test_server_with_docs().await;
+
+    let result = qfs::mcp::tools::handle_tool_call(
+        &store,
+        "qfs_get",
+        &json!({
+            "path": "docs/rust-guide.md"
+        }),
+    )
+    .await;
+
+    let err = result.unwrap_err();
+    assert!(err.message.contains("Did you mean"));
+    assert!(err.message.contains("docs/rust_guide.md"));
+    let suggestions = err.data.unwrap()["suggestions"].clone();
+    assert_eq!(suggestions[0], "docs/rust_guide.md");
+}
+
+#[tokio::test]
+async fn test_mcp_find_tool() {
+    let (store, _db_dir, _content_dir) = create_test_server_with_docs().await;
+
+    let result = qfs::mcp::tools::handle_tool_call(
+        &store,
+        "qfs_find",
+        &json!({
+            "query": "pybas"
+        }),
+    )
+    .await
+    .unwrap();
+
+    let matches: serde_json::Value = serde_json::from_str(&result.content[0].text).unwrap();
+    let matches = matches.as_array().unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["path"], "python_basics.md");
+}
+
 #[tokio::test]
 async fn test_mcp_multi_get_tool() {
     let db_dir = tempdir().unwrap();
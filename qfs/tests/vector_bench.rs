@@ -0,0 +1,162 @@
+//! Benchmark for the legacy (index-free) vector search
+//!
+//! Compares the streaming top-k scan in `Store::search_vector_legacy` with the
+//! previous approach of loading every embedding via
+//! `get_all_embeddings_for_search`, decoding and sorting them all.
+//!
+//! ## Running
+//!
+//! ```bash
+//! cargo test --release --test vector_bench -- --ignored --nocapture
+//! QFS_BENCH_EMBEDDINGS=100000 cargo test --release --test vector_bench -- --ignored --nocapture
+//! ```
+
+use qfs::store::DocumentFilter;
+use qfs::Store;
+use std::time::{Duration, Instant};
+
+const DIMENSIONS: usize = 384;
+const LIMIT: usize = 20;
+const ITERATIONS: u32 = 5;
+
+/// Deterministic pseudo-random vector (xorshift), values in [-1, 1)
+fn random_vector(seed: u64) -> Vec<f32> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..DIMENSIONS)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+        })
+        .collect()
+}
+
+async fn create_store(embeddings: usize) -> Store {
+    let store = Store::open_memory().await.unwrap();
+    store
+        .add_collection("bench", "/tmp/bench", &["**/*.md"])
+        .await
+        .unwrap();
+
+    // Several chunks per document, like real embeddings
+    let chunks_per_doc = 4;
+    for doc in 0..embeddings.div_ceil(chunks_per_doc) {
+        let hash = format!("{:012x}", doc);
+        let content = format!("document {}", doc);
+        store
+            .insert_content(&hash, content.as_bytes(), "text/markdown")
+            .await
+            .unwrap();
+        store
+            .upsert_document(
+                "bench",
+                &format!("doc{}.md", doc),
+                None,
+                &hash,
+                ".md",
+                &content,
+            )
+            .await
+            .unwrap();
+
+        for chunk in 0..chunks_per_doc {
+            let seed = (doc * chunks_per_doc + chunk) as u64;
+            let bytes: Vec<u8> = random_vector(seed)
+                .iter()
+                .flat_map(|f| f.to_le_bytes())
+                .collect();
+            store
+                .insert_embedding(&hash, chunk as i32, 0, "bench", &bytes)
+                .await
+                .unwrap();
+        }
+    }
+
+    store
+}
+
+/// The pre-streaming implementation: load everything, score, sort, truncate
+async fn load_all_and_sort(store: &Store, query: &[f32], limit: usize) -> Vec<(String, i32)> {
+    let rows = store
+        .get_all_embeddings_for_search(&DocumentFilter::default())
+        .await
+        .unwrap();
+
+    let mut scored: Vec<(f32, String, i32)> = rows
+        .into_iter()
+        .map(|row| {
+            let embedding: Vec<f32> = row
+                .embedding
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            let dot: f32 = query.iter().zip(&embedding).map(|(a, b)| a * b).sum();
+            let norm_a = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+            let norm_b = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            (dot / (norm_a * norm_b), row.hash, row.chunk_index)
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, hash, chunk)| (hash, chunk))
+        .collect()
+}
+
+fn report(label: &str, times: &[Duration]) {
+    let total: Duration = times.iter().sum();
+    let best = times.iter().min().unwrap();
+    println!(
+        "  {:<24} mean {:>8.1} ms   best {:>8.1} ms",
+        label,
+        total.as_secs_f64() * 1000.0 / times.len() as f64,
+        best.as_secs_f64() * 1000.0
+    );
+}
+
+#[tokio::test]
+#[ignore] // Run with: cargo test --release --test vector_bench -- --ignored --nocapture
+async fn bench_legacy_vector_search() {
+    let embeddings: usize = std::env::var("QFS_BENCH_EMBEDDINGS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20_000);
+
+    let start = Instant::now();
+    let store = create_store(embeddings).await;
+    println!(
+        "\n=== Legacy vector search: {} embeddings x {} dims, top {} ===",
+        embeddings, DIMENSIONS, LIMIT
+    );
+    println!("  setup {:.1}s\n", start.elapsed().as_secs_f64());
+
+    let mut streaming = Vec::new();
+    let mut baseline = Vec::new();
+
+    for i in 0..ITERATIONS {
+        let query = random_vector(u64::MAX - i as u64);
+
+        let start = Instant::now();
+        let results = store
+            .search_vector_legacy(&query, &DocumentFilter::default(), LIMIT)
+            .await
+            .unwrap();
+        streaming.push(start.elapsed());
+
+        let start = Instant::now();
+        let expected = load_all_and_sort(&store, &query, LIMIT).await;
+        baseline.push(start.elapsed());
+
+        let actual: Vec<(String, i32)> = results
+            .into_iter()
+            .map(|r| (r.hash, r.chunk_index))
+            .collect();
+        assert_eq!(actual, expected, "Both implementations rank the same rows");
+    }
+
+    report("streaming top-k", &streaming);
+    report("load all + sort", &baseline);
+}